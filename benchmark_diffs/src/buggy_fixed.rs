@@ -35,6 +35,122 @@ fn test_simple_1() {
     )
 }
 
+#[test]
+fn test_apply_simple_1() {
+    use hyper_diff::actions::apply;
+    use hyperast::{hashed::SyntaxNodeHashsKinds, types::WithHashs};
+    let buggy = r#"class A{class C{}class B{{while(1){if(1){}else{}};}}}class D{class E{}class F{{while(2){if(2){}else{}};}}}"#;
+    let fixed = r#"class A{class C{}}class B{{while(1){if(1){}else{}};}}class D{class E{}}class F{{while(3){if(2){}else{}};}}"#;
+    let mut stores = SimpleStores::default();
+    let mut md_cache = Default::default();
+    let (src_tr, dst_tr) = parse_string_pair(&mut stores, &mut md_cache, &buggy, &fixed);
+    let mut stores = stores.change_type_store::<hyperast_gen_ts_java::types::TStore>();
+    let src = src_tr.local.compressed_node;
+    let dst = dst_tr.local.compressed_node;
+
+    let actions = algorithms::gumtree::diff(&stores, &src, &dst)
+        .actions
+        .unwrap();
+    let patched = apply::apply_actions(&stores, &src, &actions).unwrap();
+    let mut builder = apply::LegionBuilder::<hyperast_gen_ts_java::types::TType>::new(
+        &mut stores.node_store,
+        &stores.label_store,
+    );
+    let patched = patched.build(&mut builder).unwrap();
+
+    let structt = |id| {
+        let n = stores.node_store.resolve(id);
+        WithHashs::hash(&n, SyntaxNodeHashsKinds::Struct)
+    };
+    assert_ne!(structt(src), structt(dst));
+    assert_eq!(structt(patched), structt(dst));
+}
+
+/// Patches `buggy` with its edit script to `fixed`,
+/// then checks the built tree has the structure and the byte length of `fixed`.
+fn check_apply(buggy: &str, fixed: &str) {
+    use hyper_diff::actions::apply;
+    use hyperast::{
+        hashed::SyntaxNodeHashsKinds,
+        types::{WithHashs, WithSerialization},
+    };
+    let mut stores = SimpleStores::default();
+    let mut md_cache = Default::default();
+    let (src_tr, dst_tr) = parse_string_pair(&mut stores, &mut md_cache, &buggy, &fixed);
+    let mut stores = stores.change_type_store::<hyperast_gen_ts_java::types::TStore>();
+    let src = src_tr.local.compressed_node;
+    let dst = dst_tr.local.compressed_node;
+
+    let actions = algorithms::gumtree::diff(&stores, &src, &dst)
+        .actions
+        .unwrap();
+    let patched = apply::apply_actions(&stores, &src, &actions).unwrap();
+    let mut builder = apply::LegionBuilder::<hyperast_gen_ts_java::types::TType>::new(
+        &mut stores.node_store,
+        &stores.label_store,
+    );
+    let patched = patched.build(&mut builder).unwrap();
+    let structt = |id| {
+        let n = stores.node_store.resolve(id);
+        WithHashs::hash(&n, SyntaxNodeHashsKinds::Struct)
+    };
+    let bytes_len = |id| stores.node_store.resolve(id).try_bytes_len();
+    assert_ne!(structt(src), structt(dst));
+    assert_eq!(structt(patched), structt(dst));
+    assert_eq!(bytes_len(patched), bytes_len(dst));
+}
+
+#[test]
+fn test_apply_nested_moves() {
+    // B is moved into C, and its members are swapped
+    let buggy = r#"class A{class B{int a;int b;}class C{int c;}}"#;
+    let fixed = r#"class A{class C{int c;class B{int b;int a;}}}"#;
+    check_apply(buggy, fixed);
+}
+
+#[test]
+fn test_apply_move_update() {
+    // the body of f is moved to h and its argument is updated
+    let buggy = r#"class A{void f(){g(1);}void h(){}}"#;
+    let fixed = r#"class A{void f(){}void h(){g(2);}}"#;
+    check_apply(buggy, fixed);
+}
+
+#[test]
+fn test_apply_deleted_subtrees() {
+    let buggy = r#"class A{void f(){if(a){b();}else{c();}}int x;}"#;
+    let fixed = r#"class A{void f(){}}"#;
+    check_apply(buggy, fixed);
+}
+
+#[test]
+fn test_group_operations() {
+    use hyper_diff::actions::operations::{self, Role};
+    use hyperast::types::HyperType;
+    use hyperast_gen_ts_java::types::{TType, Type};
+    let buggy = r#"class A{void f(int a){if(a){}}}"#;
+    let fixed = r#"class A{void g(int a, int b){if(b){}}}"#;
+    let mut stores = SimpleStores::default();
+    let mut md_cache = Default::default();
+    let (src_tr, dst_tr) = parse_string_pair(&mut stores, &mut md_cache, &buggy, &fixed);
+    let stores = stores.change_type_store::<hyperast_gen_ts_java::types::TStore>();
+    let src = src_tr.local.compressed_node;
+    let dst = dst_tr.local.compressed_node;
+
+    let actions = algorithms::gumtree::diff(&stores, &src, &dst)
+        .actions
+        .unwrap();
+    let roles = |parent: Option<&TType>, ty: &TType| -> Option<Role> {
+        let java = |t: &TType| t.as_any().downcast_ref::<Type>().copied();
+        operations::type_trait_role(parent.and_then(java).as_ref(), &java(ty)?)
+    };
+    let ops = operations::group_actions(&stores, &src, &dst, &actions, &roles).unwrap();
+    let ops: Vec<_> = ops.iter().map(|op| op.to_string()).collect();
+    assert!(ops.contains(&"method renamed".to_string()));
+    assert!(ops.contains(&"parameter added".to_string()));
+    assert!(ops.contains(&"condition changed".to_string()));
+}
+
 #[test]
 fn test_crash1() {
     // https://github.com/GumTreeDiff/datasets/tree/2bd8397f5939233a7d6205063bac9340d59f5165/defects4j/{buggy,fixed}/*/[0-9]+/*
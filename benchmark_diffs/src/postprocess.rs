@@ -534,8 +534,8 @@ impl SimpleJsonPostProcess {
         let mut formator_src = FormatCached::from((stores, src_arena, src_tr, with_p, with_lsib));
         let mut formator_dst = FormatCached::from((stores, dst_arena, dst_tr, with_p, with_lsib));
         let mut formator = |a, b| diff_output::Match {
-            src: diff_output::Tree::from_pos(stores, formator_src.format(a)),
-            dest: diff_output::Tree::from_pos(stores, formator_dst.format(b)),
+            src: diff_output::Tree::from_pos(&stores, formator_src.format(a)),
+            dest: diff_output::Tree::from_pos(&stores, formator_dst.format(b)),
         };
         use hashbrown::HashSet;
         let now = Instant::now();
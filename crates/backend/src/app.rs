@@ -3,7 +3,7 @@ use std::time::Duration;
 use axum::{
     error_handling::HandleErrorLayer,
     response::{IntoResponse, Response},
-    routing::{get, post},
+    routing::{delete, get, post},
     BoxError, Json, Router,
 };
 use http::StatusCode;
@@ -11,7 +11,7 @@ use tower::ServiceBuilder;
 use tower_http::trace::TraceLayer;
 
 use crate::{
-    commit, fetch, file, pull_requests, querying,
+    commit, diff, fetch, file, inventory, jobs, pull_requests, querying,
     scriptingv1::{self, ScriptContent, ScriptContentDepth, ScriptingError, ScriptingParam},
     smells, track, view, SharedState,
 };
@@ -44,6 +44,7 @@ impl IntoResponse for crate::tsg::QueryingError {
 mod tsg {
     #[derive(serde::Deserialize)]
     pub struct Param {
+        forge: String,
         user: String,
         name: String,
         commit: String,
@@ -86,11 +87,11 @@ pub fn scripting_app(_st: SharedState) -> Router<SharedState> {
         .layer(TraceLayer::new_for_http());
     Router::new()
         .route(
-            "/script/github/:user/:name/:commit",
+            "/script/:forge/:user/:name/:commit",
             post(scripting).layer(scripting_service_config.clone()),
         )
         .route(
-            "/script-depth/github/:user/:name/:commit",
+            "/script-depth/:forge/:user/:name/:commit",
             post(scripting_depth).layer(scripting_service_config.clone()),
         )
         .route("/sharing-scripts/shared-db", get(crate::ws::connect_db))
@@ -182,15 +183,15 @@ pub fn querying_app(_st: SharedState) -> Router<SharedState> {
         .layer(TraceLayer::new_for_http());
     Router::new()
         .route(
-            "/query/github/:user/:name/*commit",
+            "/query/:forge/:user/:name/*commit",
             post(querying).layer(querying_service_config.clone()), // .with_state(Arc::clone(&shared_state)),
         )
         .route(
-            "/query-st/github/:user/:name/*commit",
+            "/query-st/:forge/:user/:name/*commit",
             post(querying_streamed).layer(querying_service_config.clone()), // .with_state(Arc::clone(&shared_state)),
         )
         .route(
-            "/query-differential/github/:user/:name/:commit/:baseline",
+            "/query-differential/:forge/:user/:name/:commit/:baseline",
             post(querying_differential).layer(querying_service_config.clone()), // .with_state(Arc::clone(&shared_state)),
         )
         .route(
@@ -234,7 +235,7 @@ pub fn tsg_app(_st: SharedState) -> Router<SharedState> {
         .layer(TraceLayer::new_for_http());
     Router::new()
         .route(
-            "/tsg/github/:user/:name/:commit",
+            "/tsg/:forge/:user/:name/:commit",
             post(tsg).layer(tsg_service_config.clone()), // .with_state(Arc::clone(&shared_state)),
         )
         .route(
@@ -278,11 +279,11 @@ pub fn smells_app(_st: SharedState) -> Router<SharedState> {
         .layer(TraceLayer::new_for_http());
     Router::new()
         .route(
-            "/smells/github/:user/:name/:commit/:len",
+            "/smells/:forge/:user/:name/:commit/:len",
             post(smells).layer(smells_service_config.clone()),
         )
         .route(
-            "/smells_ex_from_diffs/github/:user/:name/:commit/:len",
+            "/smells_ex_from_diffs/:forge/:user/:name/:commit/:len",
             post(smells_ex_from_diffs).layer(smells_service_config.clone()),
         )
 }
@@ -300,7 +301,7 @@ pub fn fetch_git_file(_st: SharedState) -> Router<SharedState> {
         .timeout(Duration::from_secs(10))
         .layer(TraceLayer::new_for_http());
     Router::new().route(
-        "/file/github/:user/:name/:commit/*file",
+        "/file/:forge/:user/:name/:commit/*file",
         get(file).layer(service_config.clone()), // .with_state(Arc::clone(&shared_state)),
     )
 }
@@ -328,17 +329,21 @@ pub fn track_code_route(_st: SharedState) -> Router<SharedState> {
         .layer(TraceLayer::new_for_http());
     Router::new()
         .route(
-            "/track/github/:user/:name/:commit/*file",
+            "/track/:forge/:user/:name/:commit/*file",
             get(track_code).layer(service_config.clone()), // .with_state(Arc::clone(&shared_state)),
         )
         .route(
-            "/track_at_path/github/:user/:name/:commit/*path",
+            "/track_at_path/:forge/:user/:name/:commit/*path",
             get(track_code_at_path).layer(service_config.clone()),
         )
         .route(
-            "/track_at_path_with_changes/github/:user/:name/:commit/*path",
+            "/track_at_path_with_changes/:forge/:user/:name/:commit/*path",
             get(track_code_at_path_with_changes).layer(service_config.clone()),
         )
+        .route(
+            "/track_in_dag/:forge/:user/:name/:commit/*path",
+            get(track_code_in_dag).layer(service_config.clone()),
+        )
 }
 
 // #[axum_macros::debug_handler]
@@ -369,6 +374,46 @@ async fn track_code_at_path_with_changes(
     dbg!(&query);
     track::track_code_at_path_with_changes(state, path, query)
 }
+async fn track_code_in_dag(
+    axum::extract::Path(path): axum::extract::Path<track::TrackingAtPathParam>,
+    axum::extract::State(state): axum::extract::State<SharedState>,
+    axum::extract::Query(query): axum::extract::Query<track::TrackingQuery>,
+) -> impl IntoResponse {
+    track::track_code_in_dag(state, path, query)
+}
+
+pub fn diff_route(_st: SharedState) -> Router<SharedState> {
+    let service_config = ServiceBuilder::new()
+        .layer(HandleErrorLayer::new(|e: BoxError| async move {
+            dbg!(e);
+        }))
+        .load_shed()
+        .concurrency_limit(8)
+        .buffer(20)
+        .rate_limit(2, Duration::from_secs(2))
+        .timeout(Duration::from_secs(60))
+        .layer(TraceLayer::new_for_http());
+    Router::new()
+        .route(
+            "/diff/:forge/:user/:name/:before/:after/*path",
+            get(diff_commits).layer(service_config.clone()),
+        )
+        .route(
+            "/diff/:forge/:user/:name/:before/:after",
+            get(diff_commits).layer(service_config.clone()),
+        )
+}
+
+async fn diff_commits(
+    axum::extract::Path(path): axum::extract::Path<diff::Param>,
+    axum::extract::Query(query): axum::extract::Query<diff::Query>,
+    axum::extract::State(state): axum::extract::State<SharedState>,
+) -> axum::response::Result<Json<diff::DiffRes>> {
+    dbg!(&path);
+    diff::diff(state, path, query)
+        .map(Json)
+        .map_err(|err| err.into())
+}
 
 pub fn view_code_route(_st: SharedState) -> Router<SharedState> {
     let service_config = ServiceBuilder::new()
@@ -384,17 +429,23 @@ pub fn view_code_route(_st: SharedState) -> Router<SharedState> {
         .layer(TraceLayer::new_for_http());
     Router::new()
         .route(
-            "/view/github/:user/:name/:commit/*path",
+            "/view/:forge/:user/:name/:commit/*path",
             get(view_code).layer(service_config.clone()), // .with_state(Arc::clone(&shared_state)),
         )
         .route(
-            "/view/github/:user/:name/:commit/",
+            "/view/:forge/:user/:name/:commit/",
             get(view_code).layer(service_config.clone()), // .with_state(Arc::clone(&shared_state)),
         )
         .route(
-            "/view/:id",
+            "/view-node/:id",
             get(view_code_with_node_id).layer(service_config.clone()), // .with_state(Arc::clone(&shared_state)),
         )
+        // kept for existing clients, the segment is a node id,
+        // it is named like in the routes above as the router forbids distinct names at the same place
+        .route(
+            "/view/:forge",
+            get(view_code_with_node_id).layer(service_config.clone()),
+        )
 }
 
 // #[axum_macros::debug_handler]
@@ -426,11 +477,11 @@ pub fn fetch_code_route(_st: SharedState) -> Router<SharedState> {
         .layer(TraceLayer::new_for_http());
     Router::new()
         .route(
-            "/fetch/github/:user/:name/:commit/*path",
+            "/fetch/:forge/:user/:name/:commit/*path",
             get(fetch_code).layer(service_config.clone()), // .with_state(Arc::clone(&shared_state)),
         )
         .route(
-            "/fetch/github/:user/:name/:commit/",
+            "/fetch/:forge/:user/:name/:commit/",
             get(fetch_code).layer(service_config.clone()), // .with_state(Arc::clone(&shared_state)),
         )
         .route(
@@ -504,9 +555,13 @@ pub fn commit_metadata_route(_st: SharedState) -> Router<SharedState> {
             get(commit_metadata).layer(service_config.clone()), // .with_state(Arc::clone(&shared_state)),
         )
         .route(
-            "/pr/github/:user/:name/:version",
+            "/pr/:forge/:user/:name/:version",
             get(pull_requests::pr_commits).layer(service_config.clone()),
         )
+        .route(
+            "/pr/:forge/:user/:name/number/:number",
+            get(pull_requests::pr_by_number).layer(service_config.clone()),
+        )
         .route(
             "/fork/github/:user/:name/:other_user/:other_name/:head",
             post(add_remote).layer(service_config.clone()),
@@ -530,6 +585,204 @@ async fn add_remote(
     dbg!(&path);
     commit::add_remote(state, path).map_err(|err| err.into())
 }
+pub fn jobs_route(_st: SharedState) -> Router<SharedState> {
+    let service_config = ServiceBuilder::new()
+        .layer(HandleErrorLayer::new(|e: BoxError| async move {
+            dbg!(e);
+        }))
+        .concurrency_limit(16)
+        .buffer(64)
+        .rate_limit(60, Duration::from_secs(1))
+        .timeout(Duration::from_secs(10))
+        .layer(TraceLayer::new_for_http());
+    Router::new()
+        .route(
+            "/process/:forge/:user/:name/*commit",
+            post(submit_job).layer(service_config.clone()),
+        )
+        .route("/jobs", get(list_jobs).layer(service_config.clone()))
+        .route("/jobs/:id", get(job_status).layer(service_config.clone()))
+        .route(
+            "/jobs/:id/cancel",
+            post(cancel_job).layer(service_config.clone()),
+        )
+}
+
+async fn submit_job(
+    axum::extract::Path(path): axum::extract::Path<jobs::Param>,
+    axum::extract::State(state): axum::extract::State<SharedState>,
+    axum::extract::Json(content): axum::extract::Json<jobs::Content>,
+) -> axum::response::Result<(StatusCode, Json<jobs::JobStatus>)> {
+    let r = jobs::submit(state, path, content)?;
+    Ok((StatusCode::ACCEPTED, r))
+}
+
+async fn list_jobs(
+    axum::extract::State(state): axum::extract::State<SharedState>,
+) -> Json<Vec<jobs::JobStatus>> {
+    jobs::list(state)
+}
+
+async fn job_status(
+    axum::extract::Path(path): axum::extract::Path<jobs::JobParam>,
+    axum::extract::State(state): axum::extract::State<SharedState>,
+) -> axum::response::Result<Json<jobs::JobStatus>> {
+    jobs::status(state, path).map_err(|err| err.into())
+}
+
+async fn cancel_job(
+    axum::extract::Path(path): axum::extract::Path<jobs::JobParam>,
+    axum::extract::State(state): axum::extract::State<SharedState>,
+) -> axum::response::Result<Json<jobs::JobStatus>> {
+    jobs::cancel(state, path).map_err(|err| err.into())
+}
+
+pub fn inventory_route(_st: SharedState) -> Router<SharedState> {
+    let service_config = ServiceBuilder::new()
+        .layer(HandleErrorLayer::new(|e: BoxError| async move {
+            dbg!(e);
+        }))
+        .concurrency_limit(16)
+        .buffer(64)
+        .rate_limit(60, Duration::from_secs(1))
+        .timeout(Duration::from_secs(10))
+        .layer(TraceLayer::new_for_http());
+    Router::new()
+        .route(
+            "/repositories",
+            get(list_repositories).layer(service_config.clone()),
+        )
+        .route(
+            "/repositories/:forge/:user/:name/commits",
+            get(repository_commits).layer(service_config.clone()),
+        )
+        .route(
+            "/repositories/:forge/:user/:name/commits/:commit",
+            get(commit_stats).layer(service_config.clone()),
+        )
+        .route(
+            "/repositories/:forge/:user/:name/commits/:commit/parse-errors",
+            get(commit_parse_errors).layer(service_config.clone()),
+        )
+        .route(
+            "/repositories/:forge/:user/:name/labels/:label",
+            get(label_occurrences).layer(service_config.clone()),
+        )
+        .route("/configs", get(list_configs).layer(service_config.clone()))
+        .route("/memory", get(memory_usage).layer(service_config.clone()))
+}
+
+async fn list_repositories(
+    axum::extract::State(state): axum::extract::State<SharedState>,
+) -> Json<Vec<inventory::Repository>> {
+    inventory::repositories(state)
+}
+
+async fn list_configs(
+    axum::extract::State(state): axum::extract::State<SharedState>,
+) -> Json<Vec<inventory::Config>> {
+    inventory::configs(state)
+}
+
+async fn repository_commits(
+    axum::extract::Path(path): axum::extract::Path<inventory::Param>,
+    axum::extract::State(state): axum::extract::State<SharedState>,
+) -> axum::response::Result<Json<inventory::RepositoryCommits>> {
+    inventory::commits(state, path).map_err(|err| err.into())
+}
+
+async fn commit_stats(
+    axum::extract::Path(path): axum::extract::Path<inventory::CommitParam>,
+    axum::extract::State(state): axum::extract::State<SharedState>,
+) -> axum::response::Result<Json<inventory::CommitStats>> {
+    inventory::commit(state, path).map_err(|err| err.into())
+}
+
+async fn commit_parse_errors(
+    axum::extract::Path(path): axum::extract::Path<inventory::CommitParam>,
+    axum::extract::State(state): axum::extract::State<SharedState>,
+) -> axum::response::Result<Json<Vec<inventory::FileParseErrors>>> {
+    inventory::parse_errors(state, path).map_err(|err| err.into())
+}
+
+async fn label_occurrences(
+    axum::extract::Path(path): axum::extract::Path<inventory::LabelParam>,
+    axum::extract::State(state): axum::extract::State<SharedState>,
+) -> axum::response::Result<Json<inventory::LabelOccurrences>> {
+    inventory::label_occurrences(state, path).map_err(|err| err.into())
+}
+
+async fn memory_usage(
+    axum::extract::State(state): axum::extract::State<SharedState>,
+) -> Json<inventory::Memory> {
+    inventory::memory(state)
+}
+
+/// Only served with an admin token, see [`crate::AppState::admin_token`]
+pub fn admin_route(st: SharedState) -> Router<SharedState> {
+    let service_config = ServiceBuilder::new()
+        .layer(HandleErrorLayer::new(|e: BoxError| async move {
+            dbg!(e);
+        }))
+        .concurrency_limit(1)
+        .buffer(8)
+        .rate_limit(1, Duration::from_secs(1))
+        .timeout(Duration::from_secs(60))
+        .layer(TraceLayer::new_for_http());
+    Router::new()
+        .route(
+            "/admin/purge-caches",
+            post(purge_caches).layer(service_config.clone()),
+        )
+        .route(
+            "/admin/repositories/:forge/:user/:name",
+            delete(unregister_repository).layer(service_config.clone()),
+        )
+        .route(
+            "/admin/gc",
+            post(collect_garbage).layer(service_config.clone()),
+        )
+        .route_layer(axum::middleware::from_fn_with_state(st, check_admin_token))
+}
+
+/// Rejects the requests without the admin token as bearer token
+async fn check_admin_token(
+    axum::extract::State(state): axum::extract::State<SharedState>,
+    headers: http::HeaderMap,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> Response {
+    let token = (headers.get(http::header::AUTHORIZATION))
+        .and_then(|x| x.to_str().ok())
+        .and_then(|x| x.strip_prefix("Bearer "));
+    match (state.admin_token.get(), token) {
+        (Some(expected), Some(token)) if expected == token => next.run(request).await,
+        // admin routes are disabled
+        (None, _) => StatusCode::NOT_FOUND.into_response(),
+        _ => StatusCode::UNAUTHORIZED.into_response(),
+    }
+}
+
+async fn purge_caches(
+    axum::extract::State(state): axum::extract::State<SharedState>,
+) -> Json<inventory::Memory> {
+    inventory::purge_caches(state)
+}
+
+async fn collect_garbage(
+    axum::extract::State(state): axum::extract::State<SharedState>,
+    axum::extract::Json(content): axum::extract::Json<inventory::Retention>,
+) -> axum::response::Result<Json<inventory::Collected>> {
+    inventory::collect_garbage(state, content).map_err(|err| err.into())
+}
+
+async fn unregister_repository(
+    axum::extract::Path(path): axum::extract::Path<inventory::Param>,
+    axum::extract::State(state): axum::extract::State<SharedState>,
+) -> axum::response::Result<()> {
+    inventory::unregister(state, path).map_err(|err| err.into())
+}
+
 pub struct Timed<T> {
     pub(crate) time: f64,
     pub(crate) content: T,
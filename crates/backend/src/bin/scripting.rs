@@ -98,7 +98,7 @@ fn scripting(
         .repositories
         .write()
         .unwrap()
-        .register_config_with_prepro(repo_spec.clone(), config, script.into());
+        .register_config_with_prepro(repo_spec.clone(), config, script.into())?;
     // state
     //     .repositories
     //     .write()
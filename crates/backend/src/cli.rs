@@ -22,8 +22,82 @@ pub struct Options {
     ///
     /// use the following syntax: <forge>/<user>/<name>:<config>
     /// example: github.com/INRIA/spoon:Java
+    ///
+    /// any git url or local path can also be used in place of <forge>/<user>/<name>,
+    /// they are then reached in routes with <host>/<user>/<name> or local/<user>/<name>
+    /// example: https://git.example.org/team/project.git:Java
+    /// example: /srv/git/team/project.git:Java
     #[clap(short, long)]
     pub repository: Vec<RepoConfig>,
+
+    /// a git server from which unregistered repositories can be cloned through routes (multiple uses)
+    ///
+    /// the known forges are always allowed
+    /// example: git.example.org
+    #[clap(long)]
+    pub allow_host: Vec<String>,
+
+    /// a directory under which local repositories can be opened (multiple uses)
+    ///
+    /// none by default, the local repositories given with --repository are always allowed
+    /// example: /srv/git
+    #[clap(long)]
+    pub local_root: Vec<std::path::PathBuf>,
+
+    /// where repositories are cloned
+    #[clap(long, default_value = hyperast_vcs_git::git::DEFAULT_CLONE_ROOT)]
+    pub clone_dir: std::path::PathBuf,
+
+    /// snapshot of the processed repositories
+    ///
+    /// loaded on startup if it exists, written back on shutdown
+    #[clap(long)]
+    pub snapshot: Option<std::path::PathBuf>,
+
+    /// append-only log of processed commits
+    ///
+    /// replayed on startup, so processing survives crashes
+    #[clap(long)]
+    pub store_log: Option<std::path::PathBuf>,
+
+    /// number of workers processing the submitted jobs
+    #[clap(long, default_value_t = 2)]
+    pub workers: usize,
+
+    /// files larger than that, in bytes, are not parsed but replaced by placeholders
+    #[clap(long)]
+    pub max_file_size: Option<usize>,
+
+    /// parsing a file for longer than that, in milliseconds, is abandoned,
+    /// the file is then replaced by a placeholder
+    #[clap(long)]
+    pub parse_timeout: Option<u64>,
+
+    /// parse the new files of upcoming commits with that many threads,
+    /// by default files are parsed one by one while being processed
+    #[clap(long)]
+    pub parse_threads: Option<usize>,
+
+    /// number of upcoming commits whose new files are parsed together, see --parse-threads
+    #[clap(long, default_value_t = 8)]
+    pub parse_window: usize,
+
+    /// resolve pull requests from this JSON file instead of querying forges,
+    /// e.g. to work offline, see [`crate::pull_requests::Fixtures`]
+    #[clap(long)]
+    pub pr_fixtures: Option<std::path::PathBuf>,
+
+    /// maintain an index from labels to the files containing them,
+    /// to find where an identifier occurs and skip commits in queries with `#eq?` predicates
+    #[clap(long)]
+    pub label_index: bool,
+
+    /// serve the /admin routes, e.g. to purge caches or collect garbage,
+    /// requests must then have an `Authorization: Bearer <token>` header
+    ///
+    /// without it, admin routes are not served
+    #[clap(long)]
+    pub admin_token: Option<String>,
 }
 
 pub struct RepoConfig {
@@ -35,7 +109,8 @@ impl std::str::FromStr for RepoConfig {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (repo, config) = s.split_once(":").ok_or("")?;
+        // urls also contain ':'
+        let (repo, config) = s.rsplit_once(":").ok_or("missing :<config>")?;
         let repo = repo.parse()?;
         let config = config.parse()?;
 
@@ -0,0 +1,302 @@
+use hyper_diff::{
+    actions::{
+        action_vec::ActionsVec,
+        operations::{self, Role, Side},
+        script_generator2::ScriptGenerator,
+    },
+    algorithms,
+    decompressed_tree_store::{
+        bfs_wrapper::SimpleBfsMapper, complete_post_order_ref, ShallowDecompressedTreeStore,
+    },
+    matchers::{
+        mapping_store::{MappingStore, VecStore},
+        Decompressible, Mapping,
+    },
+};
+use hyperast::{
+    store::defaults::NodeIdentifier,
+    types::{AnyType, HyperType, TypeTrait},
+};
+use hyperast_diff_report::{locate_actions, DiffRoot, A};
+pub use hyperast_diff_report::{Action, ActionKind, Location};
+use hyperast_vcs_git::preprocessed::child_at_path_tracked;
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+
+use crate::{no_space, SharedState};
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Param {
+    forge: String,
+    user: String,
+    name: String,
+    before: String,
+    after: String,
+    /// limits the diff to this file
+    path: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct Query {
+    /// by default, whole commits are diffed with the matching of `/track` (and share its cache),
+    /// and files with gumtree_partial_lazy
+    algo: Option<Algorithm>,
+    /// also groups actions into high-level operations, e.g. "method renamed"
+    group: Option<bool>,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Algorithm {
+    Gumtree,
+    GumtreeLazy,
+    GumtreePartialLazy,
+}
+
+#[derive(Serialize, Debug)]
+pub struct DiffRes {
+    pub prepare_time: f64,
+    pub diff_time: f64,
+    pub mappings: usize,
+    pub actions: Vec<Action>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operations: Option<Vec<Operation>>,
+}
+
+/// A group of actions, see [`hyper_diff::actions::operations`]
+#[derive(Serialize, Debug)]
+pub struct Operation {
+    /// e.g. "method renamed", "parameter added" or "statement moved"
+    pub kind: String,
+    /// the node around the grouped actions, in the old version for removals, updates and moves,
+    /// in the new version otherwise
+    pub at: Location,
+    /// the declaration enclosing `at`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decl: Option<Location>,
+    /// offsets of the grouped actions in `actions`
+    pub actions: Vec<usize>,
+}
+
+type Stores<'a> = hyperast::store::SimpleStores<
+    hyperast_vcs_git::TStore,
+    no_space::NoSpaceNodeStoreWrapper<'a>,
+    &'a hyperast::store::labels::LabelStore,
+>;
+
+pub fn diff(state: SharedState, params: Param, query: Query) -> Result<DiffRes, String> {
+    let now = Instant::now();
+    let Param {
+        forge,
+        user,
+        name,
+        before,
+        after,
+        path: file,
+    } = params;
+    let repo_spec = crate::utils::resolve_repo(&state, &forge, &user, &name)?;
+    let repo = state
+        .repositories
+        .read()
+        .unwrap()
+        .get_config(repo_spec)
+        .ok_or_else(|| "missing config for repository".to_string())?;
+    let mut repo = repo.try_fetch()?;
+    log::info!("done cloning {}", repo.spec);
+    let mut process = |commit: &str| -> Result<_, String> {
+        crate::utils::handle_pre_processing(&state, &mut repo, "", commit, 1)
+            .map_err(|e| e.to_string())?
+            .first()
+            .copied()
+            .ok_or_else(|| format!("{} not found", commit))
+    };
+    let src_oid = process(&before)?;
+    let dst_oid = process(&after)?;
+    log::info!(
+        "done construction of {src_oid} and {dst_oid} in {}",
+        repo.spec
+    );
+    let repositories = state.repositories.read().unwrap();
+    let src_tr = repositories
+        .get_commit(&repo.config, &src_oid)
+        .ok_or("missing src commit")?
+        .ast_root;
+    let dst_tr = repositories
+        .get_commit(&repo.config, &dst_oid)
+        .ok_or("missing dst commit")?
+        .ast_root;
+    let with_spaces_stores = &repositories.processor.main_stores;
+    let stores = &no_space::as_nospaces2(with_spaces_stores);
+
+    // the roots of the diff, and the offsets leading to them
+    let ((src, src_offsets), (dst, dst_offsets)) = match &file {
+        Some(file) => {
+            let resolve = |root| {
+                child_at_path_tracked(with_spaces_stores, root, file.split("/"))
+                    .map(|(n, o)| (n, o.into_iter().map(|x| x as u16).collect::<Vec<_>>()))
+                    .ok_or_else(|| format!("{} not found", file))
+            };
+            (resolve(src_tr)?, resolve(dst_tr)?)
+        }
+        None => ((src_tr, vec![]), (dst_tr, vec![])),
+    };
+    let prepare_time = now.elapsed().as_secs_f64();
+    let now = Instant::now();
+    if src == dst {
+        return Ok(DiffRes {
+            prepare_time,
+            diff_time: now.elapsed().as_secs_f64(),
+            mappings: 0,
+            actions: vec![],
+            operations: query.group.filter(|x| *x).map(|_| vec![]),
+        });
+    }
+
+    let (mappings, actions) = match (query.algo, &file) {
+        (None, None) => cached_diff(&state, stores, src, dst)?,
+        (algo, _) => {
+            macro_rules! run {
+                ($algo:ident) => {{
+                    let r = algorithms::$algo::diff(stores, &src, &dst);
+                    let mappings = r.summarize().mappings;
+                    let actions = r.actions.ok_or("failed to generate the edit script")?;
+                    (mappings, actions)
+                }};
+            }
+            match algo.unwrap_or(Algorithm::GumtreePartialLazy) {
+                Algorithm::Gumtree => run!(gumtree),
+                Algorithm::GumtreeLazy => run!(gumtree_lazy),
+                Algorithm::GumtreePartialLazy => run!(gumtree_partial_lazy),
+            }
+        }
+    };
+    let diff_time = now.elapsed().as_secs_f64();
+
+    let src_root = DiffRoot {
+        commit: src_tr,
+        offsets: &src_offsets,
+        node: src,
+    };
+    let dst_root = DiffRoot {
+        commit: dst_tr,
+        offsets: &dst_offsets,
+        node: dst,
+    };
+    let result = locate_actions(with_spaces_stores, src_root, dst_root, &actions);
+    let operations = if query.group == Some(true) {
+        let ops = operations::group_actions(stores, &src, &dst, &actions, &role)?;
+        let locate = |side, path: &[u16]| match side {
+            Side::Src => src_root.locate(with_spaces_stores, path.iter().copied()),
+            Side::Dst => dst_root.locate(with_spaces_stores, path.iter().copied()),
+        };
+        let ops = ops.into_iter().map(|op| Operation {
+            kind: op.to_string(),
+            at: locate(op.side, &op.path),
+            decl: op.decl.as_ref().map(|decl| locate(op.side, decl)),
+            actions: op.actions,
+        });
+        Some(ops.collect())
+    } else {
+        None
+    };
+    Ok(DiffRes {
+        prepare_time,
+        diff_time,
+        mappings,
+        actions: result,
+        operations,
+    })
+}
+
+/// Roles of java and c++ nodes, used to group actions into operations
+fn role(parent: Option<&AnyType>, ty: &AnyType) -> Option<Role> {
+    use hyperast_gen_ts_cpp::types::Type as Cpp;
+    use hyperast_gen_ts_java::types::Type as Java;
+    if let Some(t) = ty.as_any().downcast_ref::<Java>() {
+        let parent = parent.and_then(|p| p.as_any().downcast_ref::<Java>());
+        match t {
+            Java::ImportDeclaration => Some(Role::Import),
+            t => operations::type_trait_role(parent, t),
+        }
+    } else if let Some(t) = ty.as_any().downcast_ref::<Cpp>() {
+        let parent = parent.and_then(|p| p.as_any().downcast_ref::<Cpp>());
+        match t {
+            Cpp::PreprocInclude | Cpp::UsingDeclaration => Some(Role::Import),
+            Cpp::ConditionClause => Some(Role::Condition),
+            // the name of a function is in its declarator
+            t if t.is_identifier() && parent == Some(&Cpp::FunctionDeclarator) => Some(Role::Name),
+            t => operations::type_trait_role(parent, t),
+        }
+    } else {
+        None
+    }
+}
+
+/// Diff whole commits with the matching used by `/track`, sharing its caches.
+fn cached_diff(
+    state: &crate::AppState,
+    stores: &Stores,
+    src_tr: NodeIdentifier,
+    dst_tr: NodeIdentifier,
+) -> Result<(usize, ActionsVec<A>), String> {
+    let pair = crate::utils::get_pair_simp(&state.partial_decomps, stores, &src_tr, &dst_tr);
+    let mapped = match state.mappings_alone.entry((src_tr, dst_tr)) {
+        dashmap::mapref::entry::Entry::Occupied(entry) => entry.into_ref().downgrade(),
+        dashmap::mapref::entry::Entry::Vacant(entry) => {
+            let (src_arena, dst_arena) = (pair.0.get_mut(), pair.1.get_mut());
+            let mut mapper = hyper_diff::matchers::Mapper {
+                hyperast: stores,
+                mapping: Mapping {
+                    src_arena: Decompressible {
+                        hyperast: stores,
+                        decomp: src_arena,
+                    },
+                    dst_arena: Decompressible {
+                        hyperast: stores,
+                        decomp: dst_arena,
+                    },
+                    mappings: VecStore::default(),
+                },
+            };
+            mapper.mapping.mappings.topit(
+                mapper.mapping.src_arena.len(),
+                mapper.mapping.dst_arena.len(),
+            );
+            crate::matching::full2(&mut mapper);
+            let vec_store = mapper.mappings.clone();
+            entry
+                .insert((crate::MappingStage::Bottomup, vec_store))
+                .downgrade()
+        }
+    };
+    let (src_arena, dst_arena) = (pair.0.get_mut(), pair.1.get_mut());
+    let mut src_arena = Decompressible {
+        hyperast: stores,
+        decomp: src_arena,
+    };
+    let mut dst_arena = Decompressible {
+        hyperast: stores,
+        decomp: dst_arena,
+    };
+    src_arena.complete_subtree(&src_arena.root());
+    let src_arena = complete_post_order_ref::CompletePostOrder::from(&*src_arena.decomp);
+    dst_arena.complete_subtree(&dst_arena.root());
+    let dst_arena = complete_post_order_ref::CompletePostOrder::from(&*dst_arena.decomp);
+    let dst_arena = SimpleBfsMapper::with_store(
+        stores,
+        Decompressible {
+            hyperast: stores,
+            decomp: dst_arena,
+        },
+    );
+    let mapping = Mapping {
+        src_arena: Decompressible {
+            hyperast: stores,
+            decomp: src_arena,
+        },
+        dst_arena,
+        mappings: mapped.1.clone(),
+    };
+    let actions = ScriptGenerator::compute_actions(stores, &mapping)?;
+    Ok((mapping.mappings.len(), actions))
+}
@@ -17,6 +17,7 @@ use crate::{app::Timed, SharedState};
 
 #[derive(Deserialize, Clone, Debug)]
 pub struct Parameters {
+    forge: String,
     user: String,
     name: String,
     commit: String,
@@ -41,6 +42,9 @@ pub struct FetchedRes {
 pub struct FetchedLabels {
     label_ids: Vec<nodes::fetched::LabelIdentifier>,
     labels: Vec<String>,
+    /// labels fetched under another generation are stale,
+    /// see [`hyperast::store::labels::LabelStore::generation`]
+    labels_generation: usize,
 }
 
 #[derive(Serialize)]
@@ -48,25 +52,28 @@ pub struct FetchedNodes {
     #[serde(skip_serializing_if = "Vec::is_empty")]
     root: Vec<NodeIdentifier>,
     node_store: fetched::SimplePacked<&'static str>,
+    /// of the label identifiers in `node_store`, see [`FetchedLabels::labels_generation`]
+    labels_generation: usize,
 }
 
 pub fn fetch(mut state: SharedState, path: Parameters) -> Result<FetchedNodes, String> {
     let now = Instant::now();
     let Parameters {
+        forge,
         user,
         name,
         commit,
         path,
     } = path;
     dbg!(&path);
-    let repo_spec = hyperast_vcs_git::git::Forge::Github.repo(user, name);
+    let repo_spec = crate::utils::resolve_repo(&state, &forge, &user, &name)?;
     let repo = state
         .repositories
         .read()
         .unwrap()
         .get_config(repo_spec)
         .ok_or_else(|| "missing config for repository".to_string())?;
-    let mut repo = repo.fetch();
+    let mut repo = repo.try_fetch()?;
     log::info!("done cloning {}", repo.spec);
 
     let commits = crate::utils::handle_pre_processing(&state, &mut repo, "", &commit, 2)
@@ -101,6 +108,7 @@ pub fn fetch(mut state: SharedState, path: Parameters) -> Result<FetchedNodes, S
     Ok(FetchedNodes {
         node_store,
         root: ids,
+        labels_generation: stores.label_store.generation(),
     })
 }
 
@@ -242,6 +250,9 @@ pub fn fetch_with_node_ids<'a>(
         content: FetchedNodes {
             node_store,
             root: vec![],
+            labels_generation: (repositories.processor.main_stores)
+                .label_store
+                .generation(),
         },
     })
 }
@@ -263,18 +274,25 @@ pub fn fetch_labels<'a>(
     let repositories = get_mut.repositories.read().unwrap();
     let node_store = &repositories.processor.main_stores.node_store;
     let label_store = &repositories.processor.main_stores.label_store;
-    use hyperast::types::LabelStore;
+    // identifiers from before a compaction can be out of range
     let (label_ids, labels) = ids
         .map(|x| {
-            (
-                nodes::fetched::LabelIdentifier::from(x),
-                label_store.resolve(&x).to_string(),
-            )
+            let id = nodes::fetched::LabelIdentifier::from(x);
+            let l = label_store
+                .try_resolve(&x)
+                .ok_or_else(|| format!("unknown label {}", u32::from(id)))?;
+            Ok((id, l.to_string()))
         })
+        .collect::<Result<Vec<_>, String>>()?
+        .into_iter()
         .unzip();
     Ok(Timed {
         time: now.elapsed().as_secs_f64(),
-        content: FetchedLabels { label_ids, labels },
+        content: FetchedLabels {
+            label_ids,
+            labels,
+            labels_generation: label_store.generation(),
+        },
     })
 }
 
@@ -0,0 +1,400 @@
+//! What the backend knows about: registered repositories, processed commits and stores,
+//! along with admin operations on them.
+use std::collections::{BTreeMap, HashMap};
+
+use axum::Json;
+use hyperast::store::defaults::NodeIdentifier;
+use hyperast::types::{LabelStore as _, WithStats};
+use hyperast_vcs_git::{
+    git::{Oid, Repo},
+    parse_errors::ParseErrorKind,
+    Commit,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::SharedState;
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Param {
+    forge: String,
+    user: String,
+    name: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct CommitParam {
+    forge: String,
+    user: String,
+    name: String,
+    commit: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct LabelParam {
+    forge: String,
+    user: String,
+    name: String,
+    label: String,
+}
+
+#[derive(Serialize)]
+pub struct Repository {
+    host: String,
+    user: String,
+    name: String,
+    /// None if it is not a [`hyperast_vcs_git::processing::RepoConfig`]
+    config: Option<String>,
+    /// processed with the config of the repository
+    commits: usize,
+}
+
+#[derive(Serialize)]
+pub struct Config {
+    config: Option<String>,
+    repositories: Vec<String>,
+    /// shared by all the repositories of the config
+    commits: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct RepositoryCommits {
+    repository: String,
+    config: Option<String>,
+    /// the other repositories with the same config, their commits are also listed
+    shared_with: Vec<String>,
+    commits: Vec<CommitStats>,
+}
+
+#[derive(Serialize)]
+pub struct CommitStats {
+    commit: String,
+    parents: Vec<String>,
+    tree: String,
+    /// number of nodes in the ast of the commit
+    size: usize,
+    size_no_spaces: usize,
+    height: usize,
+    /// in milliseconds
+    processing_time: f64,
+    /// difference of heap size during processing, in bytes
+    memory_used: isize,
+    parse_failures: u32,
+}
+
+#[derive(Serialize)]
+pub struct FileParseErrors {
+    /// from the root of the commit
+    path: String,
+    errors: Vec<ParseError>,
+}
+
+#[derive(Serialize)]
+pub struct ParseError {
+    /// ERROR or MISSING, or why the whole file was skipped: TOO_LARGE, TIMED_OUT or CANCELLED
+    kind: &'static str,
+    /// byte range in the file
+    start: usize,
+    end: usize,
+    /// zero-based
+    row: usize,
+    column: usize,
+}
+
+/// For each processed commit containing a label, the paths of the files containing it
+pub type LabelOccurrences = BTreeMap<String, Vec<String>>;
+
+#[derive(Serialize)]
+pub struct Memory {
+    /// heap size of the backend, in bytes, see [`hyperast::utils::memusage_linux`]
+    allocated: isize,
+    /// distinct subtrees in the node store
+    nodes: usize,
+    /// distinct labels in the label store
+    labels: usize,
+}
+
+/// Which commits to keep, see [`hyperast_vcs_git::gc::Retention`]
+#[derive(Deserialize, Clone, Debug)]
+pub struct Retention {
+    /// commits kept per repository, None to keep them all
+    last: Option<usize>,
+    /// per repository, e.g. `github.com/INRIA/spoon`, overrides `last`
+    #[serde(default)]
+    per_repo: HashMap<String, Option<usize>>,
+    #[serde(default)]
+    pinned: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct Collected {
+    nodes: usize,
+    labels: usize,
+    /// incremented if labels were renumbered,
+    /// see [`hyperast::store::labels::LabelStore::generation`]
+    labels_generation: usize,
+    /// after the collection
+    memory: Memory,
+}
+
+fn commit_stats(stores: &hyperast_vcs_git::SimpleStores, oid: Oid, commit: &Commit) -> CommitStats {
+    let root = stores.node_store.resolve(commit.ast_root);
+    CommitStats {
+        commit: oid.to_string(),
+        parents: commit.parents.iter().map(|x| x.to_string()).collect(),
+        tree: commit.tree_oid.to_string(),
+        size: root.size(),
+        size_no_spaces: root.size_no_spaces(),
+        height: root.height(),
+        processing_time: commit.processing_time() as f64 / 1_000_000.,
+        memory_used: commit.memory_used().bytes(),
+        parse_failures: commit.parse_failures(),
+    }
+}
+
+/// The commit designated by `commit` in the clone of `repo`, e.g. an abbreviated oid, a branch or a tag
+fn resolve_commit(repo: &Repo, commit: &str) -> Result<Oid, String> {
+    let repository = repo.try_nofetch()?;
+    let commit =
+        hyperast_vcs_git::git::retrieve_commit(&repository, commit).map_err(|e| e.to_string())?;
+    Ok(commit.id())
+}
+
+pub fn repositories(state: SharedState) -> Json<Vec<Repository>> {
+    let repositories = state.repositories.read().unwrap();
+    let mut r: Vec<_> = repositories
+        .configs()
+        .map(|(repo, config)| Repository {
+            host: repo.host(),
+            user: repo.user().to_string(),
+            name: repo.name().to_string(),
+            config: config.map(|x| format!("{:?}", x)),
+            commits: repositories
+                .processed_commits(repo)
+                .map_or(0, |commits| commits.len()),
+        })
+        .collect();
+    r.sort_by(|a, b| (&a.host, &a.user, &a.name).cmp(&(&b.host, &b.user, &b.name)));
+    Json(r)
+}
+
+pub fn configs(state: SharedState) -> Json<Vec<Config>> {
+    let repositories = state.repositories.read().unwrap();
+    let mut per_config: BTreeMap<Option<String>, Config> = BTreeMap::new();
+    for (repo, config) in repositories.configs() {
+        let config = config.map(|x| format!("{:?}", x));
+        let entry = per_config.entry(config.clone()).or_insert_with(|| {
+            let commits = repositories.processed_commits(repo).unwrap_or_default();
+            Config {
+                config,
+                repositories: vec![],
+                commits: commits.iter().map(|(oid, _)| oid.to_string()).collect(),
+            }
+        });
+        entry.repositories.push(repo.to_string());
+    }
+    Json(per_config.into_values().collect())
+}
+
+pub fn commits(state: SharedState, path: Param) -> Result<Json<RepositoryCommits>, String> {
+    let Param { forge, user, name } = path;
+    let repo = crate::utils::resolve_repo(&state, &forge, &user, &name)?;
+    let repositories = state.repositories.read().unwrap();
+    let commits = repositories
+        .processed_commits(&repo)
+        .ok_or_else(|| format!("{} is not registered", repo))?;
+    let config = repositories
+        .configs()
+        .find(|(r, _)| *r == &repo)
+        .and_then(|(_, config)| config);
+    let shared_with = repositories
+        .configs()
+        .filter(|(r, c)| *r != &repo && *c == config)
+        .map(|(r, _)| r.to_string())
+        .collect();
+    let stores = &repositories.processor.main_stores;
+    let commits = commits
+        .into_iter()
+        .map(|(oid, commit)| commit_stats(stores, oid, commit))
+        .collect();
+    Ok(Json(RepositoryCommits {
+        repository: repo.to_string(),
+        config: config.map(|x| format!("{:?}", x)),
+        shared_with,
+        commits,
+    }))
+}
+
+pub fn commit(state: SharedState, path: CommitParam) -> Result<Json<CommitStats>, String> {
+    let CommitParam {
+        forge,
+        user,
+        name,
+        commit,
+    } = path;
+    let repo = crate::utils::resolve_repo(&state, &forge, &user, &name)?;
+    let oid = resolve_commit(&repo, &commit)?;
+    let repositories = state.repositories.read().unwrap();
+    let config = repositories
+        .get_config(repo.clone())
+        .ok_or_else(|| format!("{} is not registered", repo))?;
+    let c = repositories
+        .get_commit(&config.config, &oid)
+        .ok_or_else(|| format!("{} is not processed", commit))?;
+    let stores = &repositories.processor.main_stores;
+    Ok(Json(commit_stats(stores, oid, c)))
+}
+
+/// The files of a commit whose CST contains ERROR or MISSING nodes, or that were skipped
+pub fn parse_errors(
+    state: SharedState,
+    path: CommitParam,
+) -> Result<Json<Vec<FileParseErrors>>, String> {
+    let CommitParam {
+        forge,
+        user,
+        name,
+        commit,
+    } = path;
+    let repo = crate::utils::resolve_repo(&state, &forge, &user, &name)?;
+    let oid = resolve_commit(&repo, &commit)?;
+    let repositories = state.repositories.read().unwrap();
+    let config = repositories
+        .get_config(repo.clone())
+        .ok_or_else(|| format!("{} is not registered", repo))?;
+    let c = repositories
+        .get_commit(&config.config, &oid)
+        .ok_or_else(|| format!("{} is not processed", commit))?;
+    let processor = &repositories.processor;
+    let files = processor
+        .parse_errors
+        .in_tree(&processor.main_stores, c.ast_root)
+        .into_iter()
+        .map(|(path, file)| FileParseErrors {
+            path,
+            errors: processor
+                .parse_errors
+                .get(&file)
+                .unwrap_or_default()
+                .iter()
+                .map(|e| ParseError {
+                    kind: match e.kind {
+                        ParseErrorKind::Error => "ERROR",
+                        ParseErrorKind::Missing => "MISSING",
+                        ParseErrorKind::TooLarge => "TOO_LARGE",
+                        ParseErrorKind::TimedOut => "TIMED_OUT",
+                        ParseErrorKind::Cancelled => "CANCELLED",
+                    },
+                    start: e.start,
+                    end: e.end,
+                    row: e.row,
+                    column: e.column,
+                })
+                .collect(),
+        })
+        .collect();
+    Ok(Json(files))
+}
+
+/// The processed commits of a repository containing a label, e.g. an identifier,
+/// found with the label index, see [`hyperast_vcs_git::label_index`]
+pub fn label_occurrences(
+    state: SharedState,
+    path: LabelParam,
+) -> Result<Json<LabelOccurrences>, String> {
+    let LabelParam {
+        forge,
+        user,
+        name,
+        label,
+    } = path;
+    let repo = crate::utils::resolve_repo(&state, &forge, &user, &name)?;
+    let repositories = state.repositories.read().unwrap();
+    let commits = repositories
+        .processed_commits(&repo)
+        .ok_or_else(|| format!("{} is not registered", repo))?;
+    let processor = &repositories.processor;
+    let index = (processor.label_index.as_ref())
+        .ok_or_else(|| "the label index is not enabled".to_string())?;
+    let stores = &processor.main_stores;
+    let Some(label) = stores.label_store.get(label.as_str()) else {
+        return Ok(Json(Default::default()));
+    };
+    let occurrences = commits
+        .into_iter()
+        .filter_map(|(oid, commit)| {
+            let files = index.in_tree(stores, commit.ast_root, &label);
+            let paths: Vec<_> = files.into_iter().map(|(path, _)| path).collect();
+            (!paths.is_empty()).then(|| (oid.to_string(), paths))
+        })
+        .collect();
+    Ok(Json(occurrences))
+}
+
+pub fn memory(state: SharedState) -> Json<Memory> {
+    let allocated = hyperast::utils::memusage().into();
+    let repositories = state.repositories.read().unwrap();
+    let stores = &repositories.processor.main_stores;
+    Json(Memory {
+        allocated,
+        nodes: stores.node_store.len(),
+        labels: stores.label_store.len(),
+    })
+}
+
+/// Drops the caches of processors, processed commits and stores are kept
+pub fn purge_caches(state: SharedState) -> Json<Memory> {
+    state.repositories.write().unwrap().purge_caches();
+    memory(state)
+}
+
+/// Forgets the commits that are not retained, along the nodes and labels only they use.
+/// Cached mappings and decompressed trees of removed nodes are dropped.
+///
+/// Remaining labels might be renumbered, no cache of the backend holds label identifiers,
+/// but clients must drop the labels they fetched under a previous generation.
+pub fn collect_garbage(
+    state: SharedState,
+    retention: Retention,
+) -> Result<Json<Collected>, String> {
+    let per_repo = (retention.per_repo.into_iter())
+        .map(|(repo, last)| Ok((repo.parse::<Repo>()?, last)))
+        .collect::<Result<_, String>>()?;
+    let pinned = (retention.pinned.iter())
+        .map(|x| Oid::from_str(x).map_err(|e| e.to_string()))
+        .collect::<Result<_, _>>()?;
+    let retention = hyperast_vcs_git::gc::Retention {
+        last: retention.last,
+        per_repo,
+        pinned,
+    };
+    let (collected, labels_generation) = {
+        let repositories = &mut *state.repositories.write().unwrap();
+        let collected = repositories.collect_garbage(&retention);
+        // remaining nodes keep their identifiers
+        let node_store = &repositories.processor.main_stores.node_store;
+        let alive = |x: &NodeIdentifier| node_store.contains(*x);
+        state.mappings.retain(|(a, b), _| alive(a) && alive(b));
+        state
+            .mappings_alone
+            .retain(|(a, b), _| alive(a) && alive(b));
+        state.partial_decomps.retain(|x, _| alive(x));
+        let label_store = &repositories.processor.main_stores.label_store;
+        (collected, label_store.generation())
+    };
+    Ok(Json(Collected {
+        nodes: collected.nodes,
+        labels: collected.labels,
+        labels_generation,
+        memory: memory(state).0,
+    }))
+}
+
+pub fn unregister(state: SharedState, path: Param) -> Result<(), String> {
+    let Param { forge, user, name } = path;
+    let repo = crate::utils::resolve_repo(&state, &forge, &user, &name)?;
+    if state.repositories.write().unwrap().unregister(&repo) {
+        Ok(())
+    } else {
+        Err(format!("{} is not registered", repo))
+    }
+}
@@ -0,0 +1,453 @@
+//! Processing of commit ranges in the background.
+//!
+//! Submitting a range directly returns the id of a [`Job`],
+//! a pool of workers (see [`spawn_workers`]) then fetches the repository and processes it.
+//! Its progress can be followed and it can be cancelled.
+//! The processed commits are kept in the shared stores,
+//! so other routes reuse them once the job is done.
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Condvar, Mutex, OnceLock,
+    },
+    time::{Duration, Instant},
+};
+
+use axum::Json;
+use dashmap::DashMap;
+use hyperast_vcs_git::{
+    git::{Oid, Repo},
+    processing::{ConfiguredRepo2, ConfiguredRepoHandle2, Progress, RepoConfig},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::SharedState;
+
+pub type JobId = u64;
+
+/// number of commits processed while holding the write lock on the repositories,
+/// other routes can read the stores in between
+const CHUNK: usize = 8;
+
+/// finished jobs are forgotten after that
+const RETENTION: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Param {
+    forge: String,
+    user: String,
+    name: String,
+    commit: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Content {
+    /// number of commits to process, following first parents
+    pub commits: usize,
+    /// used if the repository is not configured yet, see [`RepoConfig`]
+    #[serde(default)]
+    pub config: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct JobParam {
+    id: JobId,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub enum Status {
+    Queued,
+    Running,
+    Done,
+    Cancelled,
+    Failed(String),
+}
+
+impl Status {
+    fn is_finished(&self) -> bool {
+        !matches!(self, Status::Queued | Status::Running)
+    }
+}
+
+pub struct Job {
+    pub id: JobId,
+    repo: Repo,
+    /// as submitted, e.g. a branch or an abbreviated oid
+    commit: String,
+    /// set by the worker once the repository is fetched
+    resolved: OnceLock<Oid>,
+    limit: usize,
+    config: Option<RepoConfig>,
+    progress: Arc<Progress>,
+    submitted: Instant,
+    ended: OnceLock<Instant>,
+    state: Mutex<(Status, Vec<Oid>)>,
+    finished: Condvar,
+}
+
+impl Job {
+    pub fn status(&self) -> Status {
+        self.state.lock().unwrap().0.clone()
+    }
+
+    pub fn cancel(&self) {
+        self.progress.cancel();
+        let mut state = self.state.lock().unwrap();
+        if state.0 == Status::Queued {
+            // never picked by a worker
+            state.0 = Status::Cancelled;
+            let _ = self.ended.set(Instant::now());
+            self.finished.notify_all();
+        }
+    }
+
+    /// Blocks until the job is done, cancelled or failed
+    pub fn wait(&self) -> Status {
+        let state = self.state.lock().unwrap();
+        let state = (self.finished)
+            .wait_while(state, |(status, _)| !status.is_finished())
+            .unwrap();
+        state.0.clone()
+    }
+
+    fn covers(&self, repo: &Repo, limit: usize) -> bool {
+        &self.repo == repo && self.limit >= limit
+    }
+
+    /// Designated by the resolved oid or, until it is done, as it was submitted
+    fn reusable_for(&self, commit: &str) -> bool {
+        match self.status() {
+            Status::Queued | Status::Running if self.commit == commit => true,
+            // branches might have moved since
+            Status::Queued | Status::Running | Status::Done => {
+                (self.resolved.get()).map_or(false, |oid| oid.to_string() == commit)
+            }
+            Status::Cancelled | Status::Failed(_) => false,
+        }
+    }
+
+    fn set_status(&self, status: Status) {
+        self.state.lock().unwrap().0 = status;
+    }
+
+    fn finish(&self, result: Result<Vec<Oid>, String>) {
+        let mut state = self.state.lock().unwrap();
+        *state = match result {
+            Ok(commits) if self.progress.is_cancelled() => (Status::Cancelled, commits),
+            Ok(commits) => (Status::Done, commits),
+            Err(err) => (Status::Failed(err), vec![]),
+        };
+        let _ = self.ended.set(Instant::now());
+        self.finished.notify_all();
+    }
+}
+
+/// The jobs submitted to the backend, along their queue
+#[derive(Default)]
+pub struct Jobs {
+    next_id: AtomicU64,
+    jobs: DashMap<JobId, Arc<Job>>,
+    queue: Mutex<VecDeque<Arc<Job>>>,
+    available: Condvar,
+}
+
+impl Jobs {
+    /// Queues the processing of `limit` commits from `commit`,
+    /// a pending or done job covering the same commits is reused
+    pub fn submit(
+        &self,
+        repo: Repo,
+        commit: String,
+        limit: usize,
+        config: Option<RepoConfig>,
+    ) -> Arc<Job> {
+        let mut queue = self.queue.lock().unwrap();
+        self.evict_finished(RETENTION);
+        let reusable = self.jobs.iter().find_map(|job| {
+            let reusable = job.covers(&repo, limit) && job.reusable_for(&commit);
+            reusable.then(|| job.clone())
+        });
+        if let Some(job) = reusable {
+            return job;
+        }
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let job = Arc::new(Job {
+            id,
+            repo,
+            commit,
+            resolved: OnceLock::new(),
+            limit,
+            config,
+            progress: Default::default(),
+            submitted: Instant::now(),
+            ended: OnceLock::new(),
+            state: Mutex::new((Status::Queued, vec![])),
+            finished: Condvar::new(),
+        });
+        self.jobs.insert(id, job.clone());
+        queue.push_back(job.clone());
+        self.available.notify_one();
+        job
+    }
+
+    pub fn get(&self, id: JobId) -> Option<Arc<Job>> {
+        self.jobs.get(&id).map(|x| x.clone())
+    }
+
+    /// The running job that processes at least `limit` commits from `commit`, if any
+    pub fn running(&self, repo: &Repo, commit: Oid, limit: usize) -> Option<Arc<Job>> {
+        self.jobs.iter().find_map(|job| {
+            let running = job.covers(repo, limit)
+                && job.resolved.get() == Some(&commit)
+                && job.status() == Status::Running;
+            running.then(|| job.clone())
+        })
+    }
+
+    /// Forget the jobs that finished more than `retention` ago
+    fn evict_finished(&self, retention: Duration) {
+        (self.jobs).retain(|_, job| job.ended.get().map_or(true, |t| t.elapsed() < retention));
+    }
+
+    fn list(&self) -> Vec<Arc<Job>> {
+        self.evict_finished(RETENTION);
+        let mut jobs: Vec<_> = self.jobs.iter().map(|x| x.clone()).collect();
+        jobs.sort_by_key(|x| x.id);
+        jobs
+    }
+
+    fn next(&self) -> Arc<Job> {
+        let queue = self.queue.lock().unwrap();
+        let mut queue = (self.available)
+            .wait_while(queue, |queue| queue.is_empty())
+            .unwrap();
+        queue.pop_front().unwrap()
+    }
+}
+
+/// Starts `workers` threads processing the submitted jobs
+pub fn spawn_workers(state: SharedState, workers: usize) {
+    for i in 0..workers {
+        let state = state.clone();
+        std::thread::Builder::new()
+            .name(format!("processing-worker-{i}"))
+            .spawn(move || loop {
+                let job = state.jobs.next();
+                if job.status() != Status::Queued {
+                    continue; // cancelled while queued
+                }
+                job.set_status(Status::Running);
+                log::info!("start job {} on {}", job.id, job.repo);
+                let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    process(&state, &job)
+                }))
+                .unwrap_or_else(|_| Err("panicked while processing".to_string()));
+                log::info!("end job {} on {}", job.id, job.repo);
+                job.finish(result);
+            })
+            .unwrap();
+    }
+}
+
+/// The configured `repo`, registered with `config` if it is not configured yet
+fn configured(
+    state: &SharedState,
+    repo: &Repo,
+    config: Option<RepoConfig>,
+) -> ConfiguredRepoHandle2 {
+    let configured = state.repositories.read().unwrap().get_config(repo.clone());
+    match configured {
+        Some(configured) => configured,
+        None => {
+            let configs = &mut state.repositories.write().unwrap();
+            let config = config.unwrap_or(RepoConfig::Any);
+            configs.register_config(repo.clone(), config);
+            configs.get_config(repo.clone()).unwrap()
+        }
+    }
+}
+
+fn process(state: &SharedState, job: &Job) -> Result<Vec<Oid>, String> {
+    let repo = configured(state, &job.repo, job.config).try_fetch()?;
+    log::info!("done cloning {}", repo.spec);
+    // resolved, so that jobs designating the same commit differently are reused
+    let commit = hyperast_vcs_git::git::retrieve_commit(&repo.repo, &job.commit)
+        .map_err(|e| e.to_string())?
+        .id();
+    let _ = job.resolved.set(commit);
+    process_commits(state, job, &repo, commit).map_err(|e| e.to_string())
+}
+
+fn process_commits(
+    state: &SharedState,
+    job: &Job,
+    repo: &ConfiguredRepo2,
+    commit: Oid,
+) -> Result<Vec<Oid>, Box<dyn std::error::Error>> {
+    let rw = hyperast_vcs_git::git::Builder::new(&repo.repo)?
+        .before("")?
+        .after(&commit.to_string())?
+        .walk()?
+        .take(job.limit)
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut rw = rw.into_iter().peekable();
+    let mut commits = vec![];
+    while rw.peek().is_some() && !job.progress.is_cancelled() {
+        // reuse the commits already processed, only locking for reading
+        let done = {
+            let repositories = state.repositories.read().unwrap();
+            repositories.processor.ensure_prepro(&mut rw, repo)
+        };
+        let (Ok(done) | Err(done)) = done;
+        done.iter().for_each(|_| job.progress.commit_done());
+        commits.extend(done);
+        if rw.peek().is_none() {
+            break;
+        }
+        let repositories = &mut *state.repositories.write().unwrap();
+        let processor = &mut repositories.processor;
+        processor.set_progress(Some(job.progress.clone()));
+        // caught while holding the lock, so that unwinding does not poison it
+        let processed = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            processor.pre_pro(&mut rw, repo, CHUNK)
+        }));
+        processor.set_progress(None);
+        if processed.is_err() || job.progress.is_cancelled() {
+            // caches might refer to placeholders of the interrupted commit
+            repositories.purge_blob_caches(&job.repo);
+        }
+        commits.extend(processed.map_err(|_| "panicked while processing")?);
+    }
+    Ok(commits)
+}
+
+#[derive(Serialize)]
+pub struct JobStatus {
+    id: JobId,
+    repo: String,
+    commit: String,
+    /// the oid of commit, once the job started
+    #[serde(skip_serializing_if = "Option::is_none")]
+    resolved: Option<String>,
+    limit: usize,
+    status: Status,
+    commits: usize,
+    files: usize,
+    bytes: usize,
+    errors: usize,
+    /// in seconds, since submission
+    elapsed: f64,
+    /// the processed commits, once done
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    processed: Vec<String>,
+}
+
+impl From<&Job> for JobStatus {
+    fn from(job: &Job) -> Self {
+        let (status, processed) = {
+            let state = job.state.lock().unwrap();
+            (
+                state.0.clone(),
+                state.1.iter().map(|x| x.to_string()).collect(),
+            )
+        };
+        let progress = job.progress.snapshot();
+        JobStatus {
+            id: job.id,
+            repo: job.repo.to_string(),
+            commit: job.commit.clone(),
+            resolved: job.resolved.get().map(|x| x.to_string()),
+            limit: job.limit,
+            status,
+            commits: progress.commits,
+            files: progress.files,
+            bytes: progress.bytes,
+            errors: progress.errors,
+            elapsed: job.submitted.elapsed().as_secs_f64(),
+            processed,
+        }
+    }
+}
+
+pub fn submit(
+    state: SharedState,
+    path: Param,
+    content: Content,
+) -> Result<Json<JobStatus>, String> {
+    let Param {
+        forge,
+        user,
+        name,
+        commit,
+    } = path;
+    let config = content.config.map(|x| x.parse()).transpose()?;
+    let repo = crate::utils::resolve_repo(&state, &forge, &user, &name)?;
+    let job = state.jobs.submit(repo, commit, content.commits, config);
+    Ok(Json(job.as_ref().into()))
+}
+
+pub fn status(state: SharedState, path: JobParam) -> Result<Json<JobStatus>, String> {
+    let job = state.jobs.get(path.id).ok_or("unknown job")?;
+    Ok(Json(job.as_ref().into()))
+}
+
+pub fn cancel(state: SharedState, path: JobParam) -> Result<Json<JobStatus>, String> {
+    let job = state.jobs.get(path.id).ok_or("unknown job")?;
+    job.cancel();
+    Ok(Json(job.as_ref().into()))
+}
+
+pub fn list(state: SharedState) -> Json<Vec<JobStatus>> {
+    Json(
+        state
+            .jobs
+            .list()
+            .iter()
+            .map(|x| x.as_ref().into())
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reuse_covering_jobs() {
+        let jobs = Jobs::default();
+        let repo = hyperast_vcs_git::git::Forge::Github.repo("INRIA", "spoon");
+        let a = jobs.submit(repo.clone(), "main".into(), 10, None);
+        let b = jobs.submit(repo.clone(), "main".into(), 5, None);
+        assert_eq!(a.id, b.id);
+        let c = jobs.submit(repo.clone(), "main".into(), 20, None);
+        assert_ne!(a.id, c.id);
+        c.cancel();
+        assert_eq!(c.wait(), Status::Cancelled);
+        let d = jobs.submit(repo.clone(), "main".into(), 20, None);
+        assert_ne!(c.id, d.id);
+        // once resolved by a worker, the oid designates the same job
+        let main = Oid::from_str("7c7f4cb6f7f43e8f1b4c3ea3f2b4dbf1e1f0a4f5").unwrap();
+        a.resolved.set(main).unwrap();
+        let e = jobs.submit(repo.clone(), main.to_string(), 5, None);
+        assert_eq!(a.id, e.id);
+        let other = "0f3e2d1c4b5a69788796a5b4c3d2e1f0a9b8c7d6".to_string();
+        let f = jobs.submit(repo, other, 5, None);
+        assert_ne!(a.id, f.id);
+        assert_eq!(jobs.list().len(), 4);
+    }
+
+    #[test]
+    fn evict_finished_jobs() {
+        let jobs = Jobs::default();
+        let repo = hyperast_vcs_git::git::Forge::Github.repo("INRIA", "spoon");
+        let a = jobs.submit(repo.clone(), "main".into(), 10, None);
+        let b = jobs.submit(repo, "main".into(), 20, None);
+        b.cancel();
+        jobs.evict_finished(RETENTION);
+        assert_eq!(jobs.list().len(), 2);
+        jobs.evict_finished(Duration::ZERO);
+        assert!(jobs.get(a.id).is_some());
+        assert!(jobs.get(b.id).is_none());
+    }
+}
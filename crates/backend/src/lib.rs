@@ -17,11 +17,14 @@ pub mod app;
 mod changes;
 pub mod cli;
 mod commit;
+mod diff;
 pub mod examples;
 mod fetch;
 mod file;
+mod inventory;
+pub mod jobs;
 mod matching;
-mod pull_requests;
+pub mod pull_requests;
 mod querying;
 mod scriptingv1;
 mod smells;
@@ -52,7 +55,16 @@ pub struct AppState {
     )>,
     // Multiple shared docs
     doc2: ws::SharedDocs,
-    pr_cache: RwLock<std::collections::HashMap<commit::Param, pull_requests::RawPrData>>,
+    pr_cache: RwLock<std::collections::HashMap<pull_requests::Param, pull_requests::PrData>>,
+    /// if set, pull requests are resolved from it instead of forges, see [`pull_requests`]
+    pub pr_fixtures: std::sync::OnceLock<pull_requests::Fixtures>,
+    /// see [`jobs`]
+    pub jobs: jobs::Jobs,
+    /// git servers, besides the known forges, from which unregistered repositories can be cloned,
+    /// see [`utils::resolve_repo`]
+    pub allowed_hosts: std::sync::OnceLock<Vec<String>>,
+    /// expected by admin routes, which are not served without it, see [`app::admin_route`]
+    pub admin_token: std::sync::OnceLock<String>,
 }
 
 impl Default for AppState {
@@ -70,6 +82,10 @@ impl Default for AppState {
             )),
             doc2: Default::default(),
             pr_cache: Default::default(),
+            pr_fixtures: Default::default(),
+            jobs: Default::default(),
+            allowed_hosts: Default::default(),
+            admin_token: Default::default(),
         }
     }
 }
@@ -392,7 +408,7 @@ fn run_scripting(
         .repositories
         .write()
         .unwrap()
-        .register_config_with_prepro(repo_spec.clone(), config, prepro.into());
+        .register_config_with_prepro(repo_spec.clone(), config, prepro.into())?;
     // state
     //     .repositories
     //     .write()
@@ -404,7 +420,7 @@ fn run_scripting(
         .unwrap()
         .get_config(repo_spec)
         .ok_or_else(|| "missing config for repository".to_string())?;
-    let mut repository = repo.fetch();
+    let mut repository = repo.try_fetch()?;
     log::debug!("done cloning {}", repository.spec);
     let commits = state.repositories.write().unwrap().pre_process_with_limit(
         &mut repository,
@@ -514,14 +530,14 @@ fn run_tsg(
         .repositories
         .write()
         .unwrap()
-        .register_config_with_tsg(repo_spec.clone(), config, tsg.into());
+        .register_config_with_tsg(repo_spec.clone(), config, tsg.into())?;
     let repo = state
         .repositories
         .read()
         .unwrap()
         .get_config(repo_spec)
         .ok_or_else(|| "missing config for repository".to_string())?;
-    let mut repository = repo.fetch();
+    let mut repository = repo.try_fetch()?;
     log::debug!("done cloning {}", repository.spec);
     let commits = state.repositories.write().unwrap().pre_process_with_limit(
         &mut repository,
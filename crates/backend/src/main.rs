@@ -8,8 +8,9 @@ use backend::*;
 use axum::Router;
 use backend::{
     app::{
-        commit_metadata_route, fetch_code_route, fetch_git_file, querying_app, scripting_app,
-        smells_app, track_code_route, tsg_app, view_code_route,
+        admin_route, commit_metadata_route, diff_route, fetch_code_route, fetch_git_file,
+        inventory_route, jobs_route, querying_app, scripting_app, smells_app, track_code_route,
+        tsg_app, view_code_route,
     },
     examples::{example_app, kv_store_app},
 };
@@ -53,7 +54,22 @@ async fn main() {
             log::error!("error logging languages: {}", e)
         };
     }
+    hyperast_vcs_git::git::set_clone_root(&opts.clone_dir);
+    for root in &opts.local_root {
+        hyperast_vcs_git::git::allow_local_root(root);
+    }
+    for path in opts.repository.iter().filter_map(|x| x.repo.local_path()) {
+        hyperast_vcs_git::git::allow_local_root(path);
+    }
     let shared_state = SharedState::default();
+    if let Some(path) = opts.snapshot.as_ref().filter(|p| p.exists()) {
+        let mut repos = shared_state.repositories.write().unwrap();
+        let registry = hyperast_vcs_git::snapshot::registry();
+        match repos.load_snapshot_from_file(&registry, path) {
+            Ok(()) => log::info!("warm started from {}", path.display()),
+            Err(e) => log::error!("could not load snapshot {}: {}", path.display(), e),
+        }
+    }
     {
         use hyperast_vcs_git::processing::RepoConfig;
         let mut repos = shared_state.repositories.write().unwrap();
@@ -74,9 +90,41 @@ async fn main() {
         );
         opts.repository.iter().for_each(|x| {
             repos.register_config(x.repo.clone(), x.config);
-        })
+        });
+        repos.processor.limits = hyperast_vcs_git::processing::FileLimits {
+            max_size: opts.max_file_size,
+            timeout: opts.parse_timeout.map(std::time::Duration::from_millis),
+        };
+        repos.processor.parallel =
+            opts.parse_threads
+                .map(|threads| hyperast_vcs_git::processing::ParallelConfig {
+                    threads,
+                    window: opts.parse_window,
+                });
+        if let Some(path) = &opts.store_log {
+            let registry = hyperast_vcs_git::snapshot::registry();
+            if let Err(e) = repos.open_store_log(registry, path) {
+                log::error!("could not open store log {}: {}", path.display(), e);
+            }
+        }
+        if opts.label_index {
+            repos.enable_label_index();
+        }
     }
-    let app = Router::new()
+    let _ = shared_state.allowed_hosts.set(opts.allow_host.clone());
+    if let Some(token) = &opts.admin_token {
+        let _ = shared_state.admin_token.set(token.clone());
+    }
+    if let Some(path) = &opts.pr_fixtures {
+        match backend::pull_requests::Fixtures::load(path) {
+            Ok(fixtures) => {
+                let _ = shared_state.pr_fixtures.set(fixtures);
+            }
+            Err(e) => log::error!("could not load pr fixtures {}: {}", path.display(), e),
+        }
+    }
+    backend::jobs::spawn_workers(Arc::clone(&shared_state), opts.workers);
+    let mut app = Router::new()
         .fallback(fallback)
         .route("/ws", axum::routing::get(backend::ws_handler))
         .merge(kv_store_app(Arc::clone(&shared_state)))
@@ -89,7 +137,14 @@ async fn main() {
         .merge(view_code_route(Arc::clone(&shared_state)))
         .merge(fetch_code_route(Arc::clone(&shared_state)))
         .merge(commit_metadata_route(Arc::clone(&shared_state)))
-        .merge(example_app())
+        .merge(diff_route(Arc::clone(&shared_state)))
+        .merge(jobs_route(Arc::clone(&shared_state)))
+        .merge(inventory_route(Arc::clone(&shared_state)))
+        .merge(example_app());
+    if opts.admin_token.is_some() {
+        app = app.merge(admin_route(Arc::clone(&shared_state)));
+    }
+    let app = app
         .layer(CorsLayer::permissive()) // WARN unwanted for deployment
         .layer(TraceLayer::new_for_http())
         .with_state(Arc::clone(&shared_state));
@@ -107,6 +162,14 @@ async fn main() {
     .with_graceful_shutdown(shutdown_signal())
     .await
     .unwrap();
+    if let Some(path) = &opts.snapshot {
+        let repos = shared_state.repositories.read().unwrap();
+        let registry = hyperast_vcs_git::snapshot::registry();
+        match repos.save_snapshot_to_file(&registry, path) {
+            Ok(()) => log::info!("snapshot written to {}", path.display()),
+            Err(e) => log::error!("could not write snapshot {}: {}", path.display(), e),
+        }
+    }
 }
 pub(crate) use hyperast_vcs_git::no_space;
 /// axum handler for any request that fails to match the router routes.
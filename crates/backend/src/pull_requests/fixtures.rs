@@ -0,0 +1,117 @@
+//! Pull requests recorded in a JSON file, to resolve them without network nor token.
+//!
+//! The file contains a list of the answers of the `/pr` routes,
+//! each one with the repository it belongs to:
+//! ```json
+//! [{
+//!   "forge": "gitlab.com", "user": "team", "name": "project",
+//!   "number": 42, "title": "Fix parsing of generics",
+//!   "base_commit": { "id": "1a2b...", "user": "team", "name": "project" },
+//!   "head_commit": { "id": "4c2b...", "user": "someone", "name": "project" },
+//!   "merge_commit": { "id": "9f1e...", "user": "team", "name": "project" }
+//! }]
+//! ```
+use std::path::Path;
+
+use hyperast_vcs_git::git::Forge;
+use serde::Deserialize;
+
+use super::{PrData, PrProvider};
+
+#[derive(Deserialize, Debug)]
+struct Recorded {
+    forge: String,
+    user: String,
+    name: String,
+    #[serde(flatten)]
+    pr: PrData,
+}
+
+/// see the [module documentation](self)
+#[derive(Default, Debug)]
+pub struct Fixtures(Vec<Recorded>);
+
+impl Fixtures {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let file = std::fs::File::open(path).map_err(|e| e.to_string())?;
+        Self::from_reader(file)
+    }
+
+    pub fn from_reader(r: impl std::io::Read) -> Result<Self, String> {
+        let recorded: Vec<Recorded> = serde_json::from_reader(r).map_err(|e| e.to_string())?;
+        Ok(Self(recorded))
+    }
+
+    /// The pull requests recorded for `forge`
+    pub(super) fn forge<'a>(&'a self, forge: &'a str) -> RecordedForge<'a> {
+        RecordedForge {
+            fixtures: self,
+            host: forge.parse::<Forge>().map_or(forge, |x| x.host()),
+        }
+    }
+}
+
+pub(super) struct RecordedForge<'a> {
+    fixtures: &'a Fixtures,
+    host: &'a str,
+}
+
+impl RecordedForge<'_> {
+    fn find(&self, user: &str, name: &str, pred: impl Fn(&PrData) -> bool) -> Option<PrData> {
+        let same_forge =
+            |x: &Recorded| x.forge.parse::<Forge>().map_or(&x.forge[..], |x| x.host()) == self.host;
+        (self.fixtures.0.iter())
+            .find(|x| same_forge(x) && x.user == user && x.name == name && pred(&x.pr))
+            .map(|x| x.pr.clone())
+    }
+}
+
+impl PrProvider for RecordedForge<'_> {
+    async fn merged_by(&self, user: &str, name: &str, commit: &str) -> Result<PrData, String> {
+        self.find(user, name, |pr| {
+            (pr.merge_commit.as_ref()).map_or(false, |c| c.id.starts_with(commit))
+        })
+        .ok_or_else(|| format!("no recorded Pr merging {} in {}/{}", commit, user, name))
+    }
+
+    async fn by_number(&self, user: &str, name: &str, number: u64) -> Result<PrData, String> {
+        self.find(user, name, |pr| pr.number == number as i64)
+            .ok_or_else(|| format!("no recorded Pr {} in {}/{}", number, user, name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_recorded_prs() {
+        let fixtures = include_str!("fixtures.json");
+        let fixtures = Fixtures::from_reader(fixtures.as_bytes()).unwrap();
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let gitlab = fixtures.forge("gitlab");
+        let pr = rt
+            .block_on(gitlab.merged_by("team", "project", "9f1e2d3"))
+            .unwrap();
+        assert_eq!(pr.number, 42);
+        assert_eq!(pr.head_commit.user, "someone");
+        let same = rt
+            .block_on(gitlab.by_number("team", "project", 42))
+            .unwrap();
+        assert_eq!(pr, same);
+        // recorded for another forge
+        let github = fixtures.forge("github.com");
+        assert!(rt
+            .block_on(github.by_number("team", "project", 42))
+            .is_err());
+        let pr = rt
+            .block_on(github.merged_by("INRIA", "spoon", "3f1c9a0"))
+            .unwrap();
+        assert_eq!(pr.number, 5000);
+        assert!(rt
+            .block_on(github.merged_by("INRIA", "spoon", "0000"))
+            .is_err());
+    }
+}
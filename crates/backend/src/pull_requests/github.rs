@@ -0,0 +1,138 @@
+//! Pull requests of GitHub, through its GraphQL API, a token is needed (GITHUB_API_TOKEN)
+use graphql_client::GraphQLQuery;
+use http::header::{AUTHORIZATION, USER_AGENT};
+use serde::Deserialize;
+
+use super::{Commit, PrData, PrProvider};
+
+#[allow(clippy::upper_case_acronyms)]
+type URI = String;
+
+#[allow(clippy::upper_case_acronyms)]
+type GitObjectID = String;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "src/pull_requests/schema.graphql",
+    query_path = "src/pull_requests/query.graphql",
+    response_derives = "Debug,PartialEq"
+)]
+struct RepoView;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "src/pull_requests/schema.graphql",
+    query_path = "src/pull_requests/query.graphql",
+    response_derives = "Debug,PartialEq"
+)]
+struct PrView;
+
+pub(super) struct GitHub {
+    token: String,
+}
+
+impl GitHub {
+    pub(super) fn from_env() -> Result<Self, String> {
+        let token = std::env::var("GITHUB_API_TOKEN")
+            .map_err(|_| "Missing GITHUB_API_TOKEN env var".to_string())?;
+        Ok(Self { token })
+    }
+
+    async fn query<Q: GraphQLQuery>(
+        &self,
+        variables: Q::Variables,
+    ) -> Result<Q::ResponseData, String> {
+        let body = Q::build_query(variables);
+        let req = hyper::Request::builder()
+            .header(USER_AGENT, "graphql-rust/0.14.0")
+            .header(AUTHORIZATION, format!("Bearer {}", self.token))
+            .method("POST")
+            .uri("https://api.github.com/graphql")
+            .body(serde_json::to_string(&body).unwrap())
+            .unwrap();
+        let bytes = super::request(req).await?;
+        #[derive(Deserialize)]
+        struct Response<T> {
+            data: Option<T>,
+        }
+        let response: Response<Q::ResponseData> =
+            serde_json::from_slice(&bytes).map_err(|e| e.to_string())?;
+        response
+            .data
+            .ok_or_else(|| "no data in response".to_string())
+    }
+}
+
+/// The generated types of each query differ, but they have the same fields
+macro_rules! pr_data {
+    ($pr:expr) => {{
+        let pr = $pr;
+        let commit = |id: &str| Commit {
+            id: id.to_string(),
+            user: pr.repository.owner.login.clone(),
+            name: pr.repository.name.clone(),
+        };
+        let head_commit = if let Some(repo) = &pr.head_repository {
+            Commit {
+                id: pr.head_ref_oid.clone(),
+                user: repo.owner.login.clone(),
+                name: repo.name.clone(),
+            }
+        } else {
+            commit(&pr.head_ref_oid)
+        };
+        PrData {
+            merge_commit: pr.merge_commit.as_ref().map(|c| commit(&c.oid)),
+            head_commit,
+            base_commit: Some(commit(&pr.base_ref_oid)),
+            title: pr.title.clone(),
+            number: pr.number,
+        }
+    }};
+}
+
+impl PrProvider for GitHub {
+    async fn merged_by(&self, user: &str, name: &str, commit: &str) -> Result<PrData, String> {
+        let mut cursor = "".to_string();
+        loop {
+            let variables = repo_view::Variables {
+                query: format!("is:merged type:pr oid:{} repo:{}/{}", commit, user, name),
+                after: cursor,
+            };
+            let data = self.query::<RepoView>(variables).await?;
+            let Some(Some(node)) = data.search.nodes.as_ref().and_then(|x| x.first()) else {
+                return Err("no Pr merging this commit".to_string());
+            };
+            let repo_view::RepoViewSearchNodes::PullRequest(pr) = node else {
+                unreachable!("only pull requests are searched")
+            };
+            if pr
+                .merge_commit
+                .as_ref()
+                .map_or(false, |c| c.oid.starts_with(commit))
+            {
+                return Ok(pr_data!(pr));
+            } else if !data.search.page_info.has_next_page {
+                return Err("not a merged commit".to_string());
+            } else if let Some(c) = &data.search.page_info.end_cursor {
+                cursor = c.to_string();
+            } else {
+                return Err("missing cursor to the next page".to_string());
+            }
+        }
+    }
+
+    async fn by_number(&self, user: &str, name: &str, number: u64) -> Result<PrData, String> {
+        let variables = pr_view::Variables {
+            owner: user.to_string(),
+            name: name.to_string(),
+            number: number as i64,
+        };
+        let data = self.query::<PrView>(variables).await?;
+        let pr = data
+            .repository
+            .and_then(|x| x.pull_request)
+            .ok_or_else(|| format!("no Pr {} in {}/{}", number, user, name))?;
+        Ok(pr_data!(&pr))
+    }
+}
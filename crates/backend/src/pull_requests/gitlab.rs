@@ -0,0 +1,186 @@
+//! Merge requests of GitLab instances, through their REST API,
+//! a token (GITLAB_API_TOKEN) is only needed for private projects
+use http::header::USER_AGENT;
+use serde::{de::DeserializeOwned, Deserialize};
+
+use super::{Commit, PrData, PrProvider};
+
+pub(super) struct GitLab {
+    host: String,
+    token: Option<String>,
+}
+
+/// The fields of a merge request that are used, see GitLab's `/merge_requests` API
+#[derive(Deserialize, Debug)]
+struct MergeRequest {
+    iid: i64,
+    title: String,
+    state: String,
+    /// head commit
+    sha: String,
+    merge_commit_sha: Option<String>,
+    squash_commit_sha: Option<String>,
+    diff_refs: Option<DiffRefs>,
+    source_project_id: u64,
+    target_project_id: u64,
+}
+
+#[derive(Deserialize, Debug)]
+struct DiffRefs {
+    base_sha: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct Project {
+    path: String,
+    namespace: Namespace,
+}
+
+#[derive(Deserialize, Debug)]
+struct Namespace {
+    full_path: String,
+}
+
+impl MergeRequest {
+    /// the commit on the target branch,
+    /// the head itself if it was fast-forwarded
+    fn merge_commit(&self) -> Option<&str> {
+        if self.state != "merged" {
+            return None;
+        }
+        let merge = self.merge_commit_sha.as_ref();
+        Some(
+            merge
+                .or(self.squash_commit_sha.as_ref())
+                .unwrap_or(&self.sha)
+                .as_str(),
+        )
+    }
+
+    /// `head` is the repository of the source branch, if it is a fork
+    fn into_pr_data(self, user: &str, name: &str, head: Option<(String, String)>) -> PrData {
+        let commit = |id: &str| Commit {
+            id: id.to_string(),
+            user: user.to_string(),
+            name: name.to_string(),
+        };
+        let head_commit = match head {
+            Some((user, name)) => Commit {
+                id: self.sha.clone(),
+                user,
+                name,
+            },
+            None => commit(&self.sha),
+        };
+        PrData {
+            merge_commit: self.merge_commit().map(commit),
+            head_commit,
+            base_commit: self.diff_refs.as_ref().map(|x| commit(&x.base_sha)),
+            title: self.title,
+            number: self.iid,
+        }
+    }
+}
+
+impl GitLab {
+    /// `host` is the one of the instance, see [`hyperast_vcs_git::git::Repo::host`]
+    pub(super) fn from_env(host: String) -> Self {
+        Self {
+            host,
+            token: std::env::var("GITLAB_API_TOKEN").ok(),
+        }
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, String> {
+        let mut req = hyper::Request::builder()
+            .header(USER_AGENT, "hyperast")
+            .method("GET")
+            .uri(format!("https://{}/api/v4/{}", self.host, path));
+        if let Some(token) = &self.token {
+            req = req.header("PRIVATE-TOKEN", token);
+        }
+        let req = req.body(String::new()).map_err(|e| e.to_string())?;
+        let bytes = super::request(req).await?;
+        serde_json::from_slice(&bytes).map_err(|e| e.to_string())
+    }
+
+    async fn pr_data(&self, user: &str, name: &str, mr: MergeRequest) -> Result<PrData, String> {
+        let head = if mr.source_project_id != mr.target_project_id {
+            let project: Project = self
+                .get(&format!("projects/{}", mr.source_project_id))
+                .await?;
+            Some((project.namespace.full_path, project.path))
+        } else {
+            None
+        };
+        Ok(mr.into_pr_data(user, name, head))
+    }
+}
+
+/// the id of a project in urls
+fn project(user: &str, name: &str) -> String {
+    format!("{}%2F{}", user, name)
+}
+
+impl PrProvider for GitLab {
+    async fn merged_by(&self, user: &str, name: &str, commit: &str) -> Result<PrData, String> {
+        // the merge requests that contain the commit, the merge commit is among them
+        let path = format!(
+            "projects/{}/repository/commits/{}/merge_requests",
+            project(user, name),
+            commit
+        );
+        let mrs: Vec<MergeRequest> = self.get(&path).await?;
+        let mr = mrs
+            .into_iter()
+            .find(|mr| mr.merge_commit().map_or(false, |c| c.starts_with(commit)))
+            .ok_or_else(|| "not a merged commit".to_string())?;
+        self.pr_data(user, name, mr).await
+    }
+
+    async fn by_number(&self, user: &str, name: &str, number: u64) -> Result<PrData, String> {
+        let path = format!("projects/{}/merge_requests/{}", project(user, name), number);
+        let mr: MergeRequest = self.get(&path).await?;
+        self.pr_data(user, name, mr).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_request_of_fork() {
+        // shaped like an answer of the API, trimmed to the used fields
+        let mr = r#"{
+            "iid": 42,
+            "title": "Fix parsing of generics",
+            "state": "merged",
+            "sha": "4c2b4f0a4d0c6c1a7e2c1f0b8a1d0e3f5a6b7c8d",
+            "merge_commit_sha": "9f1e2d3c4b5a69788796a5b4c3d2e1f0a9b8c7d6",
+            "squash_commit_sha": null,
+            "diff_refs": {
+                "base_sha": "1a2b3c4d5e6f708192a3b4c5d6e7f8091a2b3c4d",
+                "head_sha": "4c2b4f0a4d0c6c1a7e2c1f0b8a1d0e3f5a6b7c8d",
+                "start_sha": "1a2b3c4d5e6f708192a3b4c5d6e7f8091a2b3c4d"
+            },
+            "source_project_id": 7,
+            "target_project_id": 3
+        }"#;
+        let mr: MergeRequest = serde_json::from_str(mr).unwrap();
+        let pr = mr.into_pr_data(
+            "team",
+            "project",
+            Some(("someone".into(), "project".into())),
+        );
+        assert_eq!(pr.number, 42);
+        let merge = pr.merge_commit.unwrap();
+        assert_eq!(merge.id, "9f1e2d3c4b5a69788796a5b4c3d2e1f0a9b8c7d6");
+        assert_eq!(merge.user, "team");
+        assert_eq!(pr.head_commit.user, "someone");
+        assert_eq!(
+            pr.base_commit.unwrap().id,
+            "1a2b3c4d5e6f708192a3b4c5d6e7f8091a2b3c4d"
+        );
+    }
+}
@@ -1,41 +1,38 @@
+//! Pull requests (merge requests on GitLab) resolved to their base, head and merge commits.
+//!
+//! Each forge is queried through its own API, see [`PrProvider`].
+//! Given [`Fixtures`], pull requests are instead resolved from recorded data,
+//! so routes work offline.
 use std::str::from_utf8;
 
-use axum::response::IntoResponse;
-use http::header::{AUTHORIZATION, USER_AGENT};
+use axum::{body::Bytes, response::IntoResponse};
 use hyper_rustls::ConfigBuilderExt;
 use hyper_util::{client::legacy::Client, rt::TokioExecutor};
+use hyperast_vcs_git::git::Forge;
 use serde::{Deserialize, Serialize};
 
 use super::*;
-use graphql_client::GraphQLQuery;
 
-#[allow(clippy::upper_case_acronyms)]
-type URI = String;
+mod fixtures;
+mod github;
+mod gitlab;
 
-#[allow(clippy::upper_case_acronyms)]
-type GitObjectID = String;
-
-#[derive(GraphQLQuery)]
-#[graphql(
-    schema_path = "src/pull_requests/schema.graphql",
-    query_path = "src/pull_requests/query.graphql",
-    response_derives = "Debug,Serialize,PartialEq"
-)]
-struct RepoView;
-
-pub struct RawPrData(String);
+pub use fixtures::Fixtures;
 
 type Oid = String;
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct PrData {
     merge_commit: Option<Commit>,
     head_commit: Commit,
+    /// the commit the pull request is based on, in the repository receiving it
+    #[serde(default)]
+    base_commit: Option<Commit>,
     title: String,
     number: i64,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 struct Commit {
     id: Oid,
     user: String,
@@ -48,99 +45,119 @@ impl IntoResponse for PrData {
     }
 }
 
+/// A source of pull requests, usually the API of a forge
+pub(crate) trait PrProvider {
+    /// The merged pull request of `user`/`name` whose merge commit starts with `commit`
+    async fn merged_by(&self, user: &str, name: &str, commit: &str) -> Result<PrData, String>;
+
+    /// The pull request `number` of `user`/`name`, merged or not
+    async fn by_number(&self, user: &str, name: &str, number: u64) -> Result<PrData, String>;
+}
+
+#[derive(Deserialize, Clone, Debug, Hash, PartialEq, Eq)]
+pub struct Param {
+    forge: String,
+    user: String,
+    name: String,
+    /// a prefix of the merge commit
+    version: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct NumberParam {
+    forge: String,
+    user: String,
+    name: String,
+    number: u64,
+}
+
+enum Query<'a> {
+    MergedBy(&'a str),
+    Number(u64),
+}
+
+async fn lookup(
+    provider: &impl PrProvider,
+    user: &str,
+    name: &str,
+    query: Query<'_>,
+) -> Result<PrData, String> {
+    match query {
+        Query::MergedBy(commit) => provider.merged_by(user, name, commit).await,
+        Query::Number(number) => provider.by_number(user, name, number).await,
+    }
+}
+
+/// Resolves the pull request with the provider of the repository designated by `forge`/`user`/`name`,
+/// see [`crate::utils::resolve_repo`].
+///
+/// Repositories on [`Forge::Github`] go through its API,
+/// the others are taken for GitLab instances, e.g. self-hosted ones given as urls.
+async fn resolve(
+    state: &AppState,
+    forge: &str,
+    user: &str,
+    name: &str,
+    query: Query<'_>,
+) -> Result<PrData, String> {
+    if let Some(fixtures) = state.pr_fixtures.get() {
+        return lookup(&fixtures.forge(forge), user, name, query).await;
+    }
+    let repo = crate::utils::resolve_repo(state, forge, user, name)?;
+    let (user, name) = (repo.user(), repo.name());
+    match repo.forge() {
+        Some(Forge::Github) => lookup(&github::GitHub::from_env()?, user, name, query).await,
+        _ if repo.local_path().is_some() => Err(format!(
+            "{}/{} is a local repository, it has no pull requests",
+            user, name
+        )),
+        _ => lookup(&gitlab::GitLab::from_env(repo.host()), user, name, query).await,
+    }
+}
+
 #[axum_macros::debug_handler]
 pub(super) async fn pr_commits(
-    axum::extract::Path(path): axum::extract::Path<commit::Param>,
+    axum::extract::Path(path): axum::extract::Path<Param>,
     axum::extract::State(state): axum::extract::State<SharedState>,
 ) -> Result<PrData, String> {
+    // once merged, a pull request does not change anymore
     if let Some(x) = state.pr_cache.read().unwrap().get(&path) {
-        let data = serde_json::from_str(&x.0).map_err(|e| e.to_string())?;
-        return format_result(&data);
+        return Ok(x.clone());
     };
 
     log::info!("Pr not in cache: {path:?}");
 
-    let mut cursor = "".to_string();
-    loop {
-        let variables = repo_view::Variables {
-            query: format!(
-                "is:merged type:pr oid:{} repo:{}/{}",
-                path.version, path.user, path.name
-            ),
-            after: cursor,
-        };
-        let data = query_github(variables).await?;
-        let a = data.search.nodes.as_ref().unwrap()[0].as_ref().unwrap();
-        match a {
-            repo_view::RepoViewSearchNodes::PullRequest(pr) => {
-                if pr
-                    .merge_commit
-                    .as_ref()
-                    .unwrap()
-                    .oid
-                    .starts_with(&path.version)
-                {
-                    state
-                        .pr_cache
-                        .write()
-                        .unwrap()
-                        .insert(path, RawPrData(serde_json::to_string(&data).unwrap()));
-                    return format_result(&data);
-                } else if !data.search.page_info.has_next_page {
-                    return Err("not a merged commit".to_string());
-                } else if let Some(c) = &data.search.page_info.end_cursor {
-                    cursor = c.to_string();
-                } else {
-                    panic!()
-                }
-            }
-            _ => unreachable!(),
-        }
-    }
+    let Param {
+        forge,
+        user,
+        name,
+        version,
+    } = &path;
+    let data = resolve(&state, forge, user, name, Query::MergedBy(version)).await?;
+    state.pr_cache.write().unwrap().insert(path, data.clone());
+    Ok(data)
 }
 
-fn format_result(data: &repo_view::ResponseData) -> Result<PrData, String> {
-    let a: &repo_view::RepoViewSearchNodes =
-        data.search.nodes.as_ref().unwrap()[0].as_ref().unwrap();
-    match a {
-        repo_view::RepoViewSearchNodes::PullRequest(pr) => {
-            let merge_commit = pr.merge_commit.as_ref().map(|c| Commit {
-                id: c.oid.clone(),
-                user: pr.repository.owner.login.clone(),
-                name: pr.repository.name.clone(),
-            });
-            let head_commit = if let Some(repo) = &pr.head_repository {
-                Commit {
-                    id: pr.head_ref_oid.clone(),
-                    user: repo.owner.login.clone(),
-                    name: repo.name.clone(),
-                }
-            } else {
-                Commit {
-                    id: pr.head_ref_oid.clone(),
-                    user: pr.repository.owner.login.clone(),
-                    name: pr.repository.name.clone(),
-                }
-            };
-            Ok(PrData {
-                merge_commit,
-                head_commit,
-                title: pr.title.clone(),
-                number: pr.number,
-            })
-        }
-        _ => unreachable!(),
-    }
+#[axum_macros::debug_handler]
+pub(super) async fn pr_by_number(
+    axum::extract::Path(path): axum::extract::Path<NumberParam>,
+    axum::extract::State(state): axum::extract::State<SharedState>,
+) -> Result<PrData, String> {
+    let NumberParam {
+        forge,
+        user,
+        name,
+        number,
+    } = &path;
+    resolve(&state, forge, user, name, Query::Number(*number)).await
 }
 
-async fn query_github(variables: repo_view::Variables) -> Result<repo_view::ResponseData, String> {
-    let github_api_token =
-        std::env::var("GITHUB_API_TOKEN").expect("Missing GITHUB_API_TOKEN env var");
-
+/// Sends `req` to the API of a forge, the body of the response if successful
+async fn request(req: hyper::Request<String>) -> Result<Bytes, String> {
     // Prepare the TLS client config
     let tls = rustls::ClientConfig::builder()
         .with_native_roots()
-        .unwrap()
+        .map_err(|e| e.to_string())?
         .with_no_client_auth();
     // Prepare the HTTPS connector
     let https = hyper_rustls::HttpsConnectorBuilder::new()
@@ -152,34 +169,20 @@ async fn query_github(variables: repo_view::Variables) -> Result<repo_view::Resp
     // Build the hyper client from the HTTPS connector.
     let client: Client<_, _> = Client::builder(TokioExecutor::new()).build(https);
 
-    let body = RepoView::build_query(variables);
-    dbg!(body.query);
-    dbg!(&body.variables.query);
-    let req = hyper::Request::builder()
-        .header(USER_AGENT, "graphql-rust/0.14.0")
-        .header(AUTHORIZATION, format!("Bearer {}", github_api_token))
-        .method("POST")
-        .uri("https://api.github.com/graphql")
-        .body(serde_json::to_string(&body).unwrap())
-        .unwrap();
-    let resp = client.request(req).await.unwrap();
+    let uri = req.uri().clone();
+    let resp = client.request(req).await.map_err(|e| e.to_string())?;
     let (parts, body) = resp.into_parts();
-    dbg!(parts.status);
     use http_body_util::BodyExt;
-    let body = body.collect().await.unwrap();
-    let bytes = &body.to_bytes();
-    log::info!("{}", from_utf8(bytes).unwrap());
-    #[derive(Deserialize)]
-    struct AAA {
-        data: repo_view::ResponseData,
+    let body = body.collect().await.map_err(|e| e.to_string())?;
+    let bytes = body.to_bytes();
+    log::info!(
+        "{} {}: {}",
+        parts.status,
+        uri,
+        from_utf8(&bytes).unwrap_or("?")
+    );
+    if !parts.status.is_success() {
+        return Err(format!("{} answered {}", uri, parts.status));
     }
-    let b: AAA = serde_json::from_slice(bytes).unwrap();
-    dbg!(&b.data);
-    let c = b.data.search.nodes.as_ref().unwrap();
-    let Some(d) = c.get(0) else {
-        return Err("no Pr merging this commit".to_string());
-    };
-    dbg!(d.as_ref().unwrap());
-    let data: repo_view::ResponseData = b.data;
-    Ok(data)
+    Ok(bytes)
 }
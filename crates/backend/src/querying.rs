@@ -16,6 +16,7 @@ use std::time::Instant;
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Param {
+    forge: String,
     user: String,
     name: String,
     commit: String,
@@ -36,6 +37,9 @@ pub struct Content {
     /// checked each match (in milli seconds)
     #[serde(default = "default_timeout")]
     pub timeout: u64,
+    /// opt-in, also return where each match is, see [`MatchLocation`]
+    #[serde(default)]
+    pub matches: Option<MatchesPage>,
 }
 
 fn default_max_matches() -> u64 {
@@ -46,6 +50,29 @@ fn default_timeout() -> u64 {
     1000
 }
 
+/// Selects the located matches returned for each commit,
+/// matches are numbered in the order they are found, all patterns together
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MatchesPage {
+    #[serde(default)]
+    pub page: usize,
+    /// also bounded by `max_matches`
+    #[serde(default = "default_per_page")]
+    pub per_page: usize,
+}
+
+fn default_per_page() -> usize {
+    50
+}
+
+impl MatchesPage {
+    fn range(&self, max_matches: u64) -> std::ops::Range<usize> {
+        let per_page = self.per_page.min(max_matches as usize);
+        let start = self.page.saturating_mul(per_page);
+        start..start.saturating_add(per_page)
+    }
+}
+
 #[derive(Serialize)]
 pub enum QueryingError {
     ProcessingError(String),
@@ -87,6 +114,37 @@ pub struct ComputeResultIdentified {
 pub struct ComputeResult {
     pub compute_time: f64,
     pub result: Vec<u64>,
+    /// the requested page of matches, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matches: Option<Vec<MatchLocation>>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct MatchLocation {
+    /// index among enabled patterns, as in [`ComputeResult::result`]
+    pub pattern: usize,
+    /// spans all the captures of the match, or its root if the pattern has no captures
+    #[serde(flatten)]
+    pub range: Range,
+    pub captures: Vec<CaptureLocation>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct CaptureLocation {
+    pub name: String,
+    #[serde(flatten)]
+    pub range: Range,
+    pub text: String,
+}
+
+/// byte range in a file, with the row and column of its start
+#[derive(Debug, Serialize, Clone)]
+pub struct Range {
+    pub file: String,
+    pub start: usize,
+    pub end: usize,
+    pub row: usize,
+    pub col: usize,
 }
 impl ComputeResult {
     fn with(self, commit_oid: &Oid) -> ComputeResultIdentified {
@@ -105,7 +163,7 @@ pub fn simple(
     path: Param,
 ) -> Result<ComputeResults, QueryingError> {
     let now = Instant::now();
-    let Param { user, name, commit } = path.clone();
+    let Param { forge, user, name, commit } = path.clone();
     let mut additional = commit.split("/");
     let commit = additional.next().unwrap();
     let Content {
@@ -115,20 +173,28 @@ pub fn simple(
         commits,
         max_matches,
         timeout,
+        matches,
     } = query;
+    let query = match &matches {
+        Some(_) => capture_pattern_roots(&query),
+        None => query,
+    };
     let timeout = std::time::Duration::from_millis(timeout);
     let mut proc_commit_limit = commits;
     let config = if language == "Java" {
         hyperast_vcs_git::processing::RepoConfig::JavaMaven
     } else if language == "Cpp" {
         hyperast_vcs_git::processing::RepoConfig::CppMake
+    } else if language == "Ts" || language == "TypeScript" || language == "JavaScript" {
+        hyperast_vcs_git::processing::RepoConfig::TsNpm
     } else {
         hyperast_vcs_git::processing::RepoConfig::Any
     };
     let lang = &language;
     let language: tree_sitter::Language = hyperast_vcs_git::resolve_language(&language)
         .ok_or_else(|| QueryingError::MissingLanguage(language.to_string()))?;
-    let repo_spec = hyperast_vcs_git::git::Forge::Github.repo(user, name);
+    let repo_spec = crate::utils::resolve_repo(&state, &forge, &user, &name)
+        .map_err(QueryingError::ProcessingError)?;
     let repo = state
         .repositories
         .read()
@@ -139,7 +205,9 @@ pub fn simple(
         None => {
             let configs = &mut state.repositories.write().unwrap();
             if let Some(precomp) = precomp {
-                configs.register_config_with_prequeries(repo_spec.clone(), config, &[&precomp]);
+                configs
+                    .register_config_with_prequeries(repo_spec.clone(), config, &[&precomp])
+                    .map_err(QueryingError::ProcessingError)?;
             } else {
                 // configs.register_config_alt_lang(repo_spec.clone(), config, "C");
                 configs.register_config(repo_spec.clone(), config);
@@ -149,7 +217,7 @@ pub fn simple(
         }
     };
 
-    let mut repo = repo.fetch();
+    let mut repo = repo.try_fetch().map_err(QueryingError::ProcessingError)?;
     log::warn!("done cloning {}", &repo.spec);
     let commits = crate::utils::handle_pre_processing(&state, &mut repo, "", &commit, commits)
         .map_err(|x| QueryingError::ProcessingError(x.to_string()))?;
@@ -174,6 +242,7 @@ pub fn simple(
     .map_err(|e| QueryingError::ParsingError(e.to_string()))?;
 
     log::info!("done query construction");
+    let required = query.required_texts();
     let prepare_time = now.elapsed().as_secs_f64();
     let mut results = vec![];
     let mut matching_error_count = 0;
@@ -192,7 +261,18 @@ pub fn simple(
         let commit = repositories.get_commit(&repo.config, commit_oid).unwrap();
         let code = commit.ast_root;
         let stores = &repositories.processor.main_stores;
-        let result = simple_aux(stores, code, &query, timeout, max_matches);
+        let index = repositories.processor.label_index.as_ref();
+        if index.map_or(false, |index| !index.may_match(stores, code, &required)) {
+            // no file of the commit has the texts required by the `#eq?` predicates
+            let result = ComputeResult {
+                compute_time: 0.0,
+                result: vec![0; query.enabled_pattern_count()],
+                matches: matches.as_ref().map(|_| vec![]),
+            };
+            results.push(Ok(result.with(commit_oid)));
+            continue;
+        }
+        let result = simple_aux(stores, code, &query, timeout, max_matches, matches.as_ref());
         let result = match result {
             Ok(inner) => Ok(inner.with(commit_oid)),
             Err(err) if results.is_empty() => {
@@ -252,6 +332,7 @@ pub fn streamed(mut state: SharedState, path: Param, content: Content) -> axum::
         commits: mut proc_commit_limit,
         max_matches,
         timeout,
+        matches,
         ..
     } = content.clone();
     let timeout = std::time::Duration::from_millis(timeout);
@@ -298,7 +379,7 @@ pub fn streamed(mut state: SharedState, path: Param, content: Content) -> axum::
             let commit = repositories.get_commit(&repo.config, &commit_oid).unwrap();
             let code = commit.ast_root;
             let stores = &repositories.processor.main_stores;
-            let result = simple_aux(stores, code, &query, timeout, max_matches);
+            let result = simple_aux(stores, code, &query, timeout, max_matches, matches.as_ref());
             let result = match result {
                 Ok(inner) => Ok(inner.with(&commit_oid)),
                 Err(err) => {
@@ -344,7 +425,7 @@ fn pre_repo(
     path: &Param,
     content: &Content,
 ) -> Result<(hyperast_vcs_git::processing::ConfiguredRepo2, Vec<Oid>), Box<dyn std::error::Error>> {
-    let Param { user, name, commit } = path.clone();
+    let Param { forge, user, name, commit } = path.clone();
     let mut additional = commit.split("/");
     let commit = additional.next().unwrap();
     let Content {
@@ -354,15 +435,18 @@ fn pre_repo(
         commits,
         max_matches: _,
         timeout: _,
+        matches: _,
     } = content.clone();
     let config = if language == "Java" {
         hyperast_vcs_git::processing::RepoConfig::JavaMaven
     } else if language == "Cpp" {
         hyperast_vcs_git::processing::RepoConfig::CppMake
+    } else if language == "Ts" || language == "TypeScript" || language == "JavaScript" {
+        hyperast_vcs_git::processing::RepoConfig::TsNpm
     } else {
         hyperast_vcs_git::processing::RepoConfig::Any
     };
-    let repo_spec = hyperast_vcs_git::git::Forge::Github.repo(user, name);
+    let repo_spec = crate::utils::resolve_repo(&state, &forge, &user, &name)?;
     let repo = state
         .repositories
         .read()
@@ -372,7 +456,7 @@ fn pre_repo(
         Some(_) | None => {
             let configs = &mut state.repositories.write().unwrap();
             if let Some(precomp) = precomp {
-                configs.register_config_with_prequeries(repo_spec.clone(), config, &[&precomp]);
+                configs.register_config_with_prequeries(repo_spec.clone(), config, &[&precomp])?;
             } else {
                 configs.register_config(repo_spec.clone(), config);
             }
@@ -380,7 +464,7 @@ fn pre_repo(
             configs.get_config(repo_spec.clone()).unwrap()
         }
     };
-    let repo = repo.fetch();
+    let repo = repo.try_fetch()?;
     log::warn!("done cloning {}", &repo.spec);
     let afters = [commit].into_iter().chain(additional.into_iter());
     let rw = crate::utils::walk_commits_multi(&repo, afters)?.take(commits);
@@ -397,7 +481,12 @@ fn pre_query(
     content: &Content,
     repo_config: hyperast_vcs_git::processing::ParametrizedCommitProcessorHandle,
 ) -> Result<hyperast_tsquery::Query, QueryingError> {
-    let Param { user, name, commit } = path.clone();
+    let Param {
+        forge: _,
+        user,
+        name,
+        commit,
+    } = path.clone();
     let mut additional = commit.split("/");
     let commit = additional.next().unwrap();
     let Content {
@@ -407,11 +496,18 @@ fn pre_query(
         commits: _,
         max_matches: _,
         timeout: _,
+        matches,
     } = &content;
+    let query = match matches {
+        Some(_) => capture_pattern_roots(query),
+        None => query.clone(),
+    };
     let config = if language == "Java" {
         hyperast_vcs_git::processing::RepoConfig::JavaMaven
     } else if language == "Cpp" {
         hyperast_vcs_git::processing::RepoConfig::CppMake
+    } else if language == "Ts" || language == "TypeScript" || language == "JavaScript" {
+        hyperast_vcs_git::processing::RepoConfig::TsNpm
     } else {
         hyperast_vcs_git::processing::RepoConfig::Any
     };
@@ -442,22 +538,31 @@ fn simple_aux(
     query: &hyperast_tsquery::Query,
     timeout: std::time::Duration,
     max_matches: u64,
+    page: Option<&MatchesPage>,
 ) -> Result<ComputeResult, MatchingError<ComputeResult>> {
     let pos = hyperast::position::StructuralPosition::new(code);
     let cursor = hyperast_tsquery::hyperast_cursor::TreeCursor::new(stores, pos);
     let qcursor = query.matches(cursor);
     let now = Instant::now();
     let mut result = vec![0; query.enabled_pattern_count()];
-    for m in qcursor {
+    let page = page.map(|page| page.range(max_matches));
+    let mut matches = page.as_ref().map(|_| vec![]);
+    for (k, m) in qcursor.enumerate() {
         let i = m.pattern_index;
         let i = query.enabled_pattern_index(i).unwrap();
         result[i as usize] += 1;
+        if let (Some(page), Some(matches)) = (&page, &mut matches) {
+            if page.contains(&k) {
+                matches.extend(locate_match(stores, query, i as usize, &m));
+            }
+        }
         let compute_time = now.elapsed();
         if compute_time >= timeout {
             let compute_time = now.elapsed().as_secs_f64();
             return Err(MatchingError::TimeOut(ComputeResult {
                 result,
                 compute_time,
+                matches,
             }));
         } else if result[i as usize] > max_matches {
             // TODO disable the pattern, return the new query
@@ -465,26 +570,183 @@ fn simple_aux(
             return Err(MatchingError::MaxMatches(ComputeResult {
                 result,
                 compute_time,
+                matches,
             }));
         }
-
-        // dbg!(m.pattern_index);
-        // dbg!(m.captures.len());
-        // for c in &m.captures {
-        //     let i = c.index;
-        //     dbg!(i);
-        //     let name = query.capture_name(i);
-        //     dbg!(name);
-        //     use hyperast::position::TreePath;
-        //     let n = c.node.pos.node().unwrap();
-        //     let n = hyperast::nodes::SyntaxSerializer::new(c.node.stores, *n);
-        //     dbg!(n.to_string());
-        // }
     }
     let compute_time = now.elapsed().as_secs_f64();
     Ok(ComputeResult {
         result,
         compute_time,
+        matches,
+    })
+}
+
+/// The capture added to the root of patterns without captures, so that their matches can be located,
+/// it is not listed in [`MatchLocation::captures`]
+const ROOT_CAPTURE: &str = "_hyperast_root";
+
+/// Captures the root of each pattern of `query` that has no captures, see [`ROOT_CAPTURE`].
+///
+/// The query is returned as is if its patterns cannot be delimited,
+/// it is then rejected when parsed anyway.
+fn capture_pattern_roots(query: &str) -> String {
+    let Some(ends) = uncaptured_pattern_ends(query.as_bytes()) else {
+        return query.to_string();
+    };
+    let mut r = String::with_capacity(query.len() + ends.len() * (ROOT_CAPTURE.len() + 2));
+    let mut last = 0;
+    for end in ends {
+        r.push_str(&query[last..end]);
+        r.push_str(" @");
+        r.push_str(ROOT_CAPTURE);
+        last = end;
+    }
+    r.push_str(&query[last..]);
+    r
+}
+
+/// The ends of top-level patterns without any capture, including in their predicates.
+///
+/// Patterns are delimited by balancing parentheses and brackets, skipping strings and comments.
+fn uncaptured_pattern_ends(b: &[u8]) -> Option<Vec<usize>> {
+    fn skip_trivia(b: &[u8], mut i: usize) -> usize {
+        loop {
+            while i < b.len() && b[i].is_ascii_whitespace() {
+                i += 1;
+            }
+            if i < b.len() && b[i] == b';' {
+                while i < b.len() && b[i] != b'\n' {
+                    i += 1;
+                }
+            } else {
+                return i;
+            }
+        }
+    }
+    fn skip_string(b: &[u8], mut i: usize) -> Option<usize> {
+        i += 1;
+        while i < b.len() {
+            match b[i] {
+                b'\\' => i += 2,
+                b'"' => return Some(i + 1),
+                _ => i += 1,
+            }
+        }
+        None
+    }
+    let is_name = |c: u8| c.is_ascii_alphanumeric() || matches!(c, b'_' | b'.' | b'-');
+    let mut ends = vec![];
+    let mut i = skip_trivia(b, 0);
+    while i < b.len() {
+        let mut captured = false;
+        match b[i] {
+            b'(' | b'[' => {
+                let mut depth = 0;
+                loop {
+                    match *b.get(i)? {
+                        b'"' => {
+                            i = skip_string(b, i)?;
+                            continue;
+                        }
+                        b';' => i = skip_trivia(b, i) - 1,
+                        b'(' | b'[' => depth += 1,
+                        b')' | b']' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                i += 1;
+                                break;
+                            }
+                        }
+                        b'@' => captured = true,
+                        _ => (),
+                    }
+                    i += 1;
+                }
+            }
+            b'"' => i = skip_string(b, i)?,
+            // e.g. the wildcard `_`
+            c if is_name(c) => {
+                while i < b.len() && is_name(b[i]) {
+                    i += 1;
+                }
+            }
+            _ => return None,
+        }
+        if i < b.len() && matches!(b[i], b'*' | b'+' | b'?') {
+            i += 1;
+        }
+        let end = i;
+        i = skip_trivia(b, i);
+        while i < b.len() && b[i] == b'@' {
+            captured = true;
+            i += 1;
+            while i < b.len() && is_name(b[i]) {
+                i += 1;
+            }
+            i = skip_trivia(b, i);
+        }
+        if !captured {
+            ends.push(end);
+        }
+    }
+    Some(ends)
+}
+
+/// None if the match has no captures,
+/// patterns without captures are given a [`ROOT_CAPTURE`] so it does not happen
+fn locate_match(
+    stores: &hyperast::store::SimpleStores<hyperast_vcs_git::TStore>,
+    query: &hyperast_tsquery::Query,
+    pattern: usize,
+    m: &hyperast_tsquery::QueryMatch<
+        hyperast_tsquery::hyperast_cursor::Node<
+            '_,
+            hyperast::store::SimpleStores<hyperast_vcs_git::TStore>,
+        >,
+    >,
+) -> Option<MatchLocation> {
+    use hyperast::position::position_accessors::SolvedPosition;
+    let captures: Vec<_> = (&m.captures)
+        .into_iter()
+        .map(|c| {
+            let pos = &c.node.pos;
+            let conv = hyperast::position::PositionConverter::new(pos).with_stores(stores);
+            let p: hyperast::position::file_and_range::Position<std::path::PathBuf, usize> =
+                conv.compute_pos_post_order::<_, hyperast::position::file_and_range::Position<_, _>>();
+            let row_col: hyperast::position::row_col::RowCol<usize> =
+                conv.compute_pos_post_order::<_, hyperast::position::row_col::RowCol<usize>>();
+            let text = hyperast::nodes::TextSerializer::new(stores, pos.node()).to_string();
+            CaptureLocation {
+                name: query.capture_name(c.index).to_string(),
+                range: Range {
+                    file: p.file().to_string_lossy().to_string(),
+                    start: p.range().start,
+                    end: p.range().end,
+                    row: row_col.row(),
+                    col: row_col.col(),
+                },
+                text,
+            }
+        })
+        .collect();
+    let (roots, captures): (Vec<_>, Vec<_>) =
+        captures.into_iter().partition(|c| c.name == ROOT_CAPTURE);
+    let spanned = if captures.is_empty() {
+        &roots
+    } else {
+        &captures
+    };
+    let first = spanned.iter().min_by_key(|c| c.range.start)?;
+    let end = spanned.iter().map(|c| c.range.end).max()?;
+    let range = Range {
+        end,
+        ..first.range.clone()
+    };
+    Some(MatchLocation {
+        pattern,
+        range,
+        captures,
     })
 }
 
@@ -495,6 +757,7 @@ pub struct ComputeResultsDifferential {
 }
 #[derive(Serialize, Deserialize, Clone)]
 pub struct ParamDifferential {
+    forge: String,
     user: String,
     name: String,
     commit: String,
@@ -508,6 +771,7 @@ pub fn differential(
 ) -> Result<Json<ComputeResultsDifferential>, QueryingError> {
     let now = Instant::now();
     let ParamDifferential {
+        forge,
         user,
         name,
         commit,
@@ -526,13 +790,16 @@ pub fn differential(
         hyperast_vcs_git::processing::RepoConfig::JavaMaven
     } else if language == "Cpp" {
         hyperast_vcs_git::processing::RepoConfig::CppMake
+    } else if language == "Ts" || language == "TypeScript" || language == "JavaScript" {
+        hyperast_vcs_git::processing::RepoConfig::TsNpm
     } else {
         hyperast_vcs_git::processing::RepoConfig::Any
     };
     let lang = &language;
     let language: tree_sitter::Language = hyperast_vcs_git::resolve_language(&language)
         .ok_or_else(|| QueryingError::MissingLanguage(language.to_string()))?;
-    let repo_spec = hyperast_vcs_git::git::Forge::Github.repo(user, name);
+    let repo_spec = crate::utils::resolve_repo(&state, &forge, &user, &name)
+        .map_err(QueryingError::ProcessingError)?;
     let repo = state
         .repositories
         .read()
@@ -543,7 +810,9 @@ pub fn differential(
         None => {
             let configs = &mut state.repositories.write().unwrap();
             if let Some(precomp) = precomp {
-                configs.register_config_with_prequeries(repo_spec.clone(), config, &[&precomp]);
+                configs
+                    .register_config_with_prequeries(repo_spec.clone(), config, &[&precomp])
+                    .map_err(QueryingError::ProcessingError)?;
             } else {
                 configs.register_config(repo_spec.clone(), config);
             }
@@ -551,7 +820,7 @@ pub fn differential(
             configs.get_config(repo_spec.clone()).unwrap()
         }
     };
-    let mut repo = repo.fetch();
+    let mut repo = repo.try_fetch().map_err(QueryingError::ProcessingError)?;
     log::info!("done cloning {}", &repo.spec);
     let commit = crate::utils::handle_pre_processing(&state, &mut repo, "", &commit, 1)
         .map_err(|x| QueryingError::ProcessingError(x.to_string()))?[0];
@@ -817,3 +1086,89 @@ fn differential_aux(
     let compute_time = now.elapsed().as_secs_f64();
     Ok(results)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capture_roots_of_uncaptured_patterns() {
+        let query = "(method_declaration) ; missing (\n(class_declaration name: (_) @name)";
+        assert_eq!(
+            capture_pattern_roots(query),
+            "(method_declaration) @_hyperast_root ; missing (\n(class_declaration name: (_) @name)"
+        );
+        assert_eq!(
+            capture_pattern_roots(r#"[(a) "("]+ (b) @b"#),
+            r#"[(a) "("]+ @_hyperast_root (b) @b"#
+        );
+        let predicate = r#"((a) @a (#eq? @a "b"))"#;
+        assert_eq!(capture_pattern_roots(predicate), predicate);
+        assert_eq!(capture_pattern_roots("(a"), "(a");
+    }
+
+    /// A java file, in stores like the ones of processed repositories
+    fn java_file(text: &'static str) -> (hyperast_vcs_git::SimpleStores, NodeIdentifier) {
+        let mut stores = hyperast_vcs_git::SimpleStores::default();
+        let mut md_cache = Default::default();
+        let mut java_tree_gen = hyperast_gen_ts_java::legion_with_refs::JavaTreeGen::new(
+            stores.mut_with_ts::<hyperast_gen_ts_java::types::TStore>(),
+            &mut md_cache,
+        );
+        let tree =
+            hyperast_gen_ts_java::legion_with_refs::tree_sitter_parse(text.as_bytes()).unwrap();
+        let code = java_tree_gen
+            .generate_file(b"A.java", text.as_bytes(), tree.walk())
+            .local
+            .compressed_node;
+        (stores, code)
+    }
+
+    #[test]
+    fn paginate_and_locate_matches() {
+        let text = "class A { void f() {} void g() { h(); } }";
+        let (stores, code) = java_file(text);
+        let language = hyperast_vcs_git::resolve_language("Java").unwrap();
+        let query = "(method_declaration) (method_invocation name: (identifier) @name)";
+        let query = hyperast_tsquery::Query::new(&capture_pattern_roots(query), language).unwrap();
+        let timeout = std::time::Duration::from_secs(10);
+        let page = |page, per_page| {
+            let page = MatchesPage { page, per_page };
+            let result = simple_aux(&stores, code, &query, timeout, 500, Some(&page)).unwrap();
+            (result.result, result.matches.unwrap())
+        };
+        let span = |m: &MatchLocation| (m.pattern, m.range.start, m.range.end);
+
+        let (counts, all) = page(0, 50);
+        assert_eq!(counts, vec![2, 1]);
+        // matches of the pattern without captures are located too
+        assert_eq!(all.len(), 3);
+        let f_start = text.find("void f").unwrap();
+        let f = all.iter().find(|m| m.range.start == f_start).unwrap();
+        assert_eq!(span(f), (0, f_start, f_start + "void f() {}".len()));
+        assert_eq!(f.range.row, 0);
+        assert!(f.captures.is_empty());
+        let h_start = text.find("h(").unwrap();
+        let h = all.iter().find(|m| m.pattern == 1).unwrap();
+        assert_eq!(span(h), (1, h_start, h_start + 1));
+        assert_eq!(h.captures.len(), 1);
+        assert_eq!(h.captures[0].name, "name");
+        assert_eq!(h.captures[0].text, "h");
+
+        // pages follow the order of all the matches, but counts are not paginated
+        for i in 0..3 {
+            let (counts, matches) = page(i, 1);
+            assert_eq!(counts, vec![2, 1]);
+            assert_eq!(
+                matches.iter().map(span).collect::<Vec<_>>(),
+                vec![span(&all[i])]
+            );
+        }
+        assert!(page(3, 1).1.is_empty());
+        let (_, matches) = page(1, 2);
+        assert_eq!(
+            matches.iter().map(span).collect::<Vec<_>>(),
+            vec![span(&all[2])]
+        );
+    }
+}
@@ -28,6 +28,7 @@ use std::fmt::Display;
 
 #[derive(Deserialize, Clone)]
 pub struct ScriptingParam {
+    forge: String,
     user: String,
     name: String,
     commit: String,
@@ -125,7 +126,7 @@ pub fn simple_depth(
         commits,
     } = script;
     let now = Instant::now();
-    let ScriptingParam { user, name, commit } = path.clone();
+    let ScriptingParam { forge, user, name, commit } = path.clone();
     let mut engine = Engine::new();
     engine.disable_symbol("/");
     add_utils(&mut engine);
@@ -138,7 +139,8 @@ pub fn simple_depth(
     let accumulate_script = engine.compile(script.accumulate.clone()).map_err(|x| {
         ScriptingError::AtCompilation(format!("Acc: {}, {}", x, script.accumulate.clone()))
     })?;
-    let repo_spec = hyperast_vcs_git::git::Forge::Github.repo(user, name);
+    let repo_spec = crate::utils::resolve_repo(&state, &forge, &user, &name)
+        .map_err(ScriptingError::Other)?;
     let repo = state
         .repositories
         .write()
@@ -157,7 +159,7 @@ pub fn simple_depth(
         }
     };
     // .ok_or_else(|| ScriptingError::Other("missing config for repository".to_string()))?;
-    let mut repo = repo.fetch();
+    let mut repo = repo.try_fetch().map_err(ScriptingError::Other)?;
     log::warn!("done cloning {}", &repo.spec);
     let commits = state
         .repositories
@@ -210,7 +212,7 @@ fn simple_prepare(
     ),
     ScriptingError,
 > {
-    let ScriptingParam { user, name, commit } = path.clone();
+    let ScriptingParam { forge, user, name, commit } = path.clone();
     let mut engine = Engine::new();
     engine.disable_symbol("/");
     add_utils(&mut engine);
@@ -223,14 +225,15 @@ fn simple_prepare(
     let accumulate_script = engine.compile(script.accumulate.clone()).map_err(|x| {
         ScriptingError::AtCompilation(format!("Acc: {}, {}", x, script.accumulate.clone()))
     })?;
-    let repo_spec = hyperast_vcs_git::git::Forge::Github.repo(user, name);
+    let repo_spec = crate::utils::resolve_repo(&state, &forge, &user, &name)
+        .map_err(ScriptingError::Other)?;
     let repo = state
         .repositories
         .write()
         .unwrap()
         .get_config(repo_spec)
         .ok_or_else(|| ScriptingError::Other("missing config for repository".to_string()))?;
-    let repo = repo.fetch();
+    let repo = repo.try_fetch().map_err(ScriptingError::Other)?;
     log::warn!("done cloning {}", &repo.spec);
     Ok((
         commit,
@@ -341,6 +344,12 @@ fn simple_aux(
                 t.is_file()
             });
             let s = state.clone();
+            filter_engine.register_fn("parse_errors", move || {
+                let repositories = s.repositories.read().unwrap();
+                let errors = repositories.processor.parse_errors.get(&current);
+                errors.map_or(0, |x| x.len() as i64)
+            });
+            let s = state.clone();
             filter_engine.register_fn("children", move || {
                 let node_store = &ns!(s);
                 node_store
@@ -473,6 +482,12 @@ fn simple_aux(
             t.is_file()
         });
         let s = state.clone();
+        acc_engine.register_fn("parse_errors", move || {
+            let repositories = s.repositories.read().unwrap();
+            let errors = repositories.processor.parse_errors.get(&current);
+            errors.map_or(0, |x| x.len() as i64)
+        });
+        let s = state.clone();
         acc_engine.register_fn("is_java_file", move || {
             let stores = &stores!(s);
             let node_store = &stores.node_store;
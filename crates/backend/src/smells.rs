@@ -27,6 +27,7 @@ type Idx = u16;
 
 #[derive(Deserialize, Clone)]
 pub struct Param {
+    forge: String,
     user: String,
     name: String,
     commit: String,
@@ -35,6 +36,7 @@ pub struct Param {
 
 #[derive(Deserialize, Clone)]
 pub struct Diffs {
+    forge: String,
     user: String,
     name: String,
     commit: String,
@@ -126,6 +128,7 @@ pub(crate) fn smells(
 ) -> Result<Json<SearchResults>, String> {
     let now = Instant::now();
     let Param {
+        forge,
         user,
         name,
         commit,
@@ -146,7 +149,7 @@ pub(crate) fn smells(
         true
     };
 
-    let repo_spec = hyperast_vcs_git::git::Forge::Github.repo(user, name);
+    let repo_spec = crate::utils::resolve_repo(&state, &forge, &user, &name)?;
     let configs = state.clone();
     let repo_handle = state
         .repositories
@@ -154,7 +157,7 @@ pub(crate) fn smells(
         .unwrap()
         .get_config(repo_spec)
         .ok_or_else(|| "missing config for repository".to_string())?;
-    let mut repository = repo_handle.fetch();
+    let mut repository = repo_handle.try_fetch()?;
     log::warn!("done cloning {}", repository.spec);
     let commits = state
         .repositories
@@ -284,12 +287,13 @@ pub(crate) fn smells_ex_from_diffs(
 ) -> Result<Json<ExamplesResults>, String> {
     let now = Instant::now();
     let Diffs {
+        forge,
         user,
         name,
         commit,
         len,
     } = path;
-    let repo_spec = hyperast_vcs_git::git::Forge::Github.repo(user, name);
+    let repo_spec = crate::utils::resolve_repo(&state, &forge, &user, &name)?;
     let configs = state.clone();
     let repo_handle = state
         .repositories
@@ -297,7 +301,7 @@ pub(crate) fn smells_ex_from_diffs(
         .unwrap()
         .get_config(repo_spec)
         .ok_or_else(|| "missing config for repository".to_string())?;
-    let mut repository = repo_handle.fetch();
+    let mut repository = repo_handle.try_fetch()?;
     log::warn!("done cloning {}", repository.spec);
     let commits = state
         .repositories
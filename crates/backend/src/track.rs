@@ -37,6 +37,7 @@ use crate::{
 
 #[derive(Deserialize, Clone, Debug)]
 pub struct TrackingParam {
+    pub forge: String,
     pub user: String,
     pub name: String,
     pub commit: String,
@@ -45,6 +46,7 @@ pub struct TrackingParam {
 
 #[derive(Deserialize, Clone, Debug)]
 pub struct TrackingAtPathParam {
+    forge: String,
     user: String,
     name: String,
     commit: String,
@@ -257,6 +259,7 @@ pub fn track_code(
 ) -> Result<TrackingResult<IdN, Idx>, TrackingError> {
     let now = Instant::now();
     let TrackingParam {
+        forge,
         user,
         name,
         commit,
@@ -268,7 +271,13 @@ pub fn track_code(
         before,
         flags,
     } = query;
-    let repo_specifier = hyperast_vcs_git::git::Forge::Github.repo(user, name);
+    let repo_specifier = crate::utils::resolve_repo(&state, &forge, &user, &name)
+        .map_err(|message| TrackingError {
+            compute_time: now.elapsed().as_secs_f64(),
+            commits_processed: 0,
+            node_processed: 0,
+            message,
+        })?;
     let repo_handle = state
         .repositories
         .write()
@@ -280,7 +289,12 @@ pub fn track_code(
             node_processed: 0,
             message: "missing config for repository".to_string(),
         })?;
-    let mut repository = repo_handle.fetch();
+    let mut repository = repo_handle.try_fetch().map_err(|message| TrackingError {
+        compute_time: now.elapsed().as_secs_f64(),
+        commits_processed: 0,
+        node_processed: 0,
+        message,
+    })?;
     log::debug!("done cloning {}", repository.spec);
     let mut commit = commit.clone();
     let mut node_processed = 0;
@@ -418,12 +432,19 @@ pub(crate) fn track_code_at_path(
         flags,
     } = query;
     let TrackingAtPathParam {
+        forge,
         user,
         name,
         commit,
         path,
     } = path;
-    let repo_specifier = hyperast_vcs_git::git::Forge::Github.repo(user, name);
+    let repo_specifier = crate::utils::resolve_repo(&state, &forge, &user, &name)
+        .map_err(|message| TrackingError {
+            compute_time: now.elapsed().as_secs_f64(),
+            commits_processed: 0,
+            node_processed: 0,
+            message,
+        })?;
     let repository = state
         .repositories
         .write()
@@ -435,7 +456,12 @@ pub(crate) fn track_code_at_path(
             node_processed: 0,
             message: "missing config for repository".to_string(),
         })?;
-    let mut repository = repository.fetch();
+    let mut repository = repository.try_fetch().map_err(|message| TrackingError {
+        compute_time: now.elapsed().as_secs_f64(),
+        commits_processed: 0,
+        node_processed: 0,
+        message,
+    })?;
     log::debug!("done cloning {}", repository.spec);
     // let mut get_mut = state.write().unwrap();
     // let state = get_mut.deref_mut();
@@ -575,12 +601,19 @@ pub(crate) fn track_code_at_path_with_changes(
         flags,
     } = query;
     let TrackingAtPathParam {
+        forge,
         user,
         name,
         commit,
         path,
     } = path;
-    let repo_spec = hyperast_vcs_git::git::Forge::Github.repo(user, name);
+    let repo_spec = crate::utils::resolve_repo(&state, &forge, &user, &name)
+        .map_err(|message| TrackingError {
+            compute_time: now.elapsed().as_secs_f64(),
+            commits_processed: 0,
+            node_processed: 0,
+            message,
+        })?;
     let configs = state.clone();
     let repo_handle = state
         .repositories
@@ -593,7 +626,12 @@ pub(crate) fn track_code_at_path_with_changes(
             node_processed: 0,
             message: "missing config for repository".to_string(),
         })?;
-    let mut repository = repo_handle.fetch();
+    let mut repository = repo_handle.try_fetch().map_err(|message| TrackingError {
+        compute_time: now.elapsed().as_secs_f64(),
+        commits_processed: 0,
+        node_processed: 0,
+        message,
+    })?;
     log::debug!("done cloning {}", repository.spec);
     let mut ori_oid = None;
     let mut commit = commit.clone();
@@ -1105,6 +1143,8 @@ fn track_aux2(
 }
 
 mod compute;
+mod dag;
+pub(crate) use dag::track_code_in_dag;
 mod more;
 #[cfg(feature = "experimental")]
 mod my_dash;
@@ -0,0 +1,360 @@
+//! Tracking over the commit graph, following every parent of merge commits.
+use std::collections::{hash_map::Entry, HashMap, VecDeque};
+
+use axum::response::IntoResponse;
+use hyperast::{position::resolve_range, types::WithChildren};
+use hyperast_vcs_git::{
+    git::{retrieve_commit, Oid},
+    processing::{ConfiguredRepo2, ConfiguredRepoTrait},
+};
+use serde::Serialize;
+use tokio::time::Instant;
+
+use super::{
+    track_aux2, IdN, Idx, MappingResult, PieceOfCode, TrackingAtPathParam, TrackingError,
+    TrackingQuery, MAX_NODES,
+};
+use crate::SharedState;
+
+#[derive(Serialize)]
+pub struct TrackingGraph {
+    pub compute_time: f64,
+    commits_processed: usize,
+    /// the tracked code in each visited commit, the first node is where the tracking started
+    nodes: Vec<TrackedNode>,
+    /// from pieces of code to their counterparts in parent commits
+    edges: Vec<TrackingEdge>,
+    /// the tracking stopped after diffing too many nodes, the graph is partial
+    truncated: bool,
+    #[serde(skip)]
+    index: HashMap<(Oid, Vec<Idx>), usize>,
+}
+
+#[derive(Serialize)]
+struct TrackedNode {
+    #[serde(flatten)]
+    code: PieceOfCode<IdN, Idx>,
+    /// the merge commit whose non-first parent led to this piece of code,
+    /// absent if it was reached through first parents only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    branch: Option<String>,
+}
+
+#[derive(Serialize)]
+struct TrackingEdge {
+    /// index in nodes of the code in the child commit
+    src: usize,
+    /// index in nodes of the code in the parent commit
+    dst: usize,
+    /// index of the parent among the parents of the child commit, 0 being the first parent
+    parent: usize,
+    change: Change,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+enum Change {
+    /// none of the flagged changes happened, the tracking continues in the parent
+    Unchanged,
+    /// the child commit introduced the change
+    Changed,
+    /// the code differs, but the change comes from another parent of the merge commit
+    Merged,
+    /// the code is not in the parent, dst is a fallback
+    Missing,
+}
+
+impl TrackingGraph {
+    fn insert(
+        &mut self,
+        oid: Oid,
+        code: PieceOfCode<IdN, Idx>,
+        branch: &Option<String>,
+    ) -> (usize, bool) {
+        match self.index.entry((oid, code.path.clone())) {
+            Entry::Occupied(e) => (*e.get(), false),
+            Entry::Vacant(e) => {
+                let i = self.nodes.len();
+                e.insert(i);
+                self.nodes.push(TrackedNode {
+                    code,
+                    branch: branch.clone(),
+                });
+                (i, true)
+            }
+        }
+    }
+}
+
+impl IntoResponse for TrackingGraph {
+    fn into_response(self) -> axum::response::Response {
+        let mut resp = serde_json::to_string(&self).unwrap().into_response();
+        let headers = resp.headers_mut();
+        headers.insert(
+            "Server-Timing",
+            format!("track;desc=\"Compute Time\";dur={}", self.compute_time)
+                .parse()
+                .unwrap(),
+        );
+        resp
+    }
+}
+
+/// Tracks the code at `path` through all the ancestors of `commit`, until `before` or a change.
+///
+/// If given, `start` and `end` select the code by its bytes in the node at `path`.
+///
+/// At a merge commit, a change is only attributed to the merge if the code differs from every parent,
+/// otherwise the change was brought by the branch where the code is unchanged, which is then followed.
+pub(crate) fn track_code_in_dag(
+    state: SharedState,
+    path: TrackingAtPathParam,
+    query: TrackingQuery,
+) -> Result<TrackingGraph, TrackingError> {
+    let now = Instant::now();
+    let TrackingQuery {
+        start,
+        end,
+        before,
+        flags,
+    } = query;
+    let TrackingAtPathParam {
+        forge,
+        user,
+        name,
+        commit,
+        path,
+    } = path;
+    let error = |commits_processed: usize, node_processed: usize, message: String| TrackingError {
+        compute_time: now.elapsed().as_secs_f64(),
+        commits_processed,
+        node_processed,
+        message,
+    };
+    let repo_spec = crate::utils::resolve_repo(&state, &forge, &user, &name)
+        .map_err(|message| error(0, 0, message))?;
+    let repo_handle = state
+        .repositories
+        .write()
+        .unwrap()
+        .get_config(repo_spec)
+        .ok_or_else(|| error(0, 0, "missing config for repository".to_string()))?;
+    let repository = repo_handle
+        .try_fetch()
+        .map_err(|message| error(0, 0, message))?;
+    log::debug!("done cloning {}", repository.spec);
+    let before = before
+        .map(|before| retrieve_commit(&repository.repo, &before).map(|c| c.id()))
+        .transpose()
+        .map_err(|e| error(0, 0, e.to_string()))?;
+    let process = |commit: &str| -> Result<Oid, String> {
+        let commits = state
+            .repositories
+            .write()
+            .unwrap()
+            .pre_process_with_limit(&repository, "", commit, 1)
+            .map_err(|e| e.to_string())?;
+        commits
+            .first()
+            .copied()
+            .ok_or_else(|| format!("{} not found", commit))
+    };
+    let oid = process(&commit).map_err(|message| error(0, 0, message))?;
+    let path: Vec<Idx> = path.split("/").filter_map(|x| x.parse().ok()).collect();
+    let path = resolve_path(&state, &repository, oid, path, start, end)
+        .map_err(|message| error(1, 0, message))?;
+
+    let mut graph = TrackingGraph {
+        compute_time: 0.0,
+        commits_processed: 1,
+        nodes: vec![],
+        edges: vec![],
+        truncated: false,
+        index: Default::default(),
+    };
+    let mut node_processed = 0;
+    // pieces of code whose parent commits remain to be visited
+    let mut queue = VecDeque::from([(oid, path, None)]);
+    while let Some((oid, path, branch)) = queue.pop_front() {
+        if node_processed >= MAX_NODES {
+            graph.truncated = true;
+            break;
+        }
+        if before == Some(oid) {
+            continue;
+        }
+        let parents = state
+            .repositories
+            .read()
+            .unwrap()
+            .get_commit(repository.config(), &oid)
+            .map_or(vec![], |c| c.parents.clone());
+        let mut results = Vec::with_capacity(parents.len());
+        for parent in parents {
+            let parent = process(&parent.to_string())
+                .map_err(|message| error(graph.commits_processed, node_processed, message))?;
+            graph.commits_processed += 1;
+            let result = track_aux2(state.clone(), &repository, oid, parent, &path, &flags);
+            results.push((parent, result));
+        }
+        let changes = attribute(results.iter().map(|(_, r)| r));
+        for (i, ((parent, result), change)) in results.into_iter().zip(changes).enumerate() {
+            let (src, next) = match result {
+                MappingResult::Error(err) => {
+                    return Err(error(graph.commits_processed, node_processed, err))
+                }
+                MappingResult::Skipped { nodes, src, next } => {
+                    node_processed += nodes;
+                    (src, next)
+                }
+                MappingResult::Direct { src, matches } => (src, matches),
+                MappingResult::Missing { src, fallback } => (src, vec![fallback]),
+            };
+            let src = src.globalize(repository.spec.clone(), oid);
+            let (src, _) = graph.insert(oid, src, &branch);
+            let branch = branch_through(i, oid, &branch);
+            if next.len() > 1 {
+                log::debug!("multiple matches in {}", parent)
+            }
+            for code in next {
+                let (dst, new) = graph.insert(parent, code, &branch);
+                graph.edges.push(TrackingEdge {
+                    src,
+                    dst,
+                    parent: i,
+                    change,
+                });
+                if new && change == Change::Unchanged {
+                    let path = graph.nodes[dst].code.path.clone();
+                    queue.push_back((parent, path, branch.clone()));
+                }
+            }
+        }
+    }
+    graph.compute_time = now.elapsed().as_secs_f64();
+    Ok(graph)
+}
+
+/// Extends `path` to the node spanning the bytes from `start` to `end` in the node at `path`.
+fn resolve_path(
+    state: &SharedState,
+    repository: &ConfiguredRepo2,
+    oid: Oid,
+    mut path: Vec<Idx>,
+    start: Option<usize>,
+    end: Option<usize>,
+) -> Result<Vec<Idx>, String> {
+    if start.is_none() && end.is_none() {
+        return Ok(path);
+    }
+    let repositories = state.repositories.read().unwrap();
+    let commit = repositories
+        .get_commit(repository.config(), &oid)
+        .ok_or_else(|| format!("{} not processed", oid))?;
+    let stores = &repositories.processor.main_stores;
+    let mut node = commit.ast_root;
+    for i in &path {
+        let Some(child) = stores.node_store.resolve(node).child(i) else {
+            return Err(format!("nothing at {:?} in {}", path, oid));
+        };
+        node = child;
+    }
+    let (_, offsets) = resolve_range(node, start.unwrap_or(0), end, stores);
+    path.extend(offsets.into_iter().map(|x| x as Idx));
+    Ok(path)
+}
+
+/// The changes of a commit relative to each of its parents, given the tracking in each of them.
+///
+/// A merge commit only introduces a change if the code is changed relative to every parent.
+fn attribute<'a, T: 'a>(
+    results: impl Iterator<Item = &'a MappingResult<IdN, Idx, T>> + Clone,
+) -> Vec<Change> {
+    let merged = results
+        .clone()
+        .any(|r| matches!(r, MappingResult::Skipped { .. }));
+    results
+        .map(|r| match r {
+            MappingResult::Skipped { .. } => Change::Unchanged,
+            MappingResult::Direct { .. } if merged => Change::Merged,
+            MappingResult::Direct { .. } => Change::Changed,
+            // errors abort the tracking anyway
+            MappingResult::Missing { .. } | MappingResult::Error(_) => Change::Missing,
+        })
+        .collect()
+}
+
+/// Code reached through a merged branch is attributed to the merge commit.
+fn branch_through(parent: usize, merge: Oid, branch: &Option<String>) -> Option<String> {
+    if parent == 0 {
+        branch.clone()
+    } else {
+        Some(merge.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::track::LocalPieceOfCode;
+
+    fn code() -> LocalPieceOfCode<IdN, Idx> {
+        LocalPieceOfCode {
+            file: "A.java".into(),
+            start: 0,
+            end: 10,
+            path: vec![0, 1],
+            path_ids: vec![],
+        }
+    }
+
+    fn unchanged() -> MappingResult<IdN, Idx, ()> {
+        MappingResult::Skipped {
+            nodes: 1,
+            src: code(),
+            next: vec![()],
+        }
+    }
+
+    fn changed() -> MappingResult<IdN, Idx, ()> {
+        MappingResult::Direct {
+            src: code(),
+            matches: vec![()],
+        }
+    }
+
+    #[test]
+    fn attribute_changes_to_merges() {
+        let results = [changed()];
+        assert_eq!(attribute(results.iter()), vec![Change::Changed]);
+        let results = [unchanged()];
+        assert_eq!(attribute(results.iter()), vec![Change::Unchanged]);
+        // the change comes from the first parent, the merged branch left the code unchanged
+        let results = [changed(), unchanged()];
+        assert_eq!(
+            attribute(results.iter()),
+            vec![Change::Merged, Change::Unchanged]
+        );
+        let results = [unchanged(), changed()];
+        assert_eq!(
+            attribute(results.iter()),
+            vec![Change::Unchanged, Change::Merged]
+        );
+        // the code differs from every parent, the merge changed it
+        let results = [changed(), changed()];
+        assert_eq!(
+            attribute(results.iter()),
+            vec![Change::Changed, Change::Changed]
+        );
+    }
+
+    #[test]
+    fn attribute_branches_to_merges() {
+        let merge = Oid::from_str("7c7f4cb6f7f43e8f1b4c3ea3f2b4dbf1e1f0a4f5").unwrap();
+        let outer = Some("0f3e2d1c4b5a69788796a5b4c3d2e1f0a9b8c7d6".to_string());
+        assert_eq!(branch_through(0, merge, &None), None);
+        assert_eq!(branch_through(0, merge, &outer), outer);
+        assert_eq!(branch_through(1, merge, &None), Some(merge.to_string()));
+        assert_eq!(branch_through(2, merge, &outer), Some(merge.to_string()));
+    }
+}
@@ -10,6 +10,7 @@ use std::time::Instant;
 
 #[derive(Deserialize, Clone)]
 pub struct Param {
+    forge: String,
     user: String,
     name: String,
     commit: String,
@@ -25,6 +26,7 @@ pub struct Content {
 
 #[derive(Debug, Serialize, Clone)]
 pub enum QueryingError {
+    ProcessingError(String),
     MissingLanguage(String),
     TsgParsing(String),
 }
@@ -54,7 +56,7 @@ pub fn simple(
     path: Param,
 ) -> Result<Json<ComputeResults>, QueryingError> {
     let now = Instant::now();
-    let Param { user, name, commit } = path.clone();
+    let Param { forge, user, name, commit } = path.clone();
     let Content {
         language: lang_name,
         query,
@@ -63,7 +65,8 @@ pub fn simple(
     } = query;
     let language: tree_sitter::Language = hyperast_vcs_git::resolve_language(&lang_name)
         .ok_or_else(|| QueryingError::MissingLanguage(lang_name.clone()))?;
-    let repo_spec = hyperast_vcs_git::git::Forge::Github.repo(user, name);
+    let repo_spec = crate::utils::resolve_repo(&state, &forge, &user, &name)
+        .map_err(QueryingError::ProcessingError)?;
     let repo = state
         .repositories
         .write()
@@ -82,7 +85,7 @@ pub fn simple(
         }
     };
     // .ok_or_else(|| ScriptingError::Other("missing config for repository".to_string()))?;
-    let mut repo = repo.fetch();
+    let mut repo = repo.try_fetch().map_err(QueryingError::ProcessingError)?;
     log::warn!("done cloning {}", &repo.spec);
     let commits = state
         .repositories
@@ -121,6 +121,14 @@ pub(crate) fn handle_pre_processing(
     after: &str,
     limit: usize,
 ) -> Result<Vec<hyperast_vcs_git::git::Oid>, Box<dyn std::error::Error>> {
+    let running = hyperast_vcs_git::git::retrieve_commit(&repo.repo, after)
+        .ok()
+        .and_then(|commit| state.jobs.running(&repo.spec, commit.id(), limit));
+    if let Some(job) = running {
+        // rather than competing with it for the write lock, then reuse its commits
+        log::info!("waiting on job {} for {}", job.id, repo.spec);
+        job.wait();
+    }
     let rw = hyperast_vcs_git::git::Builder::new(&repo.repo)?
         .before(before)?
         .after(after)?
@@ -172,3 +180,50 @@ pub(crate) fn handle_pre_processing_aux(
 }
 
 // rw: impl Iterator<Item = git2::Oid>,
+
+/// Resolve the repository designated by the `:forge/:user/:name` segments of a route.
+///
+/// Registered repositories are looked up first,
+/// this is how local clones and repositories given as urls on the command line are reached.
+/// Otherwise `forge` is either a known [`hyperast_vcs_git::git::Forge`]
+/// or one of the [`crate::AppState::allowed_hosts`].
+pub(crate) fn resolve_repo(
+    state: &crate::AppState,
+    forge: &str,
+    user: &str,
+    name: &str,
+) -> Result<hyperast_vcs_git::git::Repo, String> {
+    use hyperast_vcs_git::git::{check_segment, Forge, Repo};
+    if let Some(repo) = state
+        .repositories
+        .read()
+        .unwrap()
+        .find_repo(forge, user, name)
+    {
+        return Ok(repo);
+    }
+    if let Ok(forge) = forge.parse::<Forge>() {
+        return forge.try_repo(user, name);
+    }
+    if forge == "local" {
+        // arbitrary paths of the server must not be reachable from a route
+        return Err(format!(
+            "local repository {}/{} is not registered",
+            user, name
+        ));
+    }
+    let allowed = state
+        .allowed_hosts
+        .get()
+        .map_or(false, |hosts| hosts.iter().any(|h| h == forge));
+    if !allowed {
+        return Err(format!(
+            "'{}' is neither a known forge nor an allowed host",
+            forge
+        ));
+    }
+    for x in [forge, user, name] {
+        check_segment(x)?;
+    }
+    Repo::from_url(format!("https://{}/{}/{}", forge, user, name))
+}
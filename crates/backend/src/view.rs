@@ -16,6 +16,7 @@ use crate::SharedState;
 
 #[derive(Deserialize, Clone, Debug)]
 pub struct Parameters {
+    forge: String,
     user: String,
     name: String,
     commit: String,
@@ -79,20 +80,21 @@ pub struct ViewBoth {
 pub fn view(state: SharedState, path: Parameters) -> Result<Json<ViewRes>, String> {
     let now = Instant::now();
     let Parameters {
+        forge,
         user,
         name,
         commit,
         path,
     } = path;
     dbg!(&path);
-    let repo_spec = hyperast_vcs_git::git::Forge::Github.repo(user, name);
+    let repo_spec = crate::utils::resolve_repo(&state, &forge, &user, &name)?;
     let repo = state
         .repositories
         .write()
         .unwrap()
         .get_config(repo_spec)
         .ok_or_else(|| "missing config for repository".to_string())?;
-    let mut repo = repo.fetch();
+    let mut repo = repo.try_fetch()?;
     log::info!("done cloning {}", repo.spec);
     let commits = state
         .repositories
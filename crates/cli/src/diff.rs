@@ -0,0 +1,139 @@
+use std::error::Error;
+use std::time::Instant;
+
+use hyper_diff::algorithms;
+use hyperast::store::defaults::NodeIdentifier;
+use hyperast_diff_report::{locate_actions, to_csv, Action, DiffRoot};
+use hyperast_vcs_git::preprocessed::child_at_path_tracked;
+use hyperast_vcs_git::SimpleStores;
+use serde::Serialize;
+
+use crate::session::{RepoArgs, Session};
+use crate::Format;
+
+#[derive(clap::Args)]
+pub struct Args {
+    #[clap(flatten)]
+    repo: RepoArgs,
+    /// The old version
+    before: String,
+    /// The new version
+    after: String,
+    /// limits the diff to this file, e.g. src/main/java/A.java
+    #[clap(short, long)]
+    path: Option<String>,
+    /// gumtree, gumtree_lazy or gumtree_partial_lazy
+    #[clap(long, default_value = "gumtree_partial_lazy")]
+    algo: Algorithm,
+    #[clap(short, long, default_value = "csv")]
+    format: Format,
+}
+
+#[derive(Clone, Copy)]
+enum Algorithm {
+    Gumtree,
+    GumtreeLazy,
+    GumtreePartialLazy,
+}
+
+impl std::str::FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "gumtree" => Self::Gumtree,
+            "gumtree_lazy" => Self::GumtreeLazy,
+            "gumtree_partial_lazy" => Self::GumtreePartialLazy,
+            x => return Err(format!("'{}' is not an available algorithm", x)),
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct DiffRes {
+    diff_time: f64,
+    mappings: usize,
+    actions: Vec<Action>,
+}
+
+pub fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    let mut session = Session::open(&args.repo)?;
+    let src_oid = session.process_one(&args.before)?;
+    let dst_oid = session.process_one(&args.after)?;
+    let src_tr = session.commit(&src_oid)?.ast_root;
+    let dst_tr = session.commit(&dst_oid)?.ast_root;
+    let res = diff(
+        session.stores(),
+        src_tr,
+        dst_tr,
+        args.path.as_deref(),
+        args.algo,
+    )?;
+    match args.format {
+        Format::Csv => print!("{}", to_csv(&res.actions)),
+        Format::Json => println!("{}", serde_json::to_string_pretty(&res)?),
+    }
+    session.close()
+}
+
+fn diff(
+    with_spaces_stores: &SimpleStores,
+    src_tr: NodeIdentifier,
+    dst_tr: NodeIdentifier,
+    file: Option<&str>,
+    algo: Algorithm,
+) -> Result<DiffRes, String> {
+    let stores = &hyperast_vcs_git::no_space::as_nospaces2(with_spaces_stores);
+
+    // the roots of the diff, and the offsets leading to them
+    let ((src, src_offsets), (dst, dst_offsets)) = match file {
+        Some(file) => {
+            let resolve = |root| {
+                child_at_path_tracked(with_spaces_stores, root, file.split("/"))
+                    .map(|(n, o)| (n, o.into_iter().map(|x| x as u16).collect::<Vec<_>>()))
+                    .ok_or_else(|| format!("{} not found", file))
+            };
+            (resolve(src_tr)?, resolve(dst_tr)?)
+        }
+        None => ((src_tr, vec![]), (dst_tr, vec![])),
+    };
+    let now = Instant::now();
+    if src == dst {
+        return Ok(DiffRes {
+            diff_time: now.elapsed().as_secs_f64(),
+            mappings: 0,
+            actions: vec![],
+        });
+    }
+    macro_rules! run {
+        ($algo:ident) => {{
+            let r = algorithms::$algo::diff(stores, &src, &dst);
+            let mappings = r.summarize().mappings;
+            let actions = r.actions.ok_or("failed to generate the edit script")?;
+            (mappings, actions)
+        }};
+    }
+    let (mappings, actions) = match algo {
+        Algorithm::Gumtree => run!(gumtree),
+        Algorithm::GumtreeLazy => run!(gumtree_lazy),
+        Algorithm::GumtreePartialLazy => run!(gumtree_partial_lazy),
+    };
+    let diff_time = now.elapsed().as_secs_f64();
+
+    let src = DiffRoot {
+        commit: src_tr,
+        offsets: &src_offsets,
+        node: src,
+    };
+    let dst = DiffRoot {
+        commit: dst_tr,
+        offsets: &dst_offsets,
+        node: dst,
+    };
+    let actions = locate_actions(with_spaces_stores, src, dst, &actions);
+    Ok(DiffRes {
+        diff_time,
+        mappings,
+        actions,
+    })
+}
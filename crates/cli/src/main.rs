@@ -0,0 +1,79 @@
+//! Run HyperAST analyses on a repository from the command line, without the server.
+//!
+//! set the env variable RUST_LOG=debug to display logs during computation
+use clap::{Parser, Subcommand};
+
+mod diff;
+mod metrics;
+mod process;
+mod query;
+mod session;
+mod show;
+mod track;
+
+#[cfg(not(target_env = "msvc"))]
+use jemallocator::Jemalloc;
+
+#[cfg(not(target_env = "msvc"))]
+#[global_allocator]
+static GLOBAL: Jemalloc = Jemalloc;
+
+#[derive(Parser)]
+#[clap(name = "hyperast", version, about, long_about = None)]
+struct Cli {
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Process a range of commits, e.g. to fill a snapshot
+    Process(process::Args),
+    /// Count the matches of a tree-sitter query over commits
+    Query(query::Args),
+    /// Diff two commits, or a file between two commits
+    Diff(diff::Args),
+    /// Track a range of code through the ancestors of a commit
+    Track(track::Args),
+    /// Compute metrics with a lua script
+    Metrics(metrics::Args),
+    /// Print a subtree of a commit
+    Show(show::Args),
+}
+
+/// How results are printed
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Csv,
+    Json,
+}
+
+impl std::str::FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "csv" => Self::Csv,
+            "json" => Self::Json,
+            x => {
+                return Err(format!(
+                    "'{}' is not an available format, try csv or json",
+                    x
+                ))
+            }
+        })
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+    let cli = Cli::parse();
+    match cli.command {
+        Command::Process(args) => process::run(args),
+        Command::Query(args) => query::run(args),
+        Command::Diff(args) => diff::run(args),
+        Command::Track(args) => track::run(args),
+        Command::Metrics(args) => metrics::run(args),
+        Command::Show(args) => show::run(args),
+    }
+}
@@ -0,0 +1,86 @@
+use std::error::Error;
+
+use hyperast::scripting::lua_scripting::{
+    DerivedData, PREPRO_LOC, PREPRO_MCC_WITH_FINISH, PREPRO_SIZE_WITH_FINISH,
+};
+use serde::Serialize;
+
+use crate::session::{RepoArgs, Session};
+use crate::Format;
+
+#[derive(clap::Args)]
+pub struct Args {
+    #[clap(flatten)]
+    repo: RepoArgs,
+    /// A lua script computing metrics while subtrees are built,
+    /// only the java configs (java and gradle) support scripts
+    #[clap(short, long, conflicts_with = "example")]
+    script: Option<std::path::PathBuf>,
+    /// An example script instead: size, mcc or LoC
+    #[clap(short, long)]
+    example: Option<String>,
+    /// The most recent commit to measure
+    #[clap(long, default_value = "HEAD")]
+    commit: String,
+    /// Number of commits to measure, walking back from --commit
+    #[clap(short = 'n', long, default_value_t = 1)]
+    commits: usize,
+    #[clap(short, long, default_value = "json")]
+    format: Format,
+}
+
+#[derive(Serialize)]
+struct Measured {
+    commit: String,
+    /// the values computed by the script on the root of the commit
+    metrics: rhai::Map,
+}
+
+pub fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    let script = match (&args.script, args.example.as_deref()) {
+        (Some(file), _) => std::fs::read_to_string(file)?,
+        (None, Some("size")) => PREPRO_SIZE_WITH_FINISH.to_string(),
+        (None, Some("mcc")) => PREPRO_MCC_WITH_FINISH.to_string(),
+        (None, Some("LoC")) => PREPRO_LOC.to_string(),
+        (None, Some(x)) => {
+            return Err(format!("{} is not an available example, try size, mcc or LoC", x).into())
+        }
+        (None, None) => return Err("give a script with --script or --example".into()),
+    };
+    let mut session = Session::open_with(&args.repo, |repos, repo, config| {
+        repos.register_config_with_prepro(repo, config, script.into())
+    })?;
+    let oids = session.process("", &args.commit, args.commits, true)?;
+    let mut results = Vec::with_capacity(oids.len());
+    for oid in &oids {
+        let root = session.commit(oid)?.ast_root;
+        let n = session.stores().node_store.resolve(root);
+        let metrics = n
+            .get_component::<DerivedData>()
+            .map_err(|_| format!("no metrics computed on {}, is the config java?", oid))?
+            .0
+            .clone();
+        results.push(Measured {
+            commit: oid.to_string(),
+            metrics,
+        });
+    }
+    match args.format {
+        Format::Csv => {
+            // the columns are the metrics of the first commit
+            let names: Vec<_> = results.first().map_or(vec![], |r| {
+                r.metrics.keys().map(|k| k.to_string()).collect()
+            });
+            println!("commit,{}", names.join(","));
+            for r in &results {
+                let values = names.iter().map(|k| match r.metrics.get(k.as_str()) {
+                    Some(v) => v.to_string(),
+                    None => String::new(),
+                });
+                println!("{},{}", r.commit, values.collect::<Vec<_>>().join(","));
+            }
+        }
+        Format::Json => println!("{}", serde_json::to_string_pretty(&results)?),
+    }
+    session.close()
+}
@@ -0,0 +1,66 @@
+use std::error::Error;
+
+use hyperast::types::WithStats;
+use serde::Serialize;
+
+use crate::session::{RepoArgs, Session};
+use crate::Format;
+
+#[derive(clap::Args)]
+pub struct Args {
+    #[clap(flatten)]
+    repo: RepoArgs,
+    /// The most recent commit of the range
+    #[clap(long, default_value = "HEAD")]
+    after: String,
+    /// The oldest commit of the range, by default the range is only bounded by --limit
+    #[clap(long, default_value = "")]
+    before: String,
+    /// Maximum number of commits to process
+    #[clap(short, long, default_value_t = 1)]
+    limit: usize,
+    /// Only follow the first parent of merge commits
+    #[clap(long)]
+    first_parents: bool,
+    #[clap(short, long, default_value = "csv")]
+    format: Format,
+}
+
+#[derive(Serialize)]
+struct Processed {
+    commit: String,
+    /// in nanoseconds
+    processing_time: u128,
+    /// number of nodes of the commit, including spaces
+    size: usize,
+    parse_failures: u32,
+}
+
+pub fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    let mut session = Session::open(&args.repo)?;
+    let oids = session.process(&args.before, &args.after, args.limit, args.first_parents)?;
+    let mut results = Vec::with_capacity(oids.len());
+    for oid in &oids {
+        let commit = session.commit(oid)?;
+        let size = session.stores().node_store.resolve(commit.ast_root).size();
+        results.push(Processed {
+            commit: oid.to_string(),
+            processing_time: commit.processing_time(),
+            size,
+            parse_failures: commit.parse_failures(),
+        });
+    }
+    match args.format {
+        Format::Csv => {
+            println!("commit,processing_time,size,parse_failures");
+            for r in &results {
+                println!(
+                    "{},{},{},{}",
+                    r.commit, r.processing_time, r.size, r.parse_failures
+                );
+            }
+        }
+        Format::Json => println!("{}", serde_json::to_string_pretty(&results)?),
+    }
+    session.close()
+}
@@ -0,0 +1,141 @@
+use std::error::Error;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::session::{RepoArgs, Session};
+use crate::Format;
+
+#[derive(clap::Args)]
+pub struct Args {
+    #[clap(flatten)]
+    repo: RepoArgs,
+    /// The language of the query, e.g. Java, Cpp or Ts
+    #[clap(long)]
+    lang: String,
+    /// The query, in the tree-sitter syntax
+    #[clap(short, long, conflicts_with = "query_file")]
+    query: Option<String>,
+    /// A file containing the query
+    #[clap(long)]
+    query_file: Option<std::path::PathBuf>,
+    /// The most recent commit to query
+    #[clap(long, default_value = "HEAD")]
+    commit: String,
+    /// Number of commits to query, walking back from --commit
+    #[clap(short = 'n', long, default_value_t = 1)]
+    commits: usize,
+    /// Querying a commit for longer than that, in milliseconds, is abandoned
+    #[clap(long, default_value_t = 60_000)]
+    timeout: u64,
+    #[clap(short, long, default_value = "csv")]
+    format: Format,
+}
+
+#[derive(Serialize)]
+struct QueryResult {
+    commit: String,
+    /// in seconds
+    compute_time: f64,
+    /// number of matches per pattern
+    result: Vec<u64>,
+    /// the querying did not finish, then `result` is partial
+    timed_out: bool,
+}
+
+pub fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    let text = match (&args.query, &args.query_file) {
+        (Some(query), _) => query.clone(),
+        (None, Some(file)) => std::fs::read_to_string(file)?,
+        (None, None) => return Err("give a query with --query or --query-file".into()),
+    };
+    let language = hyperast_vcs_git::resolve_language(&args.lang)
+        .ok_or_else(|| format!("{} is not a supported language", args.lang))?;
+    let mut session = Session::open(&args.repo)?;
+    let oids = session.process("", &args.commit, args.commits, false)?;
+    let precomputeds = session
+        .repos
+        .get_precomp_query(session.repo.config, &args.lang);
+    let query = if let Some(precomputeds) = precomputeds {
+        hyperast_tsquery::Query::with_precomputed(&text, language, precomputeds).map(|x| x.1)
+    } else {
+        hyperast_tsquery::Query::new(&text, language)
+    }
+    .map_err(|e| e.to_string())?;
+    let timeout = Duration::from_millis(args.timeout);
+    let mut results = Vec::with_capacity(oids.len());
+    for oid in &oids {
+        let root = session.commit(oid)?.ast_root;
+        let mut r = count_matches(session.stores(), root, &query, timeout);
+        r.commit = oid.to_string();
+        if r.timed_out {
+            log::warn!("querying {} timed out", oid);
+        }
+        results.push(r);
+    }
+    match args.format {
+        Format::Csv => {
+            print!("{}", to_csv(query.enabled_pattern_count(), &results));
+        }
+        Format::Json => println!("{}", serde_json::to_string_pretty(&results)?),
+    }
+    session.close()
+}
+
+fn count_matches(
+    stores: &hyperast_vcs_git::SimpleStores,
+    root: hyperast::store::defaults::NodeIdentifier,
+    query: &hyperast_tsquery::Query,
+    timeout: Duration,
+) -> QueryResult {
+    let pos = hyperast::position::StructuralPosition::new(root);
+    let cursor = hyperast_tsquery::hyperast_cursor::TreeCursor::new(stores, pos);
+    let now = Instant::now();
+    let mut result = vec![0; query.enabled_pattern_count()];
+    let mut timed_out = false;
+    for m in query.matches(cursor) {
+        let i = query.enabled_pattern_index(m.pattern_index).unwrap();
+        result[i as usize] += 1;
+        if now.elapsed() >= timeout {
+            timed_out = true;
+            break;
+        }
+    }
+    QueryResult {
+        commit: String::new(),
+        compute_time: now.elapsed().as_secs_f64(),
+        result,
+        timed_out,
+    }
+}
+
+/// One row per commit, one column per pattern
+fn to_csv(patterns: usize, results: &[QueryResult]) -> String {
+    let mut csv = "commit,compute_time,timed_out".to_string();
+    for i in 0..patterns {
+        csv += &format!(",p{}", i);
+    }
+    csv += "\n";
+    for r in results {
+        csv += &format!("{},{},{}", r.commit, r.compute_time, r.timed_out);
+        for x in &r.result {
+            csv += &format!(",{}", x);
+        }
+        csv += "\n";
+    }
+    csv
+}
+
+#[test]
+fn csv_of_results() {
+    let results = [QueryResult {
+        commit: "abc".to_string(),
+        compute_time: 0.5,
+        result: vec![3, 0],
+        timed_out: false,
+    }];
+    assert_eq!(
+        to_csv(2, &results),
+        "commit,compute_time,timed_out,p0,p1\nabc,0.5,false,3,0\n"
+    );
+}
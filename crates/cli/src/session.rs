@@ -0,0 +1,166 @@
+//! The analyzed repository, along the stores built from its commits.
+use std::error::Error;
+use std::path::PathBuf;
+
+use hyperast_vcs_git::git::{Oid, Repo};
+use hyperast_vcs_git::multi_preprocessed::PreProcessedRepositories;
+use hyperast_vcs_git::processing::{
+    ConfiguredRepo2, ConfiguredRepoHandle2, ParallelConfig, RepoConfig,
+};
+use hyperast_vcs_git::{Commit, SimpleStores};
+
+#[derive(clap::Args)]
+pub struct RepoArgs {
+    /// The repository, a local path, any git url or <forge>/<user>/<name>
+    ///
+    /// remote repositories are cloned in --clone-dir
+    #[clap(short, long, default_value = ".")]
+    pub repo: String,
+
+    /// How the repository is processed: java, gradle, cpp, ts or any
+    #[clap(short, long, default_value = "any")]
+    pub config: RepoConfig,
+
+    /// where remote repositories are cloned
+    #[clap(long, default_value = hyperast_vcs_git::git::DEFAULT_CLONE_ROOT)]
+    pub clone_dir: PathBuf,
+
+    /// snapshot of the processed commits, loaded if it exists and written back at the end,
+    /// so that successive runs, e.g. in CI, only process new commits
+    #[clap(long)]
+    pub snapshot: Option<PathBuf>,
+
+    /// files larger than that, in bytes, are not parsed but replaced by placeholders
+    #[clap(long)]
+    pub max_file_size: Option<usize>,
+
+    /// parsing a file for longer than that, in milliseconds, is abandoned,
+    /// the file is then replaced by a placeholder
+    #[clap(long)]
+    pub parse_timeout: Option<u64>,
+
+    /// parse the new files of upcoming commits with that many threads,
+    /// by default files are parsed one by one while being processed
+    #[clap(long)]
+    pub parse_threads: Option<usize>,
+
+    /// number of upcoming commits whose new files are parsed together, see --parse-threads
+    #[clap(long, default_value_t = 8)]
+    pub parse_window: usize,
+}
+
+impl RepoArgs {
+    fn resolve_repo(&self) -> Result<Repo, String> {
+        let path = std::path::Path::new(&self.repo);
+        let repo: Repo = if path.exists() {
+            // e.g. `.`, its name is only known once canonicalized
+            let path = path.canonicalize().map_err(|e| e.to_string())?;
+            Repo::local(path)?
+        } else {
+            self.repo.parse()?
+        };
+        // the user designated it explicitly
+        if let Some(path) = repo.local_path() {
+            hyperast_vcs_git::git::allow_local_root(path);
+        }
+        Ok(repo)
+    }
+}
+
+pub struct Session {
+    pub repos: PreProcessedRepositories,
+    pub repo: ConfiguredRepo2,
+    snapshot: Option<PathBuf>,
+}
+
+impl Session {
+    pub fn open(args: &RepoArgs) -> Result<Self, Box<dyn Error>> {
+        Self::open_with(args, |repos, repo, config| {
+            Ok(repos.register_config(repo, config))
+        })
+    }
+
+    /// `register` lets subcommands customize the processing, e.g. with a script
+    pub fn open_with(
+        args: &RepoArgs,
+        register: impl FnOnce(
+            &mut PreProcessedRepositories,
+            Repo,
+            RepoConfig,
+        ) -> Result<ConfiguredRepoHandle2, String>,
+    ) -> Result<Self, Box<dyn Error>> {
+        hyperast_vcs_git::git::set_clone_root(&args.clone_dir);
+        let mut repos = PreProcessedRepositories::default();
+        if let Some(path) = args.snapshot.as_ref().filter(|p| p.exists()) {
+            let registry = hyperast_vcs_git::snapshot::registry();
+            repos.load_snapshot_from_file(&registry, path)?;
+            log::info!("warm started from {}", path.display());
+        }
+        repos.processor.limits = hyperast_vcs_git::processing::FileLimits {
+            max_size: args.max_file_size,
+            timeout: args.parse_timeout.map(std::time::Duration::from_millis),
+        };
+        repos.processor.parallel = args.parse_threads.map(|threads| ParallelConfig {
+            threads,
+            window: args.parse_window,
+        });
+        let spec = args.resolve_repo()?;
+        let repo = register(&mut repos, spec, args.config)?.fetch();
+        log::info!("opened {}", repo.spec);
+        Ok(Self {
+            repos,
+            repo,
+            snapshot: args.snapshot.clone(),
+        })
+    }
+
+    /// Processes the commits from `after` (e.g. `HEAD`) back to `before` (if not empty),
+    /// at most `limit` of them, in the order of the walk
+    pub fn process(
+        &mut self,
+        before: &str,
+        after: &str,
+        limit: usize,
+        first_parents: bool,
+    ) -> Result<Vec<Oid>, Box<dyn Error>> {
+        let mut rw = hyperast_vcs_git::git::Builder::new(&self.repo.repo)?
+            .before(before)?
+            .after(after)?;
+        if first_parents {
+            rw = rw.first_parents()?;
+        }
+        let oids = rw.walk()?.take(limit).collect::<Result<Vec<_>, _>>()?;
+        Ok(self
+            .repos
+            .pre_process_chunk(&mut oids.into_iter(), &self.repo, usize::MAX))
+    }
+
+    /// Processes a single commit, given anything `git rev-parse` understands
+    pub fn process_one(&mut self, commit: &str) -> Result<Oid, Box<dyn Error>> {
+        let oids = self.process("", commit, 1, true)?;
+        let oid = oids
+            .first()
+            .ok_or_else(|| format!("{} not found", commit))?;
+        Ok(*oid)
+    }
+
+    pub fn commit(&self, oid: &Oid) -> Result<&Commit, String> {
+        self.repos
+            .get_commit(&self.repo.config, oid)
+            .ok_or_else(|| format!("{} is not processed", oid))
+    }
+
+    pub fn stores(&self) -> &SimpleStores {
+        &self.repos.processor.main_stores
+    }
+
+    /// Writes back the snapshot, if any
+    pub fn close(self) -> Result<(), Box<dyn Error>> {
+        if let Some(path) = &self.snapshot {
+            let registry = hyperast_vcs_git::snapshot::registry();
+            self.repos.save_snapshot_to_file(&registry, path)?;
+            log::info!("snapshot written to {}", path.display());
+        }
+        Ok(())
+    }
+}
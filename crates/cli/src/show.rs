@@ -0,0 +1,71 @@
+use std::error::Error;
+
+use hyperast::nodes::{JsonSerializer, SexpSerializer, SyntaxSerializer, TextSerializer};
+use hyperast_vcs_git::preprocessed::child_at_path;
+
+use crate::session::{RepoArgs, Session};
+
+#[derive(clap::Args)]
+pub struct Args {
+    #[clap(flatten)]
+    repo: RepoArgs,
+    /// The commit containing the subtree
+    #[clap(long, default_value = "HEAD")]
+    commit: String,
+    /// The file or directory to print, by default the whole commit
+    #[clap(short, long)]
+    path: Option<String>,
+    /// text, sexp, syntax or json
+    #[clap(long = "as", default_value = "text")]
+    kind: Kind,
+}
+
+/// How the subtree is printed
+#[derive(Clone, Copy)]
+enum Kind {
+    /// the original code
+    Text,
+    /// an indented s-expression of the types and labels
+    Sexp,
+    /// an s-expression on a single line, including spaces
+    Syntax,
+    Json,
+}
+
+impl std::str::FromStr for Kind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "text" => Self::Text,
+            "sexp" => Self::Sexp,
+            "syntax" => Self::Syntax,
+            "json" => Self::Json,
+            x => {
+                return Err(format!(
+                    "'{}' is not an available kind, try text, sexp, syntax or json",
+                    x
+                ))
+            }
+        })
+    }
+}
+
+pub fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    let mut session = Session::open(&args.repo)?;
+    let oid = session.process_one(&args.commit)?;
+    let root = session.commit(&oid)?.ast_root;
+    let stores = session.stores();
+    let id = match &args.path {
+        Some(path) => child_at_path(stores, root, path.split("/"))
+            .ok_or_else(|| format!("{} not found in {}", path, oid))?,
+        None => root,
+    };
+    match args.kind {
+        Kind::Text => println!("{}", TextSerializer::new(stores, id)),
+        Kind::Sexp => println!("{}", SexpSerializer::new(stores, id)),
+        Kind::Syntax => println!("{}", SyntaxSerializer::new(stores, id)),
+        Kind::Json => println!("{}", JsonSerializer::<_, _, false>::new(stores, id)),
+    }
+    session.close()
+}
@@ -0,0 +1,170 @@
+use std::error::Error;
+
+use hyper_diff::algorithms;
+use hyper_diff::decompressed_tree_store::{DecompressedWithParent, ShallowDecompressedTreeStore};
+use hyper_diff::matchers::mapping_store::MonoMappingStore;
+use hyperast::store::defaults::NodeIdentifier;
+use hyperast::types::{HyperAST, HyperType};
+use hyperast_vcs_git::git::Oid;
+use hyperast_vcs_git::preprocessed::child_at_path_tracked;
+use hyperast_vcs_git::SimpleStores;
+use serde::Serialize;
+
+use crate::session::{RepoArgs, Session};
+use crate::Format;
+
+#[derive(clap::Args)]
+pub struct Args {
+    #[clap(flatten)]
+    repo: RepoArgs,
+    /// The file containing the code to track, e.g. src/main/java/A.java
+    file: String,
+    /// Offset in bytes of the start of the code in the file
+    start: usize,
+    /// Offset in bytes of the end of the code in the file
+    end: usize,
+    /// The commit where the code is
+    #[clap(long, default_value = "HEAD")]
+    commit: String,
+    /// Number of ancestors to track the code through, following first parents
+    #[clap(short = 'n', long, default_value_t = 10)]
+    commits: usize,
+    #[clap(short, long, default_value = "csv")]
+    format: Format,
+}
+
+/// Where the tracked code is in a commit
+#[derive(Serialize)]
+struct Tracked {
+    commit: String,
+    file: String,
+    start: usize,
+    end: usize,
+    r#type: String,
+}
+
+pub fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    let mut session = Session::open(&args.repo)?;
+    let mut oid = session.process_one(&args.commit)?;
+    let mut file = args.file.clone();
+    let root = session.commit(&oid)?.ast_root;
+    let (file_node, _) = child_at_path_tracked(session.stores(), root, file.split("/"))
+        .ok_or_else(|| format!("{} not found in {}", file, oid))?;
+    let (_, offsets) =
+        hyperast::position::resolve_range(file_node, args.start, Some(args.end), session.stores());
+    // offsets of the tracked node from the root of its file, including spaces
+    let mut path: Vec<u16> = offsets.into_iter().map(|x| x as u16).collect();
+    let mut results = vec![locate(session.stores(), oid, root, &file, &path)?];
+    for _ in 0..args.commits {
+        let Some(parent) = session.commit(&oid)?.parents.first().copied() else {
+            break;
+        };
+        let parent = session.process_one(&parent.to_string())?;
+        let src_tr = session.commit(&oid)?.ast_root;
+        let dst_tr = session.commit(&parent)?.ast_root;
+        match track_step(session.stores(), src_tr, dst_tr, &file, &path)? {
+            Some(next) => (file, path) = next,
+            None => {
+                log::warn!(
+                    "the code was not found in {}, it might have been added by {}",
+                    parent,
+                    oid
+                );
+                break;
+            }
+        }
+        oid = parent;
+        results.push(locate(session.stores(), oid, dst_tr, &file, &path)?);
+    }
+    match args.format {
+        Format::Csv => {
+            println!("commit,file,start,end,type");
+            for r in &results {
+                println!("{},{},{},{},{}", r.commit, r.file, r.start, r.end, r.r#type);
+            }
+        }
+        Format::Json => println!("{}", serde_json::to_string_pretty(&results)?),
+    }
+    session.close()
+}
+
+/// Maps the node at `path` in `file` from `src_tr` to `dst_tr`,
+/// files are diffed when they are at the same place in both commits, otherwise whole commits are diffed
+fn track_step(
+    with_spaces_stores: &SimpleStores,
+    src_tr: NodeIdentifier,
+    dst_tr: NodeIdentifier,
+    file: &str,
+    path: &[u16],
+) -> Result<Option<(String, Vec<u16>)>, String> {
+    let (src_file, offsets_to_file) =
+        child_at_path_tracked(with_spaces_stores, src_tr, file.split("/"))
+            .ok_or_else(|| format!("{} not found", file))?;
+    let dst_file = child_at_path_tracked(with_spaces_stores, dst_tr, file.split("/"));
+    let (src, dst, src_path) = match dst_file {
+        // the file is unchanged, so is the tracked code
+        Some((dst_file, _)) if dst_file == src_file => {
+            return Ok(Some((file.to_string(), path.to_vec())));
+        }
+        Some((dst_file, _)) => (src_file, dst_file, path.to_vec()),
+        None => {
+            let offsets_to_file = offsets_to_file.into_iter().map(|x| x as u16);
+            let src_path = offsets_to_file.chain(path.iter().copied()).collect();
+            (src_tr, dst_tr, src_path)
+        }
+    };
+    let (_, _, no_spaces) = hyperast::position::compute_position_with_no_spaces(
+        src,
+        &mut src_path.iter().copied(),
+        with_spaces_stores,
+    );
+    let stores = &hyperast_vcs_git::no_space::as_nospaces2(with_spaces_stores);
+    let mapping = algorithms::gumtree_partial_lazy::diff(stores, &src, &dst)
+        .mapper
+        .mapping;
+    let target = mapping
+        .src_arena
+        .child(&mapping.src_arena.root(), &no_spaces[..]);
+    let Some(mapped) = mapping.mappings.get_dst(&target) else {
+        return Ok(None);
+    };
+    let no_spaces: Vec<u16> = mapping.dst_arena.path_rooted(&mapped);
+    let (dst_path, _) =
+        hyperast::position::path_with_spaces(dst, &mut no_spaces.into_iter(), with_spaces_stores);
+    if dst != dst_tr {
+        return Ok(Some((file.to_string(), dst_path)));
+    }
+    // the code moved to another file
+    let (pos, _) = hyperast::position::compute_position(
+        dst_tr,
+        &mut dst_path.iter().copied(),
+        with_spaces_stores,
+    );
+    let file = pos.file().to_string_lossy().to_string();
+    let (_, offsets_to_file) = child_at_path_tracked(with_spaces_stores, dst_tr, file.split("/"))
+        .ok_or_else(|| format!("{} not found", file))?;
+    let path = dst_path[offsets_to_file.len()..].to_vec();
+    Ok(Some((file, path)))
+}
+
+fn locate(
+    stores: &SimpleStores,
+    oid: Oid,
+    root: NodeIdentifier,
+    file: &str,
+    path: &[u16],
+) -> Result<Tracked, String> {
+    let (_, offsets_to_file) = child_at_path_tracked(stores, root, file.split("/"))
+        .ok_or_else(|| format!("{} not found in {}", file, oid))?;
+    let offsets_to_file = offsets_to_file.into_iter().map(|x| x as u16);
+    let path: Vec<u16> = offsets_to_file.chain(path.iter().copied()).collect();
+    let (pos, id) = hyperast::position::compute_position(root, &mut path.into_iter(), stores);
+    let range = pos.range();
+    Ok(Tracked {
+        commit: oid.to_string(),
+        file: file.to_string(),
+        start: range.start,
+        end: range.end,
+        r#type: stores.resolve_type(&id).as_static_str().to_string(),
+    })
+}
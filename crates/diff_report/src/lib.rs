@@ -0,0 +1,224 @@
+//! Edit scripts located in the files of commits,
+//! shared by the backend and the command-line tool.
+//!
+//! Actions of [`hyper_diff`] scripts designate nodes with paths in stores without spaces,
+//! here they are given as byte ranges in files, along the types of the nodes.
+
+use std::fmt::Display;
+
+use hyper_diff::{
+    actions::{
+        action_vec::ActionsVec, pair_updates_with_moves, script_generator2::Act,
+        script_generator2::SimpleAction, Moved,
+    },
+    tree::tree_path::{CompressedTreePath, TreePath},
+};
+use hyperast::{
+    store::defaults::{LabelIdentifier, NodeIdentifier},
+    types::{HyperAST, HyperType, LabelStore},
+};
+use hyperast_vcs_git::SimpleStores;
+use serde::Serialize;
+
+pub type A = SimpleAction<LabelIdentifier, CompressedTreePath<u16>, NodeIdentifier>;
+
+#[derive(Serialize, Debug)]
+pub struct Location {
+    pub file: String,
+    pub start: usize,
+    pub end: usize,
+    pub r#type: String,
+    /// offsets from the root of the commit, including spaces
+    pub path: Vec<u16>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Action {
+    pub kind: ActionKind,
+    /// where the node was, absent for insertions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub src: Option<Location>,
+    /// where the node goes, absent for deletions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dst: Option<Location>,
+    /// the new label of updates
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionKind {
+    Delete,
+    Insert,
+    Update,
+    Move,
+    MoveUpdate,
+}
+
+impl Display for ActionKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ActionKind::Delete => "delete",
+            ActionKind::Insert => "insert",
+            ActionKind::Update => "update",
+            ActionKind::Move => "move",
+            ActionKind::MoveUpdate => "move_update",
+        })
+    }
+}
+
+/// A side of a diff, either a whole commit or one of its files
+#[derive(Clone, Copy)]
+pub struct DiffRoot<'a> {
+    /// the root of the commit, positions are computed from it so that files are resolved
+    pub commit: NodeIdentifier,
+    /// offsets from `commit` to `node`, including spaces
+    pub offsets: &'a [u16],
+    /// the diffed node
+    pub node: NodeIdentifier,
+}
+
+impl<'a> DiffRoot<'a> {
+    /// A whole commit
+    pub fn commit(commit: NodeIdentifier) -> Self {
+        Self {
+            commit,
+            offsets: &[],
+            node: commit,
+        }
+    }
+
+    /// Locates the node at `path`, a path without spaces from [`DiffRoot::node`]
+    pub fn locate(&self, stores: &SimpleStores, path: impl IntoIterator<Item = u16>) -> Location {
+        let (p, _) = hyperast::position::path_with_spaces(self.node, &mut path.into_iter(), stores);
+        let path: Vec<u16> = self.offsets.iter().copied().chain(p).collect();
+        let (pos, id) =
+            hyperast::position::compute_position(self.commit, &mut path.iter().copied(), stores);
+        let range = pos.range();
+        Location {
+            file: pos.file().to_string_lossy().to_string(),
+            start: range.start,
+            end: range.end,
+            r#type: stores.resolve_type(&id).as_static_str().to_string(),
+            path,
+        }
+    }
+}
+
+/// Locates the actions of a script computed between `src` and `dst`.
+///
+/// A node both moved and renamed is given as a single [`ActionKind::MoveUpdate`].
+pub fn locate_actions(
+    stores: &SimpleStores,
+    src: DiffRoot,
+    dst: DiffRoot,
+    actions: &ActionsVec<A>,
+) -> Vec<Action> {
+    let locate_src = |path: &CompressedTreePath<u16>| Some(src.locate(stores, path.iter()));
+    let locate_dst = |path: &CompressedTreePath<u16>| Some(dst.locate(stores, path.iter()));
+    let label = |l: &LabelIdentifier| Some(stores.label_store.resolve(l).to_string());
+    pair_updates_with_moves(&actions.0)
+        .map(|paired| {
+            let a = paired.action;
+            match &a.action {
+                Act::Delete {} => Action {
+                    kind: ActionKind::Delete,
+                    src: locate_src(&a.path.ori),
+                    dst: None,
+                    label: None,
+                },
+                Act::Insert { .. } => Action {
+                    kind: ActionKind::Insert,
+                    src: None,
+                    dst: locate_dst(&a.path.ori),
+                    label: None,
+                },
+                Act::Update { new } => match paired.moved {
+                    Some(Moved { from, path, .. }) => Action {
+                        kind: ActionKind::MoveUpdate,
+                        src: locate_src(&from.ori),
+                        dst: locate_dst(&path.ori),
+                        label: label(new),
+                    },
+                    None => Action {
+                        kind: ActionKind::Update,
+                        src: locate_src(&a.path.ori),
+                        dst: None,
+                        label: label(new),
+                    },
+                },
+                Act::Move { from } => Action {
+                    kind: ActionKind::Move,
+                    src: locate_src(&from.ori),
+                    dst: locate_dst(&a.path.ori),
+                    label: None,
+                },
+                Act::MovUpd { from, new } => Action {
+                    kind: ActionKind::MoveUpdate,
+                    src: locate_src(&from.ori),
+                    dst: locate_dst(&a.path.ori),
+                    label: label(new),
+                },
+            }
+        })
+        .collect()
+}
+
+/// One line per action, after a header
+pub fn to_csv(actions: &[Action]) -> String {
+    let mut csv = "kind,src_file,src_start,src_end,dst_file,dst_start,dst_end,label\n".to_string();
+    let loc = |l: &Option<Location>| match l {
+        Some(l) => format!("{},{},{}", csv_escape(&l.file), l.start, l.end),
+        None => ",,".to_string(),
+    };
+    for a in actions {
+        let label = a.label.as_deref().map_or(String::new(), csv_escape);
+        csv += &format!("{},{},{},{}\n", a.kind, loc(&a.src), loc(&a.dst), label);
+    }
+    csv
+}
+
+pub fn csv_escape(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_of_actions() {
+        let at = |file: &str, start, end| Location {
+            file: file.to_string(),
+            start,
+            end,
+            r#type: "identifier".to_string(),
+            path: vec![],
+        };
+        let actions = [
+            Action {
+                kind: ActionKind::Delete,
+                src: Some(at("A.java", 4, 8)),
+                dst: None,
+                label: None,
+            },
+            Action {
+                kind: ActionKind::MoveUpdate,
+                src: Some(at("A.java", 10, 12)),
+                dst: Some(at("b,c.java", 0, 3)),
+                label: Some("say \"hi\"".to_string()),
+            },
+        ];
+        assert_eq!(
+            to_csv(&actions),
+            "kind,src_file,src_start,src_end,dst_file,dst_start,dst_end,label\n\
+             delete,A.java,4,8,,,,\n\
+             move_update,A.java,10,12,\"b,c.java\",0,3,\"say \"\"hi\"\"\"\n"
+        );
+    }
+}
@@ -59,6 +59,19 @@ impl Mcc {
     // }
 }
 
+impl crate::store::nodes::legion::persist::Persist for Mcc {
+    fn persist(&self, w: &mut dyn std::io::Write) -> std::io::Result<()> {
+        crate::store::nodes::legion::persist::write_u32(w, self.value)
+    }
+    fn restore(
+        r: &mut dyn std::io::Read,
+        _: &crate::store::nodes::legion::persist::IdRemap,
+    ) -> std::io::Result<Self> {
+        let value = crate::store::nodes::legion::persist::read_u32(r)?;
+        Ok(Self { value })
+    }
+}
+
 impl<T: Typed + WithMetaData<Mcc>> MetaData<T> for Mcc
 where
     T::Type: TypeTrait,
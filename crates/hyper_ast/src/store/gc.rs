@@ -0,0 +1,40 @@
+//! Garbage collection of [`SimpleStores`].
+//!
+//! Stores only ever grow while processing, the nodes (and labels)
+//! that are not reachable from some roots (e.g. the roots of the commits that are kept)
+//! can be removed with [`SimpleStores::collect_garbage`].
+//! Remaining nodes keep their identifiers, labels are renumbered.
+//!
+//! Whatever refers to removed nodes or to labels must be dropped by the caller,
+//! e.g. caches of processors.
+use super::{defaults::NodeIdentifier, labels::LabelStore, nodes::legion, SimpleStores};
+pub use legion::gc::Marked;
+
+/// What a collection removed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Collected {
+    pub nodes: usize,
+    pub labels: usize,
+}
+
+impl<TS> SimpleStores<TS, legion::NodeStore, LabelStore> {
+    /// Removes the nodes that are not reachable from `roots`,
+    /// then compacts the label store to the labels of the remaining nodes.
+    pub fn collect_garbage(
+        &mut self,
+        roots: impl IntoIterator<Item = NodeIdentifier>,
+    ) -> Collected {
+        let marked = self.node_store.mark(roots);
+        let nodes = self.node_store.sweep(&marked);
+        let before = self.label_store.len();
+        let remap = self.label_store.compact(|l| marked.labels.contains(&l));
+        let labels = before - self.label_store.len();
+        if labels != 0 {
+            use string_interner::Symbol;
+            self.node_store
+                .remap_labels(|l| remap[l.to_usize()].expect("labels of remaining nodes are kept"));
+        }
+        log::info!("collected {} nodes and {} labels", nodes, labels);
+        Collected { nodes, labels }
+    }
+}
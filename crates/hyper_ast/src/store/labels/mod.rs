@@ -10,6 +10,8 @@ use crate::types::LabelStore as _;
 #[derive(Default)]
 pub struct LabelStore {
     count: usize,
+    /// see [`LabelStore::generation`]
+    generation: usize,
     internal: StringInterner<string_interner::DefaultBackend>, //VecMapStore<OwnedLabel, LabelIdentifier>,
 }
 
@@ -17,6 +19,7 @@ impl Debug for LabelStore {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("LabelStore")
             .field("count", &self.count)
+            .field("generation", &self.generation)
             .field("internal_len", &self.internal.len())
             .field("internal", &self.internal)
             .finish()
@@ -80,9 +83,104 @@ impl LabelStore {
     pub fn new() -> Self {
         let mut r = Self {
             count: 1,
+            generation: 0,
             internal: Default::default(),
         };
         r.get_or_insert("length"); // TODO verify/model statically
         r
     }
 }
+
+impl LabelStore {
+    /// Number of interned labels.
+    pub fn len(&self) -> usize {
+        self.internal.len()
+    }
+
+    /// Labels in interning order, starting from the `start`th one.
+    pub fn iter_from(&self, start: usize) -> impl Iterator<Item = &str> {
+        (start..self.internal.len()).map(|i| {
+            let id = label_id_from_usize(i).unwrap();
+            self.internal.resolve(id).unwrap()
+        })
+    }
+
+    /// Number of times labels were renumbered by [`LabelStore::compact`].
+    ///
+    /// Identifiers handed out under another generation must not be resolved,
+    /// they might designate other labels or none.
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    /// Same as [`crate::types::LabelStore::resolve`], but None for unknown identifiers
+    pub fn try_resolve(&self, id: &DefaultLabelIdentifier) -> Option<&DefaultLabelValue> {
+        self.internal.resolve(*id)
+    }
+
+    /// Keeps only the labels for which `used` holds, in the same order,
+    /// the first one is always kept (see [`LabelStore::new`]).
+    /// The returned vector maps previous identifiers (as usize) to the new ones.
+    ///
+    /// If some labels are removed, the following ones are renumbered
+    /// and the generation is incremented, see [`LabelStore::generation`].
+    /// Identifiers held outside of the stores must then be remapped or dropped.
+    pub fn compact(
+        &mut self,
+        used: impl Fn(DefaultLabelIdentifier) -> bool,
+    ) -> Vec<Option<DefaultLabelIdentifier>> {
+        let mut compacted = Self::default();
+        let remap = (&self.internal)
+            .into_iter()
+            .map(|(id, s)| (id.to_usize() == 0 || used(id)).then(|| compacted.get_or_insert(s)))
+            .collect();
+        compacted.count = compacted.internal.len();
+        compacted.generation = self.generation;
+        if compacted.internal.len() != self.internal.len() {
+            compacted.generation += 1;
+        }
+        *self = compacted;
+        remap
+    }
+
+    /// Write every interned label, in interning order.
+    pub fn persist(&self, w: &mut dyn std::io::Write) -> std::io::Result<()> {
+        w.write_all(&(self.internal.len() as u64).to_le_bytes())?;
+        for (_, s) in &self.internal {
+            w.write_all(&(s.len() as u32).to_le_bytes())?;
+            w.write_all(s.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Read back a store written by [`LabelStore::persist`],
+    /// labels are interned in the same order thus keep their identifiers.
+    pub fn restore(r: &mut dyn std::io::Read) -> std::io::Result<Self> {
+        let mut b = [0; 8];
+        r.read_exact(&mut b)?;
+        let len = u64::from_le_bytes(b) as usize;
+        let mut internal: StringInterner<string_interner::DefaultBackend> = Default::default();
+        let mut buf = vec![];
+        for i in 0..len {
+            let mut b = [0; 4];
+            r.read_exact(&mut b)?;
+            buf.resize(u32::from_le_bytes(b) as usize, 0);
+            r.read_exact(&mut buf)?;
+            let s = std::str::from_utf8(&buf).map_err(|_| {
+                std::io::Error::new(std::io::ErrorKind::InvalidData, "label is not utf8")
+            })?;
+            let id = internal.get_or_intern(s);
+            if id.to_usize() != i {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "duplicated label",
+                ));
+            }
+        }
+        Ok(Self {
+            count: len,
+            generation: 0,
+            internal,
+        })
+    }
+}
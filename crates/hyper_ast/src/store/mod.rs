@@ -2,10 +2,14 @@ use std::{borrow::Borrow, marker::PhantomData};
 
 use crate::types::TypeStore;
 
+#[cfg(feature = "legion")]
+pub mod gc;
 pub mod handle;
 pub mod labels;
 // pub mod mapped_world;
 pub mod nodes;
+#[cfg(feature = "legion")]
+pub mod snapshot;
 // pub mod ecs; // TODO try a custom ecs ?
 // pub mod radix_hash_store; // TODO yet another WIP store
 // pub mod vec_map_store; // TODO yet another WIP store
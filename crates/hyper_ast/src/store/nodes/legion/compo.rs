@@ -30,3 +30,6 @@ impl<'a, T: Eq, const N: usize> From<&'a CS0<T, N>> for &'a [T] {
 pub struct RoleOffsets(pub Box<[u8]>);
 pub struct Precomp<T>(pub T);
 pub struct PrecompFlag;
+/// Marks nodes built outside of generators, e.g. by applying an edit script,
+/// they lack language specific metadata, so generators never deduplicate to them.
+pub struct Patched;
@@ -670,14 +670,21 @@ impl<'a, T: crate::types::NodeId<IdN = NodeIdentifier>> crate::types::WithRoles
 }
 
 impl<'a, T> crate::types::WithPrecompQueries for HashedNodeRef<'a, T> {
-    fn wont_match_given_precomputed_queries(&self, needed: u16) -> bool {
-        if needed == num::zero() {
+    fn wont_match_given_precomputed_queries(
+        &self,
+        needed: &crate::tree_gen::PrecompQueries,
+    ) -> bool {
+        if needed.is_empty() {
             return false;
         }
-        let Ok(v) = self.get_component::<compo::Precomp<u16>>() else {
-            return self.get_component::<compo::PrecompFlag>().is_ok();
-        };
-        v.0 & needed != needed
+        if let Ok(v) = self.get_component::<compo::Precomp<u16>>() {
+            return !needed.is_within(&[v.0 as u64]);
+        }
+        // more than 16 patterns, see [`crate::tree_gen::add_md_precomp_queries`]
+        if let Ok(v) = self.get_component::<compo::Precomp<Box<[u64]>>>() {
+            return !needed.is_within(&v.0);
+        }
+        self.get_component::<compo::PrecompFlag>().is_ok()
     }
 }
 
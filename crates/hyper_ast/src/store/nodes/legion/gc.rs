@@ -0,0 +1,144 @@
+//! Removal of the nodes that are not reachable anymore, see [`crate::store::gc`].
+//!
+//! Legion never reuses the identifier of a removed entity,
+//! so the remaining nodes keep their [`NodeIdentifier`].
+use std::collections::HashSet;
+
+use legion::IntoQuery;
+
+use super::{compo, NodeIdentifier, NodeStore};
+use crate::store::defaults::LabelIdentifier;
+use crate::types::{Labeled, WithChildren};
+
+/// The nodes reachable from some roots along their labels, see [`NodeStore::mark`]
+#[derive(Default, Debug)]
+pub struct Marked {
+    pub(crate) nodes: HashSet<NodeIdentifier>,
+    pub(crate) labels: HashSet<LabelIdentifier>,
+}
+
+impl Marked {
+    pub fn contains(&self, id: &NodeIdentifier) -> bool {
+        self.nodes.contains(id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
+impl NodeStore {
+    pub fn contains(&self, id: NodeIdentifier) -> bool {
+        self.inner.internal.contains(id)
+    }
+
+    /// Marks `roots` and their descendants, along the labels they use
+    pub fn mark(&self, roots: impl IntoIterator<Item = NodeIdentifier>) -> Marked {
+        let mut marked = Marked::default();
+        let mut stack: Vec<_> = roots.into_iter().collect();
+        while let Some(id) = stack.pop() {
+            if !marked.nodes.insert(id) {
+                continue;
+            }
+            let n = self.resolve(id);
+            if let Some(l) = n.try_get_label() {
+                marked.labels.insert(*l);
+            }
+            if let Ok(names) = n.get_component::<compo::CS<LabelIdentifier>>() {
+                marked.labels.extend(names.0.iter().copied());
+            }
+            if let Some(cs) = n.children() {
+                stack.extend(cs.iter_children().filter(|c| !marked.nodes.contains(*c)));
+            }
+        }
+        marked
+    }
+
+    /// Removes the nodes that are not `marked`, returns how many were removed
+    pub fn sweep(&mut self, marked: &Marked) -> usize {
+        let removed: Vec<_> = (self.dedup.keys())
+            .filter(|x| !marked.contains(x))
+            .copied()
+            .collect();
+        self.dedup.retain(|x, _| marked.contains(x));
+        for id in &removed {
+            self.inner.internal.remove(*id);
+        }
+        if let Some(journal) = &mut self.inner.journal {
+            journal.retain(|x| marked.contains(x));
+        }
+        removed.len()
+    }
+
+    /// Rewrites the labels of every node, e.g. after compacting the label store
+    pub fn remap_labels(&mut self, remap: impl Fn(LabelIdentifier) -> LabelIdentifier) {
+        let world = &mut self.inner.internal;
+        <&mut LabelIdentifier>::query().for_each_mut(world, |l| *l = remap(*l));
+        <&mut compo::CS<LabelIdentifier>>::query()
+            .for_each_mut(world, |cs| cs.0.iter_mut().for_each(|l| *l = remap(*l)));
+    }
+}
+
+#[test]
+fn collect_unreachable() {
+    use super::dyn_builder;
+    use crate::hashed::SyntaxNodeHashs;
+    use crate::store::gc::Collected;
+    use crate::store::labels::LabelStore;
+    use crate::store::nodes::EntityBuilder as _;
+    use crate::types::LabelStore as _;
+    type SimpleStores = crate::store::SimpleStores<(), NodeStore, LabelStore>;
+    let mut stores = SimpleStores {
+        label_store: LabelStore::new(),
+        node_store: NodeStore::new(),
+        type_store: std::marker::PhantomData,
+    };
+    let leaf = |stores: &mut SimpleStores, l: &str, h: u32| {
+        let mut b = dyn_builder::EntityBuilder::new();
+        b.add(SyntaxNodeHashs {
+            structt: 1,
+            label: h,
+            syntax: h,
+        });
+        b.add(stores.label_store.get_or_insert(l));
+        stores.node_store.insert_restored(b.build())
+    };
+    let a = leaf(&mut stores, "a", 2);
+    let b = leaf(&mut stores, "b", 3);
+    let c = leaf(&mut stores, "c", 4);
+    let parent = |stores: &mut SimpleStores, cs: [NodeIdentifier; 2], h: u32| {
+        let mut b = dyn_builder::EntityBuilder::new();
+        b.add(SyntaxNodeHashs {
+            structt: h,
+            label: h,
+            syntax: h,
+        });
+        b.add(compo::CS0(cs));
+        stores.node_store.insert_restored(b.build())
+    };
+    let kept = parent(&mut stores, [a, c], 5);
+    let dropped = parent(&mut stores, [a, b], 6);
+
+    let collected = stores.collect_garbage([kept]);
+    assert_eq!(
+        collected,
+        Collected {
+            nodes: 2,
+            labels: 1
+        }
+    );
+    assert!(!stores.node_store.contains(dropped));
+    assert!(!stores.node_store.contains(b));
+    assert!(stores.label_store.get("b").is_none());
+    let l = *stores.node_store.resolve(c).try_get_label().unwrap();
+    assert_eq!(stores.label_store.resolve(&l), "c");
+    // "c" was renumbered
+    assert_eq!(stores.label_store.generation(), 1);
+    // nothing left to collect
+    assert_eq!(stores.collect_garbage([kept]), Collected::default());
+    assert_eq!(stores.label_store.generation(), 1);
+}
@@ -15,6 +15,8 @@ use std::{fmt::Debug, hash::Hash, ops::Deref};
 pub mod compo;
 pub mod dyn_builder;
 mod elem;
+pub mod gc;
+pub mod persist;
 pub use elem::{EntryRef, HashedNode, HashedNodeRef, NodeIdentifier};
 
 pub struct NodeStore {
@@ -41,6 +43,8 @@ pub struct NodeStoreInner {
     // roots: HashMap<(u8, u8, u8), NodeIdentifier>,
     // dedup: hashbrown::HashMap<NodeIdentifier, (), ()>,
     internal: legion::World,
+    /// nodes inserted since the last [`NodeStore::take_journal`], if journaling is enabled
+    journal: Option<Vec<NodeIdentifier>>,
     // TODO intern lists of [`NodeIdentifier`]s, e.g. children, no space children, ...
     hasher: DefaultHashBuilder, //fasthash::city::Hash64,//fasthash::RandomState<fasthash::>,
                                 // internal: VecMapStore<HashedNode, NodeIdentifier, legion::World>,
@@ -141,6 +145,9 @@ impl NodeStore {
                 make_hash(&inner.hasher, &node)
             })
         };
+        if let Some(journal) = &mut inner.journal {
+            journal.push(symbol);
+        }
         symbol
     }
 
@@ -164,6 +171,9 @@ impl NodeStore {
                 make_hash(&inner.hasher, &node)
             })
         };
+        if let Some(journal) = &mut inner.journal {
+            journal.push(symbol);
+        }
         symbol
     }
 
@@ -467,6 +477,20 @@ impl NodeStore {
     pub fn len(&self) -> usize {
         self.inner.len()
     }
+
+    /// Start recording inserted nodes, e.g. to append them to a log.
+    pub fn start_journal(&mut self) {
+        self.inner.journal.get_or_insert_with(Vec::new);
+    }
+
+    /// Nodes inserted since the previous call, in insertion order (children first).
+    pub fn take_journal(&mut self) -> Vec<NodeIdentifier> {
+        self.inner
+            .journal
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
 }
 
 impl NodeStore {
@@ -489,6 +513,7 @@ impl NodeStore {
                 labels: std::collections::HashSet::with_capacity(100),
                 // roots: Default::default(),
                 internal: Default::default(),
+                journal: None,
                 hasher: Default::default(),
             },
             dedup: hashbrown::HashMap::<_, (), ()>::with_capacity_and_hasher(
@@ -740,11 +765,28 @@ mod stores_impl {
     }
 }
 
+/// Matches nodes with the given type, label and children,
+/// except nodes marked as [`compo::Patched`] which lack metadata computed by generators.
 pub fn eq_node<'a, K, L, I>(
     kind: &'a K,
     label_id: Option<&'a L>,
     children: &'a [I],
 ) -> impl Fn(EntryRef) -> bool + 'a
+where
+    K: 'static + Eq + Copy + std::marker::Send + std::marker::Sync,
+    L: 'static + Eq + Copy + std::marker::Send + std::marker::Sync,
+    I: 'static + Eq + Copy + std::marker::Send + std::marker::Sync,
+{
+    let eq = eq_any_node(kind, label_id, children);
+    move |x: EntryRef| x.get_component::<compo::Patched>().is_err() && eq(x)
+}
+
+/// Like [`eq_node`], but also matches nodes marked as [`compo::Patched`].
+pub fn eq_any_node<'a, K, L, I>(
+    kind: &'a K,
+    label_id: Option<&'a L>,
+    children: &'a [I],
+) -> impl Fn(EntryRef) -> bool + 'a
 where
     K: 'static + Eq + Copy + std::marker::Send + std::marker::Sync,
     L: 'static + Eq + Copy + std::marker::Send + std::marker::Sync,
@@ -0,0 +1,655 @@
+//! Binary snapshots of the legion node store.
+//!
+//! Legion components are plain rust types, so each one that should survive a snapshot
+//! must be registered in a [`Registry`] with a [`Persist`] implementation.
+//! Entities are written in allocation order, which is a valid topological order
+//! (children are always interned before their parents).
+//! On load, entities are re-created in the same order,
+//! but legion allocates identifiers from a counter shared by all the worlds of the process,
+//! so even in an empty store a node generally gets a different [`NodeIdentifier`].
+//! Identifiers read from a snapshot are thus only valid once remapped:
+//! the differences are recorded in an [`IdRemap`], references between nodes are rewritten with it,
+//! and it must also be applied to identifiers stored elsewhere (commit roots, ...).
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::num::NonZeroU64;
+
+use legion::storage::{Component, ComponentTypeId};
+
+use super::{compo, dyn_builder, EntryRef, HashedNodeRef, NodeIdentifier, NodeStore};
+use crate::utils::make_hash;
+
+/// Byte level (de)serialization of a component.
+pub trait Persist: Sized {
+    fn persist(&self, w: &mut dyn Write) -> io::Result<()>;
+    fn restore(r: &mut dyn Read, remap: &IdRemap) -> io::Result<Self>;
+}
+
+/// Rewrites identifiers of a snapshot into identifiers of the restored store.
+/// Identifiers missing from the map were restored unchanged.
+#[derive(Default, Debug)]
+pub struct IdRemap(HashMap<u64, NodeIdentifier>);
+
+impl IdRemap {
+    pub fn get(&self, id: NodeIdentifier) -> NodeIdentifier {
+        self.0.get(&to_raw(id)).copied().unwrap_or(id)
+    }
+    /// None for the null identifier, which cannot come from a snapshot
+    pub fn get_raw(&self, raw: u64) -> Option<NodeIdentifier> {
+        self.0.get(&raw).copied().or_else(|| from_raw(raw))
+    }
+    /// true if every restored node kept its original identifier
+    pub fn is_identity(&self) -> bool {
+        self.0.is_empty()
+    }
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+// a legion Entity is a transparent wrapper around a NonZeroU64,
+// checked here so that a change of its layout in legion fails to compile
+const _: () = {
+    use std::mem::{align_of, size_of};
+    assert!(size_of::<NodeIdentifier>() == size_of::<NonZeroU64>());
+    assert!(align_of::<NodeIdentifier>() == align_of::<NonZeroU64>());
+    // the null value is a niche, like for NonZeroU64
+    assert!(size_of::<Option<NodeIdentifier>>() == size_of::<NodeIdentifier>());
+};
+
+/// The raw value of an identifier, only meaningful for the store that wrote it, see [`IdRemap`]
+pub fn to_raw(id: NodeIdentifier) -> u64 {
+    // SAFETY: same layout, checked above, and every bit pattern of an Entity is a valid NonZeroU64
+    let raw: NonZeroU64 = unsafe { std::mem::transmute(id) };
+    raw.get()
+}
+
+fn from_raw(raw: u64) -> Option<NodeIdentifier> {
+    let raw = NonZeroU64::new(raw)?;
+    // SAFETY: same layout, checked above, an Entity only holds its NonZeroU64
+    Some(unsafe { std::mem::transmute::<NonZeroU64, NodeIdentifier>(raw) })
+}
+
+type SaveFn = Box<dyn Fn(&EntryRef, &mut dyn Write) -> io::Result<()> + Send + Sync>;
+type LoadFn = Box<
+    dyn Fn(&mut dyn Read, &IdRemap, &mut dyn_builder::EntityBuilder) -> io::Result<()>
+        + Send
+        + Sync,
+>;
+
+struct Entry {
+    name: &'static str,
+    save: SaveFn,
+    load: LoadFn,
+}
+
+/// Components that can be written to and read from a snapshot, identified by a stable name.
+pub struct Registry {
+    by_type: HashMap<ComponentTypeId, usize>,
+    by_name: HashMap<&'static str, usize>,
+    entries: Vec<Entry>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self {
+            by_type: Default::default(),
+            by_name: Default::default(),
+            entries: vec![],
+        }
+    }
+
+    /// Registry with all the components produced by the generic parts of the HyperAST,
+    /// language specific components (e.g. types) must be registered by the user.
+    pub fn with_defaults() -> Self {
+        let mut r = Self::new();
+        r.register::<crate::store::defaults::LabelIdentifier>("label");
+        r.register::<crate::hashed::SyntaxNodeHashs<u32>>("hashs");
+        r.register::<compo::Size>("size");
+        r.register::<compo::SizeNoSpaces>("size_no_spaces");
+        r.register::<compo::Height>("height");
+        r.register::<compo::BytesLen>("bytes_len");
+        r.register::<compo::LineCount>("line_count");
+        r.register::<compo::HStruct>("hstruct");
+        r.register::<compo::HLabel>("hlabel");
+        r.register::<compo::CS<NodeIdentifier>>("cs");
+        r.register::<compo::CS0<NodeIdentifier, 1>>("cs1");
+        r.register::<compo::CS0<NodeIdentifier, 2>>("cs2");
+        r.register::<compo::NoSpacesCS<NodeIdentifier>>("no_spaces_cs");
+        r.register::<compo::NoSpacesCS0<NodeIdentifier, 1>>("no_spaces_cs1");
+        r.register::<compo::NoSpacesCS0<NodeIdentifier, 2>>("no_spaces_cs2");
+        r.register::<compo::CS<crate::store::defaults::LabelIdentifier>>("cs_names");
+        r.register::<compo::RoleOffsets>("role_offsets");
+        r.register::<Box<[crate::types::Role]>>("roles");
+        r.register::<compo::Precomp<u16>>("precomp");
+        r.register::<compo::Precomp<Box<[u64]>>>("precomp_wide");
+        r.register::<compo::PrecompFlag>("precomp_flag");
+        r.register::<compo::Patched>("patched");
+        r.register::<crate::cyclomatic::Mcc>("mcc");
+        r.register::<crate::filter::BloomSize>("bloom_size");
+        r
+    }
+
+    /// Register a component under a name that must stay stable across snapshots.
+    pub fn register<T: Component + Persist>(&mut self, name: &'static str) -> &mut Self {
+        self.register_with::<T>(name, T::persist, T::restore)
+    }
+
+    /// Register a component with explicit (de)serialization functions,
+    /// useful for foreign types that cannot implement [`Persist`].
+    pub fn register_with<T: Component>(
+        &mut self,
+        name: &'static str,
+        save: fn(&T, &mut dyn Write) -> io::Result<()>,
+        load: fn(&mut dyn Read, &IdRemap) -> io::Result<T>,
+    ) -> &mut Self {
+        use crate::store::nodes::EntityBuilder as _;
+        let i = self.entries.len();
+        let t = ComponentTypeId::of::<T>();
+        assert!(self.by_type.insert(t, i).is_none(), "{} already registered", name);
+        assert!(self.by_name.insert(name, i).is_none(), "{} already used", name);
+        self.entries.push(Entry {
+            name,
+            save: Box::new(move |e, w| {
+                let c = e
+                    .get_component::<T>()
+                    .map_err(|_| io::Error::other("missing component"))?;
+                save(c, w)
+            }),
+            load: Box::new(move |r, remap, builder| {
+                builder.add(load(r, remap)?);
+                Ok(())
+            }),
+        });
+        self
+    }
+
+    /// Register the type of nodes of a language.
+    pub fn register_type<L>(&mut self, name: &'static str) -> &mut Self
+    where
+        L: 'static + crate::types::LLang<crate::types::TypeU16<L>, I = u16>,
+    {
+        self.register::<crate::types::TypeU16<L>>(name)
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self::with_defaults()
+    }
+}
+
+impl NodeStore {
+    /// Write every node of the store.
+    /// Fails if a node holds a component missing from the `registry`.
+    pub fn persist(&self, registry: &Registry, w: &mut dyn Write) -> io::Result<()> {
+        registry.write_table(w)?;
+        let mut ids: Vec<_> = self.dedup.keys().copied().collect();
+        ids.sort_by_key(|x| to_raw(*x));
+        self.persist_nodes(registry, &ids, w)
+    }
+
+    /// Write the given nodes, children must come before their parents.
+    /// The component table of the `registry` must have been written before, see [`Registry::write_table`].
+    pub fn persist_nodes(
+        &self,
+        registry: &Registry,
+        ids: &[NodeIdentifier],
+        w: &mut dyn Write,
+    ) -> io::Result<()> {
+        write_u64(w, ids.len() as u64)?;
+        for &id in ids {
+            let e = self.inner.internal.entry_ref(id).unwrap();
+            let types = e.archetype().layout().component_types();
+            write_u64(w, to_raw(id))?;
+            write_u16(w, types.len() as u16)?;
+            for t in types {
+                let Some(&i) = registry.by_type.get(t) else {
+                    return Err(io::Error::new(
+                        io::ErrorKind::Unsupported,
+                        format!("component {:?} is not registered for persistence", t),
+                    ));
+                };
+                write_u16(w, i as u16)?;
+                (registry.entries[i].save)(&e, w)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Read back a store written by [`NodeStore::persist`].
+    pub fn restore(registry: &Registry, r: &mut dyn Read) -> io::Result<(Self, IdRemap)> {
+        let mut store = NodeStore::new();
+        let remap = store.extend_from(registry, r)?;
+        Ok((store, remap))
+    }
+
+    /// Insert nodes written by [`NodeStore::persist`] into the current store.
+    pub fn extend_from(&mut self, registry: &Registry, r: &mut dyn Read) -> io::Result<IdRemap> {
+        let table = registry.read_table(r)?;
+        let mut remap = IdRemap::default();
+        self.restore_nodes(&table, &mut remap, r)?;
+        if !remap.is_identity() {
+            log::warn!("{} restored nodes changed identifier", remap.len());
+        }
+        Ok(remap)
+    }
+
+    /// Insert nodes written by [`NodeStore::persist_nodes`],
+    /// `remap` accumulates the identifiers that changed.
+    pub fn restore_nodes(
+        &mut self,
+        table: &ComponentTable,
+        remap: &mut IdRemap,
+        r: &mut dyn Read,
+    ) -> io::Result<()> {
+        let n = read_u64(r)?;
+        for _ in 0..n {
+            let old = read_u64(r)?;
+            let k = read_u16(r)?;
+            let mut builder = dyn_builder::EntityBuilder::new();
+            for _ in 0..k {
+                let i = read_u16(r)? as usize;
+                let load = table.0.get(i).ok_or_else(|| invalid("bad component index"))?;
+                let load = load.ok_or_else(|| invalid("component of snapshot is not registered"))?;
+                load(r, remap, &mut builder)?;
+            }
+            let id = self.insert_restored(builder.build());
+            if to_raw(id) != old {
+                remap.0.insert(old, id);
+            }
+        }
+        Ok(())
+    }
+
+    pub(super) fn insert_restored(&mut self, components: dyn_builder::BuiltEntity) -> NodeIdentifier {
+        let Self { dedup, inner } = self;
+        let id = inner.internal.extend(components)[0];
+        let hash = {
+            let node: HashedNodeRef<'_, NodeIdentifier> =
+                inner.internal.entry_ref(id).map(HashedNodeRef::new).unwrap();
+            make_hash(&inner.hasher, &node)
+        };
+        match dedup.raw_entry_mut().from_hash(hash, |x| *x == id) {
+            hashbrown::hash_map::RawEntryMut::Vacant(vacant) => {
+                vacant.insert_with_hasher(hash, id, (), |id| {
+                    let node: HashedNodeRef<'_, NodeIdentifier> =
+                        inner.internal.entry_ref(*id).map(HashedNodeRef::new).unwrap();
+                    make_hash(&inner.hasher, &node)
+                });
+            }
+            hashbrown::hash_map::RawEntryMut::Occupied(_) => unreachable!(),
+        }
+        id
+    }
+}
+
+/// Maps the component indexes of a snapshot to the loaders of the current registry.
+pub struct ComponentTable<'a>(Vec<Option<&'a LoadFn>>);
+
+impl Registry {
+    /// Write the names of registered components, later referenced by index.
+    pub fn write_table(&self, w: &mut dyn Write) -> io::Result<()> {
+        write_u32(w, self.entries.len() as u32)?;
+        for e in &self.entries {
+            write_str(w, e.name)?;
+        }
+        Ok(())
+    }
+
+    pub fn read_table<'a>(&'a self, r: &mut dyn Read) -> io::Result<ComponentTable<'a>> {
+        let n = read_u32(r)?;
+        let mut table = Vec::with_capacity(n as usize);
+        for _ in 0..n {
+            let name = read_string(r)?;
+            let load = self.by_name.get(name.as_str()).map(|&i| &self.entries[i].load);
+            if load.is_none() {
+                log::warn!("component {} is not registered", name);
+            }
+            table.push(load);
+        }
+        Ok(ComponentTable(table))
+    }
+}
+
+pub(crate) fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+pub fn write_u8(w: &mut dyn Write, x: u8) -> io::Result<()> {
+    w.write_all(&[x])
+}
+pub fn write_u16(w: &mut dyn Write, x: u16) -> io::Result<()> {
+    w.write_all(&x.to_le_bytes())
+}
+pub fn write_u32(w: &mut dyn Write, x: u32) -> io::Result<()> {
+    w.write_all(&x.to_le_bytes())
+}
+pub fn write_u64(w: &mut dyn Write, x: u64) -> io::Result<()> {
+    w.write_all(&x.to_le_bytes())
+}
+pub fn write_str(w: &mut dyn Write, x: &str) -> io::Result<()> {
+    write_u32(w, x.len() as u32)?;
+    w.write_all(x.as_bytes())
+}
+pub fn read_u8(r: &mut dyn Read) -> io::Result<u8> {
+    let mut b = [0; 1];
+    r.read_exact(&mut b)?;
+    Ok(b[0])
+}
+pub fn read_u16(r: &mut dyn Read) -> io::Result<u16> {
+    let mut b = [0; 2];
+    r.read_exact(&mut b)?;
+    Ok(u16::from_le_bytes(b))
+}
+pub fn read_u32(r: &mut dyn Read) -> io::Result<u32> {
+    let mut b = [0; 4];
+    r.read_exact(&mut b)?;
+    Ok(u32::from_le_bytes(b))
+}
+pub fn read_u64(r: &mut dyn Read) -> io::Result<u64> {
+    let mut b = [0; 8];
+    r.read_exact(&mut b)?;
+    Ok(u64::from_le_bytes(b))
+}
+pub fn read_string(r: &mut dyn Read) -> io::Result<String> {
+    let len = read_u32(r)? as usize;
+    let mut b = vec![0; len];
+    r.read_exact(&mut b)?;
+    String::from_utf8(b).map_err(|_| invalid("not utf8"))
+}
+
+// # Persist impls
+
+macro_rules! persist_newtype {
+    ($( $t:ty => $w:ident, $r:ident; )*) => {
+        $(
+            impl Persist for $t {
+                fn persist(&self, w: &mut dyn Write) -> io::Result<()> {
+                    $w(w, self.0)
+                }
+                fn restore(r: &mut dyn Read, _: &IdRemap) -> io::Result<Self> {
+                    Ok(Self($r(r)?))
+                }
+            }
+        )*
+    };
+}
+
+persist_newtype!(
+    compo::Size => write_u32, read_u32;
+    compo::SizeNoSpaces => write_u32, read_u32;
+    compo::Height => write_u32, read_u32;
+    compo::BytesLen => write_u32, read_u32;
+    compo::LineCount => write_u16, read_u16;
+    compo::HStruct => write_u32, read_u32;
+    compo::HLabel => write_u32, read_u32;
+    compo::Precomp<u16> => write_u16, read_u16;
+);
+
+impl Persist for NodeIdentifier {
+    fn persist(&self, w: &mut dyn Write) -> io::Result<()> {
+        write_u64(w, to_raw(*self))
+    }
+    fn restore(r: &mut dyn Read, remap: &IdRemap) -> io::Result<Self> {
+        let raw = read_u64(r)?;
+        remap
+            .get_raw(raw)
+            .ok_or_else(|| invalid("null node identifier"))
+    }
+}
+
+impl Persist for crate::store::defaults::LabelIdentifier {
+    fn persist(&self, w: &mut dyn Write) -> io::Result<()> {
+        use string_interner::Symbol;
+        write_u32(w, self.to_usize() as u32)
+    }
+    fn restore(r: &mut dyn Read, _: &IdRemap) -> io::Result<Self> {
+        crate::store::labels::label_id_from_usize(read_u32(r)? as usize)
+            .ok_or_else(|| invalid("bad label identifier"))
+    }
+}
+
+impl Persist for crate::hashed::SyntaxNodeHashs<u32> {
+    fn persist(&self, w: &mut dyn Write) -> io::Result<()> {
+        write_u32(w, self.structt)?;
+        write_u32(w, self.label)?;
+        write_u32(w, self.syntax)
+    }
+    fn restore(r: &mut dyn Read, _: &IdRemap) -> io::Result<Self> {
+        Ok(Self {
+            structt: read_u32(r)?,
+            label: read_u32(r)?,
+            syntax: read_u32(r)?,
+        })
+    }
+}
+
+impl<T: Persist> Persist for Box<[T]> {
+    fn persist(&self, w: &mut dyn Write) -> io::Result<()> {
+        write_u32(w, self.len() as u32)?;
+        self.iter().try_for_each(|x| x.persist(w))
+    }
+    fn restore(r: &mut dyn Read, remap: &IdRemap) -> io::Result<Self> {
+        let len = read_u32(r)? as usize;
+        (0..len).map(|_| T::restore(r, remap)).collect()
+    }
+}
+
+impl<T: Persist, const N: usize> Persist for [T; N] {
+    fn persist(&self, w: &mut dyn Write) -> io::Result<()> {
+        self.iter().try_for_each(|x| x.persist(w))
+    }
+    fn restore(r: &mut dyn Read, remap: &IdRemap) -> io::Result<Self> {
+        let v = (0..N)
+            .map(|_| T::restore(r, remap))
+            .collect::<io::Result<Vec<_>>>()?;
+        Ok(v.try_into().ok().unwrap())
+    }
+}
+
+impl Persist for u8 {
+    fn persist(&self, w: &mut dyn Write) -> io::Result<()> {
+        write_u8(w, *self)
+    }
+    fn restore(r: &mut dyn Read, _: &IdRemap) -> io::Result<Self> {
+        read_u8(r)
+    }
+}
+
+impl Persist for u64 {
+    fn persist(&self, w: &mut dyn Write) -> io::Result<()> {
+        write_u64(w, *self)
+    }
+    fn restore(r: &mut dyn Read, _: &IdRemap) -> io::Result<Self> {
+        read_u64(r)
+    }
+}
+
+impl<T: Persist> Persist for compo::CS<T> {
+    fn persist(&self, w: &mut dyn Write) -> io::Result<()> {
+        self.0.persist(w)
+    }
+    fn restore(r: &mut dyn Read, remap: &IdRemap) -> io::Result<Self> {
+        Ok(Self(Persist::restore(r, remap)?))
+    }
+}
+
+impl<T: Persist + Eq> Persist for compo::NoSpacesCS<T> {
+    fn persist(&self, w: &mut dyn Write) -> io::Result<()> {
+        self.0.persist(w)
+    }
+    fn restore(r: &mut dyn Read, remap: &IdRemap) -> io::Result<Self> {
+        Ok(Self(Persist::restore(r, remap)?))
+    }
+}
+
+impl<T: Persist + Eq, const N: usize> Persist for compo::CS0<T, N> {
+    fn persist(&self, w: &mut dyn Write) -> io::Result<()> {
+        self.0.persist(w)
+    }
+    fn restore(r: &mut dyn Read, remap: &IdRemap) -> io::Result<Self> {
+        Ok(Self(Persist::restore(r, remap)?))
+    }
+}
+
+impl<T: Persist + Eq, const N: usize> Persist for compo::NoSpacesCS0<T, N> {
+    fn persist(&self, w: &mut dyn Write) -> io::Result<()> {
+        self.0.persist(w)
+    }
+    fn restore(r: &mut dyn Read, remap: &IdRemap) -> io::Result<Self> {
+        Ok(Self(Persist::restore(r, remap)?))
+    }
+}
+
+impl Persist for compo::RoleOffsets {
+    fn persist(&self, w: &mut dyn Write) -> io::Result<()> {
+        self.0.persist(w)
+    }
+    fn restore(r: &mut dyn Read, remap: &IdRemap) -> io::Result<Self> {
+        Ok(Self(Persist::restore(r, remap)?))
+    }
+}
+
+impl Persist for compo::Precomp<Box<[u64]>> {
+    fn persist(&self, w: &mut dyn Write) -> io::Result<()> {
+        self.0.persist(w)
+    }
+    fn restore(r: &mut dyn Read, remap: &IdRemap) -> io::Result<Self> {
+        Ok(Self(Persist::restore(r, remap)?))
+    }
+}
+
+impl Persist for compo::PrecompFlag {
+    fn persist(&self, _: &mut dyn Write) -> io::Result<()> {
+        Ok(())
+    }
+    fn restore(_: &mut dyn Read, _: &IdRemap) -> io::Result<Self> {
+        Ok(Self)
+    }
+}
+
+impl Persist for compo::Patched {
+    fn persist(&self, _: &mut dyn Write) -> io::Result<()> {
+        Ok(())
+    }
+    fn restore(_: &mut dyn Read, _: &IdRemap) -> io::Result<Self> {
+        Ok(Self)
+    }
+}
+
+impl Persist for crate::types::Role {
+    fn persist(&self, w: &mut dyn Write) -> io::Result<()> {
+        write_str(w, &self.to_string())
+    }
+    fn restore(r: &mut dyn Read, _: &IdRemap) -> io::Result<Self> {
+        let s = read_string(r)?;
+        Self::try_from(s.as_str()).map_err(|_| invalid("unknown role"))
+    }
+}
+
+impl<L> Persist for crate::types::TypeU16<L>
+where
+    L: 'static + crate::types::LLang<crate::types::TypeU16<L>, I = u16>,
+{
+    fn persist(&self, w: &mut dyn Write) -> io::Result<()> {
+        write_u16(w, <L as crate::types::Lang<L::E>>::to_u16(self.e()))
+    }
+    fn restore(r: &mut dyn Read, _: &IdRemap) -> io::Result<Self> {
+        let t = read_u16(r)?;
+        if t as usize >= L::TE.len() {
+            return Err(invalid("unknown type"));
+        }
+        Ok(Self::new(*<L as crate::types::Lang<L::E>>::make(t)))
+    }
+}
+
+impl Persist for crate::filter::BloomSize {
+    fn persist(&self, w: &mut dyn Write) -> io::Result<()> {
+        use crate::filter::BloomSize;
+        let x = match self {
+            BloomSize::None => 0,
+            BloomSize::B16 => 1,
+            BloomSize::B32 => 2,
+            BloomSize::B64 => 3,
+            BloomSize::B128 => 4,
+            BloomSize::B256 => 5,
+            BloomSize::B512 => 6,
+            BloomSize::B1024 => 7,
+            BloomSize::B2048 => 8,
+            BloomSize::B4096 => 9,
+            BloomSize::Much => 10,
+        };
+        write_u8(w, x)
+    }
+    fn restore(r: &mut dyn Read, _: &IdRemap) -> io::Result<Self> {
+        use crate::filter::BloomSize;
+        Ok(match read_u8(r)? {
+            0 => BloomSize::None,
+            1 => BloomSize::B16,
+            2 => BloomSize::B32,
+            3 => BloomSize::B64,
+            4 => BloomSize::B128,
+            5 => BloomSize::B256,
+            6 => BloomSize::B512,
+            7 => BloomSize::B1024,
+            8 => BloomSize::B2048,
+            9 => BloomSize::B4096,
+            10 => BloomSize::Much,
+            _ => return Err(invalid("unknown bloom size")),
+        })
+    }
+}
+
+#[test]
+fn roundtrip() {
+    use crate::hashed::SyntaxNodeHashs;
+    use crate::store::nodes::EntityBuilder as _;
+    use crate::types::LabelStore as _;
+    let mut labels = crate::store::labels::LabelStore::new();
+    let mut store = NodeStore::new();
+    let mut leaf = |store: &mut NodeStore, l: &str, h: u32| {
+        let mut b = dyn_builder::EntityBuilder::new();
+        b.add(SyntaxNodeHashs {
+            structt: 1,
+            label: h,
+            syntax: h,
+        });
+        b.add(labels.get_or_insert(l));
+        b.add(compo::BytesLen(l.len() as u32));
+        store.insert_restored(b.build())
+    };
+    let a = leaf(&mut store, "a", 2);
+    let b = leaf(&mut store, "b", 3);
+    let mut builder = dyn_builder::EntityBuilder::new();
+    builder.add(SyntaxNodeHashs {
+        structt: 4,
+        label: 5,
+        syntax: 6,
+    });
+    builder.add(compo::CS0([a, b]));
+    let root = store.insert_restored(builder.build());
+
+    let registry = Registry::with_defaults();
+    let mut buf = vec![];
+    store.persist(&registry, &mut buf).unwrap();
+    let (restored, remap) = NodeStore::restore(&registry, &mut &buf[..]).unwrap();
+    assert_eq!(restored.len(), store.len());
+    // the identifiers of the first store are not given back, even in an empty store
+    assert!(!remap.is_identity());
+    assert_eq!(from_raw(to_raw(root)), Some(root));
+    assert_eq!(from_raw(0), None);
+    let e = restored.inner.internal.entry_ref(remap.get(root)).unwrap();
+    let cs = e.get_component::<compo::CS0<NodeIdentifier, 2>>().unwrap();
+    assert_eq!(cs.0, [remap.get(a), remap.get(b)]);
+    let e = restored.inner.internal.entry_ref(remap.get(b)).unwrap();
+    let l = e
+        .get_component::<crate::store::defaults::LabelIdentifier>()
+        .unwrap();
+    assert_eq!(labels.resolve(l), "b");
+
+    let mut buf = vec![];
+    labels.persist(&mut buf).unwrap();
+    let restored = crate::store::labels::LabelStore::restore(&mut &buf[..]).unwrap();
+    assert_eq!(restored.get("b"), labels.get("b"));
+}
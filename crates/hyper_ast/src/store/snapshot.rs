@@ -0,0 +1,76 @@
+//! Versioned on-disk snapshots of [`SimpleStores`].
+//!
+//! Layout: the `HYPERAST` magic, a format version, the label store,
+//! then the node store (see [`crate::store::nodes::legion::persist`]).
+use std::io::{self, Read, Write};
+
+use super::{labels::LabelStore, nodes::legion, SimpleStores};
+pub use legion::persist::{IdRemap, Persist, Registry};
+
+pub const MAGIC: &[u8; 8] = b"HYPERAST";
+/// Bumped on every layout change, including the ones of the snapshots built on top of it
+/// (2: the parse errors of the processed files).
+pub const VERSION: u32 = 2;
+
+impl<TS> SimpleStores<TS, legion::NodeStore, LabelStore> {
+    pub fn save_snapshot(&self, registry: &Registry, w: &mut dyn Write) -> io::Result<()> {
+        w.write_all(MAGIC)?;
+        legion::persist::write_u32(w, VERSION)?;
+        self.label_store.persist(w)?;
+        self.node_store.persist(registry, w)
+    }
+
+    /// Load stores written by [`SimpleStores::save_snapshot`].
+    /// The returned [`IdRemap`] must be used to translate identifiers stored elsewhere,
+    /// it is the identity in the common case.
+    pub fn load_snapshot(registry: &Registry, r: &mut dyn Read) -> io::Result<(Self, IdRemap)> {
+        let mut magic = [0; 8];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(legion::persist::invalid("not a HyperAST snapshot"));
+        }
+        let version = legion::persist::read_u32(r)?;
+        if version != VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "unsupported snapshot version {}, expected {}",
+                    version, VERSION
+                ),
+            ));
+        }
+        let label_store = LabelStore::restore(r)?;
+        let (node_store, remap) = legion::NodeStore::restore(registry, r)?;
+        Ok((
+            Self {
+                label_store,
+                node_store,
+                type_store: std::marker::PhantomData,
+            },
+            remap,
+        ))
+    }
+
+    pub fn save_snapshot_to_file(
+        &self,
+        registry: &Registry,
+        path: impl AsRef<std::path::Path>,
+    ) -> io::Result<()> {
+        let path = path.as_ref();
+        // write to a temporary file first, to never leave a truncated snapshot behind
+        let tmp = path.with_extension("tmp");
+        let mut w = io::BufWriter::new(std::fs::File::create(&tmp)?);
+        self.save_snapshot(registry, &mut w)?;
+        w.flush()?;
+        drop(w);
+        std::fs::rename(tmp, path)
+    }
+
+    pub fn load_snapshot_from_file(
+        registry: &Registry,
+        path: impl AsRef<std::path::Path>,
+    ) -> io::Result<(Self, IdRemap)> {
+        let mut r = io::BufReader::new(std::fs::File::open(path)?);
+        Self::load_snapshot(registry, &mut r)
+    }
+}
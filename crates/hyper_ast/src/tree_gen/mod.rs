@@ -607,13 +607,17 @@ impl<R> RoleAcc<R> {
 #[cfg(feature = "legion")]
 pub fn add_md_precomp_queries(
     dyn_builder: &mut impl crate::store::nodes::EntityBuilder,
-    precomp_queries: PrecompQueries,
+    precomp_queries: &PrecompQueries,
 ) {
     use crate::store::nodes::legion::compo;
-    if precomp_queries > 0 {
-        dyn_builder.add(compo::Precomp(precomp_queries));
-    } else {
+    if precomp_queries.is_empty() {
         dyn_builder.add(compo::PrecompFlag);
+    } else if let Some(x) = precomp_queries.as_u16() {
+        // the most common case, so it stays compact
+        dyn_builder.add(compo::Precomp(x));
+    } else {
+        let words: Box<[u64]> = precomp_queries.words().collect();
+        dyn_builder.add(compo::Precomp(words));
     }
 }
 
@@ -644,17 +648,35 @@ pub mod utils_ts {
         text: &[u8],
         language: &tree_sitter::Language,
     ) -> Result<tree_sitter::Tree, tree_sitter::Tree> {
+        tree_sitter_parse_bounded(text, language, ParseBounds::default())
+            .expect("an unbounded parsing always produces a tree")
+    }
+
+    /// Bounds the time spent parsing a file, see [`tree_sitter_parse_bounded`]
+    #[derive(Debug, Clone, Copy, Default)]
+    pub struct ParseBounds<'a> {
+        /// in microseconds, 0 for no timeout
+        pub timeout_micros: u64,
+        /// parsing stops once it is non zero
+        pub cancellation_flag: Option<&'a std::sync::atomic::AtomicUsize>,
+    }
+
+    /// Like [`tree_sitter_parse`] but returns None if the parsing timed out or was cancelled
+    pub fn tree_sitter_parse_bounded(
+        text: &[u8],
+        language: &tree_sitter::Language,
+        bounds: ParseBounds,
+    ) -> Option<Result<tree_sitter::Tree, tree_sitter::Tree>> {
         let mut parser = tree_sitter::Parser::new();
-        // TODO see if a timeout of a cancellation flag could be useful
-        // const MINUTE: u64 = 60 * 1000 * 1000;
-        // parser.set_timeout_micros(timeout_micros);
-        // parser.set_cancellation_flag(flag);
         parser.set_language(language).unwrap();
-        let tree = parser.parse(text, None).unwrap();
+        parser.set_timeout_micros(bounds.timeout_micros);
+        // SAFETY: the flag outlives the parser, dropped at the end of this function
+        unsafe { parser.set_cancellation_flag(bounds.cancellation_flag) };
+        let tree = parser.parse(text, None)?;
         if tree.root_node().has_error() {
-            Err(tree)
+            Some(Err(tree))
         } else {
-            Ok(tree)
+            Some(Ok(tree))
         }
     }
 
@@ -1055,7 +1077,8 @@ where
     }
 }
 
-pub type PrecompQueries = u16;
+mod precomp;
+pub use precomp::PrecompQueries;
 
 pub trait More<HAST: types::StoreRefAssoc> {
     type Acc: WithChildren<<HAST as HyperASTShared>::IdN>;
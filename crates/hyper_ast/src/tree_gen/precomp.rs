@@ -0,0 +1,175 @@
+//! Sets of precomputed query patterns, see [`super::More::match_precomp_queries`].
+use std::fmt::Debug;
+use std::ops::BitOrAssign;
+
+const BITS: usize = u64::BITS as usize;
+
+/// The precomputed query patterns matching a subtree, pattern `i` being bit `i`.
+///
+/// The first 64 patterns are stored inline, so small sets of patterns do not allocate,
+/// the following ones take as many words as needed, there is no limit on their number.
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct PrecompQueries {
+    first: u64,
+    /// without trailing zeros, so equal sets are equal
+    rest: Box<[u64]>,
+}
+
+impl PrecompQueries {
+    /// The set of the first `n` patterns
+    pub fn all(n: usize) -> Self {
+        let mut words = vec![u64::MAX; n / BITS];
+        if n % BITS != 0 {
+            words.push((1u64 << (n % BITS)) - 1);
+        }
+        Self::from_words(words)
+    }
+
+    /// see [`PrecompQueries::words`]
+    pub fn from_words(words: impl IntoIterator<Item = u64>) -> Self {
+        let mut words = words.into_iter();
+        let first = words.next().unwrap_or(0);
+        let mut rest: Vec<_> = words.collect();
+        while rest.last() == Some(&0) {
+            rest.pop();
+        }
+        Self {
+            first,
+            rest: rest.into(),
+        }
+    }
+
+    /// The words of the set, the first one holds patterns 0 to 63
+    pub fn words(&self) -> impl Iterator<Item = u64> + '_ {
+        std::iter::once(self.first).chain(self.rest.iter().copied())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.first == 0 && self.rest.is_empty()
+    }
+
+    pub fn insert(&mut self, pattern: usize) {
+        if pattern < BITS {
+            self.first |= 1u64 << pattern;
+            return;
+        }
+        let i = pattern / BITS - 1;
+        if i >= self.rest.len() {
+            let mut rest = std::mem::take(&mut self.rest).into_vec();
+            rest.resize(i + 1, 0);
+            self.rest = rest.into();
+        }
+        self.rest[i] |= 1u64 << (pattern % BITS);
+    }
+
+    pub fn contains(&self, pattern: usize) -> bool {
+        let word = if pattern < BITS {
+            self.first
+        } else {
+            self.rest.get(pattern / BITS - 1).copied().unwrap_or(0)
+        };
+        word & (1u64 << (pattern % BITS)) != 0
+    }
+
+    /// The patterns of the set, in increasing order
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words().enumerate().flat_map(|(i, w)| {
+            (0..BITS)
+                .filter(move |j| w & (1u64 << j) != 0)
+                .map(move |j| i * BITS + j)
+        })
+    }
+
+    /// Whether all the patterns of `self` are in the set given by `words`, see [`PrecompQueries::words`]
+    pub fn is_within(&self, words: &[u64]) -> bool {
+        let word = |i: usize| words.get(i).copied().unwrap_or(0);
+        if self.first & word(0) != self.first {
+            return false;
+        }
+        (self.rest.iter().enumerate()).all(|(i, w)| w & word(i + 1) == *w)
+    }
+
+    /// Keeps the patterns that are also in `other`
+    pub fn intersect_with(&mut self, other: &Self) {
+        let words: Vec<_> = self
+            .words()
+            .zip(other.words())
+            .map(|(a, b)| a & b)
+            .collect();
+        *self = Self::from_words(words);
+    }
+
+    /// The set as a single `u16`, if it only contains some of the first 16 patterns
+    pub fn as_u16(&self) -> Option<u16> {
+        if !self.rest.is_empty() {
+            return None;
+        }
+        u16::try_from(self.first).ok()
+    }
+}
+
+impl BitOrAssign<&PrecompQueries> for PrecompQueries {
+    fn bitor_assign(&mut self, rhs: &PrecompQueries) {
+        self.first |= rhs.first;
+        if rhs.rest.is_empty() {
+            return;
+        }
+        if rhs.rest.len() > self.rest.len() {
+            let mut rest = std::mem::take(&mut self.rest).into_vec();
+            rest.resize(rhs.rest.len(), 0);
+            self.rest = rest.into();
+        }
+        for (a, b) in self.rest.iter_mut().zip(rhs.rest.iter()) {
+            *a |= b;
+        }
+    }
+}
+
+impl BitOrAssign for PrecompQueries {
+    fn bitor_assign(&mut self, rhs: PrecompQueries) {
+        *self |= &rhs
+    }
+}
+
+impl From<u16> for PrecompQueries {
+    fn from(value: u16) -> Self {
+        Self {
+            first: value as u64,
+            rest: Default::default(),
+        }
+    }
+}
+
+impl Debug for PrecompQueries {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+#[test]
+fn more_than_64_patterns() {
+    let mut a = PrecompQueries::default();
+    assert!(a.is_empty());
+    a.insert(3);
+    assert_eq!(a.as_u16(), Some(1 << 3));
+    a.insert(200);
+    assert!(a.contains(3) && a.contains(200) && !a.contains(199));
+    assert_eq!(a.iter().collect::<Vec<_>>(), vec![3, 200]);
+    assert_eq!(a.as_u16(), None);
+
+    let words: Vec<_> = a.words().collect();
+    assert_eq!(words.len(), 4);
+    assert_eq!(PrecompQueries::from_words(words.clone()), a);
+    assert!(a.is_within(&words));
+    assert!(PrecompQueries::from(1 << 3).is_within(&words));
+    assert!(!a.is_within(&words[..1]));
+
+    let mut b = PrecompQueries::from(1 << 3);
+    b.insert(70);
+    b |= &a;
+    assert_eq!(b.iter().collect::<Vec<_>>(), vec![3, 70, 200]);
+    b.intersect_with(&PrecompQueries::all(100));
+    assert_eq!(b.iter().collect::<Vec<_>>(), vec![3, 70]);
+    b.intersect_with(&PrecompQueries::from(1));
+    assert!(b.is_empty());
+}
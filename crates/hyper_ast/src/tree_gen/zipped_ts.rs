@@ -61,7 +61,7 @@ impl<T> From<Local<T>> for DD {
 
 pub type Global<'a> = SpacedGlobalData<'a>;
 
-type PrecompQueries = u16;
+type PrecompQueries = crate::tree_gen::PrecompQueries;
 
 #[derive(Debug, Clone)]
 pub struct Local<T> {
@@ -538,7 +538,7 @@ where
             debug_assert_eq!(metrics.line_count, md.metrics.line_count);
             debug_assert_eq!(metrics.hashs.build(), md.metrics.hashs);
             let metrics = md.metrics;
-            let precomp_queries = md.precomp_queries;
+            let precomp_queries = md.precomp_queries.clone();
             Local {
                 compressed_node,
                 metrics,
@@ -564,7 +564,7 @@ where
 
                 todo!("AAA");//acc.precomp_queries |= more.match_precomp_queries(stores, &acc, label.as_deref());
                 if More::ENABLED {
-                    add_md_precomp_queries(&mut dyn_builder, acc.precomp_queries);
+                    add_md_precomp_queries(&mut dyn_builder, &acc.precomp_queries);
                 }
             }
 
@@ -631,7 +631,7 @@ impl<'store, 'cache, TS: types::ETypeStore, More, const HIDDEN_NODES: bool>
     {
         todo!("AAA");//acc.precomp_queries |= more.match_precomp_queries(stores, &*acc, label.as_deref());
         if More::ENABLED {
-            add_md_precomp_queries(dyn_builder, acc.precomp_queries);
+            add_md_precomp_queries(dyn_builder, &acc.precomp_queries);
         }
     }
 }
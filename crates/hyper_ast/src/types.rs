@@ -700,7 +700,10 @@ pub trait WithRoles: WithChildren {
 }
 
 pub trait WithPrecompQueries {
-    fn wont_match_given_precomputed_queries(&self, needed: u16) -> bool;
+    fn wont_match_given_precomputed_queries(
+        &self,
+        needed: &crate::tree_gen::PrecompQueries,
+    ) -> bool;
 }
 pub struct ChildrenSlice<'a, T>(pub &'a [T]);
 
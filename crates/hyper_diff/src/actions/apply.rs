@@ -0,0 +1,385 @@
+//! Application of edit scripts produced by [`super::script_generator2`] on a HyperAST.
+//!
+//! Actions are first replayed on a lightweight copy of the source tree,
+//! where untouched subtrees are left as references to the original nodes.
+//! Then only the modified nodes are built, bottom-up, using a [`NodeBuilder`].
+//!
+//! ```ignore
+//! let patched = apply::apply_actions(&stores, &src, &actions)?;
+//! let mut builder = apply::LegionBuilder::<TType>::new(&mut node_store, &label_store);
+//! let new_src = patched.build(&mut builder)?;
+//! ```
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::marker::PhantomData;
+
+use num_traits::ToPrimitive;
+
+use hyperast::{
+    hashed::{IndexingHashBuilder, MetaDataHashsBuilder, SyntaxNodeHashs, SyntaxNodeHashsKinds},
+    store::{
+        defaults::{LabelIdentifier, NodeIdentifier},
+        labels::LabelStore,
+        nodes::legion::{compo, dyn_builder, eq_any_node, NodeStore},
+    },
+    tree_gen::{self, BasicAccumulator, SubTreeMetrics},
+    types::{
+        Childrn, HyperAST, HyperType, LabelStore as _, Labeled, NodeId, WithChildren, WithHashs,
+        WithSerialization, WithStats,
+    },
+};
+
+use super::{
+    action_vec::ActionsVec,
+    pair_updates_with_moves,
+    script_generator2::{Act, SimpleAction},
+    Moved, Paired,
+};
+use crate::tree::tree_path::TreePath;
+
+/// Builds the nodes of a patched tree, see [`Patched::build`]
+pub trait NodeBuilder<IdN, L> {
+    /// Builds a node with the type of `template`, the given label and children.
+    fn build(
+        &mut self,
+        template: &IdN,
+        label: Option<&L>,
+        children: Vec<IdN>,
+    ) -> Result<IdN, String>;
+}
+
+/// Result of the application of actions on a source tree, before building it.
+pub struct Patched<IdN, L> {
+    arena: Vec<PatchedNode<IdN, L>>,
+    /// roots of the intermediate forest, the last one is the result
+    roots: Vec<usize>,
+}
+
+struct PatchedNode<IdN, L> {
+    /// the original node, or the inserted one, it provides the type of the node
+    template: IdN,
+    label: Option<L>,
+    /// not yet expanded if None, i.e. still the children of template
+    children: Option<Vec<usize>>,
+    /// true if its label or its list of children changed
+    changed: bool,
+}
+
+/// Applies `actions` sequentially on the tree rooted at `src`.
+///
+/// Paths are the `mid` paths of actions, i.e. they are valid after applying the previous actions.
+pub fn apply_actions<HAST, P>(
+    stores: HAST,
+    src: &HAST::IdN,
+    actions: &ActionsVec<SimpleAction<HAST::Label, P, HAST::IdN>>,
+) -> Result<Patched<HAST::IdN, HAST::Label>, String>
+where
+    HAST: HyperAST + Copy,
+    HAST::IdN: NodeId<IdN = HAST::IdN> + Clone,
+    HAST::Label: Clone,
+    P: TreePath<Item = HAST::Idx> + PartialEq + Debug,
+{
+    let mut applier = Applier {
+        stores,
+        patched: Patched {
+            arena: vec![],
+            roots: vec![],
+        },
+    };
+    let root = applier.node(src.clone());
+    applier.patched.roots.push(root);
+    for Paired {
+        action: a, moved, ..
+    } in pair_updates_with_moves(&actions.0)
+    {
+        match &a.action {
+            Act::Delete {} => {
+                applier.detach(&a.path.mid)?;
+            }
+            Act::Insert { sub } => {
+                let label = stores.resolve(sub).try_get_label().cloned();
+                let x = applier.patched.arena.len();
+                applier.patched.arena.push(PatchedNode {
+                    template: sub.clone(),
+                    label,
+                    children: Some(vec![]),
+                    changed: true,
+                });
+                applier.attach(&a.path.mid, x)?;
+            }
+            Act::Update { new } => {
+                // the update of a moved node comes before its move, but its path is the one after the move
+                let x = match moved {
+                    Some(Moved { from, path, .. }) => applier.mov(&from.mid, &path.mid)?,
+                    None => applier.at(&a.path.mid)?,
+                };
+                applier.update(x, new);
+            }
+            Act::Move { from } => {
+                applier.mov(&from.mid, &a.path.mid)?;
+            }
+            Act::MovUpd { from, new } => {
+                let x = applier.mov(&from.mid, &a.path.mid)?;
+                applier.update(x, new);
+            }
+        }
+    }
+    Ok(applier.patched)
+}
+
+struct Applier<HAST: HyperAST> {
+    stores: HAST,
+    patched: Patched<HAST::IdN, HAST::Label>,
+}
+
+impl<HAST> Applier<HAST>
+where
+    HAST: HyperAST + Copy,
+    HAST::IdN: NodeId<IdN = HAST::IdN> + Clone,
+    HAST::Label: Clone,
+{
+    fn node(&mut self, id: HAST::IdN) -> usize {
+        let label = self.stores.resolve(&id).try_get_label().cloned();
+        self.patched.arena.push(PatchedNode {
+            template: id,
+            label,
+            children: None,
+            changed: false,
+        });
+        self.patched.arena.len() - 1
+    }
+
+    fn children(&mut self, x: usize) -> &mut Vec<usize> {
+        if self.patched.arena[x].children.is_none() {
+            let cs: Vec<HAST::IdN> = {
+                let n = self.stores.resolve(&self.patched.arena[x].template);
+                n.children()
+                    .map_or(vec![], |cs| cs.iter_children().collect())
+            };
+            let cs = cs.into_iter().map(|c| self.node(c)).collect();
+            self.patched.arena[x].children = Some(cs);
+        }
+        self.patched.arena[x].children.as_mut().unwrap()
+    }
+
+    /// the node holding the last offset of `path` (None for the roots) and this offset
+    fn parent<P>(&mut self, path: &P) -> Result<(Option<usize>, usize), String>
+    where
+        P: TreePath<Item = HAST::Idx> + Debug,
+    {
+        let mut it = path.iter().map(|i| i.to_usize().unwrap());
+        let Some(mut o) = it.next() else {
+            return Err("empty path".to_string());
+        };
+        let mut parent = None;
+        for i in it {
+            let x = match parent {
+                None => self.patched.roots.get(o),
+                Some(p) => self.children(p).get(o),
+            };
+            let Some(x) = x else {
+                return Err(format!("wrong path {:?}", path));
+            };
+            parent = Some(*x);
+            o = i;
+        }
+        Ok((parent, o))
+    }
+
+    fn siblings_mut(&mut self, parent: Option<usize>) -> &mut Vec<usize> {
+        match parent {
+            None => &mut self.patched.roots,
+            Some(p) => {
+                self.patched.arena[p].changed = true;
+                self.children(p)
+            }
+        }
+    }
+
+    fn at<P>(&mut self, path: &P) -> Result<usize, String>
+    where
+        P: TreePath<Item = HAST::Idx> + Debug,
+    {
+        let (p, o) = self.parent(path)?;
+        let x = match p {
+            None => self.patched.roots.get(o),
+            Some(p) => self.children(p).get(o),
+        };
+        x.copied().ok_or_else(|| format!("wrong path {:?}", path))
+    }
+
+    fn detach<P>(&mut self, path: &P) -> Result<usize, String>
+    where
+        P: TreePath<Item = HAST::Idx> + Debug,
+    {
+        let (p, o) = self.parent(path)?;
+        let cs = self.siblings_mut(p);
+        if o >= cs.len() {
+            return Err(format!("nothing to remove at {:?}", path));
+        }
+        Ok(cs.remove(o))
+    }
+
+    fn attach<P>(&mut self, path: &P, x: usize) -> Result<(), String>
+    where
+        P: TreePath<Item = HAST::Idx> + Debug,
+    {
+        let (p, o) = self.parent(path)?;
+        let cs = self.siblings_mut(p);
+        if o > cs.len() {
+            return Err(format!("cannot insert at {:?}", path));
+        }
+        cs.insert(o, x);
+        Ok(())
+    }
+
+    fn mov<P>(&mut self, from: &P, to: &P) -> Result<usize, String>
+    where
+        P: TreePath<Item = HAST::Idx> + Debug,
+    {
+        let x = self.detach(from)?;
+        self.attach(to, x)?;
+        Ok(x)
+    }
+
+    fn update(&mut self, x: usize, new: &HAST::Label) {
+        self.patched.arena[x].label = Some(new.clone());
+        self.patched.arena[x].changed = true;
+    }
+}
+
+impl<IdN: Clone + Eq, L> Patched<IdN, L> {
+    /// Builds the patched nodes, unchanged subtrees are reused as is.
+    pub fn build(&self, builder: &mut impl NodeBuilder<IdN, L>) -> Result<IdN, String> {
+        let root = *self.roots.last().ok_or("no root left")?;
+        if self.roots.len() != 1 {
+            log::warn!("{} roots left after applying actions", self.roots.len());
+        }
+        self.build_aux(root, builder)
+    }
+
+    fn build_aux(&self, x: usize, builder: &mut impl NodeBuilder<IdN, L>) -> Result<IdN, String> {
+        let n = &self.arena[x];
+        let Some(cs) = &n.children else {
+            return Ok(n.template.clone());
+        };
+        let children = cs
+            .iter()
+            .map(|c| self.build_aux(*c, builder))
+            .collect::<Result<Vec<_>, _>>()?;
+        let unchanged = !n.changed
+            && cs
+                .iter()
+                .zip(&children)
+                .all(|(c, id)| &self.arena[*c].template == id);
+        if unchanged {
+            return Ok(n.template.clone());
+        }
+        builder.build(&n.template, n.label.as_ref(), children)
+    }
+}
+
+/// Builds nodes in a legion [`NodeStore`], deduplicating them like generators do.
+///
+/// The type component `K` of templates is reused, e.g. `TType` for java.
+/// Hashes, metrics and byte lengths are computed from children the same way as in tree generators,
+/// but language specific metadata (e.g. roles, precomputed queries or references) are not.
+/// Built nodes are thus marked as [`compo::Patched`], so that generators never reuse them,
+/// while built nodes reuse the equal nodes of generators.
+pub struct LegionBuilder<'a, K> {
+    node_store: &'a mut NodeStore,
+    label_store: &'a LabelStore,
+    _phantom: PhantomData<K>,
+}
+
+impl<'a, K> LegionBuilder<'a, K> {
+    pub fn new(node_store: &'a mut NodeStore, label_store: &'a LabelStore) -> Self {
+        Self {
+            node_store,
+            label_store,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<'a, K> NodeBuilder<NodeIdentifier, LabelIdentifier> for LegionBuilder<'a, K>
+where
+    K: 'static + HyperType + Copy + Eq + Hash + Send + Sync,
+{
+    fn build(
+        &mut self,
+        template: &NodeIdentifier,
+        label: Option<&LabelIdentifier>,
+        children: Vec<NodeIdentifier>,
+    ) -> Result<NodeIdentifier, String> {
+        let kind = *self
+            .node_store
+            .resolve(*template)
+            .get_component::<K>()
+            .map_err(|_| format!("{:?} does not have the expected type component", template))?;
+        let mut acc: SubTreeMetrics<SyntaxNodeHashs<u32>> = Default::default();
+        let mut no_space = vec![];
+        let mut bytes_len = 0;
+        for c in &children {
+            let node = self.node_store.resolve(*c);
+            let spaces = node.get_component::<K>().map_or(false, |t| t.is_spaces());
+            let hashs = SyntaxNodeHashs {
+                structt: WithHashs::hash(&node, SyntaxNodeHashsKinds::Struct),
+                label: WithHashs::hash(&node, SyntaxNodeHashsKinds::Label),
+                syntax: WithHashs::hash(&node, SyntaxNodeHashsKinds::Syntax),
+            };
+            acc.acc(SubTreeMetrics {
+                size: node.size().to_u32().unwrap(),
+                // spaces are accounted for like in generators
+                height: if spaces {
+                    0
+                } else {
+                    node.height().to_u32().unwrap()
+                },
+                size_no_spaces: if spaces {
+                    0
+                } else {
+                    node.size_no_spaces().to_u32().unwrap()
+                },
+                hashs,
+                line_count: node.line_count().to_u16().unwrap(),
+            });
+            if !spaces {
+                no_space.push(*c);
+            }
+            bytes_len += node
+                .try_bytes_len()
+                .ok_or_else(|| format!("{:?} does not have a byte length", c))?;
+        }
+        let label_str = label.map(|l| self.label_store.resolve(l));
+        if children.is_empty() {
+            bytes_len = label_str.map_or(0, |l| l.len());
+        }
+        let own_line_count = label_str.map_or(0, |l| {
+            l.matches("\n").count().to_u16().expect("too many newlines")
+        });
+        let metrics = acc.finalize(&kind, &label_str, own_line_count);
+        let hashable = &metrics.hashs.most_discriminating();
+        let eq = eq_any_node(&kind, label, &children);
+        let insertion = self.node_store.prepare_insertion(hashable, eq);
+        if let Some(id) = insertion.occupied_id() {
+            return Ok(id);
+        }
+        let vacant = insertion.vacant();
+        let metrics = metrics.map_hashs(|h| h.build());
+        use hyperast::store::nodes::EntityBuilder;
+        let mut dyn_builder = dyn_builder::EntityBuilder::new();
+        let hashs = metrics.add_md_metrics(&mut dyn_builder, children.is_empty());
+        hashs.persist(&mut dyn_builder);
+        dyn_builder.add(compo::BytesLen(bytes_len.to_u32().unwrap()));
+        dyn_builder.add(compo::Patched);
+        if children.len() != no_space.len() {
+            tree_gen::add_cs_no_spaces(&mut dyn_builder, no_space);
+        }
+        BasicAccumulator { kind, children }.add_primary(&mut dyn_builder, kind, label.copied());
+        Ok(NodeStore::insert_built_after_prepare(
+            vacant,
+            dyn_builder.build(),
+        ))
+    }
+}
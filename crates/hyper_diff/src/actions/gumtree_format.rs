@@ -0,0 +1,339 @@
+//! Export of diffs in the output formats of GumTree,
+//! i.e. its json format (the one read by `benchmark_diffs::diff_output`) and its textual format.
+//!
+//! Nodes are located with byte ranges computed in the stores given to the diff,
+//! so with stores without spaces, ranges are only comparable with each other.
+
+use std::fmt::Display;
+
+use hyperast::{
+    position::{compute_position, Position},
+    types::{self, HyperAST, LabelStore, Labeled, WithSerialization},
+};
+use num_traits::ToPrimitive;
+
+use crate::{
+    algorithms::{DiffResult, PreparedMappingDurations},
+    decompressed_tree_store::{DecompressedWithParent, ShallowDecompressedTreeStore},
+    matchers::{
+        mapping_store::{MonoMappingStore, VecStore},
+        Mapper,
+    },
+    tree::tree_path::TreePath,
+};
+
+use super::{
+    action_vec::ActionsVec,
+    pair_updates_with_moves,
+    script_generator2::{Act, SimpleAction},
+    Moved, Paired,
+};
+
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub struct Output<T> {
+    /// durations of each phase, in nanoseconds
+    pub times: Vec<usize>,
+    pub matches: Vec<Match<T>>,
+    pub actions: Option<Vec<Action<T>>>,
+}
+
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub struct Match<T> {
+    pub src: T,
+    pub dest: T,
+}
+
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub struct Action<T> {
+    pub action: Kind,
+    /// the src node, except for insertions
+    pub tree: T,
+    #[cfg_attr(feature = "serialize", serde(skip_serializing_if = "Option::is_none"))]
+    pub parent: Option<T>,
+    #[cfg_attr(feature = "serialize", serde(skip_serializing_if = "Option::is_none"))]
+    pub at: Option<usize>,
+    #[cfg_attr(feature = "serialize", serde(skip_serializing_if = "Option::is_none"))]
+    pub label: Option<String>,
+}
+
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub enum Kind {
+    #[cfg_attr(feature = "serialize", serde(rename = "update-node"))]
+    Upd,
+    #[cfg_attr(feature = "serialize", serde(rename = "move-tree"))]
+    Move,
+    #[cfg_attr(feature = "serialize", serde(rename = "insert-node"))]
+    Ins,
+    #[cfg_attr(feature = "serialize", serde(rename = "delete-node"))]
+    Del,
+}
+
+impl Kind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Kind::Upd => "update-node",
+            Kind::Move => "move-tree",
+            Kind::Ins => "insert-node",
+            Kind::Del => "delete-node",
+        }
+    }
+}
+
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub struct Tree {
+    pub r#type: String,
+    #[cfg_attr(feature = "serialize", serde(skip_serializing_if = "Option::is_none"))]
+    pub label: Option<String>,
+    pub file: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Tree {
+    /// Locates the node at the end of `path`, starting from `root`.
+    pub fn at<HAST>(
+        stores: &HAST,
+        root: HAST::IdN,
+        path: impl IntoIterator<Item = HAST::Idx>,
+    ) -> Self
+    where
+        HAST: HyperAST,
+        HAST::IdN: types::NodeId<IdN = HAST::IdN>,
+        for<'t> <HAST as types::AstLending<'t>>::RT: WithSerialization,
+    {
+        let pos = compute_position(root, &mut path.into_iter(), stores);
+        Self::from_pos(stores, pos)
+    }
+
+    /// Describes the node `x` located at `pos`.
+    pub fn from_pos<HAST>(stores: &HAST, (pos, x): (Position, HAST::IdN)) -> Self
+    where
+        HAST: HyperAST,
+    {
+        let range = pos.range();
+        let r = stores.resolve(&x);
+        Tree {
+            r#type: stores.resolve_type(&x).to_string(),
+            label: r
+                .try_get_label()
+                .map(|l| stores.label_store().resolve(l).to_string())
+                .filter(|l| !l.is_empty()),
+            file: pos.file().to_string_lossy().to_string(),
+            start: range.start,
+            end: range.end,
+        }
+    }
+}
+
+/// Same as GumTree's `Tree::toString`
+impl Display for Tree {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.r#type)?;
+        if let Some(label) = &self.label {
+            write!(f, ": {}", label)?;
+        }
+        write!(f, " [{},{}]", self.start, self.end)
+    }
+}
+
+/// GumTree's textual format, see its `TextDiffFormatter`
+impl Display for Output<Tree> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for m in &self.matches {
+            writeln!(f, "===\nmatch\n---\n{}\n{}", m.src, m.dest)?;
+        }
+        for a in self.actions.iter().flatten() {
+            write!(f, "{}", a)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Action<Tree> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "===\n{}\n---\n{}", self.action.as_str(), self.tree)?;
+        match self.action {
+            Kind::Upd => writeln!(
+                f,
+                "replace {} by {}",
+                self.tree.label.as_deref().unwrap_or_default(),
+                self.label.as_deref().unwrap_or_default()
+            ),
+            Kind::Move | Kind::Ins => match (&self.parent, self.at) {
+                (Some(parent), Some(at)) => writeln!(f, "to\n{}\nat {}", parent, at),
+                _ => Ok(()),
+            },
+            Kind::Del => Ok(()),
+        }
+    }
+}
+
+/// Converts the actions of script_generator2 into GumTree actions.
+///
+/// Moves combined with an update (`MovUpd`, or an `Update` on the dst path directly followed by a `Move`)
+/// become an update-node then a move-tree, like in GumTree.
+pub fn actions<HAST, P>(
+    stores: &HAST,
+    src: HAST::IdN,
+    dst: HAST::IdN,
+    actions: &ActionsVec<SimpleAction<HAST::Label, P, HAST::IdN>>,
+) -> Vec<Action<Tree>>
+where
+    HAST: HyperAST,
+    HAST::IdN: types::NodeId<IdN = HAST::IdN> + Clone,
+    for<'t> <HAST as types::AstLending<'t>>::RT: WithSerialization,
+    P: TreePath<Item = HAST::Idx> + PartialEq,
+{
+    let tree_src = |p: &P| Tree::at(stores, src.clone(), p.iter());
+    // parent and position in parent of a dst path
+    let parent_dst = |p: &P| {
+        let mut p: Vec<_> = p.iter().collect();
+        let at = p.pop()?.to_usize()?;
+        Some((Tree::at(stores, dst.clone(), p), at))
+    };
+    let label = |l: &HAST::Label| stores.label_store().resolve(l).to_string();
+    let mut result = Vec::with_capacity(actions.0.len());
+    for Paired {
+        action: a, moved, ..
+    } in pair_updates_with_moves(&actions.0)
+    {
+        match &a.action {
+            Act::Delete {} => result.push(Action {
+                action: Kind::Del,
+                tree: tree_src(&a.path.ori),
+                parent: None,
+                at: None,
+                label: None,
+            }),
+            Act::Insert { .. } => {
+                let (parent, at) = parent_dst(&a.path.ori).unzip();
+                result.push(Action {
+                    action: Kind::Ins,
+                    tree: Tree::at(stores, dst.clone(), a.path.ori.iter()),
+                    parent,
+                    at,
+                    label: None,
+                })
+            }
+            Act::Update { new } => {
+                let src_path = match &moved {
+                    Some(Moved { from, .. }) => &from.ori,
+                    None => &a.path.ori,
+                };
+                let tree = tree_src(src_path);
+                result.push(Action {
+                    action: Kind::Upd,
+                    tree: tree.clone(),
+                    parent: None,
+                    at: None,
+                    label: Some(label(new)),
+                });
+                if let Some(Moved { path, .. }) = moved {
+                    let (parent, at) = parent_dst(&path.ori).unzip();
+                    result.push(Action {
+                        action: Kind::Move,
+                        tree,
+                        parent,
+                        at,
+                        label: None,
+                    })
+                }
+            }
+            Act::Move { from } | Act::MovUpd { from, .. } => {
+                let tree = tree_src(&from.ori);
+                if let Act::MovUpd { new, .. } = &a.action {
+                    result.push(Action {
+                        action: Kind::Upd,
+                        tree: tree.clone(),
+                        parent: None,
+                        at: None,
+                        label: Some(label(new)),
+                    })
+                }
+                let (parent, at) = parent_dst(&a.path.ori).unzip();
+                result.push(Action {
+                    action: Kind::Move,
+                    tree,
+                    parent,
+                    at,
+                    label: None,
+                })
+            }
+        }
+    }
+    result
+}
+
+/// Locates each pair of mapped nodes.
+pub fn matches<HAST, SD, DD>(
+    stores: &HAST,
+    src_arena: &SD,
+    dst_arena: &DD,
+    mappings: &VecStore<u32>,
+) -> Vec<Match<Tree>>
+where
+    HAST: HyperAST + Copy,
+    HAST::IdN: types::NodeId<IdN = HAST::IdN> + Clone,
+    for<'t> <HAST as types::AstLending<'t>>::RT: WithSerialization,
+    SD: ShallowDecompressedTreeStore<HAST, u32> + DecompressedWithParent<HAST, u32>,
+    DD: ShallowDecompressedTreeStore<HAST, u32> + DecompressedWithParent<HAST, u32>,
+{
+    let src = src_arena.original(&src_arena.root());
+    let dst = dst_arena.original(&dst_arena.root());
+    mappings
+        .iter()
+        .map(|(a, b)| Match {
+            src: Tree::at(stores, src.clone(), src_arena.path_rooted::<HAST::Idx>(&a)),
+            dest: Tree::at(stores, dst.clone(), dst_arena.path_rooted::<HAST::Idx>(&b)),
+        })
+        .collect()
+}
+
+impl<HAST, P, SD, DD, const N: usize>
+    DiffResult<
+        SimpleAction<HAST::Label, P, HAST::IdN>,
+        Mapper<HAST, SD, DD, VecStore<u32>>,
+        PreparedMappingDurations<N>,
+    >
+where
+    HAST: HyperAST + Copy,
+    HAST::IdN: types::NodeId<IdN = HAST::IdN> + Clone,
+    for<'t> <HAST as types::AstLending<'t>>::RT: WithSerialization,
+    P: TreePath<Item = HAST::Idx> + PartialEq,
+    SD: ShallowDecompressedTreeStore<HAST, u32> + DecompressedWithParent<HAST, u32>,
+    DD: ShallowDecompressedTreeStore<HAST, u32> + DecompressedWithParent<HAST, u32>,
+{
+    /// Formats the result like GumTree,
+    /// `times` contains the duration of each matching phase then the one of the script generation.
+    ///
+    /// Use serde to get the json format, and [`Display`] to get the textual format.
+    pub fn to_gumtree(&self) -> Output<Tree> {
+        let stores = &self.mapper.hyperast;
+        let mapping = &self.mapper.mapping;
+        let nanos = |t: f64| (t * 1e9) as usize;
+        let times = (self.mapping_durations.preparation.iter())
+            .zip(self.mapping_durations.mappings.0.iter())
+            .map(|(p, m)| nanos(p + m))
+            .chain([nanos(self.prepare_gen_t + self.gen_t)])
+            .collect();
+        let matches = matches(
+            stores,
+            &mapping.src_arena,
+            &mapping.dst_arena,
+            &mapping.mappings,
+        );
+        let src = mapping.src_arena.original(&mapping.src_arena.root());
+        let dst = mapping.dst_arena.original(&mapping.dst_arena.root());
+        let actions = (self.actions.as_ref()).map(|a| actions(stores, src, dst, a));
+        Output {
+            times,
+            matches,
+            actions,
+        }
+    }
+}
@@ -1,9 +1,69 @@
 #[allow(unused)] // still very experimental
 pub mod action_tree;
 pub mod action_vec;
+pub mod apply;
+pub mod gumtree_format;
+pub mod operations;
 pub mod script_generator;
 pub mod script_generator2;
 
 pub trait Actions {
     fn len(&self) -> usize;
 }
+
+use script_generator2::{Act, ApplicablePath, SimpleAction};
+
+/// An action of an edit script, see [`pair_updates_with_moves`]
+pub struct Paired<'a, L, P, I> {
+    /// index of `action` in the script
+    pub index: usize,
+    pub action: &'a SimpleAction<L, P, I>,
+    /// the move of the same node, when `action` is the update of a node that also moved
+    pub moved: Option<Moved<'a, P>>,
+}
+
+/// A move paired with the update preceding it
+pub struct Moved<'a, P> {
+    /// index of the move in the script
+    pub index: usize,
+    pub from: &'a ApplicablePath<P>,
+    pub path: &'a ApplicablePath<P>,
+}
+
+/// Iterates over the actions of a script generated by [`script_generator2`].
+///
+/// A node both moved and renamed is an update followed by a move on the same mid path,
+/// such a move is paired with the update and not yielded on its own.
+pub fn pair_updates_with_moves<L, P: PartialEq, I>(
+    actions: &[SimpleAction<L, P, I>],
+) -> impl Iterator<Item = Paired<'_, L, P, I>> {
+    let mut it = actions.iter().enumerate().peekable();
+    std::iter::from_fn(move || {
+        let (index, action) = it.next()?;
+        let moved = match (&action.action, it.peek().copied()) {
+            (
+                Act::Update { .. },
+                Some((
+                    j,
+                    SimpleAction {
+                        path,
+                        action: Act::Move { from },
+                    },
+                )),
+            ) if path.mid == action.path.mid => Some(Moved {
+                index: j,
+                from,
+                path,
+            }),
+            _ => None,
+        };
+        if moved.is_some() {
+            it.next();
+        }
+        Some(Paired {
+            index,
+            action,
+            moved,
+        })
+    })
+}
@@ -0,0 +1,265 @@
+//! Grouping of edit actions into higher level change operations,
+//! e.g. "method renamed", "parameter added" or "condition changed".
+//!
+//! Each action is attached to the closest node around it that plays a [`Role`]
+//! (its anchor, e.g. a statement or a parameter), actions with the same anchor form an [`Operation`].
+//! The kind of [`Change`] comes from the action on the anchor itself if there is one,
+//! otherwise the anchor is considered changed, or renamed if its name was updated.
+//! Roles are language specific, see [`Roles`] and [`type_trait_role`].
+use std::fmt::Display;
+
+use hyperast::types::{HyperAST, NodeId, TypeStore, TypeTrait, WithChildren};
+
+use super::{
+    action_vec::ActionsVec,
+    pair_updates_with_moves,
+    script_generator2::{Act, SimpleAction},
+    Moved, Paired,
+};
+use crate::tree::tree_path::TreePath;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Role {
+    /// classes, interfaces, structs, enums, ...
+    TypeDecl,
+    /// methods, constructors and functions
+    MethodDecl,
+    /// fields and other value members
+    FieldDecl,
+    /// the identifier naming the enclosing declaration
+    Name,
+    Parameter,
+    Import,
+    /// the condition of an if, a loop, ...
+    Condition,
+    Statement,
+}
+
+impl Role {
+    pub fn is_decl(&self) -> bool {
+        matches!(self, Role::TypeDecl | Role::MethodDecl | Role::FieldDecl)
+    }
+}
+
+impl Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Role::TypeDecl => "type",
+            Role::MethodDecl => "method",
+            Role::FieldDecl => "field",
+            Role::Name => "name",
+            Role::Parameter => "parameter",
+            Role::Import => "import",
+            Role::Condition => "condition",
+            Role::Statement => "statement",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Change {
+    Added,
+    Removed,
+    Moved,
+    Renamed,
+    Changed,
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Change::Added => "added",
+            Change::Removed => "removed",
+            Change::Moved => "moved",
+            Change::Renamed => "renamed",
+            Change::Changed => "changed",
+        })
+    }
+}
+
+/// The tree in which a path must be resolved
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Side {
+    Src,
+    Dst,
+}
+
+/// A cluster of actions
+#[derive(Debug, Clone)]
+pub struct Operation<Idx> {
+    /// None if no node with a role encloses the actions
+    pub role: Option<Role>,
+    pub change: Change,
+    /// where the anchor is, i.e. src for deletions, updates and moves, dst for insertions
+    pub side: Side,
+    /// path to the anchor, in the tree given by `side`
+    pub path: Vec<Idx>,
+    /// path to the declaration enclosing the anchor, in the tree given by `side`
+    pub decl: Option<Vec<Idx>>,
+    /// offsets of the grouped actions in the action list
+    pub actions: Vec<usize>,
+}
+
+impl<Idx> Display for Operation<Idx> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.role {
+            Some(role) => write!(f, "{} {}", role, self.change),
+            None => write!(f, "{}", self.change),
+        }
+    }
+}
+
+/// Gives the language specific role of nodes
+pub trait Roles<Ty> {
+    /// The role of a node of type `ty`, whose parent is of type `parent`
+    fn role(&self, parent: Option<&Ty>, ty: &Ty) -> Option<Role>;
+}
+
+impl<Ty, F: Fn(Option<&Ty>, &Ty) -> Option<Role>> Roles<Ty> for F {
+    fn role(&self, parent: Option<&Ty>, ty: &Ty) -> Option<Role> {
+        self(parent, ty)
+    }
+}
+
+/// Roles derived from a [`TypeTrait`], it does not know about imports.
+pub fn type_trait_role<T: TypeTrait>(parent: Option<&T>, ty: &T) -> Option<Role> {
+    if ty.is_type_declaration() {
+        Some(Role::TypeDecl)
+    } else if ty.is_executable_member() {
+        Some(Role::MethodDecl)
+    } else if ty.is_value_member() {
+        Some(Role::FieldDecl)
+    } else if ty.is_parameter() {
+        Some(Role::Parameter)
+    } else if ty.is_identifier()
+        && parent.map_or(false, |p| {
+            p.is_type_declaration() || p.is_executable_member()
+        })
+    {
+        Some(Role::Name)
+    } else if ty.is_expression() && parent.map_or(false, |p| p.is_fork()) {
+        Some(Role::Condition)
+    } else if ty.is_statement() && !ty.is_block_related() {
+        Some(Role::Statement)
+    } else {
+        None
+    }
+}
+
+/// Groups `actions`, generated between `src` and `dst`, into operations.
+///
+/// Operations are ordered by their first action.
+pub fn group_actions<HAST, P, R>(
+    stores: HAST,
+    src: &HAST::IdN,
+    dst: &HAST::IdN,
+    actions: &ActionsVec<SimpleAction<HAST::Label, P, HAST::IdN>>,
+    roles: &R,
+) -> Result<Vec<Operation<HAST::Idx>>, String>
+where
+    HAST: HyperAST + Copy,
+    HAST::IdN: NodeId<IdN = HAST::IdN> + Clone,
+    HAST::Idx: Clone + PartialEq + std::fmt::Debug,
+    P: TreePath<Item = HAST::Idx> + PartialEq,
+    R: Roles<<HAST::TS as TypeStore>::Ty>,
+{
+    let mut result: Vec<Operation<HAST::Idx>> = vec![];
+    for Paired {
+        index: i,
+        action: a,
+        moved,
+    } in pair_updates_with_moves(&actions.0)
+    {
+        let mut group = vec![i];
+        let (side, path, change) = match &a.action {
+            Act::Delete {} => (Side::Src, &a.path.ori, Change::Removed),
+            Act::Insert { .. } => (Side::Dst, &a.path.ori, Change::Added),
+            Act::Update { .. } => match moved {
+                Some(Moved { index: j, from, .. }) => {
+                    group.push(j);
+                    (Side::Src, &from.ori, Change::Moved)
+                }
+                None => (Side::Src, &a.path.ori, Change::Renamed),
+            },
+            Act::Move { from } | Act::MovUpd { from, .. } => (Side::Src, &from.ori, Change::Moved),
+        };
+        let root = match side {
+            Side::Src => src,
+            Side::Dst => dst,
+        };
+        let path: Vec<HAST::Idx> = path.iter().collect();
+        let roles_on_path = roles_along(stores, root, &path, roles)
+            .ok_or_else(|| format!("wrong path {:?} in {:?}", path, side))?;
+
+        let own = roles_on_path.last().copied().flatten();
+        // the anchor is the deepest node with a role, names belong to their declaration
+        let anchor = roles_on_path
+            .iter()
+            .rposition(|r| r.map_or(false, |r| r != Role::Name));
+        let on_anchor = anchor.map_or(false, |j| j + 1 == roles_on_path.len());
+        let change = match change {
+            // an update on the anchor itself is just a change of its label
+            Change::Renamed if on_anchor => Change::Changed,
+            change if on_anchor => change,
+            Change::Renamed if own == Some(Role::Name) => Change::Renamed,
+            _ => Change::Changed,
+        };
+        let (role, anchor_path) = match anchor {
+            Some(j) => (roles_on_path[j], path[..j].to_vec()),
+            None => (None, vec![]),
+        };
+        let decl = anchor.and_then(|j| {
+            roles_on_path[..j]
+                .iter()
+                .rposition(|r| r.map_or(false, |r| r.is_decl()))
+                .map(|k| path[..k].to_vec())
+        });
+
+        if let Some(op) = result
+            .iter_mut()
+            .find(|op| op.side == side && op.path == anchor_path)
+        {
+            op.actions.extend(group);
+            if on_anchor || (change == Change::Renamed && op.change == Change::Changed) {
+                op.change = change;
+            }
+        } else {
+            result.push(Operation {
+                role,
+                change,
+                side,
+                path: anchor_path,
+                decl,
+                actions: group,
+            });
+        }
+    }
+    Ok(result)
+}
+
+/// the roles of the nodes from `root` to the end of `path`, both included
+fn roles_along<HAST, R>(
+    stores: HAST,
+    root: &HAST::IdN,
+    path: &[HAST::Idx],
+    roles: &R,
+) -> Option<Vec<Option<Role>>>
+where
+    HAST: HyperAST + Copy,
+    HAST::IdN: NodeId<IdN = HAST::IdN> + Clone,
+    R: Roles<<HAST::TS as TypeStore>::Ty>,
+{
+    let mut x = root.clone();
+    let mut parent = None;
+    let mut result = Vec::with_capacity(path.len() + 1);
+    let mut path = path.iter();
+    loop {
+        let ty = stores.resolve_type(&x);
+        result.push(roles.role(parent.as_ref(), &ty));
+        let Some(i) = path.next() else {
+            return Some(result);
+        };
+        x = stores.resolve(&x).child(i)?;
+        parent = Some(ty);
+    }
+}
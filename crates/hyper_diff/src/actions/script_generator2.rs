@@ -746,22 +746,23 @@ where
                     action: Act::Delete {},
                 };
                 if SUBTREE_DEL {
+                    // deletions are passed to the parent in the order their paths were computed,
+                    // so that they stay applicable sequentially
+                    let mut waiting = waiting;
                     if self.dirty[w.to_usize().unwrap()] {
                         // non uniform del.
-                        // dbg!(waiting.len());
-                        self.actions.extend(waiting);
-                        log::trace!("{:?}", action);
-                        self.actions.push(action);
+                        waiting.push(action);
                         // transitively
                         self.dirty.set(v.to_usize().unwrap(), true);
-                    } else if let Some(i) = parent.len().checked_sub(1) {
-                        // dbg!(waiting.len());
-                        // uniform, so wait in parent
-                        parent[i].w.push(action);
                     } else {
-                        // dbg!(waiting.len());
-                        log::trace!("{:?}", action);
-                        self.actions.push(action);
+                        // uniform, so the deletions of descendants are subsumed
+                        waiting = vec![action];
+                    }
+                    if let Some(i) = parent.len().checked_sub(1) {
+                        parent[i].w.extend(waiting);
+                    } else {
+                        log::trace!("{:?}", waiting);
+                        self.actions.extend(waiting);
                     }
                 } else {
                     log::trace!("{:?}", action);
@@ -769,7 +770,11 @@ where
                 }
             } else {
                 if SUBTREE_DEL {
-                    self.actions.extend(waiting);
+                    if let Some(i) = parent.len().checked_sub(1) {
+                        parent[i].w.extend(waiting);
+                    } else {
+                        self.actions.extend(waiting);
+                    }
                 }
                 // not modified
                 // all parents were not modified
@@ -824,18 +829,9 @@ where
             for b in &s2 {
                 if self.ori_mappings.unwrap().has(&a, &b) && !lcs.contains(&(*a, *b)) {
                     let k = self.find_pos(&b, x);
-                    let path = ApplicablePath {
-                        ori: self.orig_src(*w).extend(&[k]),
-                        mid: self.path(*w),
-                    };
-                    let action = SimpleAction {
-                        path,
-                        action: Act::Move {
-                            from: ApplicablePath {
-                                ori: self.orig_src(*a),
-                                mid: self.path(*a),
-                            },
-                        },
+                    let from = ApplicablePath {
+                        ori: self.orig_src(*a),
+                        mid: self.path(*a),
                     };
                     // let action = SimpleAction::Move {
                     //     sub: self.ori_to_copy(*a),
@@ -862,10 +858,15 @@ where
                     };
                     self.mid_arena[a.to_usize().unwrap()].parent = cast(z).unwrap();
                     self.mid_arena[a.to_usize().unwrap()].action = Some(self.actions.len());
-                    assert!({
-                        self.path(*a);
-                        true
-                    });
+                    // like other moves, the target path is the one of the moved node once reinserted
+                    let path = ApplicablePath {
+                        ori: self.orig_src(*w).extend(&[k]),
+                        mid: self.path(*a),
+                    };
+                    let action = SimpleAction {
+                        path,
+                        action: Act::Move { from },
+                    };
                     // self.apply_move(&action, &Some(*w), &self.ori_to_copy(*a), b);
                     self.actions.push(action);
                     self.src_in_order.push(*a);
@@ -0,0 +1,155 @@
+//! GumTree 3's default diff: greedy subtree matching then hybrid bottom-up matching,
+//! i.e. using an optimal matching (Zhang-Shasha) to recover mappings in small subtrees.
+use super::MappingDurations;
+use super::{DiffResult, PreparedMappingDurations};
+use crate::{
+    actions::script_generator2::{ScriptGenerator, SimpleAction},
+    decompressed_tree_store::{
+        bfs_wrapper::SimpleBfsMapper, lazy_post_order::LazyPostOrder, CompletePostOrder,
+    },
+    matchers::{
+        heuristic::gt::{
+            greedy_subtree_matcher::GreedySubtreeMatcher,
+            hybrid_bottom_up_matcher::HybridBottomUpMatcher,
+            lazy2_greedy_subtree_matcher::LazyGreedySubtreeMatcher,
+            lazy_hybrid_bottom_up_matcher::LazyHybridBottomUpMatcher,
+        },
+        mapping_store::{DefaultMultiMappingStore, VecStore},
+        Decompressible, Mapper,
+    },
+    tree::tree_path::CompressedTreePath,
+};
+use hyperast::types::{self, HyperAST, HyperASTShared, NodeId};
+use std::{fmt::Debug, time::Instant};
+
+type DS<HAST: HyperASTShared> = Decompressible<HAST, LazyPostOrder<HAST::IdN, u32>>;
+type CDS<HAST: HyperASTShared> = Decompressible<HAST, CompletePostOrder<HAST::IdN, u32>>;
+
+pub fn diff<HAST: HyperAST + Copy>(
+    hyperast: HAST,
+    src: &HAST::IdN,
+    dst: &HAST::IdN,
+) -> DiffResult<
+    SimpleAction<HAST::Label, CompressedTreePath<HAST::Idx>, HAST::IdN>,
+    Mapper<HAST, CDS<HAST>, CDS<HAST>, VecStore<u32>>,
+    PreparedMappingDurations<2>,
+>
+where
+    HAST::IdN: Clone + Debug + Eq,
+    HAST::IdN: NodeId<IdN = HAST::IdN>,
+    HAST::Idx: hyperast::PrimInt,
+    HAST::Label: Debug + Clone + Copy + Eq,
+    <HAST::TS as types::TypeStore>::Ty: Copy + Send + Sync + Eq + std::hash::Hash,
+    for<'t> <HAST as hyperast::types::AstLending<'t>>::RT: types::WithHashs + types::WithStats,
+{
+    let now = Instant::now();
+    let mapper: Mapper<_, CDS<HAST>, CDS<HAST>, VecStore<_>> =
+        hyperast.decompress_pair(src, dst).into();
+    let subtree_prepare_t = now.elapsed().as_secs_f64();
+    let now = Instant::now();
+    let mapper =
+        GreedySubtreeMatcher::<_, _, _, _>::match_it::<DefaultMultiMappingStore<_>>(mapper);
+    let subtree_matcher_t = now.elapsed().as_secs_f64();
+    let now = Instant::now();
+    let mapper = HybridBottomUpMatcher::<_, _, _, _>::match_it(mapper);
+    let bottomup_matcher_t = now.elapsed().as_secs_f64();
+    let now = Instant::now();
+
+    let mapper = mapper.map(
+        |x| x,
+        |dst_arena| SimpleBfsMapper::with_store(hyperast, dst_arena),
+    );
+    let prepare_gen_t = now.elapsed().as_secs_f64();
+    let now = Instant::now();
+    let actions = ScriptGenerator::compute_actions(hyperast, &mapper.mapping).ok();
+    let gen_t = now.elapsed().as_secs_f64();
+    let mapper = mapper.map(|x| x, |dst_arena| dst_arena.back);
+    DiffResult {
+        mapping_durations: PreparedMappingDurations {
+            mappings: MappingDurations([subtree_matcher_t, bottomup_matcher_t]),
+            preparation: [subtree_prepare_t, 0.0],
+        },
+        mapper,
+        actions,
+        prepare_gen_t,
+        gen_t,
+    }
+}
+
+/// Same as [`diff`] but only decompresses the subtrees that need to be
+pub fn diff_lazy<HAST: HyperAST + Copy>(
+    hyperast: HAST,
+    src: &HAST::IdN,
+    dst: &HAST::IdN,
+) -> DiffResult<
+    SimpleAction<HAST::Label, CompressedTreePath<HAST::Idx>, HAST::IdN>,
+    Mapper<HAST, CDS<HAST>, CDS<HAST>, VecStore<u32>>,
+    PreparedMappingDurations<2>,
+>
+where
+    HAST::IdN: Clone + Debug + Eq,
+    HAST::IdN: NodeId<IdN = HAST::IdN>,
+    HAST::Label: Clone + Copy + Eq + Debug,
+    HAST::Idx: hyperast::PrimInt,
+    <HAST::TS as types::TypeStore>::Ty: Copy + Eq + std::hash::Hash,
+    for<'t> types::LendT<'t, HAST>: types::WithHashs + types::WithStats,
+{
+    let now = Instant::now();
+    let mapper: (HAST, (DS<HAST>, DS<HAST>)) = hyperast.decompress_pair(src, dst);
+    let mut mapper_owned: Mapper<_, DS<HAST>, DS<HAST>, VecStore<_>> = mapper.into();
+    let mapper = Mapper {
+        hyperast,
+        mapping: crate::matchers::Mapping {
+            src_arena: mapper_owned.mapping.src_arena.as_mut(),
+            dst_arena: mapper_owned.mapping.dst_arena.as_mut(),
+            mappings: mapper_owned.mapping.mappings,
+        },
+    };
+    let subtree_prepare_t = now.elapsed().as_secs_f64();
+    let now = Instant::now();
+    let mapper =
+        LazyGreedySubtreeMatcher::<_, _, _, _>::match_it::<DefaultMultiMappingStore<_>>(mapper);
+    let subtree_matcher_t = now.elapsed().as_secs_f64();
+    let now = Instant::now();
+    let mapper = LazyHybridBottomUpMatcher::<_, _, _, _, VecStore<_>>::match_it(mapper);
+    let bottomup_matcher_t = now.elapsed().as_secs_f64();
+    let now = Instant::now();
+
+    let mapper = Mapper {
+        hyperast,
+        mapping: crate::matchers::Mapping {
+            mappings: mapper.mapping.mappings,
+            src_arena: mapper_owned.mapping.src_arena,
+            dst_arena: mapper_owned.mapping.dst_arena,
+        },
+    };
+    let mapper = mapper.map(
+        |src_arena| {
+            Decompressible::<HAST, CompletePostOrder<HAST::IdN, _>>::from(
+                src_arena.map(|x| x.complete(hyperast)),
+            )
+        },
+        |dst_arena| {
+            let complete = Decompressible::<HAST, CompletePostOrder<HAST::IdN, _>>::from(
+                dst_arena.map(|x| x.complete(hyperast)),
+            );
+            SimpleBfsMapper::with_store(hyperast, complete)
+        },
+    );
+
+    let prepare_gen_t = now.elapsed().as_secs_f64();
+    let now = Instant::now();
+    let actions = ScriptGenerator::compute_actions(mapper.hyperast, &mapper.mapping).ok();
+    let gen_t = now.elapsed().as_secs_f64();
+    let mapper = mapper.map(|x| x, |dst_arena| dst_arena.back);
+    DiffResult {
+        mapping_durations: PreparedMappingDurations {
+            mappings: MappingDurations([subtree_matcher_t, bottomup_matcher_t]),
+            preparation: [subtree_prepare_t, 0.0],
+        },
+        mapper,
+        actions,
+        prepare_gen_t,
+        gen_t,
+    }
+}
@@ -6,6 +6,7 @@ use crate::{
 pub mod gumtree;
 pub mod gumtree_lazy;
 pub mod gumtree_partial_lazy;
+pub mod hybrid;
 
 #[derive(Debug, Clone)]
 pub struct MappingDurations<const N: usize>(pub [f64; N]);
@@ -186,7 +186,6 @@ where
                 }
             }
         }
-        todo!()
     }
 }
 
@@ -360,6 +359,5 @@ where
                 }
             }
         }
-        todo!()
     }
 }
@@ -25,7 +25,7 @@ pub struct GreedyBottomUpMatcher<
     const SIM_THRESHOLD_NUM: u64 = 1,
     const SIM_THRESHOLD_DEN: u64 = 2,
 > {
-    internal: BottomUpMatcher<Dsrc, Ddst, HAST, M>,
+    pub(super) internal: BottomUpMatcher<Dsrc, Ddst, HAST, M>,
 }
 
 /// Enable using a slice instead of recreating a ZsTree for each call to ZsMatch, see last_chance_match
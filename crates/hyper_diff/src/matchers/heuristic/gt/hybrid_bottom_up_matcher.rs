@@ -0,0 +1,194 @@
+//! Bottom-up matcher of GumTree 3 ("hybrid"),
+//! it recovers mappings between small subtrees with an optimal matcher (Zhang-Shasha),
+//! and falls back to lcs and histogram matching for bigger ones.
+//! The similarity threshold is also adapted to the size of the compared subtrees.
+use super::bottom_up_matcher::BottomUpMatcher;
+use super::greedy_bottom_up_matcher::GreedyBottomUpMatcher;
+use crate::decompressed_tree_store::{
+    ContiguousDescendants, DecompressedTreeStore, DecompressedWithParent, POBorrowSlice, PostOrder,
+    PostOrderIterable,
+};
+use crate::matchers::mapping_store::MonoMappingStore;
+use crate::matchers::similarity_metrics;
+use hyperast::types::{DecompressedFrom, HyperAST, NodeId, NodeStore, Tree, TypeStore, WithHashs};
+use hyperast::PrimInt;
+use num_traits::{cast, one, ToPrimitive};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// Above SIZE_THRESHOLD descendants (on both sides), ZS is considered too expensive.
+///
+/// GumTree uses 20 by default (`bu_minsize`)
+pub struct HybridBottomUpMatcher<
+    Dsrc,
+    Ddst,
+    HAST,
+    M: MonoMappingStore,
+    const SIZE_THRESHOLD: usize = 20,
+> {
+    internal: GreedyBottomUpMatcher<Dsrc, Ddst, HAST, M, SIZE_THRESHOLD>,
+}
+
+impl<Dsrc, Ddst, HAST: HyperAST, M: MonoMappingStore, const SIZE_THRESHOLD: usize>
+    Into<BottomUpMatcher<Dsrc, Ddst, HAST, M>>
+    for HybridBottomUpMatcher<Dsrc, Ddst, HAST, M, SIZE_THRESHOLD>
+{
+    fn into(self) -> BottomUpMatcher<Dsrc, Ddst, HAST, M> {
+        self.internal.internal
+    }
+}
+
+impl<
+        'a,
+        Dsrc: DecompressedTreeStore<HAST, M::Src>
+            + DecompressedWithParent<HAST, M::Src>
+            + PostOrder<HAST, M::Src>
+            + PostOrderIterable<HAST, M::Src>
+            + DecompressedFrom<HAST, Out = Dsrc>
+            + ContiguousDescendants<HAST, M::Src>
+            + POBorrowSlice<HAST, M::Src>,
+        Ddst: DecompressedTreeStore<HAST, M::Dst>
+            + DecompressedWithParent<HAST, M::Dst>
+            + PostOrder<HAST, M::Dst>
+            + PostOrderIterable<HAST, M::Dst>
+            + DecompressedFrom<HAST, Out = Ddst>
+            + ContiguousDescendants<HAST, M::Dst>
+            + POBorrowSlice<HAST, M::Dst>,
+        HAST: HyperAST + Copy,
+        M: MonoMappingStore + Default,
+        const SIZE_THRESHOLD: usize,
+    > HybridBottomUpMatcher<Dsrc, Ddst, HAST, M, SIZE_THRESHOLD>
+where
+    for<'t> <HAST as hyperast::types::AstLending<'t>>::RT: WithHashs,
+    <HAST::TS as TypeStore>::Ty: Copy + Send + Sync + Eq + Hash,
+    M::Src: PrimInt,
+    M::Dst: PrimInt,
+    HAST::Label: Eq,
+    HAST::IdN: Debug,
+    HAST::IdN: NodeId<IdN = HAST::IdN>,
+{
+    pub fn new(stores: HAST, src_arena: Dsrc, dst_arena: Ddst, mappings: M) -> Self {
+        Self {
+            internal: GreedyBottomUpMatcher::new(stores, src_arena, dst_arena, mappings),
+        }
+    }
+
+    pub fn match_it(
+        mapping: crate::matchers::Mapper<HAST, Dsrc, Ddst, M>,
+    ) -> crate::matchers::Mapper<HAST, Dsrc, Ddst, M> {
+        let mut matcher = Self::new(
+            mapping.hyperast,
+            mapping.mapping.src_arena,
+            mapping.mapping.dst_arena,
+            mapping.mapping.mappings,
+        );
+        let internal = &mut matcher.internal.internal;
+        internal
+            .mappings
+            .topit(internal.src_arena.len(), internal.dst_arena.len());
+        Self::execute(&mut matcher);
+        let internal = matcher.internal.internal;
+        crate::matchers::Mapper {
+            hyperast: mapping.hyperast,
+            mapping: crate::matchers::Mapping {
+                src_arena: internal.src_arena,
+                dst_arena: internal.dst_arena,
+                mappings: internal.mappings,
+            },
+        }
+    }
+
+    pub fn matchh(store: HAST, src: &'a HAST::IdN, dst: &'a HAST::IdN, mappings: M) -> Self {
+        let mut matcher = Self::new(
+            store,
+            Dsrc::decompress(store, src),
+            Ddst::decompress(store, dst),
+            mappings,
+        );
+        let internal = &mut matcher.internal.internal;
+        internal
+            .mappings
+            .topit(internal.src_arena.len(), internal.dst_arena.len());
+        Self::execute(&mut matcher);
+        matcher
+    }
+
+    pub fn execute(&mut self) {
+        let internal = &self.internal.internal;
+        assert_eq!(
+            // TODO move it inside the arena ...
+            internal.src_arena.root(),
+            cast::<_, M::Src>(internal.src_arena.len()).unwrap() - one()
+        );
+        assert!(internal.src_arena.len() > 0);
+        // WARN it is in postorder and it depends on decomp store
+        for a in internal.src_arena.iter_df_post::<true>() {
+            let internal = &self.internal.internal;
+            if internal.src_arena.parent(&a).is_none() {
+                // the root is handled after the loop
+                break;
+            }
+            if internal.mappings.is_src(&a) || !self.src_has_children(a) {
+                continue;
+            }
+            let candidates = internal.get_dst_candidates(&a);
+            let src_size = internal.src_arena.descendants_count(&a) as f64;
+            let mut best = None;
+            let mut max: f64 = -1.;
+            for cand in candidates {
+                let dst_size = internal.dst_arena.descendants_count(&cand) as f64;
+                let threshold = 1. / (1. + f64::ln(src_size + dst_size));
+                let sim = similarity_metrics::SimilarityMeasure::range(
+                    &internal.src_arena.descendants_range(&a),
+                    &internal.dst_arena.descendants_range(&cand),
+                    &internal.mappings,
+                )
+                .chawathe();
+                if sim > max && sim >= threshold {
+                    max = sim;
+                    best = Some(cand);
+                }
+            }
+            if let Some(best) = best {
+                self.last_chance_match(a, best);
+                self.internal.internal.mappings.link(a, best);
+            }
+        }
+        // for root
+        let internal = &mut self.internal.internal;
+        let (src, dst) = (internal.src_arena.root(), internal.dst_arena.root());
+        internal.mappings.link(src, dst);
+        self.last_chance_match(src, dst);
+    }
+
+    fn src_has_children(&self, src: M::Src) -> bool {
+        let internal = &self.internal.internal;
+        let r = internal
+            .stores
+            .node_store()
+            .resolve(&internal.src_arena.original(&src))
+            .has_children();
+        debug_assert_eq!(
+            r,
+            internal.src_arena.lld(&src) < src,
+            "{:?} {:?}",
+            internal.src_arena.lld(&src),
+            src.to_usize()
+        );
+        r
+    }
+
+    /// Optimal matching for small subtrees, otherwise lcs and histogram matching like in the simple matcher.
+    pub(crate) fn last_chance_match(&mut self, src: M::Src, dst: M::Dst) {
+        let internal = &self.internal.internal;
+        let src_s = internal.src_arena.descendants_count(&src);
+        let dst_s = internal.dst_arena.descendants_count(&dst);
+        if src_s < SIZE_THRESHOLD || dst_s < SIZE_THRESHOLD {
+            self.internal.last_chance_match_zs(src, dst);
+        } else {
+            self.internal
+                .internal
+                .last_chance_match_histogram(&src, &dst);
+        }
+    }
+}
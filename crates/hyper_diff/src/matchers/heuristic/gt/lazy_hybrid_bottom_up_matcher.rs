@@ -0,0 +1,186 @@
+//! makes hybrid_bottom_up_matcher lazy
+//!
+//! ZS recovery is shared with lazy2_greedy_bottom_up_matcher,
+//! the fallback for big subtrees only does the histogram matching,
+//! as it only needs to decompress children.
+use super::lazy2_greedy_bottom_up_matcher::GreedyBottomUpMatcher;
+use crate::decompressed_tree_store::{
+    ContiguousDescendants, DecompressedTreeStore, DecompressedWithParent, LazyDecompressed,
+    LazyDecompressedTreeStore, LazyPOBorrowSlice, PostOrder, PostOrderIterable, Shallow,
+    ShallowDecompressedTreeStore,
+};
+use crate::matchers::mapping_store::MonoMappingStore;
+use crate::matchers::{similarity_metrics, Mapper};
+use hyperast::types::{HyperAST, NodeId, NodeStore, Tree, TypeStore, WithHashs, WithStats};
+use hyperast::PrimInt;
+use num_traits::{cast, one};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::{fmt::Debug, marker::PhantomData};
+
+/// Above SIZE_THRESHOLD descendants (on both sides), ZS is considered too expensive.
+pub struct LazyHybridBottomUpMatcher<
+    Dsrc,
+    Ddst,
+    HAST: HyperAST + Copy,
+    M: MonoMappingStore,
+    MZs: MonoMappingStore = M,
+    const SIZE_THRESHOLD: usize = 20,
+> {
+    internal: Mapper<HAST, Dsrc, Ddst, M>,
+    _phantom: PhantomData<*const MZs>,
+}
+
+impl<
+        Dsrc: LazyDecompressed<M::Src>,
+        Ddst: LazyDecompressed<M::Dst>,
+        HAST,
+        M,
+        MZs,
+        const SIZE_THRESHOLD: usize,
+    > LazyHybridBottomUpMatcher<Dsrc, Ddst, HAST, M, MZs, SIZE_THRESHOLD>
+where
+    for<'t> <HAST as hyperast::types::AstLending<'t>>::RT: Tree + WithHashs + WithStats,
+    <HAST::TS as TypeStore>::Ty: Copy + Eq + Hash,
+    HAST::IdN: Clone + Eq + Debug,
+    Dsrc::IdD: PrimInt,
+    Ddst::IdD: PrimInt,
+    M::Src: PrimInt,
+    M::Dst: PrimInt,
+    MZs: MonoMappingStore<Src = Dsrc::IdD, Dst = <Ddst as LazyDecompressed<M::Dst>>::IdD> + Default,
+    HAST: HyperAST + Copy,
+    M: MonoMappingStore,
+    Dsrc: DecompressedTreeStore<HAST, Dsrc::IdD, M::Src>
+        + DecompressedWithParent<HAST, Dsrc::IdD>
+        + PostOrder<HAST, Dsrc::IdD, M::Src>
+        + PostOrderIterable<HAST, Dsrc::IdD, M::Src>
+        + ContiguousDescendants<HAST, Dsrc::IdD, M::Src>
+        + LazyPOBorrowSlice<HAST, Dsrc::IdD, M::Src>
+        + ShallowDecompressedTreeStore<HAST, Dsrc::IdD, M::Src>
+        + LazyDecompressedTreeStore<HAST, M::Src>,
+    Ddst: DecompressedTreeStore<HAST, Ddst::IdD, M::Dst>
+        + DecompressedWithParent<HAST, Ddst::IdD>
+        + PostOrder<HAST, Ddst::IdD, M::Dst>
+        + PostOrderIterable<HAST, Ddst::IdD, M::Dst>
+        + ContiguousDescendants<HAST, Ddst::IdD, M::Dst>
+        + LazyPOBorrowSlice<HAST, Ddst::IdD, M::Dst>
+        + ShallowDecompressedTreeStore<HAST, Ddst::IdD, M::Dst>
+        + LazyDecompressedTreeStore<HAST, M::Dst>
+        + LazyDecompressed<M::Dst>,
+    HAST::Label: Eq,
+    HAST::IdN: NodeId<IdN = HAST::IdN>,
+{
+    pub fn match_it(
+        mapping: crate::matchers::Mapper<HAST, Dsrc, Ddst, M>,
+    ) -> crate::matchers::Mapper<HAST, Dsrc, Ddst, M> {
+        let mut matcher = Self {
+            internal: mapping,
+            _phantom: PhantomData,
+        };
+        matcher.internal.mapping.mappings.topit(
+            matcher.internal.mapping.src_arena.len(),
+            matcher.internal.mapping.dst_arena.len(),
+        );
+        Self::execute(&mut matcher.internal);
+        matcher.internal
+    }
+
+    pub fn execute(internal: &mut Mapper<HAST, Dsrc, Ddst, M>) {
+        assert_eq!(
+            // TODO move it inside the arena ...
+            internal.src_arena.root(),
+            cast::<_, M::Src>(internal.src_arena.len()).unwrap() - one()
+        );
+        assert!(internal.src_arena.len() > 0);
+        // WARN it is in postorder and it depends on decomp store
+        for a in internal.src_arena.iter_df_post::<false>() {
+            if internal.mappings.is_src(&a) {
+                continue;
+            }
+            let a = internal.mapping.src_arena.decompress_to(&a);
+            let o = internal.src_arena.original(&a);
+            if !internal.hyperast.node_store().resolve(&o).has_children() {
+                continue;
+            }
+            let candidates = internal.get_dst_candidates_lazily(&a);
+            let src_size = internal.src_arena.descendants_count(&a) as f64;
+            let mut best = None;
+            let mut max: f64 = -1.;
+            for cand in candidates {
+                let dst_size = internal.dst_arena.descendants_count(&cand) as f64;
+                let threshold = 1. / (1. + f64::ln(src_size + dst_size));
+                let sim = similarity_metrics::SimilarityMeasure::range(
+                    &internal.src_arena.descendants_range(&a),
+                    &internal.dst_arena.descendants_range(&cand),
+                    &internal.mappings,
+                )
+                .chawathe();
+                if sim > max && sim >= threshold {
+                    max = sim;
+                    best = Some(cand);
+                }
+            }
+            if let Some(best) = best {
+                Self::last_chance_match(internal, a, best);
+                internal.mappings.link(*a.shallow(), *best.shallow());
+            }
+        }
+        // for root
+        internal.mapping.mappings.link(
+            internal.mapping.src_arena.root(),
+            internal.mapping.dst_arena.root(),
+        );
+        let src = internal.src_arena.starter();
+        let dst = internal.dst_arena.starter();
+        Self::last_chance_match(internal, src, dst);
+    }
+
+    /// Optimal matching for small subtrees, otherwise histogram matching.
+    pub(crate) fn last_chance_match(
+        internal: &mut Mapper<HAST, Dsrc, Ddst, M>,
+        src: Dsrc::IdD,
+        dst: Ddst::IdD,
+    ) {
+        let src_s = internal.src_arena.descendants_count(&src);
+        let dst_s = internal.dst_arena.descendants_count(&dst);
+        if src_s < SIZE_THRESHOLD || dst_s < SIZE_THRESHOLD {
+            GreedyBottomUpMatcher::<Dsrc, Ddst, HAST, M, MZs, SIZE_THRESHOLD>::last_chance_match_zs(
+                internal, src, dst,
+            );
+        } else {
+            Self::histogram_matching(internal, src, dst);
+        }
+    }
+
+    /// Matches children having a type unique among the children of both src and dst
+    fn histogram_matching(
+        internal: &mut Mapper<HAST, Dsrc, Ddst, M>,
+        src: Dsrc::IdD,
+        dst: Ddst::IdD,
+    ) {
+        let stores = internal.hyperast;
+        let mut src_histogram: HashMap<<HAST::TS as TypeStore>::Ty, Vec<Dsrc::IdD>> =
+            HashMap::new();
+        for c in internal.mapping.src_arena.decompress_children(&src) {
+            let t = stores.resolve_type(&internal.src_arena.original(&c));
+            src_histogram.entry(t).or_default().push(c);
+        }
+        let mut dst_histogram: HashMap<<HAST::TS as TypeStore>::Ty, Vec<Ddst::IdD>> =
+            HashMap::new();
+        for c in internal.mapping.dst_arena.decompress_children(&dst) {
+            let t = stores.resolve_type(&internal.dst_arena.original(&c));
+            dst_histogram.entry(t).or_default().push(c);
+        }
+        for (t, srcs) in src_histogram {
+            let Some(dsts) = dst_histogram.get(&t) else {
+                continue;
+            };
+            if let ([t1], [t2]) = (&srcs[..], &dsts[..]) {
+                let (t1, t2) = (*t1, *t2);
+                if (internal.mappings).link_if_both_unmapped(*t1.shallow(), *t2.shallow()) {
+                    Self::last_chance_match(internal, t1, t2);
+                }
+            }
+        }
+    }
+}
@@ -3,6 +3,7 @@ use hyperast::types::{NodeId, NodeStore, WithChildren, Childrn};
 pub mod bottom_up_matcher;
 pub mod greedy_bottom_up_matcher;
 pub mod greedy_subtree_matcher;
+pub mod hybrid_bottom_up_matcher;
 #[allow(unused)] // TODO finish simple bottom up matcher
 pub mod simple_bottom_up_matcher;
 
@@ -12,6 +13,7 @@ pub mod lazy2_greedy_subtree_matcher;
 pub mod lazy_bottom_up_matcher;
 pub mod lazy_greedy_bottom_up_matcher;
 pub mod lazy_greedy_subtree_matcher;
+pub mod lazy_hybrid_bottom_up_matcher;
 // pub mod simple_bottom_up_matcher2;
 
 pub fn size<'a, IdC: Clone + NodeId<IdN = IdC>, S>(store: &'a S, x: &IdC) -> usize
@@ -0,0 +1,485 @@
+//! Tree edit distance with left and right path decompositions
+//!
+//! A decomposition strategy is first computed for every pair of subtrees,
+//! then the general tree edit distance algorithm (GTED, Pawlik and Augsten, 2011)
+//! follows it to fill the distances between all pairs of subtrees.
+//! Decompositions are done along leftmost paths (like Zhang-Shasha) or rightmost paths,
+//! mappings are backtracked along the same paths.
+//! The distance stays optimal, and the cost model is the one of [`super::zs`],
+//! and right-branching trees are handled far better than with Zhang-Shasha.
+//!
+//! Unlike RTED and APTED, heavy paths are not considered,
+//! so the worst case stays O(n⁴) like with Zhang-Shasha,
+//! e.g. on pairs of trees that branch both left and right.
+
+use crate::decompressed_tree_store::{PostOrder, ShallowDecompressedTreeStore};
+use crate::matchers::mapping_store::MonoMappingStore;
+use hyperast::types::{DecompressedFrom, HyperAST};
+use hyperast::PrimInt;
+use num_traits::{cast, ToPrimitive};
+
+use super::zs::update_cost;
+
+const DEL: f64 = 1.0;
+const INS: f64 = 1.0;
+
+pub struct LrTedMatcher<M, SD, DD = SD> {
+    pub mappings: M,
+    pub src_arena: SD,
+    pub dst_arena: DD,
+    /// the tree edit distance between both roots
+    pub distance: f64,
+}
+
+impl<SD, DD, M: MonoMappingStore + Default> LrTedMatcher<M, SD, DD> {
+    pub fn matchh<HAST>(stores: HAST, src: HAST::IdN, dst: HAST::IdN) -> Self
+    where
+        M::Src: PrimInt,
+        M::Dst: PrimInt,
+        SD: PostOrder<HAST, M::Src> + DecompressedFrom<HAST, Out = SD>,
+        DD: PostOrder<HAST, M::Dst> + DecompressedFrom<HAST, Out = DD>,
+        HAST: HyperAST + Copy,
+        HAST::Label: Eq,
+    {
+        let src_arena = SD::decompress(stores, &src);
+        let dst_arena = DD::decompress(stores, &dst);
+        let mut mappings = M::default();
+        mappings.topit(src_arena.len(), dst_arena.len());
+        let mut ted = LrTed::new(stores, &src_arena, &dst_arena);
+        let distance = ted.compute_dist();
+        ted.compute_mappings(&mut mappings);
+        Self {
+            src_arena,
+            dst_arena,
+            mappings,
+            distance,
+        }
+    }
+
+    /// Same signature as [`super::zs::ZsMatcher::match_with`], so it can replace it in bottom-up matchers
+    pub fn match_with<HAST>(stores: HAST, src_arena: SD, dst_arena: DD) -> M
+    where
+        M::Src: PrimInt,
+        M::Dst: PrimInt,
+        SD: PostOrder<HAST, M::Src>,
+        DD: PostOrder<HAST, M::Dst>,
+        HAST: HyperAST + Copy,
+        HAST::Label: Eq,
+    {
+        let mut mappings = M::default();
+        mappings.topit(src_arena.len(), dst_arena.len());
+        let mut ted = LrTed::new(stores, &src_arena, &dst_arena);
+        ted.compute_dist();
+        ted.compute_mappings(&mut mappings);
+        mappings
+    }
+}
+
+/// The path along which a pair of subtrees is decomposed
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum Path {
+    LeftSrc,
+    RightSrc,
+    LeftDst,
+    RightDst,
+}
+
+/// Post-order indexes of a tree, in both directions.
+///
+/// Nodes are identified by their left-to-right post-order position,
+/// the right-to-left post-order is the one of the mirrored tree.
+struct Index<IdN> {
+    nodes: Vec<IdN>,
+    /// leftmost leaf descendant
+    lld: Vec<usize>,
+    /// from left to right
+    children: Vec<Vec<usize>>,
+    first_child: Vec<bool>,
+    last_child: Vec<bool>,
+    /// right-to-left post-order position of each node
+    rpo: Vec<usize>,
+    /// node at each right-to-left post-order position
+    rpo_node: Vec<usize>,
+    /// rightmost leaf descendant, as a right-to-left post-order position,
+    /// indexed by right-to-left post-order position
+    rld: Vec<usize>,
+    /// number of relevant subforests of the left decomposition of each subtree
+    /// i.e. the sum of the sizes of its left keyroots
+    left_cost: Vec<u64>,
+    /// same for right decompositions
+    right_cost: Vec<u64>,
+}
+
+impl<IdN> Index<IdN> {
+    fn new<HAST, IdD>(arena: &impl PostOrder<HAST, IdD>) -> Self
+    where
+        HAST: HyperAST<IdN = IdN> + Copy,
+        IdD: PrimInt,
+    {
+        let n = arena.len();
+        let mut nodes = Vec::with_capacity(n);
+        let mut lld = Vec::with_capacity(n);
+        for x in 0..n {
+            let x: IdD = cast(x).unwrap();
+            nodes.push(arena.tree(&x));
+            lld.push(arena.lld(&x).to_usize().unwrap());
+        }
+        // in post-order, the last child of x is just before x,
+        // and the previous sibling of a child c is just before the lld of c
+        let mut children = vec![vec![]; n];
+        let mut first_child = vec![false; n];
+        let mut last_child = vec![false; n];
+        for x in 0..n {
+            let cs: &mut Vec<usize> = &mut children[x];
+            let mut c = x;
+            while c > lld[x] {
+                cs.push(c - 1);
+                c = lld[c - 1];
+            }
+            cs.reverse();
+            if let (Some(&first), Some(&last)) = (cs.first(), cs.last()) {
+                first_child[first] = true;
+                last_child[last] = true;
+            }
+        }
+        // the right-to-left post-order is the reverse of the left-to-right pre-order
+        let mut pre = Vec::with_capacity(n);
+        let mut stack: Vec<usize> = n.checked_sub(1).into_iter().collect();
+        while let Some(x) = stack.pop() {
+            pre.push(x);
+            stack.extend(children[x].iter().rev());
+        }
+        let rpo_node: Vec<usize> = pre.into_iter().rev().collect();
+        let mut rpo = vec![0; n];
+        for (k, &x) in rpo_node.iter().enumerate() {
+            rpo[x] = k;
+        }
+        let mut rightmost_leaf = vec![0; n];
+        let mut left_cost = vec![0; n];
+        let mut right_cost = vec![0; n];
+        for x in 0..n {
+            let cs = &children[x];
+            rightmost_leaf[x] = cs.last().map_or(x, |&c| rightmost_leaf[c]);
+            let size = (x - lld[x] + 1) as u64;
+            let sum: u64 = cs.iter().map(|&c| left_cost[c]).sum();
+            left_cost[x] = size + sum - cs.first().map_or(0, |&c| (c - lld[c] + 1) as u64);
+            let sum: u64 = cs.iter().map(|&c| right_cost[c]).sum();
+            right_cost[x] = size + sum - cs.last().map_or(0, |&c| (c - lld[c] + 1) as u64);
+        }
+        let rld = rpo_node.iter().map(|&x| rpo[rightmost_leaf[x]]).collect();
+        Self {
+            nodes,
+            lld,
+            children,
+            first_child,
+            last_child,
+            rpo,
+            rpo_node,
+            rld,
+            left_cost,
+            right_cost,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn size(&self, x: usize) -> u64 {
+        (x - self.lld[x] + 1) as u64
+    }
+
+    /// position of node x in the chosen post-order
+    fn pos(&self, right: bool, x: usize) -> usize {
+        if right {
+            self.rpo[x]
+        } else {
+            x
+        }
+    }
+
+    /// node at position k in the chosen post-order
+    fn at(&self, right: bool, k: usize) -> usize {
+        if right {
+            self.rpo_node[k]
+        } else {
+            k
+        }
+    }
+
+    /// position of the first leaf of the subtree at position k, in the chosen post-order
+    fn first_leaf(&self, right: bool, k: usize) -> usize {
+        if right {
+            self.rld[k]
+        } else {
+            self.lld[k]
+        }
+    }
+
+    /// Keyroots of the subtree x in post-order, i.e. the roots of the paths of its decomposition
+    fn keyroots(&self, right: bool, x: usize) -> Vec<usize> {
+        let k = self.pos(right, x);
+        let mut r: Vec<usize> = (self.first_leaf(right, k)..k)
+            .map(|k| self.at(right, k))
+            .filter(|&y| {
+                if right {
+                    !self.last_child[y]
+                } else {
+                    !self.first_child[y]
+                }
+            })
+            .collect();
+        r.push(x);
+        r
+    }
+
+    /// Roots of the subtrees hanging off the leftmost (or rightmost) path of x
+    fn off_path(&self, right: bool, x: usize) -> Vec<usize> {
+        let mut r = vec![];
+        let mut x = x;
+        while let Some(next) = if right {
+            self.children[x].last()
+        } else {
+            self.children[x].first()
+        } {
+            r.extend(self.children[x].iter().copied().filter(|c| c != next));
+            x = *next;
+        }
+        r
+    }
+}
+
+pub(crate) struct LrTed<HAST: HyperAST> {
+    stores: HAST,
+    src: Index<HAST::IdN>,
+    dst: Index<HAST::IdN>,
+    /// distances between all pairs of subtrees, row-major on src nodes
+    delta: Vec<f64>,
+    /// the paths followed by [`LrTed::compute_dist`], kept to backtrack mappings
+    strategy: Vec<Path>,
+}
+
+impl<HAST: HyperAST + Copy> LrTed<HAST>
+where
+    HAST::Label: Eq,
+{
+    pub(crate) fn new<IdS: PrimInt, IdD: PrimInt>(
+        stores: HAST,
+        src_arena: &impl PostOrder<HAST, IdS>,
+        dst_arena: &impl PostOrder<HAST, IdD>,
+    ) -> Self {
+        let src = Index::new(src_arena);
+        let dst = Index::new(dst_arena);
+        let delta = vec![0.0; src.len() * dst.len()];
+        Self {
+            stores,
+            src,
+            dst,
+            delta,
+            strategy: vec![],
+        }
+    }
+
+    fn compute_dist(&mut self) -> f64 {
+        let (n, m) = (self.src.len(), self.dst.len());
+        if n == 0 || m == 0 {
+            return (n as f64) * DEL + (m as f64) * INS;
+        }
+        let strategy = self.compute_strategy();
+        enum Task {
+            Decompose(usize, usize),
+            SinglePath(usize, usize),
+        }
+        let mut tasks = vec![Task::Decompose(n - 1, m - 1)];
+        while let Some(task) = tasks.pop() {
+            match task {
+                Task::Decompose(v, w) => {
+                    // the single path function needs the distances of all subtrees hanging off the path
+                    tasks.push(Task::SinglePath(v, w));
+                    match strategy[v * m + w] {
+                        Path::LeftSrc | Path::RightSrc => {
+                            let right = strategy[v * m + w] == Path::RightSrc;
+                            let off = self.src.off_path(right, v);
+                            tasks.extend(off.into_iter().map(|v| Task::Decompose(v, w)));
+                        }
+                        Path::LeftDst | Path::RightDst => {
+                            let right = strategy[v * m + w] == Path::RightDst;
+                            let off = self.dst.off_path(right, w);
+                            tasks.extend(off.into_iter().map(|w| Task::Decompose(v, w)));
+                        }
+                    }
+                }
+                Task::SinglePath(v, w) => match strategy[v * m + w] {
+                    Path::LeftSrc | Path::RightSrc => {
+                        let right = strategy[v * m + w] == Path::RightSrc;
+                        for w in self.dst.keyroots(right, w) {
+                            self.forest_dist(right, v, w);
+                        }
+                    }
+                    Path::LeftDst | Path::RightDst => {
+                        let right = strategy[v * m + w] == Path::RightDst;
+                        for v in self.src.keyroots(right, v) {
+                            self.forest_dist(right, v, w);
+                        }
+                    }
+                },
+            }
+        }
+        self.strategy = strategy;
+        self.delta[n * m - 1]
+    }
+
+    /// For each pair of subtrees, chooses the path minimizing the number of relevant subproblems,
+    /// counting the ones of the subtrees hanging off the path.
+    /// Row-major on src nodes, like the distances.
+    pub(crate) fn compute_strategy(&self) -> Vec<Path> {
+        let (src, dst) = (&self.src, &self.dst);
+        let (n, m) = (src.len(), dst.len());
+        let mut cost = vec![0u64; n * m];
+        // cost of the subtrees hanging off the leftmost and rightmost paths of src subtrees
+        let mut off_left_src = vec![0u64; n * m];
+        let mut off_right_src = vec![0u64; n * m];
+        // same for dst subtrees, only the current row is needed
+        let mut off_left_dst = vec![0u64; m];
+        let mut off_right_dst = vec![0u64; m];
+        let mut strategy = vec![Path::LeftSrc; n * m];
+        for v in 0..n {
+            for w in 0..m {
+                let cs = &src.children[v];
+                if let (Some(&first), Some(&last)) = (cs.first(), cs.last()) {
+                    let sum: u64 = cs.iter().map(|&c| cost[c * m + w]).sum();
+                    off_left_src[v * m + w] =
+                        off_left_src[first * m + w] + sum - cost[first * m + w];
+                    off_right_src[v * m + w] =
+                        off_right_src[last * m + w] + sum - cost[last * m + w];
+                }
+                let cs = &dst.children[w];
+                if let (Some(&first), Some(&last)) = (cs.first(), cs.last()) {
+                    let sum: u64 = cs.iter().map(|&c| cost[v * m + c]).sum();
+                    off_left_dst[w] = off_left_dst[first] + sum - cost[v * m + first];
+                    off_right_dst[w] = off_right_dst[last] + sum - cost[v * m + last];
+                } else {
+                    off_left_dst[w] = 0;
+                    off_right_dst[w] = 0;
+                }
+                let candidates = [
+                    (
+                        Path::LeftSrc,
+                        src.size(v) * dst.left_cost[w] + off_left_src[v * m + w],
+                    ),
+                    (
+                        Path::RightSrc,
+                        src.size(v) * dst.right_cost[w] + off_right_src[v * m + w],
+                    ),
+                    (
+                        Path::LeftDst,
+                        dst.size(w) * src.left_cost[v] + off_left_dst[w],
+                    ),
+                    (
+                        Path::RightDst,
+                        dst.size(w) * src.right_cost[v] + off_right_dst[w],
+                    ),
+                ];
+                let (path, c) = candidates.into_iter().min_by_key(|(_, c)| *c).unwrap();
+                strategy[v * m + w] = path;
+                cost[v * m + w] = c;
+            }
+        }
+        strategy
+    }
+
+    /// Zhang-Shasha forest distance between the subtrees v and w, in the chosen post-order.
+    ///
+    /// Fills the distances between nodes on the leftmost (or rightmost) paths of v and w,
+    /// the other ones must already be computed.
+    /// Returns the forest distance matrix, row-major.
+    fn forest_dist(&mut self, right: bool, v: usize, w: usize) -> Vec<f64> {
+        let m = self.dst.len();
+        let (kv, kw) = (self.src.pos(right, v), self.dst.pos(right, w));
+        let (a, b) = (
+            self.src.first_leaf(right, kv),
+            self.dst.first_leaf(right, kw),
+        );
+        let cols = kw - b + 2;
+        let mut fd = vec![0.0; (kv - a + 2) * cols];
+        for x in a..=kv {
+            fd[(x - a + 1) * cols] = fd[(x - a) * cols] + DEL;
+        }
+        for y in b..=kw {
+            fd[y - b + 1] = fd[y - b] + INS;
+        }
+        for x in a..=kv {
+            let vx = self.src.at(right, x);
+            let lx = self.src.first_leaf(right, x);
+            for y in b..=kw {
+                let wy = self.dst.at(right, y);
+                let ly = self.dst.first_leaf(right, y);
+                let (r, c) = (x - a + 1, y - b + 1);
+                let d = f64::min(fd[(r - 1) * cols + c] + DEL, fd[r * cols + c - 1] + INS);
+                fd[r * cols + c] = if lx == a && ly == b {
+                    let upd = update_cost(self.stores, &self.src.nodes[vx], &self.dst.nodes[wy]);
+                    let d = f64::min(d, fd[(r - 1) * cols + c - 1] + upd);
+                    self.delta[vx * m + wy] = d;
+                    d
+                } else {
+                    f64::min(d, fd[(lx - a) * cols + ly - b] + self.delta[vx * m + wy])
+                };
+            }
+        }
+        fd
+    }
+
+    /// Backtracks the forest distances of mapped subtrees, like [`super::zs`] does,
+    /// along the paths chosen by [`LrTed::compute_dist`] so that backtracking has the same cost.
+    fn compute_mappings<M: MonoMappingStore>(&mut self, mappings: &mut M)
+    where
+        M::Src: PrimInt,
+        M::Dst: PrimInt,
+    {
+        let (n, m) = (self.src.len(), self.dst.len());
+        if n == 0 || m == 0 {
+            return;
+        }
+        let mut tree_pairs = vec![(n - 1, m - 1)];
+        while let Some((v, w)) = tree_pairs.pop() {
+            let right = matches!(self.strategy[v * m + w], Path::RightSrc | Path::RightDst);
+            let fd = self.forest_dist(right, v, w);
+            let (kv, kw) = (self.src.pos(right, v), self.dst.pos(right, w));
+            let (a, b) = (
+                self.src.first_leaf(right, kv),
+                self.dst.first_leaf(right, kw),
+            );
+            let cols = kw - b + 2;
+            let (mut r, mut c) = (kv - a + 1, kw - b + 1);
+            while r > 0 || c > 0 {
+                if r > 0 && fd[(r - 1) * cols + c] + DEL == fd[r * cols + c] {
+                    // deleted from src
+                    r -= 1;
+                } else if c > 0 && fd[r * cols + c - 1] + INS == fd[r * cols + c] {
+                    // inserted in dst
+                    c -= 1;
+                } else {
+                    // positions in the chosen post-order
+                    let (x, y) = (a + r - 1, b + c - 1);
+                    let (lx, ly) = (self.src.first_leaf(right, x), self.dst.first_leaf(right, y));
+                    let (vx, wy) = (self.src.at(right, x), self.dst.at(right, y));
+                    if lx == a && ly == b {
+                        // both subforests are trees, map their roots
+                        let t_src = self.stores.resolve_type(&self.src.nodes[vx]);
+                        let t_dst = self.stores.resolve_type(&self.dst.nodes[wy]);
+                        if t_src == t_dst {
+                            mappings.link(cast(vx).unwrap(), cast(wy).unwrap());
+                        }
+                        r -= 1;
+                        c -= 1;
+                    } else {
+                        tree_pairs.push((vx, wy));
+                        // continue with the forest before the subtree pair
+                        r = lx - a;
+                        c = ly - b;
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1 +1,2 @@
+pub mod lr_ted;
 pub mod zs;
@@ -0,0 +1,117 @@
+use hyperast::store::SimpleStores;
+use hyperast::test_utils::simple_tree::{vpair_to_stores, SimpleTree, TStore, Tree, LS, NS};
+use hyperast::types::HyperAST;
+
+use crate::{
+    actions::Actions,
+    algorithms,
+    decompressed_tree_store::{CompletePostOrder, ShallowDecompressedTreeStore},
+    matchers::{
+        heuristic::gt::{
+            greedy_bottom_up_matcher::GreedyBottomUpMatcher,
+            greedy_subtree_matcher::GreedySubtreeMatcher,
+            hybrid_bottom_up_matcher::HybridBottomUpMatcher,
+        },
+        mapping_store::{DefaultMultiMappingStore, MappingStore, VecStore},
+        Decompressible, Mapper,
+    },
+    tests::tree,
+};
+
+type Stores = SimpleStores<TStore, NS<Tree>, LS<u16>>;
+type CDS<HAST> = Decompressible<HAST, CompletePostOrder<u16, u32>>;
+
+/// A container whose leaves are all renamed, next to an unchanged subtree,
+/// among enough unchanged subtrees for the roots to be too big for Zhang-Shasha.
+///
+/// Only 3 of the 7 descendants of the container are mapped by the subtree matcher,
+/// below the similarity threshold of the greedy matcher (1/2),
+/// but above the one of the hybrid matcher for such a small container (1/(1+ln(14))).
+fn example_renamed_leaves() -> (SimpleTree<u8>, SimpleTree<u8>) {
+    let container = |leaves: [&str; 4]| {
+        tree!(1, "a"; [
+            tree!(2, leaves[0]),
+            tree!(2, leaves[1]),
+            tree!(2, leaves[2]),
+            tree!(2, leaves[3]),
+            tree!(3, "s"; [tree!(4, "p"), tree!(4, "q")]),
+        ])
+    };
+    let pad = |l: [&str; 4]| tree!(5, l[0]; [tree!(6, l[1]), tree!(6, l[2]), tree!(6, l[3])]);
+    let root = |leaves| {
+        tree!(0, "r"; [
+            container(leaves),
+            pad(["b", "b0", "b1", "b2"]),
+            pad(["c", "c0", "c1", "c2"]),
+            pad(["d", "d0", "d1", "d2"]),
+            pad(["e", "e0", "e1", "e2"]),
+            pad(["f", "f0", "f1", "f2"]),
+        ])
+    };
+    (
+        root(["x1", "x2", "x3", "x4"]),
+        root(["y1", "y2", "y3", "y4"]),
+    )
+}
+
+/// Counts the renamed leaves mapped to their counterpart, and checks the container is mapped.
+fn renamed_leaves_mapped(
+    mapper: &Mapper<&Stores, CDS<&Stores>, CDS<&Stores>, VecStore<u32>>,
+) -> usize {
+    let src_arena = &mapper.mapping.src_arena;
+    let dst_arena = &mapper.mapping.dst_arena;
+    let mappings = &mapper.mapping.mappings;
+    let from_src = |path: &[u8]| src_arena.child(&src_arena.root(), path);
+    let from_dst = |path: &[u8]| dst_arena.child(&dst_arena.root(), path);
+    let container = mappings.has(&from_src(&[0]), &from_dst(&[0]));
+    let subtree = mappings.has(&from_src(&[0, 4]), &from_dst(&[0, 4]));
+    assert!(subtree, "the unchanged subtree must be mapped");
+    let leaves = (0..4u8)
+        .filter(|i| mappings.has(&from_src(&[0, *i]), &from_dst(&[0, *i])))
+        .count();
+    assert!(
+        leaves == 0 || container,
+        "leaves mapped without their parent"
+    );
+    leaves
+}
+
+#[test]
+fn test_hybrid_recovers_renamed_leaves() {
+    let (stores, src, dst) = vpair_to_stores(example_renamed_leaves());
+    let mapper: Mapper<_, CDS<_>, CDS<_>, VecStore<u32>> =
+        (&stores).decompress_pair(&src, &dst).into();
+    let mapper =
+        GreedySubtreeMatcher::<_, _, _, _>::match_it::<DefaultMultiMappingStore<_>>(mapper);
+    let mapper = HybridBottomUpMatcher::<_, _, _, _>::match_it(mapper);
+    assert_eq!(renamed_leaves_mapped(&mapper), 4);
+}
+
+#[test]
+fn test_greedy_misses_renamed_leaves() {
+    let (stores, src, dst) = vpair_to_stores(example_renamed_leaves());
+    let mapper: Mapper<_, CDS<_>, CDS<_>, VecStore<u32>> =
+        (&stores).decompress_pair(&src, &dst).into();
+    let mapper =
+        GreedySubtreeMatcher::<_, _, _, _>::match_it::<DefaultMultiMappingStore<_>>(mapper);
+    // same size threshold as the hybrid matcher, so that ZS is not applied on the roots
+    let mapper = GreedyBottomUpMatcher::<_, _, _, _, 20>::match_it(mapper);
+    assert_eq!(renamed_leaves_mapped(&mapper), 0);
+}
+
+#[test]
+fn test_hybrid_diff() {
+    let (stores, src, dst) = vpair_to_stores(example_renamed_leaves());
+    let diff = algorithms::hybrid::diff(&stores, &src, &dst);
+    assert_eq!(renamed_leaves_mapped(&diff.mapper), 4);
+    // only the 4 renamings
+    assert_eq!(diff.actions.map(|a| a.len()), Some(4));
+}
+
+#[test]
+fn test_hybrid_diff_lazy() {
+    let (stores, src, dst) = vpair_to_stores(example_renamed_leaves());
+    let lazy = algorithms::hybrid::diff_lazy(&stores, &src, &dst);
+    assert_eq!(renamed_leaves_mapped(&lazy.mapper), 4);
+    assert_eq!(lazy.actions.map(|a| a.len()), Some(4));
+}
@@ -0,0 +1,102 @@
+use crate::{
+    decompressed_tree_store::{ShallowDecompressedTreeStore, SimpleZsTree},
+    matchers::{
+        mapping_store::{DefaultMappingStore, MappingStore},
+        optimal::{
+            lr_ted::{LrTed, LrTedMatcher, Path},
+            zs::ZsMatcher,
+        },
+        Decompressible,
+    },
+    tests::{
+        examples::{example_gt_java_code, example_gt_slides, example_zs_paper},
+        tree,
+    },
+};
+
+use hyperast::test_utils::simple_tree::{vpair_to_stores, SimpleTree};
+use hyperast::types::DecompressedFrom;
+
+/// Both algorithms are optimal, mappings backtracked along right paths
+/// could only differ on ties between optimal edit scripts, none in these examples.
+fn assert_same_as_zs(example: (SimpleTree<u8>, SimpleTree<u8>)) {
+    let (stores, src, dst) = vpair_to_stores(example);
+    let zs = ZsMatcher::<DefaultMappingStore<u16>, Decompressible<_, SimpleZsTree<_, u16>>>::matchh(
+        &stores, src, dst,
+    );
+    let ted =
+        LrTedMatcher::<DefaultMappingStore<u16>, Decompressible<_, SimpleZsTree<_, u16>>>::matchh(
+            &stores, src, dst,
+        );
+    assert_eq!(zs.mappings.len(), ted.mappings.len());
+    assert_eq!(zs.mappings.src_to_dst, ted.mappings.src_to_dst);
+}
+
+#[test]
+fn test_zs_paper() {
+    assert_same_as_zs(example_zs_paper());
+}
+
+#[test]
+fn test_with_custom_example() {
+    assert_same_as_zs(example_gt_java_code());
+}
+
+#[test]
+fn test_with_slide_example() {
+    let (stores, src, dst) = vpair_to_stores(example_gt_slides());
+    let mapper =
+        LrTedMatcher::<DefaultMappingStore<u16>, Decompressible<_, SimpleZsTree<_, u16>>>::matchh(
+            &stores, src, dst,
+        );
+    let LrTedMatcher {
+        src_arena,
+        dst_arena,
+        mappings,
+        ..
+    } = mapper;
+    let src = &src_arena.root();
+    let dst = &dst_arena.root();
+    assert_eq!(5, mappings.src_to_dst.iter().filter(|x| **x != 0).count());
+    assert!(mappings.has(src, dst));
+    assert!(mappings.has(&src_arena.child(src, &[0, 0]), &dst_arena.child(dst, &[0])));
+    assert!(mappings.has(
+        &src_arena.child(src, &[0, 0, 0]),
+        &dst_arena.child(dst, &[0, 0])
+    ));
+    assert!(mappings.has(
+        &src_arena.child(src, &[0, 1]),
+        &dst_arena.child(dst, &[1, 0])
+    ));
+    assert!(mappings.has(&src_arena.child(src, &[0, 2]), &dst_arena.child(dst, &[2])));
+}
+
+/// A right-branching comb, each node of the spine has a leaf then the rest of the spine as children
+fn comb(labels: &[&str]) -> SimpleTree<u8> {
+    match labels {
+        [] => tree!(2, "end"),
+        [l, rest @ ..] => tree!(0, "n"; [tree!(1, *l), comb(rest)]),
+    }
+}
+
+#[test]
+fn test_right_branching() {
+    let src = comb(&["a", "b", "c", "d", "e", "f", "g", "h", "i", "j"]);
+    let dst = comb(&["a", "b", "x", "d", "e", "f", "g", "y", "i", "j"]);
+    let (stores, src_id, dst_id) = vpair_to_stores((src, dst));
+    let src_arena = Decompressible::<_, SimpleZsTree<_, u16>>::decompress(&stores, &src_id);
+    let dst_arena = Decompressible::<_, SimpleZsTree<_, u16>>::decompress(&stores, &dst_id);
+    let ted = LrTed::new(&stores, &src_arena, &dst_arena);
+    let strategy = ted.compute_strategy();
+    // decomposing the roots along their leftmost paths would be quadratic in the depth of the combs
+    let root = strategy[strategy.len() - 1];
+    assert!(
+        matches!(root, Path::RightSrc | Path::RightDst),
+        "{:?}",
+        root
+    );
+
+    let src = comb(&["a", "b", "c", "d", "e", "f", "g", "h", "i", "j"]);
+    let dst = comb(&["a", "b", "x", "d", "e", "f", "g", "y", "i", "j"]);
+    assert_same_as_zs((src, dst));
+}
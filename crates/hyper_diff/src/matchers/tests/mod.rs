@@ -3,3 +3,7 @@
 // pub mod gumtree_tests;
 #[cfg(test)]
 pub mod zs_tests;
+#[cfg(test)]
+pub mod lr_ted_tests;
+#[cfg(test)]
+pub mod hybrid_tests;
@@ -17,6 +17,7 @@ use crate::{
 };
 use hyperast::types::{
     DecompressedFrom, LabelStore, Labeled, NodeStore, NodeStoreExt, Stored, Typed, WithChildren,
+    WithStats,
 };
 use std::fmt;
 
@@ -832,3 +833,148 @@ fn test_with_zs_custom_example() {
     let then = *root.last().unwrap(); //ActionsVec::apply_actions(actions.iter(), *src, &mut node_store);
     assert_eq!(then, dst);
 }
+
+/// Builds the nodes of patched simple trees, deduplicated with the existing ones
+struct SimpleBuilder<'a>(&'a mut NS<Tree>);
+
+impl crate::actions::apply::NodeBuilder<u16, u16> for SimpleBuilder<'_> {
+    fn build(
+        &mut self,
+        template: &u16,
+        label: Option<&u16>,
+        children: Vec<u16>,
+    ) -> Result<u16, String> {
+        let t = self.0.resolve(template).get_type();
+        let mut size = 1;
+        let mut height = 0;
+        for c in &children {
+            let c = self.0.resolve(c);
+            size += c.size() as u16;
+            height = height.max(c.height() as u16);
+        }
+        let node = Tree {
+            t,
+            label: label.copied().unwrap_or_default(),
+            children,
+            size,
+            height: height + 1,
+        };
+        Ok(hyperast::types::NodeStoreMut::get_or_insert(self.0, node))
+    }
+}
+
+#[test]
+fn test_delete_siblings_in_order() {
+    use crate::tests::tree;
+    use hyperast::test_utils::simple_tree::SimpleTree;
+    // b is deleted but its child c is moved to x, so b is not uniformly deleted
+    let src = tree!(0, "r"; [tree!(0, "a"), tree!(0, "x"), tree!(0, "b"; [tree!(0, "c")])]);
+    let dst = tree!(0, "r"; [tree!(0, "x"; [tree!(0, "c")])]);
+    let (stores, src, dst) = vpair_to_stores((src, dst));
+    let mut ms = DefaultMappingStore::default();
+    let src_arena = Decompressible::<_, CompletePostOrder<_, u16>>::decompress(&stores, &src);
+    let dst_arena = Decompressible::<_, CompletePostOrder<u16, u16>>::decompress(&stores, &dst);
+    let actions = {
+        let src = &(src_arena.root());
+        let dst = &(dst_arena.root());
+        ms.topit(src_arena.len(), dst_arena.len());
+        let from_src = |path: &[u8]| src_arena.child(src, path);
+        let from_dst = |path: &[u8]| dst_arena.child(dst, path);
+        ms.link(from_src(&[]), from_dst(&[]));
+        ms.link(from_src(&[1]), from_dst(&[0]));
+        ms.link(from_src(&[2, 0]), from_dst(&[0, 0]));
+        let dst_arena: SimpleBfsMapper<
+            _,
+            Decompressible<_, CompletePostOrder<u16, u16>>,
+            &Decompressible<_, CompletePostOrder<u16, u16>>,
+        > = SimpleBfsMapper::with_store(&stores, &dst_arena);
+        let actions: ActionsVec<SimpleAction<u16, CompressedTreePath<u8>, u16>> =
+            ScriptGenerator::_compute_actions(&stores, &src_arena, &dst_arena, &ms).unwrap();
+        log::debug!("{:?}", actions);
+        actions
+    };
+    let mov =
+        make_move::<Tree, CompressedTreePath<_>>((&[2, 0], &[0, 2, 0]), (&[0, 0], &[0, 1, 0]));
+    assert!(actions.has_actions(&[mov]));
+    // a was deleted first, so b is at 1 when it is deleted,
+    // deleting b first at 1 would delete x
+    let del_a = make_delete::<Tree, CompressedTreePath<_>>((&[0], &[0, 0]));
+    let del_b = make_delete::<Tree, CompressedTreePath<_>>((&[2], &[0, 1]));
+    assert_eq!(actions.0[1..], [del_a, del_b]);
+
+    let patched = crate::actions::apply::apply_actions(&stores, &src, &actions).unwrap();
+    let mut node_store = stores.node_store;
+    let then = patched.build(&mut SimpleBuilder(&mut node_store)).unwrap();
+    assert_eq!(then, dst);
+}
+
+#[test]
+fn test_align_children_move_path() {
+    use crate::tests::tree;
+    use hyperast::test_utils::simple_tree::SimpleTree;
+    let src = tree!(0, "r"; [tree!(0, "a"), tree!(0, "b"), tree!(0, "c")]);
+    let dst = tree!(0, "r"; [tree!(0, "c"), tree!(0, "a"), tree!(0, "b")]);
+    let (stores, src, dst) = vpair_to_stores((src, dst));
+    let mut ms = DefaultMappingStore::default();
+    let src_arena = Decompressible::<_, CompletePostOrder<_, u16>>::decompress(&stores, &src);
+    let dst_arena = Decompressible::<_, CompletePostOrder<u16, u16>>::decompress(&stores, &dst);
+    let actions = {
+        let src = &(src_arena.root());
+        let dst = &(dst_arena.root());
+        ms.topit(src_arena.len(), dst_arena.len());
+        let from_src = |path: &[u8]| src_arena.child(src, path);
+        let from_dst = |path: &[u8]| dst_arena.child(dst, path);
+        ms.link(from_src(&[]), from_dst(&[]));
+        ms.link(from_src(&[0]), from_dst(&[1]));
+        ms.link(from_src(&[1]), from_dst(&[2]));
+        ms.link(from_src(&[2]), from_dst(&[0]));
+        let dst_arena: SimpleBfsMapper<
+            _,
+            Decompressible<_, CompletePostOrder<u16, u16>>,
+            &Decompressible<_, CompletePostOrder<u16, u16>>,
+        > = SimpleBfsMapper::with_store(&stores, &dst_arena);
+        let actions: ActionsVec<SimpleAction<u16, CompressedTreePath<u8>, u16>> =
+            ScriptGenerator::_compute_actions(&stores, &src_arena, &dst_arena, &ms).unwrap();
+        log::debug!("{:?}", actions);
+        actions
+    };
+    // c is moved in front of its siblings, not to the path of their parent
+    let mov = make_move::<Tree, CompressedTreePath<_>>((&[2], &[0, 2]), (&[0], &[0, 0]));
+    assert!(actions.has_actions(&[mov]));
+    assert_eq!(1, actions.len());
+
+    let patched = crate::actions::apply::apply_actions(&stores, &src, &actions).unwrap();
+    let mut node_store = stores.node_store;
+    let then = patched.build(&mut SimpleBuilder(&mut node_store)).unwrap();
+    assert_eq!(then, dst);
+}
+
+#[test]
+fn test_pair_updates_with_moves() {
+    use crate::actions::pair_updates_with_moves;
+    type P = CompressedTreePath<u8>;
+    let actions: Vec<SimpleAction<u16, P, u16>> = vec![
+        // renamed and moved
+        make_update::<Tree, P>(1, (&[1, 0], &[0, 2, 0])),
+        make_move::<Tree, P>((&[0, 0], &[0, 0, 0]), (&[1, 0], &[0, 2, 0])),
+        // only moved
+        make_move::<Tree, P>((&[0, 1], &[0, 0, 0]), (&[1, 1], &[0, 2, 1])),
+        // renamed then another node moved
+        make_update::<Tree, P>(2, (&[2], &[0, 1])),
+        make_move::<Tree, P>((&[3], &[0, 3]), (&[1, 2], &[0, 2, 2])),
+    ];
+    let paired: Vec<_> = pair_updates_with_moves(&actions)
+        .map(|p| {
+            assert_eq!(&actions[p.index], p.action);
+            (p.index, p.moved.map(|m| (m.index, m.from, m.path)))
+        })
+        .collect();
+    let (from, path) = match &actions[1].action {
+        Act::Move { from } => (from, &actions[1].path),
+        _ => unreachable!(),
+    };
+    assert_eq!(
+        paired,
+        vec![(0, Some((1, from, path))), (2, None), (3, None), (4, None)]
+    );
+}
@@ -0,0 +1,114 @@
+use hyperast::store::SimpleStores;
+use hyperast_gen_ts_java::{legion_with_refs, types::TStore};
+
+use crate::actions::gumtree_format::{Action, Kind, Match, Output, Tree};
+use crate::algorithms;
+
+fn tree(r#type: &str, label: Option<&str>, start: usize, end: usize) -> Tree {
+    Tree {
+        r#type: r#type.to_string(),
+        label: label.map(|l| l.to_string()),
+        file: "A.java".to_string(),
+        start,
+        end,
+    }
+}
+
+#[test]
+fn text_format() {
+    let output = Output {
+        times: vec![],
+        matches: vec![Match {
+            src: tree("class_declaration", None, 0, 20),
+            dest: tree("class_declaration", None, 0, 24),
+        }],
+        actions: Some(vec![
+            Action {
+                action: Kind::Upd,
+                tree: tree("identifier", Some("A"), 6, 7),
+                parent: None,
+                at: None,
+                label: Some("B".to_string()),
+            },
+            Action {
+                action: Kind::Ins,
+                tree: tree("identifier", Some("f"), 12, 13),
+                parent: Some(tree("class_body", None, 8, 24)),
+                at: Some(1),
+                label: None,
+            },
+            Action {
+                action: Kind::Del,
+                tree: tree("identifier", Some("g"), 10, 11),
+                parent: None,
+                at: None,
+                label: None,
+            },
+        ]),
+    };
+    assert_eq!(
+        output.to_string(),
+        "===\nmatch\n---\nclass_declaration [0,20]\nclass_declaration [0,24]\n\
+         ===\nupdate-node\n---\nidentifier: A [6,7]\nreplace A by B\n\
+         ===\ninsert-node\n---\nidentifier: f [12,13]\nto\nclass_body [8,24]\nat 1\n\
+         ===\ndelete-node\n---\nidentifier: g [10,11]\n"
+    );
+}
+
+fn gumtree_diff(buggy: &str, fixed: &str) -> Output<Tree> {
+    let mut stores = SimpleStores::<TStore>::default();
+    let mut md_cache = Default::default();
+    let mut parse = |text: &str| {
+        let tree = match legion_with_refs::tree_sitter_parse(text.as_bytes()) {
+            Ok(t) => t,
+            Err(t) => t,
+        };
+        let mut java_tree_gen = legion_with_refs::JavaTreeGen::new(&mut stores, &mut md_cache);
+        (java_tree_gen.generate_file(b"", text.as_bytes(), tree.walk()))
+            .local
+            .compressed_node
+    };
+    let src = parse(buggy);
+    let dst = parse(fixed);
+    algorithms::gumtree::diff(&stores, &src, &dst).to_gumtree()
+}
+
+static BUGGY: &str = "class A{void f(){}}";
+static FIXED: &str = "class A{void g(){}int b;}";
+
+#[test]
+fn to_gumtree() {
+    let output = gumtree_diff(BUGGY, FIXED);
+    // the 2 matching phases and the script generation
+    assert_eq!(output.times.len(), 3);
+    assert!(!output.matches.is_empty());
+    for m in &output.matches {
+        assert_eq!(m.src.r#type, m.dest.r#type);
+    }
+    let actions = output.actions.as_ref().unwrap();
+    let upd = actions.iter().find(|a| a.action == Kind::Upd).unwrap();
+    assert_eq!(&BUGGY[upd.tree.start..upd.tree.end], "f");
+    assert_eq!(upd.tree.label.as_deref(), Some("f"));
+    assert_eq!(upd.label.as_deref(), Some("g"));
+    // inserted nodes are located in the dst code
+    let ins = actions.iter().filter(|a| a.action == Kind::Ins);
+    let ins: Vec<_> = ins.map(|a| &FIXED[a.tree.start..a.tree.end]).collect();
+    assert!(ins.contains(&"int b;"), "{:?}", ins);
+    assert!(!actions.iter().any(|a| a.action == Kind::Del));
+
+    let text = output.to_string();
+    assert!(text.contains("===\nupdate-node\n---\nidentifier: f ["));
+    assert!(text.contains("replace f by g\n"));
+    assert!(text.contains("===\ninsert-node\n---\nfield_declaration ["));
+}
+
+#[cfg(feature = "serialize")]
+#[test]
+fn json_round_trip() {
+    let output = gumtree_diff(BUGGY, FIXED);
+    let json = serde_json::to_string(&output).unwrap();
+    assert!(json.contains(r#""action":"update-node""#));
+    assert!(json.contains(r#""action":"insert-node""#));
+    let read: Output<Tree> = serde_json::from_str(&json).unwrap();
+    assert_eq!(read, output);
+}
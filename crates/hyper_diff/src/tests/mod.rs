@@ -18,6 +18,8 @@ pub mod action_generator2_simple_tests;
 pub mod action_generator2_tests;
 pub mod action_generator_tests;
 #[cfg(test)]
+pub mod gumtree_format_tests;
+#[cfg(test)]
 pub mod examples;
 pub mod hungarian_tests;
 #[cfg(test)]
@@ -66,13 +66,12 @@ where
                 &str,
             >,
         > = query.matches_immediate(cursor); // TODO filter on height (and visibility?)
-        let mut r = Default::default();
+        let mut r = tree_gen::PrecompQueries::default();
         loop {
             let Some(m) = qcursor._next_match() else {
                 break;
             };
-            assert!(m.pattern_index.to_usize() < 16);
-            r |= 1 << m.pattern_index.to_usize() as u16;
+            r.insert(m.pattern_index.to_usize());
         }
         r
     }
@@ -265,8 +265,8 @@ where
         self.is_visible()
     }
 
-    fn wont_match(&self, needed: crate::Precomps) -> bool {
-        if needed == 0 {
+    fn wont_match(&self, needed: &crate::Precomps) -> bool {
+        if needed.is_empty() {
             return false;
         }
         use hyperast::types::NodeStore;
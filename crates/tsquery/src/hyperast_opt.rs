@@ -256,8 +256,8 @@ where
         is_visible(self.stores, &self.pos)
     }
 
-    fn wont_match(&self, needed: crate::Precomps) -> bool {
-        if needed == 0 {
+    fn wont_match(&self, needed: &crate::Precomps) -> bool {
+        if needed.is_empty() {
             return false;
         }
         use hyperast::types::NodeStore;
@@ -56,8 +56,7 @@ pub use utils::ZeroSepArrayStr;
 pub use utils::ZeroSepArrayStrStatic;
 
 type Depth = u32;
-type Precomps = u16;
-// type Precomps = u16;
+type Precomps = hyperast::tree_gen::PrecompQueries;
 
 #[derive(Clone)]
 pub struct Query {
@@ -139,15 +138,15 @@ impl Query {
     pub fn _check_preprocessed(&self, pattern_id: usize, precomp: usize) {
         if pattern_id == 0 && self.pattern_map.len() == 1 {
             assert_eq!(
-                self.used_precomputed.count_ones() as usize,
+                self.used_precomputed.iter().count(),
                 precomp,
-                "{:b}",
+                "{:?}",
                 self.used_precomputed
             );
         } else {
             for p in &self.pattern_map {
                 if p.pattern_index == PatternId::new(pattern_id) {
-                    assert_eq!(p.precomputed.count_ones() as usize, precomp);
+                    assert_eq!(p.precomputed.iter().count(), precomp);
                 }
             }
         }
@@ -239,7 +238,7 @@ pub trait Cursor: for<'a> CNLending<'a> {
 
     fn text_provider(&self) -> <Self::Node as TextLending<'_>>::TP;
 
-    fn wont_match(&self, _needed: Precomps) -> bool {
+    fn wont_match(&self, _needed: &Precomps) -> bool {
         false
     }
     fn is_visible_at_root(&self) -> bool {
@@ -74,10 +74,9 @@ where
                 &str,
             > as crate::Cursor>::Node,
         > = self.0.matches_immediate(cursor); // TODO filter on height (and visibility?)
-        let mut r = Default::default();
+        let mut r = tree_gen::PrecompQueries::default();
         while let Some(m) = qcursor.next() {
-            assert!(m.pattern_index.to_usize() < 16);
-            r |= 1 << m.pattern_index.to_usize() as u16;
+            r.insert(m.pattern_index.to_usize());
         }
         r
     }
@@ -83,8 +83,8 @@ pub(crate) struct PatternEntry {
     pub(crate) precomputed: Precomps,
 }
 impl PatternEntry {
-    pub(crate) fn precomputed(&self) -> Option<Precomps> {
-        (self.precomputed != 0).then_some(self.precomputed)
+    pub(crate) fn precomputed(&self) -> Option<&Precomps> {
+        (!self.precomputed.is_empty()).then_some(&self.precomputed)
     }
 }
 
@@ -818,7 +818,7 @@ impl Query {
             {
                 let pat_offset = query.pattern_count() - 1;
                 for (i, mut p) in q.pattern_map.into_iter().enumerate() {
-                    if p.precomputed != 0 {
+                    if !p.precomputed.is_empty() {
                         todo!()
                     }
                     p.pattern_index =
@@ -860,7 +860,7 @@ impl Query {
             query.general_predicates.extend(q.general_predicates);
             q.property_settings.check_empty();
             query.property_settings.extend(q.property_settings);
-            if q.used_precomputed != 0 {
+            if !q.used_precomputed.is_empty() {
                 todo!() // NOTE probably better to process precomputeds after Self::big
             }
 
@@ -1482,6 +1482,67 @@ impl Query {
         self.capture_names[i.to_usize()]
     }
 
+    /// For each enabled pattern, the strings that some of its captured nodes must be equal to in every match,
+    /// i.e. the ones of its positive `#eq?` predicates on captures that are always present,
+    /// along the kinds of these captured nodes, see [`Query::capture_kinds`].
+    /// Patterns are in the order given by [`Query::enabled_pattern_index`].
+    ///
+    /// e.g. a subtree without any `foo` cannot match `((identifier) @id (#eq? @id "foo"))`
+    pub fn required_texts(&self) -> Vec<Vec<(&str, Vec<&str>)>> {
+        let mut r = vec![vec![]; self.enabled_pattern_count()];
+        for (i, j) in self.enabled_pattern_map.iter().enumerate() {
+            if *j == u16::MAX {
+                continue;
+            }
+            let pid = PatternId::new(i);
+            let quantifiers = &self.capture_quantifiers_vec[i];
+            r[*j as usize] = self
+                .text_predicates_for_pattern_id(pid)
+                .filter_map(|p| match p {
+                    TextPredicateCapture::EqString(c, s, true, _) => {
+                        match quantifiers.get(c.to_usize()) {
+                            Some(CaptureQuantifier::One | CaptureQuantifier::OneOrMore) => {
+                                Some((s.as_ref(), self.capture_kinds(pid, *c)))
+                            }
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                })
+                .collect();
+        }
+        r
+    }
+
+    /// The kinds of the nodes captured by `capture` in the pattern `pid`,
+    /// empty if they could be of any kind, i.e. with a wildcard
+    pub fn capture_kinds(&self, pid: PatternId, capture: CaptureId) -> Vec<&str> {
+        let steps = &self.patterns[pid].steps;
+        let end = steps.offset + steps.length;
+        let mut kinds = vec![];
+        let mut id = steps.offset;
+        while id < end {
+            let step = &self.steps[id];
+            if step.capture_ids().any(|x| x == capture) {
+                if step.is_wildcard() {
+                    return vec![];
+                }
+                kinds.push(self.symbol_name(step.symbol));
+            }
+            id.inc();
+        }
+        kinds
+    }
+
+    fn symbol_name(&self, symbol: ffi::TSSymbol) -> &str {
+        let ptr = unsafe { ffi::ts_language_symbol_name(self.language, symbol) };
+        if !ptr.is_null() {
+            unsafe { std::ffi::CStr::from_ptr(ptr) }.to_str().unwrap()
+        } else {
+            ""
+        }
+    }
+
     fn parse_property(
         row: usize,
         function_name: &str,
@@ -1742,7 +1803,7 @@ impl Query {
 }
 
 fn find_precomputed_uses(query: &mut Query, precomputeds: impl ArrayStr) {
-    query.used_precomputed = Precomps::MAX;
+    query.used_precomputed = Precomps::all(precomputeds.len());
     for i in query
         .enabled_pattern_map
         .iter()
@@ -1770,18 +1831,12 @@ fn find_precomputed_uses(query: &mut Query, precomputeds: impl ArrayStr) {
             .iter_mut()
             .find(|x| x.pattern_index == patid)
         {
-            assert_eq!(m_pat.precomputed, 0);
+            assert!(m_pat.precomputed.is_empty());
             for r in &res {
-                let r = r.0.to_usize();
-                assert!(r < 16);
-                m_pat.precomputed |= 1 << r as Precomps;
+                m_pat.precomputed.insert(r.0.to_usize());
             }
-            log::debug!(
-                "found subpatts [{:0>16b}] for pattern {}",
-                m_pat.precomputed,
-                i
-            );
-            query.used_precomputed &= m_pat.precomputed;
+            log::debug!("found subpatts {:?} for pattern {}", m_pat.precomputed, i);
+            query.used_precomputed.intersect_with(&m_pat.precomputed);
         }
     }
 }
@@ -2010,6 +2065,20 @@ impl Into<Vec<StepOffset>> for &Array<crate::ffi_extra::TSStepOffset> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn required_texts_of_eq_predicates() {
+        let query = Query::new(
+            r#"((identifier) @id (#eq? @id "foo"))
+((method_declaration name: (identifier) @m) (#not-eq? @m "bar"))
+((class_declaration (modifiers)? @mods) (#eq? @mods "public"))"#,
+            tree_sitter_java::language(),
+        )
+        .unwrap();
+        let expected: Vec<Vec<(&str, Vec<&str>)>> =
+            vec![vec![("foo", vec!["identifier"])], vec![], vec![]];
+        assert_eq!(query.required_texts(), expected);
+    }
+
     #[test]
     fn test_immediate_preds() {
         // TODO make a bigger query with many steps
@@ -803,7 +803,7 @@ impl<'query, Cursor: super::Cursor> QueryCursor<'query, Cursor, Cursor::Node> {
 
     fn should_descend(&self, node_intersects_range: bool) -> bool {
         if node_intersects_range && self.depth < self.max_start_depth {
-            if self.cursor.wont_match(self.query.used_precomputed) {
+            if self.cursor.wont_match(&self.query.used_precomputed) {
                 for i in 0..self.states.len() {
                     let state = &self.states[i];
                     let next_step = &self.query.steps[state.step_index];
@@ -837,7 +837,7 @@ impl<'query, Cursor: super::Cursor> QueryCursor<'query, Cursor, Cursor::Node> {
             // dbg!(self.depth, self.max_start_depth);
             return false;
         }
-        if self.cursor.wont_match(self.query.used_precomputed) {
+        if self.cursor.wont_match(&self.query.used_precomputed) {
             return false;
         }
 
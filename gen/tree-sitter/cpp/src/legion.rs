@@ -72,7 +72,7 @@ pub type Global<'a> = SpacedGlobalData<'a>;
 #[derive(Debug, Clone, Default)]
 pub struct PartialAnalysis {}
 
-type PrecompQueries = u16;
+type PrecompQueries = hyperast::tree_gen::PrecompQueries;
 
 #[derive(Debug, Clone)]
 pub struct Local {
@@ -574,7 +574,7 @@ where
             debug_assert_eq!(metrics.line_count, md.metrics.line_count);
             debug_assert_eq!(metrics.hashs.build(), md.metrics.hashs);
             let metrics = md.metrics;
-            let precomp_queries = md.precomp_queries;
+            let precomp_queries = md.precomp_queries.clone();
             Local {
                 compressed_node,
                 metrics,
@@ -604,7 +604,7 @@ where
             let current_role = Option::take(&mut acc.role.current);
             acc.role.add_md(&mut dyn_builder);
             if More::ENABLED {
-                add_md_precomp_queries(&mut dyn_builder, acc.precomp_queries);
+                add_md_precomp_queries(&mut dyn_builder, &acc.precomp_queries);
             }
 
             let hashs = metrics.add_md_metrics(&mut dyn_builder, children_is_empty);
@@ -454,83 +454,172 @@ impl TypeTrait for Type {
     type Lang = Cpp;
 
     fn is_fork(&self) -> bool {
-        todo!()
+        match self {
+            Self::ConditionalExpression => true,
+            Self::IfStatement => true,
+            Self::ForStatement => true,
+            Self::ForRangeLoop => true,
+            Self::WhileStatement => true,
+            Self::DoStatement => true,
+            Self::CaseStatement => true,
+            Self::CatchClause => true,
+            Self::TryStatement => true,
+            Self::SehTryStatement => true,
+            _ => false,
+        }
     }
 
     fn is_literal(&self) -> bool {
-        todo!()
+        match self {
+            Self::NumberLiteral => true,
+            Self::CharLiteral => true,
+            Self::StringLiteral => true,
+            Self::RawStringLiteral => true,
+            Self::ConcatenatedString => true,
+            Self::UserDefinedLiteral => true,
+            Self::True => true,
+            Self::False => true,
+            Self::Null => true,
+            Self::Nullptr => true,
+            _ => false,
+        }
     }
 
     fn is_primitive(&self) -> bool {
-        todo!()
+        self == &Type::PrimitiveType || self == &Type::SizedTypeSpecifier
     }
 
     fn is_type_declaration(&self) -> bool {
-        todo!()
+        match self {
+            Self::ClassSpecifier => true,
+            Self::StructSpecifier => true,
+            Self::UnionSpecifier => true,
+            Self::EnumSpecifier => true,
+            _ => false,
+        }
     }
 
     fn is_identifier(&self) -> bool {
-        todo!()
+        match self {
+            Self::Identifier => true,
+            Self::TypeIdentifier => true,
+            Self::FieldIdentifier => true,
+            Self::NamespaceIdentifier => true,
+            Self::StatementIdentifier => true,
+            Self::QualifiedIdentifier => true,
+            Self::DestructorName => true,
+            Self::OperatorName => true,
+            _ => false,
+        }
     }
 
     fn is_instance_ref(&self) -> bool {
-        todo!()
+        self == &Type::This
     }
 
     fn is_type_body(&self) -> bool {
-        todo!()
+        self == &Type::FieldDeclarationList || self == &Type::EnumeratorList
     }
 
     fn is_value_member(&self) -> bool {
-        todo!()
+        self == &Type::FieldDeclaration
     }
 
     fn is_executable_member(&self) -> bool {
-        todo!()
+        self == &Type::FunctionDefinition
     }
 
     fn is_statement(&self) -> bool {
-        todo!()
+        self.is_declarative_statement()
+            || self.is_structural_statement()
+            || self.is_simple_statement()
+            || self.is_block_related()
     }
 
     fn is_declarative_statement(&self) -> bool {
-        todo!()
+        self == &Type::Declaration
+            || self == &Type::AliasDeclaration
+            || self == &Type::TypeDefinition
+            || self == &Type::StaticAssertDeclaration
+            || self == &Type::ForStatement
+            || self == &Type::ForRangeLoop
+            || self == &Type::CatchClause
     }
 
     fn is_structural_statement(&self) -> bool {
-        todo!()
+        self == &Type::IfStatement
+            || self == &Type::SwitchStatement
+            || self == &Type::WhileStatement
+            || self == &Type::DoStatement
+            || self == &Type::TryStatement
+            || self == &Type::SehTryStatement
     }
 
     fn is_block_related(&self) -> bool {
-        todo!()
+        self == &Type::CompoundStatement || self == &Type::CaseStatement
     }
 
     fn is_simple_statement(&self) -> bool {
-        todo!()
+        self == &Type::ExpressionStatement
+            || self == &Type::ReturnStatement
+            || self == &Type::BreakStatement
+            || self == &Type::ContinueStatement
+            || self == &Type::GotoStatement
+            || self == &Type::LabeledStatement
+            || self == &Type::AttributedStatement
+            || self == &Type::ThrowStatement
+            || self == &Type::CoReturnStatement
+            || self == &Type::CoYieldStatement
+            || self == &Type::SehLeaveStatement
     }
 
     fn is_local_declare(&self) -> bool {
-        todo!()
+        self == &Type::Declaration
     }
 
     fn is_parameter(&self) -> bool {
-        todo!()
+        self == &Type::ParameterDeclaration
+            || self == &Type::OptionalParameterDeclaration
+            || self == &Type::VariadicParameterDeclaration
+            || self == &Type::TypeParameterDeclaration
+            || self == &Type::VariadicTypeParameterDeclaration
+            || self == &Type::OptionalTypeParameterDeclaration
+            || self == &Type::TemplateTemplateParameterDeclaration
     }
 
     fn is_parameter_list(&self) -> bool {
-        todo!()
+        self == &Type::ParameterList || self == &Type::TemplateParameterList
     }
 
     fn is_argument_list(&self) -> bool {
-        todo!()
+        self == &Type::ArgumentList || self == &Type::TemplateArgumentList
     }
 
     fn is_expression(&self) -> bool {
-        todo!()
+        self == &Type::BinaryExpression
+            || self == &Type::UnaryExpression
+            || self == &Type::CallExpression
+            || self == &Type::CommaExpression
+            || self == &Type::ConditionalExpression
+            || self == &Type::AssignmentExpression
+            || self == &Type::PointerExpression
+            || self == &Type::UpdateExpression
+            || self == &Type::CastExpression
+            || self == &Type::SizeofExpression
+            || self == &Type::AlignofExpression
+            || self == &Type::SubscriptExpression
+            || self == &Type::FieldExpression
+            || self == &Type::ParenthesizedExpression
+            || self == &Type::NewExpression
+            || self == &Type::DeleteExpression
+            || self == &Type::LambdaExpression
+            || self == &Type::CompoundLiteralExpression
+            || self == &Type::CoAwaitExpression
+            || self == &Type::FoldExpression
     }
 
     fn is_comment(&self) -> bool {
-        todo!()
+        self == &Type::Comment
     }
 }
 
@@ -121,7 +121,7 @@ impl From<Local> for MD {
 
 pub type Global<'a> = hyperast::tree_gen::SpacedGlobalData<'a, StatsGlobalData>;
 
-type PrecompQueries = u16;
+type PrecompQueries = hyperast::tree_gen::PrecompQueries;
 
 #[derive(Debug, Clone)]
 pub struct Local {
@@ -815,7 +815,7 @@ where
             let md = self.md_cache.get(&compressed_node).unwrap();
             let ana = md.ana.clone();
             let metrics = md.metrics;
-            let precomp_queries = md.precomp_queries;
+            let precomp_queries = md.precomp_queries.clone();
             let mcc = md.mcc.clone();
             Local {
                 compressed_node,
@@ -856,7 +856,7 @@ where
             dyn_builder.add(bytes_len);
 
             if More::ENABLED {
-                tree_gen::add_md_precomp_queries(&mut dyn_builder, acc.precomp_queries);
+                tree_gen::add_md_precomp_queries(&mut dyn_builder, &acc.precomp_queries);
             }
             if More::GRAPHING {
                 // TODO find a way of removing those 'static, probably an even lower API would work (the File<G> is really bad in the end)
@@ -1061,7 +1061,7 @@ where
                 let md = self.md_cache.get(&id).unwrap();
                 let ana = md.ana.clone();
                 let metrics = md.metrics;
-                let precomp_queries = md.precomp_queries;
+                let precomp_queries = md.precomp_queries.clone();
                 let mcc = md.mcc.clone();
                 Local {
                     compressed_node: id,
@@ -1098,7 +1098,7 @@ where
                     dyn_builder.add(label_id);
                 }
                 if More::ENABLED {
-                    add_md_precomp_queries(&mut dyn_builder, acc.precomp_queries);
+                    add_md_precomp_queries(&mut dyn_builder, &acc.precomp_queries);
                 }
                 #[cfg(feature = "impact")]
                 reference_analysis::add_md_ref_ana(
@@ -208,7 +208,7 @@ impl LangRef<Type> for TsQuery {
     }
 }
 
-type TType = hyperast::types::TypeU16<Lang>;
+pub type TType = hyperast::types::TypeU16<Lang>;
 
 impl LangRef<TType> for TsQuery {
     fn make(&self, t: u16) -> &'static TType {
@@ -408,6 +408,10 @@ impl<'store, 'cache, TS: TsEnabledTypeStore> TsTreeGen<'store, 'cache, TS> {
     }
 }
 
+pub fn tree_sitter_parse(text: &[u8]) -> Result<tree_sitter::Tree, tree_sitter::Tree> {
+    hyperast::tree_gen::utils_ts::tree_sitter_parse(text, &crate::language())
+}
+
 pub fn eq_node<'a, K>(
     kind: &'a K,
     label_id: Option<&'a LabelIdentifier>,
@@ -44,6 +44,35 @@ mod legion_impls {
             t.e()
         }
     }
+
+    #[cfg(feature = "impl")]
+    impl hyperast::types::RoleStore for TStore {
+        type IdF = u16;
+
+        type Role = hyperast::types::Role;
+
+        fn resolve_field(
+            _lang: hyperast::types::LangWrapper<Self::Ty>,
+            field_id: Self::IdF,
+        ) -> Self::Role {
+            let s = crate::language()
+                .field_name_for_id(field_id)
+                .ok_or_else(|| format!("{}", field_id))
+                .unwrap();
+            hyperast::types::Role::try_from(s).expect(s)
+        }
+
+        fn intern_role(
+            _lang: hyperast::types::LangWrapper<Self::Ty>,
+            role: Self::Role,
+        ) -> Self::IdF {
+            let field_name = role.to_string();
+            crate::language()
+                .field_id_for_name(field_name)
+                .unwrap()
+                .into()
+        }
+    }
 }
 
 pub trait TsEnabledTypeStore:
@@ -96,7 +125,7 @@ impl<IdN: Clone + Eq + AAAA> TypedNodeId for TIdN<IdN> {
 }
 
 #[derive(Clone, Copy)]
-pub(crate) struct TStore;
+pub struct TStore;
 
 impl Default for TStore {
     fn default() -> Self {
@@ -195,7 +224,7 @@ impl HyperType for Type {
     }
 
     fn is_file(&self) -> bool {
-        todo!()
+        self == &Type::Program
     }
 
     fn is_spaces(&self) -> bool {
@@ -208,7 +237,12 @@ impl HyperType for Type {
     }
 
     fn is_syntax(&self) -> bool {
-        todo!()
+        // anonymous tokens, e.g. "(", ";" or keywords
+        !(self.is_named()
+            || self.is_hidden()
+            || self == &Type::Spaces
+            || self == &Type::Directory
+            || self == &Type::ERROR)
     }
 
     fn as_shared(&self) -> hyperast::types::Shared {
@@ -242,15 +276,15 @@ impl HyperType for Type {
     }
 
     fn is_hidden(&self) -> bool {
-        todo!()
+        self.is_hidden()
     }
 
     fn is_supertype(&self) -> bool {
-        todo!()
+        self.is_supertype()
     }
 
     fn is_named(&self) -> bool {
-        todo!()
+        self.is_named()
     }
 
     fn get_lang(&self) -> hyperast::types::LangWrapper<Self>
@@ -260,7 +294,7 @@ impl HyperType for Type {
         From::<&'static (dyn LangRef<Self>)>::from(&Lang)
     }
     fn lang_ref(&self) -> hyperast::types::LangWrapper<AnyType> {
-        todo!()
+        hyperast::types::LangWrapper::from(&Lang as &(dyn LangRef<AnyType> + 'static))
     }
 }
 impl TypeTrait for Type {
@@ -356,7 +390,7 @@ impl Display for Type {
     }
 }
 
-type TType = hyperast::types::TypeU16<Lang>;
+pub type TType = hyperast::types::TypeU16<Lang>;
 
 impl hyperast::types::LLang<TType> for Ts {
     type I = u16;
@@ -0,0 +1,210 @@
+use std::time::Instant;
+
+use crate::{
+    processing::{Bounds, Comment, ObjectName},
+    Accumulator, BasicDirAcc, DefaultMetrics, FailedParsing, FileProcessingResult,
+    SuccessProcessing, PROPAGATE_ERROR_ON_BAD_CST_NODE,
+};
+
+use hyperast::{
+    store::defaults::{LabelIdentifier, NodeIdentifier},
+    tree_gen::NoOpMore,
+};
+
+use hyperast_gen_ts_c::legion as c_tree_gen;
+use hyperast_gen_ts_tsquery::legion as tsquery_tree_gen;
+use hyperast_gen_ts_xml::legion as xml_tree_gen;
+
+/// The generator used for a file, chosen from its name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FileKind {
+    Java,
+    Cpp,
+    C,
+    Xml,
+    Ts,
+    TsQuery,
+    /// no matching generator, the file becomes a leaf labeled by its name
+    Opaque,
+}
+
+impl FileKind {
+    pub(crate) fn of(name: &ObjectName) -> Self {
+        use crate::processing::{file_sys, InFiles};
+        if file_sys::Java::matches(name) {
+            FileKind::Java
+        } else if file_sys::C::matches(name) {
+            // before cpp, which also accepts .c files
+            FileKind::C
+        } else if file_sys::Cpp::matches(name) {
+            FileKind::Cpp
+        } else if file_sys::Xml::matches(name) {
+            FileKind::Xml
+        } else if file_sys::Ts::matches(name) {
+            FileKind::Ts
+        } else if file_sys::TsQuery::matches(name) {
+            FileKind::TsQuery
+        } else {
+            FileKind::Opaque
+        }
+    }
+}
+
+/// Parses `$text` within `$bounds`, a placeholder is used if the file is skipped
+macro_rules! parse_or_bail {
+    ($text:expr, $language:expr, $comment:expr, $bounds:expr, $name:expr) => {{
+        let language = $language;
+        let time = Instant::now();
+        let tree = $bounds.parse($text, &language);
+        let parsing_time = time.elapsed();
+        let (text, tree, skipped) = $bounds.or_placeholder($name, $text, tree, &language, $comment);
+        let tree = match tree {
+            Ok(tree) => tree,
+            Err(tree) => {
+                log::warn!("bad CST: {:?}", $name.try_str());
+                log::debug!("{}", tree.root_node().to_sexp());
+                if PROPAGATE_ERROR_ON_BAD_CST_NODE {
+                    return Err(FailedParsing {
+                        parsing_time,
+                        tree,
+                        error: "CST contains parsing errors",
+                    });
+                } else {
+                    tree
+                }
+            }
+        };
+        let errors = skipped.map_or_else(|| crate::parse_errors::collect(&tree), |x| vec![x]);
+        (text, tree, parsing_time, errors)
+    }};
+}
+
+pub(crate) fn handle_c_file<'stores, 'cache, 'b: 'stores>(
+    tree_gen: &mut c_tree_gen::CTreeGen<
+        'stores,
+        'cache,
+        hyperast_gen_ts_c::types::TStore,
+        NoOpMore<hyperast_gen_ts_c::types::TStore, c_tree_gen::Acc>,
+    >,
+    name: &ObjectName,
+    text: &'b [u8],
+    bounds: Bounds,
+) -> FileProcessingResult<c_tree_gen::FNode> {
+    let (text, tree, parsing_time, errors) = parse_or_bail!(
+        text,
+        hyperast_gen_ts_c::language(),
+        Comment::Slashes,
+        bounds,
+        name
+    );
+    let time = Instant::now();
+    let node = tree_gen.generate_file(name.as_bytes(), text, tree.walk());
+    let processing_time = time.elapsed();
+    Ok(SuccessProcessing {
+        parsing_time,
+        processing_time,
+        node,
+        errors,
+    })
+}
+
+pub(crate) fn handle_xml_file<'stores, 'b: 'stores>(
+    tree_gen: &mut xml_tree_gen::XmlTreeGen<'stores, hyperast_gen_ts_xml::types::TStore>,
+    name: &ObjectName,
+    text: &'b [u8],
+    bounds: Bounds,
+) -> FileProcessingResult<xml_tree_gen::FNode> {
+    let (text, tree, parsing_time, errors) = parse_or_bail!(
+        text,
+        hyperast_gen_ts_xml::language(),
+        Comment::Xml,
+        bounds,
+        name
+    );
+    let time = Instant::now();
+    let node = tree_gen.generate_file(name.as_bytes(), text, tree.walk());
+    let processing_time = time.elapsed();
+    Ok(SuccessProcessing {
+        parsing_time,
+        processing_time,
+        node,
+        errors,
+    })
+}
+
+pub(crate) fn handle_tsquery_file<'stores, 'cache, 'b: 'stores>(
+    tree_gen: &mut tsquery_tree_gen::TsQueryTreeGen<
+        'stores,
+        'cache,
+        hyperast_gen_ts_tsquery::types::TStore,
+    >,
+    name: &ObjectName,
+    text: &'b [u8],
+    bounds: Bounds,
+) -> FileProcessingResult<tsquery_tree_gen::FNode> {
+    let (text, tree, parsing_time, errors) = parse_or_bail!(
+        text,
+        hyperast_gen_ts_tsquery::language(),
+        Comment::Semicolon,
+        bounds,
+        name
+    );
+    let time = Instant::now();
+    let node = tree_gen.generate_file(name.as_bytes(), text, tree.walk());
+    let processing_time = time.elapsed();
+    Ok(SuccessProcessing {
+        parsing_time,
+        processing_time,
+        node,
+        errors,
+    })
+}
+
+/// A directory containing files of any language
+pub struct AnyAcc {
+    pub(crate) primary: BasicDirAcc<NodeIdentifier, LabelIdentifier, DefaultMetrics>,
+}
+
+impl AnyAcc {
+    pub(crate) fn new(name: String) -> Self {
+        Self {
+            primary: BasicDirAcc::new(name),
+        }
+    }
+}
+
+impl From<String> for AnyAcc {
+    fn from(name: String) -> Self {
+        Self::new(name)
+    }
+}
+
+impl hyperast::tree_gen::Accumulator for AnyAcc {
+    type Node = (LabelIdentifier, (NodeIdentifier, DefaultMetrics));
+    fn push(&mut self, (name, (compressed_node, metrics)): Self::Node) {
+        self.primary.push(name, compressed_node, metrics);
+    }
+}
+
+impl Accumulator for AnyAcc {
+    type Unlabeled = (NodeIdentifier, DefaultMetrics);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dispatch_per_extension() {
+        let kind = |name: &str| FileKind::of(&name.as_bytes().into());
+        assert_eq!(kind("Main.java"), FileKind::Java);
+        assert_eq!(kind("jni.c"), FileKind::C);
+        assert_eq!(kind("jni.h"), FileKind::Cpp);
+        assert_eq!(kind("lib.cpp"), FileKind::Cpp);
+        assert_eq!(kind("pom.xml"), FileKind::Xml);
+        assert_eq!(kind("index.js"), FileKind::Ts);
+        assert_eq!(kind("highlights.scm"), FileKind::TsQuery);
+        assert_eq!(kind("build.py"), FileKind::Opaque);
+        assert_eq!(kind("Makefile"), FileKind::Opaque);
+    }
+}
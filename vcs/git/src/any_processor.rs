@@ -0,0 +1,625 @@
+use crate::{
+    any::{AnyAcc, FileKind},
+    git::BasicGitObject,
+    preprocessed::RepositoryProcessor,
+    processing::{
+        erased::{
+            CommitProcessorHandle, ParametrizedCommitProc2,
+            ParametrizedCommitProcessor2Handle as PCP2Handle,
+        },
+        CacheHolding, ObjectName, ParametrizedCommitProcessorHandle,
+    },
+    DefaultMetrics, Processor, StackEle,
+};
+use git2::{Oid, Repository};
+use hyperast::{
+    store::{defaults::NodeIdentifier, nodes::legion::eq_node},
+    types::{ETypeStore as _, LabelStore},
+};
+use hyperast_gen_ts_c::legion as c_gen;
+use hyperast_gen_ts_tsquery::legion as tsquery_gen;
+use hyperast_gen_ts_xml::{legion::XmlTreeGen, types::Type};
+use std::{
+    iter::Peekable,
+    path::{Components, PathBuf},
+};
+
+pub(crate) fn prepare_dir_exploration(tree: git2::Tree) -> Vec<BasicGitObject> {
+    tree.iter()
+        .rev()
+        .map(TryInto::try_into)
+        .filter_map(|x| x.ok())
+        .collect()
+}
+
+/// Directories use the xml types, like the directories of build systems,
+/// opaque files use the [`crate::opaque`] types
+pub type SimpleStores = hyperast::store::SimpleStores<hyperast_gen_ts_xml::types::TStore>;
+
+/// Walks all the files of a repository,
+/// each blob is handled by the generator matching its extension (see [`FileKind`]).
+pub struct AnyProcessor<'repo, 'prepro, 'd, 'c> {
+    repository: &'repo Repository,
+    prepro: &'prepro mut RepositoryProcessor,
+    stack: Vec<StackEle<AnyAcc>>,
+    dir_path: &'d mut Peekable<Components<'c>>,
+    handle: ParametrizedCommitProcessorHandle,
+}
+
+impl<'repo, 'prepro, 'd, 'c> AnyProcessor<'repo, 'prepro, 'd, 'c> {
+    pub(crate) fn new(
+        repository: &'repo Repository,
+        prepro: &'prepro mut RepositoryProcessor,
+        dir_path: &'d mut Peekable<Components<'c>>,
+        name: &[u8],
+        oid: git2::Oid,
+        handle: ParametrizedCommitProcessorHandle,
+    ) -> Self {
+        let tree = repository.find_tree(oid).unwrap();
+        let prepared = prepare_dir_exploration(tree);
+        let name = std::str::from_utf8(&name).unwrap().to_string();
+        let stack = vec![StackEle::new(oid, prepared, AnyAcc::new(name))];
+        Self {
+            stack,
+            repository,
+            prepro,
+            dir_path,
+            handle,
+        }
+    }
+}
+
+impl<'repo, 'prepro, 'd, 'c> Processor<AnyAcc> for AnyProcessor<'repo, 'prepro, 'd, 'c> {
+    fn pre(&mut self, current_object: BasicGitObject) {
+        match current_object {
+            BasicGitObject::Tree(oid, name) => {
+                self.handle_tree_cached(oid, name);
+            }
+            BasicGitObject::Blob(oid, name) => {
+                if self.dir_path.peek().is_some() {
+                    return;
+                }
+                self.prepro.help_handle_any_file(
+                    oid,
+                    &mut self.stack.last_mut().unwrap().acc,
+                    &name,
+                    self.repository,
+                    PCP2Handle(self.handle.1, std::marker::PhantomData),
+                );
+            }
+        }
+    }
+
+    fn post(&mut self, oid: Oid, acc: AnyAcc) -> Option<(NodeIdentifier, DefaultMetrics)> {
+        let name = acc.primary.name.clone();
+        let key = (oid, name.as_bytes().into());
+        let full_node = make(
+            hyperast_gen_ts_xml::types::TStore::intern(Type::Directory),
+            acc,
+            self.prepro.main_stores_mut().mut_with_ts(),
+        );
+        self.prepro
+            .processing_systems
+            .mut_or_default::<AnyProcessorHolder>()
+            .with_parameters_mut(self.handle.1)
+            .cache
+            .object_map
+            .insert(key, full_node.clone());
+        let name = self.prepro.main_stores.label_store.get_or_insert(name);
+        if self.stack.is_empty() {
+            Some(full_node)
+        } else {
+            let w = &mut self.stack.last_mut().unwrap().acc;
+            assert!(
+                !w.primary.children_names.contains(&name),
+                "{:?} {:?}",
+                w.primary.children_names,
+                name
+            );
+            hyperast::tree_gen::Accumulator::push(w, (name, full_node));
+            None
+        }
+    }
+
+    fn stack(&mut self) -> &mut Vec<StackEle<AnyAcc>> {
+        &mut self.stack
+    }
+}
+
+impl<'repo, 'prepro, 'd, 'c> AnyProcessor<'repo, 'prepro, 'd, 'c> {
+    fn handle_tree_cached(&mut self, oid: Oid, name: ObjectName) {
+        if let Some(s) = self.dir_path.peek() {
+            if name
+                .as_bytes()
+                .eq(std::ffi::OsStr::as_encoded_bytes(s.as_os_str()))
+            {
+                self.dir_path.next();
+                self.stack.last_mut().expect("never empty").cs.clear();
+                let tree = self.repository.find_tree(oid).unwrap();
+                let prepared = prepare_dir_exploration(tree);
+                self.stack.push(StackEle::new(
+                    oid,
+                    prepared,
+                    AnyAcc::new(name.try_into().unwrap()),
+                ));
+            }
+            return;
+        }
+        if let Some(already) = self
+            .prepro
+            .processing_systems
+            .mut_or_default::<AnyProcessorHolder>()
+            .with_parameters_mut(self.handle.1)
+            .cache
+            .object_map
+            .get(&(oid, name.clone()))
+        {
+            // reinit already computed node for post order
+            let full_node = already.clone();
+            let w = &mut self.stack.last_mut().unwrap().acc;
+            let name = self.prepro.intern_object_name(&name);
+            assert!(!w.primary.children_names.contains(&name));
+            hyperast::tree_gen::Accumulator::push(w, (name, full_node));
+        } else {
+            log::debug!("tree {:?}", name.try_str());
+            let tree = self.repository.find_tree(oid).unwrap();
+            let prepared: Vec<BasicGitObject> = prepare_dir_exploration(tree);
+            self.stack.push(StackEle::new(
+                oid,
+                prepared,
+                AnyAcc::new(name.try_into().unwrap()),
+            ));
+        }
+    }
+}
+
+impl RepositoryProcessor {
+    /// Dispatches the blob to the generator matching its extension,
+    /// files without generator or that could not be parsed become opaque leaves.
+    pub(crate) fn help_handle_any_file(
+        &mut self,
+        oid: Oid,
+        parent: &mut AnyAcc,
+        name: &ObjectName,
+        repository: &Repository,
+        parameters: PCP2Handle<AnyProc>,
+    ) {
+        let full_node = match self.handle_any_blob(oid, name, repository, parameters) {
+            Ok(full_node) => full_node,
+            Err(err) => {
+                log::warn!("{:?} kept as an opaque file: {:?}", name.try_str(), err);
+                self.make_opaque_file(name)
+            }
+        };
+        let name = self.intern_object_name(name);
+        assert!(!parent.primary.children_names.contains(&name));
+        hyperast::tree_gen::Accumulator::push(parent, (name, full_node));
+    }
+
+    fn handle_any_blob(
+        &mut self,
+        oid: Oid,
+        name: &ObjectName,
+        repository: &Repository,
+        parameters: PCP2Handle<AnyProc>,
+    ) -> Result<(NodeIdentifier, DefaultMetrics), crate::ParseErr> {
+        let parameter = self
+            .processing_systems
+            .mut_or_default::<AnyProcessorHolder>()
+            .with_parameters(parameters.0)
+            .parameter
+            .clone();
+        match FileKind::of(name) {
+            FileKind::Java => self
+                .handle_java_blob(oid, name, repository, parameter.java_handle)
+                .map(|(x, _)| (x.compressed_node, x.metrics)),
+            FileKind::Cpp => self
+                .handle_cpp_blob(oid, name, repository, parameter.cpp_handle)
+                .map(|(x, _)| (x.compressed_node, x.metrics)),
+            FileKind::Ts => self
+                .handle_ts_blob(oid, name, repository, parameter.ts_handle)
+                .map(|(x, _)| (x.compressed_node, x.metrics)),
+            FileKind::C => self.handle_c_blob(oid, name, repository, parameters),
+            FileKind::Xml => self.handle_xml_blob(oid, name, repository, parameters),
+            FileKind::TsQuery => self.handle_tsquery_blob(oid, name, repository, parameters),
+            FileKind::Opaque => Ok(self.make_opaque_file(name)),
+        }
+    }
+
+    fn handle_c_blob(
+        &mut self,
+        oid: Oid,
+        name: &ObjectName,
+        repository: &Repository,
+        parameters: PCP2Handle<AnyProc>,
+    ) -> Result<(NodeIdentifier, DefaultMetrics), crate::ParseErr> {
+        self.handle_lang_blob::<crate::processing::file_sys::C>(
+            oid,
+            name,
+            repository,
+            parameters,
+            |any_proc, stores, n, t, bounds, line_break| {
+                let stores = stores.mut_with_ts::<hyperast_gen_ts_c::types::TStore>();
+                let mut c_tree_gen = c_gen::CTreeGen::new(stores, &mut any_proc.c.md_cache);
+                c_tree_gen.line_break = line_break;
+                crate::any::handle_c_file(&mut c_tree_gen, n, t, bounds)
+                    .map(|x| x.map_node(|x| (x.local.compressed_node, x.local.metrics)))
+            },
+        )
+    }
+
+    fn handle_xml_blob(
+        &mut self,
+        oid: Oid,
+        name: &ObjectName,
+        repository: &Repository,
+        parameters: PCP2Handle<AnyProc>,
+    ) -> Result<(NodeIdentifier, DefaultMetrics), crate::ParseErr> {
+        self.handle_lang_blob::<crate::processing::file_sys::Xml>(
+            oid,
+            name,
+            repository,
+            parameters,
+            |_, stores, n, t, bounds, line_break| {
+                let mut xml_tree_gen = XmlTreeGen {
+                    line_break,
+                    stores: stores.mut_with_ts(),
+                };
+                crate::any::handle_xml_file(&mut xml_tree_gen, n, t, bounds)
+                    .map(|x| x.map_node(|x| (x.local.compressed_node, x.local.metrics)))
+            },
+        )
+    }
+
+    fn handle_tsquery_blob(
+        &mut self,
+        oid: Oid,
+        name: &ObjectName,
+        repository: &Repository,
+        parameters: PCP2Handle<AnyProc>,
+    ) -> Result<(NodeIdentifier, DefaultMetrics), crate::ParseErr> {
+        self.handle_lang_blob::<crate::processing::file_sys::TsQuery>(
+            oid,
+            name,
+            repository,
+            parameters,
+            |any_proc, stores, n, t, bounds, line_break| {
+                let stores = stores.mut_with_ts::<hyperast_gen_ts_tsquery::types::TStore>();
+                let md_cache = &mut any_proc.tsquery.md_cache;
+                let mut tsquery_tree_gen = tsquery_gen::TsQueryTreeGen::new(stores, md_cache);
+                tsquery_tree_gen.line_break = line_break;
+                crate::any::handle_tsquery_file(&mut tsquery_tree_gen, n, t, bounds)
+                    .map(|x| x.map_node(|x| (x.local.compressed_node, x.local.metrics)))
+            },
+        )
+    }
+
+    /// Handles a blob of one of the languages generated directly by the [`AnyProc`],
+    /// the resulting nodes are cached in the caches of `S`.
+    ///
+    /// `parse` sets up the generator, given the [`AnyProc`] (for its metadata caches),
+    /// the main stores, the name and content of the blob, its bounds and its line break.
+    fn handle_lang_blob<S>(
+        &mut self,
+        oid: Oid,
+        name: &ObjectName,
+        repository: &Repository,
+        parameters: PCP2Handle<AnyProc>,
+        parse: impl FnOnce(
+            &mut AnyProc,
+            &mut crate::SimpleStores,
+            &ObjectName,
+            &[u8],
+            crate::processing::Bounds,
+            Vec<u8>,
+        ) -> crate::FileProcessingResult<(NodeIdentifier, DefaultMetrics)>,
+    ) -> Result<(NodeIdentifier, DefaultMetrics), crate::ParseErr>
+    where
+        S: crate::processing::CachesHolding,
+        S::Caches: 'static
+            + crate::processing::ObjectMapper<
+                K = (Oid, ObjectName),
+                V = (NodeIdentifier, DefaultMetrics),
+            >
+            + Send
+            + Sync
+            + Default,
+        AnyProc: CacheHolding<S::Caches>,
+    {
+        self.processing_systems.caching_blob_handler::<S>().handle2(
+            oid,
+            repository,
+            name,
+            parameters,
+            |c, n, t| {
+                let reporting = c.reporting().clone();
+                let bounds = crate::processing::Bounds::reporting(self.limits, &reporting);
+                let line_break = if t.contains(&b'\r') { "\r\n" } else { "\n" }
+                    .as_bytes()
+                    .to_vec();
+                let any_proc = c
+                    .mut_or_default::<AnyProcessorHolder>()
+                    .with_parameters_mut(parameters.0);
+                parse(any_proc, &mut self.main_stores, n, t, bounds, line_break)
+                    .map(|x| {
+                        self.parsing_time += x.parsing_time;
+                        self.processing_time += x.processing_time;
+                        let (node, metrics) = x.node;
+                        self.parse_errors.insert(node, x.errors);
+                        (node, metrics)
+                    })
+                    .map_err(|_| crate::ParseErr::IllFormed)
+            },
+        )
+    }
+
+    /// Like Makefiles, the content is ignored, only the name is kept as label
+    fn make_opaque_file(&mut self, name: &ObjectName) -> (NodeIdentifier, DefaultMetrics) {
+        let acc = AnyAcc::new(
+            name.try_into()
+                .unwrap_or_else(|_| String::from_utf8_lossy(name.as_bytes()).into_owned()),
+        );
+        let kind = crate::opaque::TType::new(crate::opaque::Type::File);
+        make(kind, acc, self.main_stores.mut_with_ts())
+    }
+}
+
+fn make<K>(
+    interned_kind: K,
+    acc: AnyAcc,
+    stores: &mut SimpleStores,
+) -> (NodeIdentifier, DefaultMetrics)
+where
+    K: 'static + Eq + Copy + std::hash::Hash + std::marker::Send + std::marker::Sync,
+{
+    use hyperast::hashed::{IndexingHashBuilder, MetaDataHashsBuilder};
+    let node_store = &mut stores.node_store;
+    let label_store = &mut stores.label_store;
+    let label_id = label_store.get_or_insert(acc.primary.name.clone());
+
+    let primary = acc
+        .primary
+        .map_metrics(|m| m.finalize(&interned_kind, &label_id, 0));
+    let hashable = primary.metrics.hashs.most_discriminating();
+    let eq = eq_node(&interned_kind, Some(&label_id), &primary.children);
+    let insertion = node_store.prepare_insertion(&hashable, eq);
+
+    if let Some(id) = insertion.occupied_id() {
+        let metrics = primary.metrics.map_hashs(|h| h.build());
+        return (id, metrics);
+    }
+
+    let mut dyn_builder = hyperast::store::nodes::legion::dyn_builder::EntityBuilder::new();
+
+    let children_is_empty = primary.children.is_empty();
+
+    let metrics = primary.persist(&mut dyn_builder, interned_kind, label_id);
+    let metrics = metrics.map_hashs(|h| h.build());
+    let hashs = metrics.add_md_metrics(&mut dyn_builder, children_is_empty);
+    hashs.persist(&mut dyn_builder);
+
+    let vacant = insertion.vacant();
+    let node_id = hyperast::store::nodes::legion::NodeStore::insert_built_after_prepare(
+        vacant,
+        dyn_builder.build(),
+    );
+
+    (node_id, metrics)
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct Parameter {
+    pub(crate) java_handle: PCP2Handle<crate::java_processor::JavaProc>,
+    pub(crate) cpp_handle: PCP2Handle<crate::cpp_processor::CppProc>,
+    pub(crate) ts_handle: PCP2Handle<crate::ts_processor::TsProc>,
+}
+
+#[derive(Default)]
+pub struct AnyProcessorHolder(Vec<AnyProc>);
+pub struct AnyProc {
+    parameter: Parameter,
+    /// c, xml and tsquery files are generated by the [`AnyProc`] itself, see [`CommitProc::get_lang_handle`]
+    ///
+    /// [`CommitProc::get_lang_handle`]: crate::processing::erased::CommitProc::get_lang_handle
+    handle: crate::processing::erased::ConfigParametersHandle,
+    cache: crate::processing::caches::Any,
+    c: crate::processing::caches::C,
+    xml: crate::processing::caches::Xml,
+    tsquery: crate::processing::caches::TsQuery,
+    commits: std::collections::HashMap<git2::Oid, crate::Commit>,
+}
+impl crate::processing::erased::Parametrized for AnyProcessorHolder {
+    type T = Parameter;
+    fn register_param(
+        &mut self,
+        t: Self::T,
+    ) -> crate::processing::erased::ParametrizedCommitProcessorHandle {
+        let l = self
+            .0
+            .iter()
+            .position(|x| &x.parameter == &t)
+            .unwrap_or_else(|| {
+                let l = self.0.len();
+                self.0.push(AnyProc {
+                    parameter: t,
+                    handle: crate::processing::erased::ConfigParametersHandle(l),
+                    cache: Default::default(),
+                    c: Default::default(),
+                    xml: Default::default(),
+                    tsquery: Default::default(),
+                    commits: Default::default(),
+                });
+                l
+            });
+
+        use crate::processing::erased::ConfigParametersHandle;
+        use crate::processing::erased::ParametrizedCommitProc;
+        ParametrizedCommitProcessorHandle(self.erased_handle(), ConfigParametersHandle(l))
+    }
+}
+
+struct PreparedAnyCommitProc<'repo> {
+    repository: &'repo git2::Repository,
+    commit_builder: crate::preprocessed::CommitBuilder,
+    pub(crate) handle: ParametrizedCommitProcessorHandle,
+}
+
+impl<'repo> crate::processing::erased::PreparedCommitProc for PreparedAnyCommitProc<'repo> {
+    fn process(
+        self: Box<PreparedAnyCommitProc<'repo>>,
+        prepro: &mut RepositoryProcessor,
+    ) -> hyperast::store::defaults::NodeIdentifier {
+        let dir_path = PathBuf::from("");
+        let mut dir_path = dir_path.components().peekable();
+        let name = b"";
+        let root_full_node = AnyProcessor::new(
+            self.repository,
+            prepro,
+            &mut dir_path,
+            name,
+            self.commit_builder.tree_oid(),
+            self.handle,
+        )
+        .process();
+        let h = prepro
+            .processing_systems
+            .mut_or_default::<AnyProcessorHolder>();
+        let handle = self.handle;
+        let commit_oid = self.commit_builder.commit_oid();
+        let commit = self.commit_builder.finish(root_full_node.0);
+        h.with_parameters_mut(handle.1)
+            .commits
+            .insert(commit_oid, commit);
+        root_full_node.0
+    }
+}
+
+impl crate::processing::erased::CommitProc for AnyProc {
+    fn prepare_processing<'repo>(
+        &self,
+        repository: &'repo git2::Repository,
+        commit_builder: crate::preprocessed::CommitBuilder,
+        handle: crate::processing::ParametrizedCommitProcessorHandle,
+    ) -> Box<dyn crate::processing::erased::PreparedCommitProc + 'repo> {
+        Box::new(PreparedAnyCommitProc {
+            repository,
+            commit_builder,
+            handle,
+        })
+    }
+
+    fn get_commit(&self, commit_oid: git2::Oid) -> Option<&crate::Commit> {
+        self.commits.get(&commit_oid)
+    }
+
+    fn commits(&self) -> Box<dyn Iterator<Item = (&git2::Oid, &crate::Commit)> + '_> {
+        Box::new(self.commits.iter())
+    }
+
+    fn insert_commit(&mut self, commit_oid: git2::Oid, commit: crate::Commit) {
+        self.commits.insert(commit_oid, commit);
+    }
+
+    fn remove_commit(&mut self, commit_oid: git2::Oid) -> Option<crate::Commit> {
+        self.commits.remove(&commit_oid)
+    }
+
+    fn clear_caches(&mut self) {
+        self.cache = Default::default();
+        self.c = Default::default();
+        self.xml = Default::default();
+        self.tsquery = Default::default();
+    }
+
+    fn get_lang_handle(&self, lang: &str) -> Option<ParametrizedCommitProcessorHandle> {
+        if lang.eq_ignore_ascii_case("java") {
+            Some(ParametrizedCommitProcessorHandle(
+                CommitProcessorHandle(std::any::TypeId::of::<
+                    crate::java_processor::JavaProcessorHolder,
+                >()),
+                self.parameter.java_handle.0,
+            ))
+        } else if lang.eq_ignore_ascii_case("cpp") {
+            Some(ParametrizedCommitProcessorHandle(
+                CommitProcessorHandle(std::any::TypeId::of::<
+                    crate::cpp_processor::CppProcessorHolder,
+                >()),
+                self.parameter.cpp_handle.0,
+            ))
+        } else if lang.eq_ignore_ascii_case("ts")
+            || lang.eq_ignore_ascii_case("typescript")
+            || lang.eq_ignore_ascii_case("javascript")
+        {
+            Some(ParametrizedCommitProcessorHandle(
+                CommitProcessorHandle(
+                    std::any::TypeId::of::<crate::ts_processor::TsProcessorHolder>(),
+                ),
+                self.parameter.ts_handle.0,
+            ))
+        } else if lang.eq_ignore_ascii_case("c")
+            || lang.eq_ignore_ascii_case("xml")
+            || lang.eq_ignore_ascii_case("tsquery")
+        {
+            // their caches are held by this processor, which has no precomputed queries
+            Some(ParametrizedCommitProcessorHandle(
+                CommitProcessorHandle(std::any::TypeId::of::<AnyProcessorHolder>()),
+                self.handle,
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+impl crate::processing::erased::CommitProcExt for AnyProc {
+    type Holder = AnyProcessorHolder;
+}
+impl crate::processing::erased::ParametrizedCommitProc2 for AnyProcessorHolder {
+    type Proc = AnyProc;
+
+    fn with_parameters_mut(
+        &mut self,
+        parameters: crate::processing::erased::ConfigParametersHandle,
+    ) -> &mut Self::Proc {
+        &mut self.0[parameters.0]
+    }
+
+    fn with_parameters(
+        &self,
+        parameters: crate::processing::erased::ConfigParametersHandle,
+    ) -> &Self::Proc {
+        &self.0[parameters.0]
+    }
+}
+impl CacheHolding<crate::processing::caches::Any> for AnyProc {
+    fn get_caches_mut(&mut self) -> &mut crate::processing::caches::Any {
+        &mut self.cache
+    }
+    fn get_caches(&self) -> &crate::processing::caches::Any {
+        &self.cache
+    }
+}
+impl CacheHolding<crate::processing::caches::C> for AnyProc {
+    fn get_caches_mut(&mut self) -> &mut crate::processing::caches::C {
+        &mut self.c
+    }
+    fn get_caches(&self) -> &crate::processing::caches::C {
+        &self.c
+    }
+}
+impl CacheHolding<crate::processing::caches::Xml> for AnyProc {
+    fn get_caches_mut(&mut self) -> &mut crate::processing::caches::Xml {
+        &mut self.xml
+    }
+    fn get_caches(&self) -> &crate::processing::caches::Xml {
+        &self.xml
+    }
+}
+impl CacheHolding<crate::processing::caches::TsQuery> for AnyProc {
+    fn get_caches_mut(&mut self) -> &mut crate::processing::caches::TsQuery {
+        &mut self.tsquery
+    }
+    fn get_caches(&self) -> &crate::processing::caches::TsQuery {
+        &self.tsquery
+    }
+}
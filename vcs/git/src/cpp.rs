@@ -1,7 +1,7 @@
 use std::time::{Duration, Instant};
 
 use crate::{
-    cpp_processor::SimpleStores, preprocessed::IsSkippedAna, processing::ObjectName, Accumulator, BasicDirAcc, FailedParsing, FileProcessingResult, SuccessProcessing, PROPAGATE_ERROR_ON_BAD_CST_NODE
+    cpp_processor::SimpleStores, preprocessed::IsSkippedAna, processing::pre_parsing::Parsed, processing::{Bounds, Comment, ObjectName}, Accumulator, BasicDirAcc, FailedParsing, FileProcessingResult, SuccessProcessing, PROPAGATE_ERROR_ON_BAD_CST_NODE
 };
 
 use hyperast::{
@@ -45,35 +45,50 @@ where
     More: tree_gen::Prepro<SimpleStores>
         + tree_gen::PreproTSG<SimpleStores, Acc = cpp_tree_gen::Acc>,
 {
-    // handling the parsing explicitly in this function is a good idea
-    // to control complex stuff like timeout, instead of the call on next line
-    // let tree_sitter_parse = cpp_tree_gen::CppTreeGen::<TStore>::tree_sitter_parse(text);
+    handle_cpp_file_with(tree_gen, name, text, None, Default::default())
+}
 
-    let mut parser = tree_sitter::Parser::new();
-    // TODO see if a timeout of a cancellation flag could be useful
-    // const MINUTE: u64 = 60 * 1000 * 1000;
-    // parser.set_timeout_micros(MINUTE);
-    // parser.set_cancellation_flag(flag);
-    parser
-        .set_language(&hyperast_gen_ts_cpp::language())
-        .unwrap();
-    let time = Instant::now();
-    let tree = parser.parse(text, None);
-    let parsing_time = time.elapsed();
-    let Some(tree) = tree else {
-        unimplemented!("You set a timeout or an cancel flag, so it now requires special handling.")
-        // return FileProcessingResult::ParsingTimedout(parsing_time)
+/// Same as [`handle_cpp_file`] but reuses the tree of `pre` if the blob was parsed ahead of time,
+/// see [`crate::processing::pre_parsing`].
+/// Otherwise the file is parsed within `bounds`, or replaced by a placeholder.
+pub(crate) fn handle_cpp_file_with<'stores, 'cache, 'b: 'stores, More>(
+    tree_gen: &mut cpp_tree_gen::CppTreeGen<'stores, 'cache, TStore, More>,
+    name: &ObjectName,
+    text: &'b [u8],
+    pre: Option<Parsed>,
+    bounds: Bounds,
+) -> FileProcessingResult<cpp_tree_gen::FNode>
+where
+    More: tree_gen::Prepro<SimpleStores>
+        + tree_gen::PreproTSG<SimpleStores, Acc = cpp_tree_gen::Acc>,
+{
+    let language = hyperast_gen_ts_cpp::language();
+    let (tree, parsing_time) = match pre {
+        Some(pre) => (pre.tree, pre.parsing_time),
+        None => {
+            let time = Instant::now();
+            let tree = bounds.parse(text, &language);
+            (tree, time.elapsed())
+        }
     };
-    if tree.root_node().has_error() {
-        log::warn!("bad CST: {:?}", name.try_str());
-        if PROPAGATE_ERROR_ON_BAD_CST_NODE {
-            return Err(FailedParsing {
-                parsing_time,
-                tree,
-                error: "CST contains parsing errors",
-            });
+    let (text, tree, skipped) =
+        bounds.or_placeholder(name, text, tree, &language, Comment::Slashes);
+    let tree = match tree {
+        Ok(tree) => tree,
+        Err(tree) => {
+            log::warn!("bad CST: {:?}", name.try_str());
+            if PROPAGATE_ERROR_ON_BAD_CST_NODE {
+                return Err(FailedParsing {
+                    parsing_time,
+                    tree,
+                    error: "CST contains parsing errors",
+                });
+            } else {
+                tree
+            }
         }
     };
+    let errors = skipped.map_or_else(|| crate::parse_errors::collect(&tree), |x| vec![x]);
     let time = Instant::now();
     let node = tree_gen.generate_file(name.as_bytes(), text, tree.walk());
     let processing_time = time.elapsed();
@@ -81,6 +96,7 @@ where
         parsing_time,
         processing_time,
         node,
+        errors,
     })
 }
 
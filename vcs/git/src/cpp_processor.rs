@@ -259,6 +259,22 @@ impl crate::processing::erased::CommitProc for CppProc {
         self.commits.get(&commit_oid)
     }
 
+    fn commits(&self) -> Box<dyn Iterator<Item = (&git2::Oid, &crate::Commit)> + '_> {
+        Box::new(self.commits.iter())
+    }
+
+    fn insert_commit(&mut self, commit_oid: git2::Oid, commit: crate::Commit) {
+        self.commits.insert(commit_oid, commit);
+    }
+
+    fn remove_commit(&mut self, commit_oid: git2::Oid) -> Option<crate::Commit> {
+        self.commits.remove(&commit_oid)
+    }
+
+    fn clear_caches(&mut self) {
+        self.cache = Default::default();
+    }
+
     fn get_precomp_query(&self) -> Option<hyperast_tsquery::ZeroSepArrayStr> {
         dbg!(&self.parameter.query);
         self.parameter.query.clone()
@@ -307,16 +323,21 @@ impl CacheHolding<crate::processing::caches::Cpp> for CppProcessorHolder {
 
 #[cfg(feature = "cpp")]
 impl RepositoryProcessor {
-    fn handle_cpp_blob(
+    pub(crate) fn handle_cpp_blob(
         &mut self,
         oid: Oid,
         name: &ObjectName,
         repository: &Repository,
         parameters: crate::processing::erased::ParametrizedCommitProcessor2Handle<CppProc>,
     ) -> Result<(cpp_gen::Local, IsSkippedAna), crate::ParseErr> {
+        let pre = self
+            .pre_parsed
+            .take(oid, crate::processing::pre_parsing::Lang::Cpp);
         self.processing_systems
             .caching_blob_handler::<crate::processing::file_sys::Cpp>()
             .handle2(oid, repository, &name, parameters, |c, n, t| {
+                let reporting = c.reporting().clone();
+                let bounds = crate::processing::Bounds::reporting(self.limits, &reporting);
                 let line_break = if t.contains(&b'\r') { "\r\n" } else { "\n" }
                     .as_bytes()
                     .to_vec();
@@ -337,9 +358,10 @@ impl RepositoryProcessor {
                     md_cache,
                     more,
                 };
-                crate::cpp::handle_cpp_file(&mut cpp_tree_gen, n, t)
+                crate::cpp::handle_cpp_file_with(&mut cpp_tree_gen, n, t, pre, bounds)
                     .map(|x| {
                         let local = x.node.local.clone();
+                        self.parse_errors.insert(local.compressed_node, x.errors);
                         self.parsing_time += x.parsing_time;
                         self.processing_time += x.processing_time;
                         log::debug!(
@@ -0,0 +1,186 @@
+//! Garbage collection of processed commits and of the subtrees only they use.
+//!
+//! A [`Retention`] policy tells which commits of each repository are kept,
+//! the other ones are forgotten, then the nodes and labels that are not reachable
+//! from the roots of the kept commits are removed, see [`hyperast::store::gc`].
+//!
+//! Caches of processors are purged, as with [`PreProcessedRepositories::purge_caches`].
+//! The remaining nodes keep their identifiers, so caches indexed by nodes
+//! only have to drop the entries of removed nodes.
+//! Labels are renumbered, the caches holding label identifiers must be dropped,
+//! here processor caches and the label index, see [`hyperast::store::labels::LabelStore::compact`].
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+pub use hyperast::store::gc::Collected;
+
+use crate::{git::Repo, multi_preprocessed::PreProcessedRepositories};
+
+/// Which commits survive a garbage collection, see [`PreProcessedRepositories::collect_garbage`]
+#[derive(Debug, Clone, Default)]
+pub struct Retention {
+    /// number of commits kept per repository, the last ones along parents,
+    /// None to keep them all
+    pub last: Option<usize>,
+    /// overrides [`Retention::last`] for some repositories
+    pub per_repo: HashMap<Repo, Option<usize>>,
+    /// always kept, whatever their repository
+    pub pinned: HashSet<git2::Oid>,
+}
+
+impl Retention {
+    pub fn last(n: usize) -> Self {
+        Self {
+            last: Some(n),
+            ..Default::default()
+        }
+    }
+
+    /// The commits of `repo` to keep among its processed commits,
+    /// given with their commit time (in seconds since the epoch) and their parents
+    pub fn select(
+        &self,
+        repo: &Repo,
+        commits: &HashMap<git2::Oid, (i64, &[git2::Oid])>,
+    ) -> HashSet<git2::Oid> {
+        let last = self.per_repo.get(repo).copied().unwrap_or(self.last);
+        let Some(last) = last else {
+            return commits.keys().copied().collect();
+        };
+        let mut kept: HashSet<_> = (commits.keys())
+            .filter(|x| self.pinned.contains(x))
+            .copied()
+            .collect();
+        // most recent first, from the commits that are not the parent of another one
+        let parents: HashSet<_> = commits.values().flat_map(|(_, x)| x.iter()).collect();
+        let mut queue: BinaryHeap<_> = (commits.iter())
+            .filter(|(x, _)| !parents.contains(x))
+            .map(|(oid, (time, _))| (*time, *oid))
+            .collect();
+        let mut seen = HashSet::new();
+        let mut count = 0;
+        while let Some((_, oid)) = queue.pop() {
+            if count >= last {
+                break;
+            }
+            if !seen.insert(oid) {
+                continue;
+            }
+            kept.insert(oid);
+            count += 1;
+            let parents = commits[&oid].1.iter();
+            queue.extend(parents.filter_map(|x| Some((commits.get(x)?.0, *x))));
+        }
+        kept
+    }
+}
+
+impl PreProcessedRepositories {
+    /// Forgets the commits that are not retained, then removes the nodes and labels
+    /// that are not used by the remaining commits.
+    ///
+    /// Commits of unregistered repositories are forgotten.
+    ///
+    /// Commit times are read from the clones of repositories,
+    /// the commits of a repository that is not cloned are ordered as if they were all as old.
+    pub fn collect_garbage(&mut self, retention: &Retention) -> Collected {
+        let retained: HashMap<Repo, HashSet<git2::Oid>> = (self.configs.keys())
+            .filter_map(|repo| {
+                let repository = repo.try_nofetch().ok();
+                let time = |oid| {
+                    let commit = repository.as_ref()?.find_commit(oid).ok()?;
+                    Some(commit.time().seconds())
+                };
+                let commits = (self.processed_commits(repo)?.into_iter())
+                    .map(|(oid, commit)| (oid, (time(oid).unwrap_or(0), &commit.parents[..])))
+                    .collect();
+                Some((repo.clone(), retention.select(repo, &commits)))
+            })
+            .collect();
+        if let Some(store_log) = &mut self.processor.store_log {
+            if let Err(e) = store_log.append_gc(&retained) {
+                log::error!("could not log garbage collection: {}", e);
+            }
+        }
+        let collected = self.collect_unretained(&retained);
+        if let Some(store_log) = &mut self.processor.store_log {
+            store_log.labels_compacted(&self.processor.main_stores.label_store);
+        }
+        collected
+    }
+
+    /// Also used to replay the store log, so it must only depend on `retained` and the stores
+    pub(crate) fn collect_unretained(
+        &mut self,
+        retained: &HashMap<Repo, HashSet<git2::Oid>>,
+    ) -> Collected {
+        self.purge_caches_retaining(|repo, oid| {
+            retained.get(repo).map_or(false, |x| x.contains(oid))
+        });
+        let roots = self.processed_roots();
+        let processor = &mut self.processor;
+        let collected = processor.main_stores.collect_garbage(roots);
+        let node_store = &processor.main_stores.node_store;
+        processor
+            .parse_errors
+            .retain(|file| node_store.contains(*file));
+        processor.unlogged_nodes.retain(|x| node_store.contains(*x));
+        // labels were renumbered
+        self.rebuild_label_index();
+        collected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retain_last_and_pinned_commits() {
+        let oid = |i: u8| git2::Oid::from_bytes(&[i; 20]).unwrap();
+        let (c1, c2, c3, c4) = (oid(1), oid(2), oid(3), oid(4));
+        // c4 is a merge of c3 and c2, c3 and c2 are both children of c1
+        let parents: HashMap<_, Vec<_>> = [
+            (c1, vec![]),
+            (c2, vec![c1]),
+            (c3, vec![c1]),
+            (c4, vec![c3, c2]),
+        ]
+        .into();
+        let commits = parents.iter().map(|(k, v)| (*k, (0, &v[..]))).collect();
+        let repo = crate::git::Forge::Github.repo("user", "name");
+        let selected = Retention::last(3).select(&repo, &commits);
+        assert_eq!(selected, [c4, c3, c2].into());
+
+        let mut retention = Retention::last(1);
+        retention.pinned.insert(c1);
+        assert_eq!(retention.select(&repo, &commits), [c4, c1].into());
+
+        retention.per_repo.insert(repo.clone(), None);
+        assert_eq!(retention.select(&repo, &commits).len(), 4);
+    }
+
+    #[test]
+    fn retain_most_recent_commits_first() {
+        let oid = |i: u8| git2::Oid::from_bytes(&[i; 20]).unwrap();
+        let (c1, c2, c3, c4) = (oid(1), oid(2), oid(3), oid(4));
+        // c4 and c3 are both children of c1, c2 is a child of c3,
+        // c4 was committed last, whatever the order of oids
+        let parents: HashMap<_, (i64, Vec<_>)> = [
+            (c1, (10, vec![])),
+            (c2, (30, vec![c3])),
+            (c3, (20, vec![c1])),
+            (c4, (40, vec![c1])),
+        ]
+        .into();
+        let commits = (parents.iter())
+            .map(|(k, (t, v))| (*k, (*t, &v[..])))
+            .collect();
+        let repo = crate::git::Forge::Github.repo("user", "name");
+        assert_eq!(Retention::last(1).select(&repo, &commits), [c4].into());
+        assert_eq!(Retention::last(2).select(&repo, &commits), [c4, c2].into());
+        assert_eq!(
+            Retention::last(3).select(&repo, &commits),
+            [c4, c2, c3].into()
+        );
+    }
+}
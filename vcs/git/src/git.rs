@@ -1,5 +1,5 @@
 use std::{
-    fmt::{Debug, Display}, fs, path::{Path, PathBuf}, process
+    fmt::{Debug, Display}, fs, path::{Path, PathBuf}, process, str::FromStr
 };
 
 pub use git2::Oid;
@@ -123,25 +123,25 @@ pub(crate) fn all_first_parents_between<'a>(
     Ok(rw)
 }
 
+/// Resolves anything `git rev-parse` understands, e.g. an oid (possibly abbreviated),
+/// a branch, a tag, `HEAD~5` or `v1.2.0^`.
+///
+/// Tags take precedence over branches with the same name.
 pub fn retrieve_commit<'a>(
     repository: &'a Repository,
     s: &str,
 ) -> Result<git2::Commit<'a>, git2::Error> {
-    // TODO make a more advanced search with helpful error msgs
-    match repository.find_reference(&format!("refs/tags/{}", s)) {
-        Ok(c) => match c.peel_to_commit() {
-            Ok(c) => Ok(c),
-            Err(err) => {
-                log::warn!("{}", err);
-                repository.find_commit(Oid::from_str(s)?)
-            }
-        },
+    if let Ok(c) = repository
+        .find_reference(&format!("refs/tags/{}", s))
+        .and_then(|c| c.peel_to_commit())
+    {
+        return Ok(c);
+    }
+    match repository.revparse_single(s) {
+        Ok(c) => c.peel_to_commit(),
         Err(err) => {
-            let oid = Oid::from_str(s).map_err(|e| {
-                log::warn!("{}", e);
-                err
-            })?;
-            repository.find_commit(oid)
+            log::warn!("cannot resolve {}: {}", s, err);
+            Err(err)
         }
     }
 }
@@ -174,13 +174,53 @@ pub fn all_commits_from_head(repository: &Repository) -> Revwalk {
     // return walk;
 }
 
+/// Where clones go by default, see [`set_clone_root`].
+pub const DEFAULT_CLONE_ROOT: &str = "/tmp/hyperastgitresources/repo/";
+
+static CLONE_ROOT: std::sync::RwLock<Option<PathBuf>> = std::sync::RwLock::new(None);
+
+/// Change where [`Repo::fetch`], [`Repo::nofetch`] and [`fetch_github_repository`] put clones.
+pub fn set_clone_root(path: impl Into<PathBuf>) {
+    *CLONE_ROOT.write().unwrap() = Some(path.into());
+}
+
+pub fn clone_root() -> PathBuf {
+    CLONE_ROOT
+        .read()
+        .unwrap()
+        .clone()
+        .unwrap_or_else(|| DEFAULT_CLONE_ROOT.into())
+}
+
+static LOCAL_ROOTS: std::sync::RwLock<Vec<PathBuf>> = std::sync::RwLock::new(Vec::new());
+
+/// Allow opening the local repositories (see [`Repo::local`]) found under `root`.
+///
+/// None are allowed by default, as specs of repositories can come from untrusted inputs,
+/// e.g. the routes of a server.
+pub fn allow_local_root(root: impl Into<PathBuf>) {
+    LOCAL_ROOTS.write().unwrap().push(root.into());
+}
+
 pub fn fetch_repository<'a, T: TryInto<Url>, U: Into<PathBuf>>(url: T, path: U) -> Repository
 where
     <T as TryInto<Url>>::Error: std::fmt::Debug,
 {
-    let url: Url = url.try_into().unwrap();
-    let mut path: PathBuf = path.into();
-    path.push(url.path.clone());
+    try_fetch_repository(url, path).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// Clone or update the repository at `url` in `path`, followed by the path of the url.
+pub fn try_fetch_repository<'a, T: TryInto<Url>, U: Into<PathBuf>>(
+    url: T,
+    path: U,
+) -> Result<Repository, String>
+where
+    <T as TryInto<Url>>::Error: std::fmt::Debug,
+{
+    let url: Url = url
+        .try_into()
+        .map_err(|e| format!("not a valid url: {:?}", e))?;
+    let path = clone_path(&path.into(), &url, true)?;
     // let url = &format!("{}{}", "https://github.com/", repo_name);
     // let path = &format!("{}{}", "/tmp/hyperastgitresources/repo/", repo_name);
     let mut callbacks = RemoteCallbacks::new();
@@ -196,7 +236,7 @@ where
 
     let repository = up_to_date_repo(&path, Some(fo), url.clone());
     if let Ok(repository) = repository {
-        return repository;
+        return Ok(repository);
     }
 
     if let Err(err) = process::Command::new("git")
@@ -208,19 +248,69 @@ where
         log::error!("tryed to use the git executable, but failed. {}", err);
     }
 
-    nofetch_repository(url, path)
+    up_to_date_repo(&path, None, url).map_err(|e| e.to_string())
 }
 
 pub fn nofetch_repository<'a, T: TryInto<Url>, U: Into<PathBuf>>(url: T, path: U) -> Repository
 where
     <T as TryInto<Url>>::Error: std::fmt::Debug,
 {
-    let url: Url = url.try_into().unwrap();
-    let mut path: PathBuf = path.into();
-    path.push(url.path.clone());
+    try_nofetch_repository(url, path).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// Open the clone of the repository at `url` in `path`, followed by the path of the url.
+pub fn try_nofetch_repository<'a, T: TryInto<Url>, U: Into<PathBuf>>(
+    url: T,
+    path: U,
+) -> Result<Repository, String>
+where
+    <T as TryInto<Url>>::Error: std::fmt::Debug,
+{
+    let url: Url = url
+        .try_into()
+        .map_err(|e| format!("not a valid url: {:?}", e))?;
+    let path = clone_path(&path.into(), &url, false)?;
 
-    let repository = up_to_date_repo(&path, None, url);
-    repository.unwrap()
+    up_to_date_repo(&path, None, url).map_err(|e| e.to_string())
+}
+
+/// Errors if `segment` is not a single plain component of a path,
+/// e.g. a user or a repository name that would escape the clone root or alter an url.
+pub fn check_segment(segment: &str) -> Result<(), String> {
+    if segment.is_empty()
+        || segment == "."
+        || segment == ".."
+        || segment.contains(['/', '\\', '#', '?', '@'])
+    {
+        return Err(format!("'{}' is not a valid path segment", segment));
+    }
+    Ok(())
+}
+
+/// The directory of the clone of `url` in `root`, created if `create`,
+/// otherwise errors if there is none.
+///
+/// Errors if it would not be in `root`, even through symbolic links.
+fn clone_path(root: &Path, url: &Url, create: bool) -> Result<PathBuf, String> {
+    let mut path = root.to_path_buf();
+    for segment in url.path.trim_end_matches('/').split('/') {
+        check_segment(segment)?;
+        path.push(segment);
+    }
+    if create {
+        fs::create_dir_all(&path).map_err(|e| format!("failed to create {:?}: {}", path, e))?;
+    } else if !path.exists() {
+        return Err(format!("{} was not cloned in {:?}", url, root));
+    }
+    let canonical = |p: &Path| {
+        p.canonicalize()
+            .map_err(|e| format!("failed to resolve {:?}: {}", p, e))
+    };
+    let path = canonical(&path)?;
+    if !path.starts_with(canonical(root)?) {
+        return Err(format!("{:?} is outside of {:?}", path, root));
+    }
+    Ok(path)
 }
 
 #[derive(Debug, Hash, PartialEq, Eq, Copy, Clone)]
@@ -235,8 +325,8 @@ impl std::str::FromStr for Forge {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         Ok(match s {
-            "github.com" => Self::Github,
-            "gitlab.com" => Self::Gitlab,
+            "github.com" | "github" => Self::Github,
+            "gitlab.com" | "gitlab" => Self::Gitlab,
             "gitlab.inria.fr" => Self::GitlabInria,
             x => return Err(format!("'{}' is not an authorize forge", x)),
         })
@@ -244,6 +334,15 @@ impl std::str::FromStr for Forge {
 }
 
 impl Forge {
+    /// inverse of [`std::str::FromStr`]
+    pub fn host(&self) -> &'static str {
+        match self {
+            Forge::Github => "github.com",
+            Forge::Gitlab => "gitlab.com",
+            Forge::GitlabInria => "gitlab.inria.fr",
+        }
+    }
+
     fn url(&self) -> &str {
         match self {
             Forge::Github => "https://github.com/",
@@ -252,7 +351,7 @@ impl Forge {
         }
     }
 
-    /// panics in case `user`` or `name`` contain '/' '#'
+    /// panics in case `user` or `name` are not valid path segments, see [`check_segment`]
     pub fn repo(self, user: impl Into<String>, name: impl Into<String>) -> Repo {
         self.try_repo(user, name).unwrap()
     }
@@ -263,58 +362,162 @@ impl Forge {
         name: impl Into<String>,
     ) -> Result<Repo, String> {
         let user = user.into();
-        if user.contains("#") || user.contains("/") {
-            return Err("attempting to inject stuff!".to_string());
-        }
+        check_segment(&user)?;
         let name = name.into();
-        if name.contains("#") || name.contains("/") {
-            return Err("attempting to inject stuff!".to_string());
-        }
+        check_segment(&name)?;
         Ok(Repo {
-            forge: self,
+            source: Source::Forge(self),
             user,
             name,
         })
     }
 }
 
+/// Where a [`Repo`] comes from.
+#[derive(Debug, Hash, PartialEq, Eq, Clone)]
+enum Source {
+    Forge(Forge),
+    /// any git url, e.g. a self-hosted server
+    Url(String),
+    /// a clone (possibly bare) on the local filesystem, never fetched
+    Local(PathBuf),
+}
+
 // TODO use `&'static str`s to derive with Copy
 #[derive(Debug, Hash, PartialEq, Eq, Clone)]
 pub struct Repo {
-    forge: Forge,
+    source: Source,
     user: String,
     name: String,
 }
 
 impl Repo {
+    /// A repository reachable at an arbitrary `url`,
+    /// its user and name are the last two segments of the url.
+    pub fn from_url(url: impl Into<String>) -> Result<Repo, String> {
+        let url = url.into();
+        let parsed: Url = url
+            .as_str()
+            .try_into()
+            .map_err(|_| format!("'{}' is not a valid url", url))?;
+        if parsed.protocol == "file" {
+            return Repo::local(format!("/{}", parsed.path));
+        }
+        let (user, name) = user_and_name(&parsed.path)
+            .ok_or_else(|| format!("'{}' should end with <user>/<name>", url))?;
+        check_segment(&user)?;
+        check_segment(&name)?;
+        Ok(Repo {
+            source: Source::Url(url.trim_end_matches('/').to_string()),
+            user,
+            name,
+        })
+    }
+
+    /// A repository already cloned at `path`, it is opened as is, without fetching.
+    ///
+    /// It can only be opened if `path` is in one of the roots given to [`allow_local_root`].
+    pub fn local(path: impl Into<PathBuf>) -> Result<Repo, String> {
+        let path = path.into();
+        // a non-bare clone can also be given through its .git directory
+        let dir = if path.ends_with(".git") {
+            path.parent().unwrap_or(&path)
+        } else {
+            &path
+        };
+        let (user, name) = user_and_name(&dir.to_string_lossy())
+            .ok_or_else(|| format!("{:?} should have at least two components", path))?;
+        Ok(Repo {
+            source: Source::Local(path),
+            user,
+            name,
+        })
+    }
+
     pub fn url(&self) -> String {
-        format!("{}{}/{}", self.forge.url(), self.user, self.name)
+        match &self.source {
+            Source::Forge(forge) => format!("{}{}/{}", forge.url(), self.user, self.name),
+            Source::Url(url) => url.clone(),
+            Source::Local(path) => format!("file://{}", path.display()),
+        }
     }
+    /// Clone or update the repository in [`clone_root`].
     pub fn fetch(&self) -> Repository {
-        let url = self.url();
-        let path = format!("{}", "/tmp/hyperastgitresources/repo/");
-        fetch_repository(url, path)
+        self.fetch_to(clone_root())
     }
     pub fn nofetch(&self) -> Repository {
-        let url = self.url();
-        let path = format!("{}", "/tmp/hyperastgitresources/repo/");
-        nofetch_repository(url, path)
+        self.nofetch_to(clone_root())
+    }
+    /// Like [`Repo::fetch`], but errors instead of panicking, e.g. when the clone cannot be made
+    pub fn try_fetch(&self) -> Result<Repository, String> {
+        self.try_fetch_to(clone_root())
+    }
+    pub fn try_nofetch(&self) -> Result<Repository, String> {
+        self.try_nofetch_to(clone_root())
     }
 
     pub fn fetch_to(&self, path: impl Into<PathBuf>) -> Repository {
-        let url = self.url();
-        let path = path.into();
-        fetch_repository(url, path)
+        self.try_fetch_to(path).unwrap_or_else(|e| panic!("{}", e))
     }
 
     pub fn nofetch_to(&self, path: impl Into<PathBuf>) -> Repository {
+        self.try_nofetch_to(path)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_fetch_to(&self, path: impl Into<PathBuf>) -> Result<Repository, String> {
         let url = self.url();
-        let path = path.into();
-        nofetch_repository(url, path)
+        let path: PathBuf = path.into();
+        match &self.source {
+            Source::Forge(_) => try_fetch_repository(url, path),
+            // avoid mixing clones of different servers
+            Source::Url(_) => try_fetch_repository(url, self.host_dir(path)?),
+            Source::Local(local) => open_local(local),
+        }
+    }
+
+    pub fn try_nofetch_to(&self, path: impl Into<PathBuf>) -> Result<Repository, String> {
+        let url = self.url();
+        let path: PathBuf = path.into();
+        match &self.source {
+            Source::Forge(_) => try_nofetch_repository(url, path),
+            Source::Url(_) => try_nofetch_repository(url, self.host_dir(path)?),
+            Source::Local(local) => open_local(local),
+        }
     }
 
-    pub fn forge(&self) -> Forge {
-        self.forge
+    fn host_dir(&self, path: PathBuf) -> Result<PathBuf, String> {
+        let host = self.host();
+        check_segment(&host)?;
+        Ok(path.join(host))
+    }
+
+    /// The path of a local repository, see [`Repo::local`]
+    pub fn local_path(&self) -> Option<&Path> {
+        match &self.source {
+            Source::Local(path) => Some(path),
+            _ => None,
+        }
+    }
+
+    /// None if the repository is not hosted on one of the known [`Forge`]s
+    pub fn forge(&self) -> Option<Forge> {
+        match &self.source {
+            Source::Forge(forge) => Some(*forge),
+            _ => None,
+        }
+    }
+    /// The host of the repository, or `local` for local ones.
+    ///
+    /// With [`Repo::user`] and [`Repo::name`], it is what identifies a repository in routes.
+    pub fn host(&self) -> String {
+        match &self.source {
+            Source::Forge(forge) => forge.host().to_string(),
+            Source::Url(url) => Url::from_str(url)
+                .map(|x| x.domain.replace(':', "_"))
+                .unwrap_or_default(),
+            Source::Local(_) => "local".to_string(),
+        }
     }
     pub fn user(&self) -> &str {
         &self.user
@@ -322,18 +525,58 @@ impl Repo {
     pub fn name(&self) -> &str {
         &self.name
     }
+
+    /// inverse of [`std::str::FromStr`]
+    pub fn spec(&self) -> String {
+        match &self.source {
+            Source::Forge(forge) => format!("{}/{}/{}", forge.host(), self.user, self.name),
+            Source::Url(_) | Source::Local(_) => self.url(),
+        }
+    }
+}
+
+fn user_and_name(path: &str) -> Option<(String, String)> {
+    let mut segments = path.trim_end_matches('/').rsplit('/');
+    let name = segments.next().filter(|x| !x.is_empty())?;
+    let name = name.strip_suffix(".git").unwrap_or(name);
+    let user = segments.next().filter(|x| !x.is_empty())?;
+    Some((user.to_string(), name.to_string()))
+}
+
+/// Errors if `path` is not under one of the roots given to [`allow_local_root`],
+/// even through symbolic links.
+fn open_local(path: &Path) -> Result<Repository, String> {
+    let canonical = path
+        .canonicalize()
+        .map_err(|e| format!("failed to resolve {:?}: {}", path, e))?;
+    let allowed = LOCAL_ROOTS.read().unwrap().iter().any(|root| {
+        root.canonicalize()
+            .map_or(false, |root| canonical.starts_with(root))
+    });
+    if !allowed {
+        return Err(format!("{:?} is not in an allowed local root", path));
+    }
+    Repository::open(path).map_err(|e| format!("failed to open {:?}: {}", path, e))
 }
 
 impl Display for Repo {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "{}{}/{}", self.forge.url(), self.user, self.name)
+        writeln!(f, "{}", self.url())
     }
 }
 
 impl std::str::FromStr for Repo {
     type Err = String;
 
+    /// Accepts `<forge>/<user>/<name>`, any url (e.g. `https://git.example.org/team/project.git`),
+    /// a `file://` url or a local path (absolute or starting with `.`).
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.starts_with("/") || s.starts_with(".") {
+            return Repo::local(s);
+        }
+        if s.contains("://") {
+            return Repo::from_url(s);
+        }
         let (forge, repo) = s
             .split_once("/")
             .ok_or("give a valid repository address without 'https://' and '.git'")?;
@@ -353,8 +596,7 @@ impl std::str::FromStr for Repo {
 
 pub fn fetch_github_repository(repo_name: &str) -> Repository {
     let url = format!("{}{}", "https://github.com/", repo_name);
-    let path = format!("{}", "/tmp/hyperastgitresources/repo/");
-    fetch_repository(url, path)
+    fetch_repository(url, clone_root())
 }
 
 pub fn fetch_fork(mut x: git2::Remote, head: &str) -> Result<(), git2::Error> {
@@ -385,9 +627,12 @@ pub fn up_to_date_repo(
         let repository = match Repository::open(path) {
             Ok(repo) => repo,
             Err(e) if e.code() == git2::ErrorCode::NotFound => {
-                if path.starts_with("/tmp") {
+                if path.starts_with("/tmp") || path.starts_with(clone_root()) {
                     if let Err(e) = fs::remove_dir_all(path.join(".git")) {
-                        panic!("failed to remove currupted clone: {}", e)
+                        return Err(git2::Error::from_str(&format!(
+                            "failed to remove currupted clone: {}",
+                            e
+                        )));
                     } else {
                         return if let Some(fo) = fo {
                             clone_helper(url, path, fo)
@@ -396,34 +641,86 @@ pub fn up_to_date_repo(
                         };
                     }
                 } else {
-                    panic!("failed to open: {}", e)
+                    return Err(e);
                 }
             }
-            Err(e) => panic!("failed to open: {}", e),
+            Err(e) => return Err(e),
         };
 
-        if let Some(mut fo) = fo {
+        if let Some(fo) = fo {
             log::info!("fetch: {:?}", path);
-            repository
-                .find_remote("origin")
-                .unwrap()
-                .fetch(&["main"], Some(&mut fo), None)
+            update_refs(&repository, fo, FETCHED_REFSPECS)
                 .unwrap_or_else(|e| log::error!("{}", e));
         }
 
         Ok(repository)
     } else if path.exists() && path.read_dir().map_or(true, |mut x| x.next().is_some()) {
-        todo!()
+        Err(git2::Error::from_str(&format!(
+            "{:?} is not empty but does not contain a clone",
+            path
+        )))
     } else if let Some(fo) = fo {
         clone_helper(url, path, fo)
     } else {
-        panic!("there is no repo there, you can enable the cloning by provinding a fetch callback.")
+        Err(git2::Error::from_str(
+            "there is no repo there, you can enable the cloning by provinding a fetch callback.",
+        ))
     }
 }
 
-fn clone_helper(url: Url, path: &Path, fo: git2::FetchOptions) -> Result<Repository, git2::Error> {
+/// Fetch `refs` (full names like `refs/pull/42/head`, or refspecs) from origin,
+/// for the ones not covered by the branches and tags fetched by default.
+pub fn fetch_refs(repository: &Repository, refs: &[&str]) -> Result<(), git2::Error> {
+    let refspecs: Vec<String> = refs
+        .iter()
+        .map(|r| {
+            if r.contains(':') {
+                r.to_string()
+            } else {
+                format!("+{}:{}", r, r)
+            }
+        })
+        .collect();
+    let refspecs: Vec<&str> = refspecs.iter().map(|x| x.as_str()).collect();
+    update_refs(repository, git2::FetchOptions::new(), &refspecs)
+}
+
+/// Clones are bare, so branches are mirrored to be designated by their plain names.
+const FETCHED_REFSPECS: &[&str] = &["+refs/heads/*:refs/heads/*", "+refs/tags/*:refs/tags/*"];
+
+/// Fetch `refspecs` from origin, then make HEAD follow the default branch of origin.
+fn update_refs(
+    repository: &Repository,
+    mut fo: git2::FetchOptions,
+    refspecs: &[&str],
+) -> Result<(), git2::Error> {
+    let mut remote = repository.find_remote("origin")?;
+    // the default branch is only advertised while connected
+    let default_branch = remote
+        .connect(git2::Direction::Fetch)
+        .and_then(|()| remote.default_branch())
+        .ok()
+        .and_then(|b| b.as_str().map(|b| b.to_string()));
+    remote.disconnect()?;
+    fo.download_tags(git2::AutotagOption::All);
+    remote.fetch(refspecs, Some(&mut fo), None)?;
+    if let Some(branch) = default_branch {
+        if repository.find_reference(&branch).is_ok() {
+            repository.set_head(&branch)?;
+        }
+    }
+    Ok(())
+}
+
+fn clone_helper(
+    url: Url,
+    path: &Path,
+    mut fo: git2::FetchOptions,
+) -> Result<Repository, git2::Error> {
     let mut builder = git2::build::RepoBuilder::new();
 
+    fo.download_tags(git2::AutotagOption::All);
+
     builder.bare(true);
 
     builder.fetch_options(fo);
@@ -440,6 +737,17 @@ fn clone_helper(url: Url, path: &Path, fo: git2::FetchOptions) -> Result<Reposit
         }
         Err(e) => return Err(e),
     };
+    // mirror the branches, like later fetches do (see FETCHED_REFSPECS)
+    for r in repository.references_glob("refs/remotes/origin/*")? {
+        let r = r?;
+        let (Some(name), Some(oid)) = (r.name(), r.target()) else {
+            continue;
+        };
+        let branch = name.trim_start_matches("refs/remotes/origin/");
+        if branch != "HEAD" {
+            repository.reference(&format!("refs/heads/{}", branch), oid, true, "mirror")?;
+        }
+    }
     Ok(repository)
 }
 
@@ -574,7 +882,7 @@ pub fn read_position_floating_lines(
     position: &Position,
     lines: usize,
 ) -> Result<(String, String, String), git2::Error> {
-    let blob = blob_position(repo, Oid::from_str(commit)?, &position)?;
+    let blob = blob_position(repo, retrieve_commit(repo, commit)?.id(), &position)?;
     compute_range_floating(
         blob.content(),
         position,
@@ -619,7 +927,7 @@ pub fn read_position_floating(
     position: &Position,
     radius: usize,
 ) -> Result<(String, String, String), git2::Error> {
-    let blob = blob_position(repo, Oid::from_str(commit)?, &position)?;
+    let blob = blob_position(repo, retrieve_commit(repo, commit)?.id(), &position)?;
     compute_range_floating(
         blob.content(),
         position,
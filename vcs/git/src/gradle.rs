@@ -0,0 +1,495 @@
+use std::path::{Path, PathBuf};
+
+use hyperast::store::defaults::{LabelIdentifier, NodeIdentifier};
+use hyperast_gen_ts_xml::{legion::XmlTreeGen, types::TStore};
+
+use crate::{
+    maven::{MavenModuleAcc, SemFlags},
+    processing::ObjectName,
+    DefaultMetrics, ParseErr, PROPAGATE_ERROR_ON_BAD_CST_NODE,
+};
+
+/// Handles a build script (build.gradle(.kts)) or a settings script (settings.gradle(.kts)).
+///
+/// There is no groovy nor kotlin grammar for now,
+/// so like for Makefiles the script is stored as an empty document,
+/// only its name is kept, to identify gradle projects.
+pub(crate) fn handle_gradle_file<'a>(
+    tree_gen: &mut XmlTreeGen<'a, TStore>,
+    name: &ObjectName,
+    text: &'a [u8],
+) -> Result<GradleFile, ParseErr> {
+    let content = std::str::from_utf8(text)?;
+    const PLACEHOLDER: &[u8] = b"<project></project>";
+    let tree = match hyperast_gen_ts_xml::legion::tree_sitter_parse_xml(PLACEHOLDER) {
+        Ok(tree) => tree,
+        Err(tree) => {
+            log::warn!("bad CST: {:?}", name.try_str());
+            log::debug!("{}", tree.root_node().to_sexp());
+            if PROPAGATE_ERROR_ON_BAD_CST_NODE {
+                return Err(ParseErr::IllFormed);
+            } else {
+                tree
+            }
+        }
+    };
+    let x = tree_gen
+        .generate_file(name.as_bytes(), PLACEHOLDER, tree.walk())
+        .local;
+    let is_settings = name.as_bytes().starts_with(b"settings.gradle");
+    let x = if is_settings {
+        GradleFile {
+            compressed_node: x.compressed_node,
+            metrics: x.metrics,
+            subprojects: included_projects(content),
+            source_dirs: None,
+            test_source_dirs: None,
+        }
+    } else {
+        let (main, test) = source_sets(content);
+        GradleFile {
+            compressed_node: x.compressed_node,
+            metrics: x.metrics,
+            subprojects: vec![],
+            source_dirs: Some(main),
+            test_source_dirs: Some(test),
+        }
+    };
+    Ok(x)
+}
+
+#[derive(Debug, Clone)]
+pub struct GradleFile {
+    pub compressed_node: NodeIdentifier,
+    pub metrics: DefaultMetrics,
+    /// directories of the projects included by a settings script
+    subprojects: Vec<String>,
+    /// only declared by build scripts
+    source_dirs: Option<SourceDirs>,
+    test_source_dirs: Option<SourceDirs>,
+}
+
+/// The conventional java source directories of gradle projects
+pub const MAIN_JAVA: &str = "src/main/java";
+pub const TEST_JAVA: &str = "src/test/java";
+
+/// The java source directories of a source set, see [`source_sets`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceDirs {
+    /// [`MAIN_JAVA`] or [`TEST_JAVA`]
+    pub conventional: &'static str,
+    /// the directories were assigned, the conventional one is not kept
+    pub replaced: bool,
+    /// relative to the directory of the build script
+    pub dirs: Vec<String>,
+}
+
+impl SourceDirs {
+    fn new(conventional: &'static str) -> Self {
+        Self {
+            conventional,
+            replaced: false,
+            dirs: vec![],
+        }
+    }
+
+    /// all the directories, starting with the conventional one if it is kept
+    pub fn all(&self) -> impl Iterator<Item = &str> {
+        let conventional = (!self.replaced).then_some(self.conventional);
+        conventional
+            .into_iter()
+            .chain(self.dirs.iter().map(String::as_str))
+    }
+
+    fn declare(&mut self, replace: bool, dirs: Vec<&str>) {
+        if replace {
+            self.replaced = true;
+            self.dirs.clear();
+        }
+        for d in dirs {
+            let d = d.trim_start_matches("./").trim_end_matches('/');
+            if d.is_empty() || d.starts_with("..") || d.starts_with('/') || d.contains('$') {
+                log::warn!("gradle source directory {:?} is not handled", d);
+                continue;
+            }
+            if !self.dirs.iter().any(|x| x == d) {
+                self.dirs.push(d.to_string());
+            }
+        }
+    }
+}
+
+impl MavenModuleAcc {
+    /// Like [`MavenModuleAcc::push_pom`],
+    /// but a gradle project can contain both a settings and a build script, so directories are accumulated.
+    pub(crate) fn push_gradle_file(&mut self, name: LabelIdentifier, full_node: GradleFile) {
+        self.status |= SemFlags::IsMavenModule;
+        assert!(!self.primary.children_names.contains(&name));
+        self.primary.children.push(full_node.compressed_node);
+        self.primary.children_names.push(name);
+        let extend = |v: &mut Option<Vec<PathBuf>>, dirs: Vec<PathBuf>| {
+            let v = v.get_or_insert_with(Vec::new);
+            for d in dirs {
+                if !v.contains(&d) {
+                    v.push(d);
+                }
+            }
+        };
+        // the conventional directory was possibly given to a subproject before reading its build script
+        let declare = |v: &mut Option<Vec<PathBuf>>, dirs: Option<SourceDirs>| {
+            let Some(dirs) = dirs else {
+                return;
+            };
+            if let (true, Some(v)) = (dirs.replaced, v.as_mut()) {
+                v.retain(|d| d != Path::new(dirs.conventional));
+            }
+            extend(v, dirs.all().map(PathBuf::from).collect());
+        };
+        declare(&mut self.main_dirs, full_node.source_dirs);
+        declare(&mut self.test_dirs, full_node.test_source_dirs);
+        let subprojects = full_node.subprojects.into_iter().map(PathBuf::from);
+        extend(&mut self.sub_modules, subprojects.collect());
+        self.primary.metrics.acc(full_node.metrics);
+    }
+}
+
+/// Directories of the projects included in a settings script,
+/// relative to the directory of the settings script.
+///
+/// Handles `include ':a', ':b:c'`, its kotlin variant `include(":a", ":b:c")`,
+/// and `project(':a').projectDir = file('path')`.
+/// Projects outside of the settings directory are ignored.
+pub fn included_projects(text: &str) -> Vec<String> {
+    let text = strip_comments(text);
+    let mut projects: Vec<(String, String)> = vec![];
+    let mut project_dirs: Vec<(String, String)> = vec![];
+    let mut rest = text.as_str();
+    while let Some(i) = find_word(rest, "include") {
+        let args = &rest[i + "include".len()..];
+        let trimmed = args.trim_start();
+        let args = if let Some(args) = trimmed.strip_prefix('(') {
+            &args[..args.find(')').unwrap_or(args.len())]
+        } else {
+            // groovy call without parenthesis, possibly continued after a trailing comma
+            let mut end = 0;
+            for line in args.split_inclusive('\n') {
+                end += line.len();
+                if !line.trim_end().ends_with(',') && !line.trim().is_empty() {
+                    break;
+                }
+            }
+            &args[..end]
+        };
+        for p in quoted(args) {
+            projects.push((p.to_string(), project_to_dir(p)));
+        }
+        rest = &rest[i + "include".len()..];
+    }
+    for line in text.lines() {
+        if !line.contains(".projectDir") {
+            continue;
+        }
+        let q = quoted(line);
+        if let (Some(p), Some(d)) = (q.first(), q.last()) {
+            if q.len() >= 2 {
+                project_dirs.push((p.trim_start_matches(':').to_string(), d.to_string()));
+            }
+        }
+    }
+    let mut r = vec![];
+    for (p, dir) in projects {
+        let dir = project_dirs
+            .iter()
+            .find(|(q, _)| q == p.trim_start_matches(':'))
+            .map_or(dir, |(_, d)| d.trim_start_matches("./").to_string());
+        if dir.is_empty() || dir.starts_with("..") || dir.starts_with('/') {
+            log::warn!("gradle project {} in {:?} is not handled", p, dir);
+            continue;
+        }
+        if !r.contains(&dir) {
+            r.push(dir);
+        }
+    }
+    r
+}
+
+/// The java source directories of the `main` and `test` source sets of a build script.
+///
+/// Handles the usual forms of `sourceSets { main { java { srcDirs = ['src'] } } }`,
+/// e.g. `sourceSets.main.java.srcDir 'gen'` or in kotlin
+/// `sourceSets["test"].java.setSrcDirs(listOf("t"))` and `sourceSets.getByName("main") { ... }`.
+/// Assigning directories replaces the conventional one (see [`SourceDirs::replaced`]),
+/// `srcDir` and `srcDirs` without assignment add to it.
+pub fn source_sets(text: &str) -> (SourceDirs, SourceDirs) {
+    let text = strip_comments(text);
+    let mut main = SourceDirs::new(MAIN_JAVA);
+    let mut test = SourceDirs::new(TEST_JAVA);
+    // the names designated by the headers of the enclosing blocks
+    let mut blocks: Vec<Vec<String>> = vec![];
+    let mut statement = String::new();
+    let mut quote = None;
+    // of parenthesis and brackets, statements can span several lines inside them
+    let mut depth = 0usize;
+    let mut chars = text.chars().chain(['\n']);
+    while let Some(c) = chars.next() {
+        if let Some(q) = quote {
+            if c == q {
+                quote = None;
+            }
+            statement.push(c);
+            continue;
+        }
+        match c {
+            '\'' | '"' => quote = Some(c),
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth = depth.saturating_sub(1),
+            '{' => {
+                blocks.push(segments(statement.trim()));
+                statement.clear();
+                depth = 0;
+                continue;
+            }
+            '}' | '\n' | ';' if c == '}' || depth == 0 => {
+                declare_source_dirs(&blocks, &statement, &mut main, &mut test);
+                statement.clear();
+                if c == '}' {
+                    blocks.pop();
+                }
+                continue;
+            }
+            _ => (),
+        }
+        statement.push(c);
+    }
+    (main, test)
+}
+
+/// Handles a statement such as `main.java.srcDirs = ['src']`, in the scope of `blocks`
+fn declare_source_dirs(
+    blocks: &[Vec<String>],
+    statement: &str,
+    main: &mut SourceDirs,
+    test: &mut SourceDirs,
+) {
+    let Some((i, method)) = ["setSrcDirs", "srcDirs", "srcDir"]
+        .into_iter()
+        .find_map(|m| Some((find_member(statement, m)?, m)))
+    else {
+        return;
+    };
+    let prefix = statement[..i].trim().trim_end_matches('.');
+    let args = &statement[i + method.len()..];
+    let path: Vec<String> = (blocks.iter().flatten().cloned())
+        .chain(segments(prefix))
+        .collect();
+    let Some(i) = path.iter().position(|x| x == "sourceSets") else {
+        return;
+    };
+    let dirs = match &path[i + 1..] {
+        [set, java] if set == "main" && java == "java" => main,
+        [set, java] if set == "test" && java == "java" => test,
+        _ => return,
+    };
+    let replace = method == "setSrcDirs" || args.trim_start().starts_with('=');
+    dirs.declare(replace, quoted(args));
+}
+
+/// position of the method or property `name` in `s`, e.g. in `java.srcDir 'gen'`
+fn find_member(s: &str, name: &str) -> Option<usize> {
+    s.match_indices(name).map(|(i, _)| i).find(|&i| {
+        let before = s[..i].chars().next_back();
+        let after = s[i + name.len()..].chars().next();
+        before.map_or(true, |c| c == '.' || c.is_whitespace())
+            && !after.map_or(false, |c| c.is_alphanumeric() || c == '_')
+    })
+}
+
+/// the names designated by a dotted expression,
+/// e.g. `sourceSets["main"].java` or `sourceSets.getByName("main")` give `sourceSets`, `main` (and `java`)
+fn segments(s: &str) -> Vec<String> {
+    let mut r = vec![];
+    for x in s.split('.').map(str::trim) {
+        match x.split_once(['(', '[']) {
+            // an indexing, e.g. `sourceSets["main"]`
+            Some((name, arg)) if x[name.len()..].starts_with('[') => {
+                r.push(name.to_string());
+                r.extend(quoted(arg).first().map(|x| x.to_string()));
+            }
+            // a lookup, e.g. `getByName("main")` or `named("main")`
+            Some((_, arg)) => r.extend(quoted(arg).first().map(|x| x.to_string())),
+            None if !x.is_empty() => r.push(x.to_string()),
+            None => (),
+        }
+    }
+    r
+}
+
+fn project_to_dir(project: &str) -> String {
+    project
+        .split(':')
+        .filter(|x| !x.is_empty())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// position of `word` in `s`, not being part of a longer identifier (e.g. `includeBuild`)
+fn find_word(s: &str, word: &str) -> Option<usize> {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_' || c == '.';
+    let mut offset = 0;
+    while let Some(i) = s[offset..].find(word) {
+        let i = offset + i;
+        let before = s[..i].chars().next_back();
+        let after = s[i + word.len()..].chars().next();
+        if !before.map_or(false, is_ident) && !after.map_or(false, is_ident) {
+            return Some(i);
+        }
+        offset = i + word.len();
+    }
+    None
+}
+
+/// content of the single or double quoted strings in `s`
+fn quoted(s: &str) -> Vec<&str> {
+    let mut r = vec![];
+    let mut rest = s;
+    while let Some(i) = rest.find(['\'', '"']) {
+        let q = rest[i..].chars().next().unwrap();
+        let Some(j) = rest[i + 1..].find(q) else {
+            break;
+        };
+        r.push(&rest[i + 1..i + 1 + j]);
+        rest = &rest[i + 1 + j + 1..];
+    }
+    r
+}
+
+/// removes line and block comments, but not inside strings, e.g. in urls
+fn strip_comments(text: &str) -> String {
+    let mut r = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    let mut quote = None;
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) => {
+                if c == q {
+                    quote = None;
+                }
+                r.push(c);
+            }
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                r.push(c);
+            }
+            (None, '/') if chars.peek() == Some(&'/') => {
+                while chars.peek().map_or(false, |c| *c != '\n') {
+                    chars.next();
+                }
+            }
+            (None, '/') if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                while let Some(c) = chars.next() {
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+            }
+            (None, c) => r.push(c),
+        }
+    }
+    r
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groovy_settings() {
+        let text = r#"
+pluginManagement {
+    repositories { maven { url 'https://example.org/m2' } }
+}
+rootProject.name = 'demo'
+// include ':commented'
+include ':app', ':lib:core',
+    'lib:extra'
+includeBuild 'build-logic'
+include ':moved'
+project(':moved').projectDir = file('somewhere/moved')
+"#;
+        assert_eq!(
+            included_projects(text),
+            vec!["app", "lib/core", "lib/extra", "somewhere/moved"]
+        );
+    }
+
+    #[test]
+    fn kotlin_settings() {
+        let text = r#"
+rootProject.name = "demo"
+include(
+    ":app",
+    ":lib:core", /* ":old", */
+)
+include(":outside")
+project(":outside").projectDir = file("../outside")
+"#;
+        assert_eq!(included_projects(text), vec!["app", "lib/core"]);
+    }
+
+    #[test]
+    fn groovy_source_sets() {
+        let text = r#"
+plugins { id 'java' }
+sourceSets {
+    main {
+        java {
+            srcDirs = ['src/java', './gen/']
+            // srcDir 'commented'
+        }
+        resources { srcDirs = ['res'] }
+    }
+    test {
+        java.srcDir 'src/it/java'
+    }
+}
+sourceSets.main.java.srcDir "$buildDir/generated"
+"#;
+        let (main, test) = source_sets(text);
+        assert_eq!(main.all().collect::<Vec<_>>(), vec!["src/java", "gen"]);
+        assert_eq!(
+            test.all().collect::<Vec<_>>(),
+            vec!["src/test/java", "src/it/java"]
+        );
+    }
+
+    #[test]
+    fn kotlin_source_sets() {
+        let text = r#"
+sourceSets {
+    getByName("main") {
+        java.setSrcDirs(listOf(
+            "src",
+        ))
+    }
+}
+sourceSets["test"].java.srcDirs("test", "../outside")
+"#;
+        let (main, test) = source_sets(text);
+        assert_eq!(main.all().collect::<Vec<_>>(), vec!["src"]);
+        assert_eq!(
+            test.all().collect::<Vec<_>>(),
+            vec!["src/test/java", "test"]
+        );
+    }
+
+    #[test]
+    fn conventional_source_sets() {
+        let text = "plugins { java }\ndependencies { testImplementation(\"junit:junit:4.13\") }";
+        let (main, test) = source_sets(text);
+        assert_eq!(main.all().collect::<Vec<_>>(), vec![MAIN_JAVA]);
+        assert_eq!(test.all().collect::<Vec<_>>(), vec![TEST_JAVA]);
+    }
+}
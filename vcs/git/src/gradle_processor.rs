@@ -0,0 +1,579 @@
+use crate::maven_processor::{make, prep_scripting, MavenModuleHelper, Parameter};
+use crate::processing::erased::{
+    CommitProcessorHandle, ParametrizedCommitProcessor2Handle as PCP2Handle,
+};
+use crate::processing::ParametrizedCommitProcessorHandle;
+use crate::StackEle;
+use crate::{
+    git::BasicGitObject,
+    maven::{MavenModuleAcc, MD},
+    preprocessed::RepositoryProcessor,
+    processing::{erased::ParametrizedCommitProc2, CacheHolding, InFiles, ObjectName},
+    Processor,
+};
+use git2::{Oid, Repository};
+use hyperast::store::defaults::NodeIdentifier;
+use hyperast::store::nodes::legion::RawHAST;
+use hyperast::types::ETypeStore as _;
+use hyperast_gen_ts_xml::types::{Type, XmlEnabledTypeStore as _};
+use std::{
+    iter::Peekable,
+    marker::PhantomData,
+    path::{Components, PathBuf},
+};
+
+/// Like the [`crate::maven_processor::MavenProcessor`] with a recursive module search,
+/// gradle projects are also represented with maven directories.
+pub struct GradleProcessor<'a, 'b, 'c, Acc> {
+    prepro: &'b mut RepositoryProcessor,
+    repository: &'a Repository,
+    stack: Vec<StackEle<Acc>>,
+    dir_path: &'c mut Peekable<Components<'c>>,
+    handle: ParametrizedCommitProcessorHandle,
+}
+
+impl<'a, 'b, 'c> GradleProcessor<'a, 'b, 'c, MavenModuleAcc> {
+    pub fn new(
+        repository: &'a Repository,
+        prepro: &'b mut RepositoryProcessor,
+        mut dir_path: &'c mut Peekable<Components<'c>>,
+        name: &[u8],
+        oid: git2::Oid,
+        handle: ParametrizedCommitProcessorHandle,
+    ) -> Self {
+        let tree = repository.find_tree(oid).unwrap();
+        let prepared = prepare_dir_exploration(tree, &mut dir_path);
+        let name = std::str::from_utf8(&name).unwrap().to_string();
+        let acc = MavenModuleAcc::new(name);
+        let prep_scripting = prep_scripting(prepro, handle.1);
+        let acc = acc.init_scripting(
+            prep_scripting
+                .map(|x| {
+                    hyperast::scripting::Prepro::<
+                        RawHAST<hyperast_gen_ts_java::types::TStore>,
+                        &hyperast_gen_ts_java::legion_with_refs::Acc,
+                    >::from(x.clone())
+                })
+                .as_ref(),
+        );
+        let stack = vec![StackEle::new(oid, prepared, acc)];
+        Self {
+            stack,
+            repository,
+            prepro,
+            dir_path,
+            handle,
+        }
+    }
+}
+
+impl<'a, 'b, 'c> Processor<MavenModuleAcc> for GradleProcessor<'a, 'b, 'c, MavenModuleAcc> {
+    fn pre(&mut self, current_dir: BasicGitObject) {
+        match current_dir {
+            BasicGitObject::Tree(oid, name) => {
+                self.handle_tree_cached(name, oid);
+            }
+            BasicGitObject::Blob(oid, name)
+                if !self.dir_path.peek().is_some()
+                    && crate::processing::file_sys::GradleFile::matches(&name) =>
+            {
+                let parent_acc = &mut self.stack.last_mut().unwrap().acc;
+                let parameters = PCP2Handle(self.handle.1, PhantomData);
+                if let Err(err) = self.prepro.handle_gradle_file(
+                    oid,
+                    parent_acc,
+                    name,
+                    &self.repository,
+                    parameters,
+                ) {
+                    log::debug!("{:?}", err);
+                }
+            }
+            _ => {}
+        }
+    }
+    fn post(&mut self, oid: Oid, acc: MavenModuleAcc) -> Option<(NodeIdentifier, MD)> {
+        let name = acc.primary.name.clone();
+        let full_node = make(acc, self.prepro.main_stores_mut().mut_with_ts());
+        log::info!("tree size: {}", full_node.1.metrics.size);
+        self.prepro
+            .processing_systems
+            .mut_or_default::<GradleProcessorHolder>()
+            .with_parameters_mut(self.handle.1)
+            .get_caches_mut()
+            .object_map
+            .insert(oid, full_node.clone());
+        let name = self.prepro.intern_label(&name);
+        if self.stack.is_empty() {
+            Some(full_node)
+        } else {
+            let w = &mut self.stack.last_mut().unwrap().acc;
+            assert!(
+                !w.primary.children_names.contains(&name),
+                "{:?} {:?}",
+                w.primary.children_names,
+                name
+            );
+            let id = full_node.0;
+            if full_node
+                .1
+                .status
+                .contains(crate::maven::SemFlags::IsMavenModule)
+            {
+                w.push_submodule(name, full_node);
+            } else {
+                w.push((name, full_node));
+            }
+
+            if let Some(acc) = &mut w.scripting_acc {
+                // SAFETY: this side should be fine, issue when unerasing
+                let store = unsafe { self.prepro.main_stores.erase_ts_unchecked() };
+                let child: hyperast::scripting::lua_scripting::SubtreeHandle<
+                    hyperast_gen_ts_xml::types::TType,
+                > = id.into();
+                acc.acc(store, Type::Directory, child).unwrap();
+            }
+            None
+        }
+    }
+
+    fn stack(&mut self) -> &mut Vec<StackEle<MavenModuleAcc>> {
+        &mut self.stack
+    }
+}
+
+impl<'a, 'b, 'c> GradleProcessor<'a, 'b, 'c, MavenModuleAcc> {
+    fn handle_tree_cached(&mut self, name: ObjectName, oid: Oid) {
+        if let Some(s) = self.dir_path.peek() {
+            if name
+                .as_bytes()
+                .eq(std::ffi::OsStr::as_encoded_bytes(s.as_os_str()))
+            {
+                self.dir_path.next();
+                self.stack.last_mut().expect("never empty").cs.clear();
+                let tree = self.repository.find_tree(oid).unwrap();
+                let prepared = prepare_dir_exploration(tree, &mut self.dir_path);
+                let acc = MavenModuleAcc::new(name.try_into().unwrap());
+                let prep_scripting = prep_scripting(&self.prepro, self.handle.1);
+                let acc = acc.init_scripting(
+                    prep_scripting
+                        .map(|x| hyperast::scripting::Prepro::from(x.clone()))
+                        .as_ref(),
+                );
+                self.stack.push(StackEle::new(oid, prepared, acc));
+            }
+            return;
+        }
+        let gradle_proc = self
+            .prepro
+            .processing_systems
+            .mut_or_default::<GradleProcessorHolder>()
+            .with_parameters_mut(self.handle.1);
+        let java_handle = gradle_proc.parameter.java_handle;
+        if let Some(already) = gradle_proc.get_caches_mut().object_map.get(&oid) {
+            // reinit already computed node for post order
+            let full_node = already.clone();
+            let w = &mut self.stack.last_mut().unwrap().acc;
+            let name = self.prepro.intern_object_name(&name);
+            assert!(!w.primary.children_names.contains(&name));
+            let id = full_node.0;
+            if full_node
+                .1
+                .status
+                .contains(crate::maven::SemFlags::IsMavenModule)
+            {
+                w.push_submodule(name, full_node);
+            } else {
+                w.push((name, full_node));
+            }
+            if let Some(acc) = &mut w.scripting_acc {
+                // SAFETY: this side should be fine, issue when unerasing
+                let store = unsafe { self.prepro.main_stores.erase_ts_unchecked() };
+                acc.acc::<_, hyperast_gen_ts_xml::types::TType, _>(
+                    store,
+                    Type::Directory,
+                    id.into(),
+                )
+                .unwrap();
+            }
+            return;
+        }
+
+        log::debug!("gradle tree {:?}", name.try_str());
+        let parent_acc = &mut self.stack.last_mut().unwrap().acc;
+        let helper = MavenModuleHelper::from((parent_acc, &name));
+        if helper.source_directories.0 || helper.test_source_directories.0 {
+            // handle as source dir
+            let (name, (full_node, _)) = self.prepro.help_handle_java_folder(
+                &self.repository,
+                self.dir_path,
+                oid,
+                &name,
+                java_handle,
+            );
+            let id = full_node.compressed_node;
+            let parent_acc = &mut self.stack.last_mut().unwrap().acc;
+            assert!(!parent_acc.primary.children_names.contains(&name));
+            if helper.source_directories.0 {
+                parent_acc.push_source_directory(name, full_node);
+            } else {
+                parent_acc.push_test_source_directory(name, full_node);
+            }
+
+            if let Some(acc) = &mut parent_acc.scripting_acc {
+                // SAFETY: this side should be fine, issue when unerasing
+                let store = unsafe { self.prepro.main_stores.erase_ts_unchecked() };
+                acc.acc::<_, hyperast_gen_ts_java::types::TType, _>(
+                    store,
+                    Type::Directory,
+                    id.into(),
+                )
+                .unwrap();
+            }
+            return;
+        }
+        // projects included by a settings script, or with their own build script, are found the same way
+        let is_subproject = helper.submodules.0;
+        let tree = self.repository.find_tree(oid).unwrap();
+        let prepared = prepare_dir_exploration(tree, &mut self.dir_path);
+        let prep_scripting = prep_scripting(&self.prepro, self.handle.1);
+        let mut acc = helper.into_acc();
+        if is_subproject {
+            // a subproject without a build script still follows the conventional layout
+            acc.main_dirs
+                .get_or_insert_with(Vec::new)
+                .push(PathBuf::from(crate::gradle::MAIN_JAVA));
+            acc.test_dirs
+                .get_or_insert_with(Vec::new)
+                .push(PathBuf::from(crate::gradle::TEST_JAVA));
+        }
+        let acc = acc.init_scripting(
+            prep_scripting
+                .map(|x| hyperast::scripting::Prepro::from(x.clone()))
+                .as_ref(),
+        );
+        self.stack.push(StackEle::new(oid, prepared, acc));
+    }
+}
+
+use hyperast_gen_ts_xml::legion::XmlTreeGen;
+impl RepositoryProcessor {
+    fn handle_gradle_file(
+        &mut self,
+        oid: Oid,
+        parent_acc: &mut MavenModuleAcc,
+        name: ObjectName,
+        repository: &Repository,
+        parameters: PCP2Handle<GradleFileProc>,
+    ) -> Result<(), crate::ParseErr> {
+        let x = self
+            .processing_systems
+            .caching_blob_handler::<crate::processing::file_sys::GradleFile>()
+            .handle2(oid, repository, &name, parameters, |_c, n, t| {
+                crate::gradle::handle_gradle_file(
+                    &mut XmlTreeGen {
+                        line_break: "\n".as_bytes().to_vec(),
+                        stores: self.main_stores.mut_with_ts(),
+                    },
+                    n,
+                    t,
+                )
+            })?;
+        let name = self.intern_object_name(&name);
+        assert!(!parent_acc.primary.children_names.contains(&name));
+        parent_acc.push_gradle_file(name, x);
+        Ok(())
+    }
+}
+
+/// sometimes order of files/dirs can be important, similarly to order of statement
+/// exploration order for example
+pub(crate) fn prepare_dir_exploration(
+    tree: git2::Tree,
+    dir_path: &mut Peekable<Components>,
+) -> Vec<BasicGitObject> {
+    let mut children_objects: Vec<BasicGitObject> = tree
+        .iter()
+        .map(TryInto::try_into)
+        .filter_map(|x| x.ok())
+        .collect();
+    if dir_path.peek().is_none() {
+        let is_script = |x: &BasicGitObject| match x {
+            BasicGitObject::Blob(_, n) => crate::processing::file_sys::GradleFile::matches(&n),
+            _ => false,
+        };
+        if children_objects.iter().any(is_script) {
+            // priority to gradle scripts processing, they declare subprojects and source dirs
+            children_objects.sort_by_key(|x| !is_script(x));
+            children_objects.reverse(); // we use it like a stack
+        }
+    }
+    children_objects
+}
+
+// # Gradle scripts
+
+impl From<PCP2Handle<GradleProc>> for PCP2Handle<GradleFileProc> {
+    fn from(value: PCP2Handle<GradleProc>) -> Self {
+        PCP2Handle(value.0, PhantomData)
+    }
+}
+struct GradleFileProcessorHolder(Option<GradleFileProc>);
+impl Default for GradleFileProcessorHolder {
+    fn default() -> Self {
+        Self(Some(GradleFileProc {
+            parameter: None,
+            cache: Default::default(),
+        }))
+    }
+}
+struct GradleFileProc {
+    parameter: Option<Parameter>,
+    cache: crate::processing::caches::GradleFile,
+}
+impl crate::processing::erased::Parametrized for GradleFileProcessorHolder {
+    type T = Parameter;
+    fn register_param(
+        &mut self,
+        t: Self::T,
+    ) -> crate::processing::erased::ParametrizedCommitProcessorHandle {
+        let l = self
+            .0
+            .iter()
+            .position(|x| x.parameter.as_ref() == Some(&t))
+            .unwrap_or_else(|| {
+                let l = 0;
+                self.0 = Some(GradleFileProc {
+                    parameter: Some(t),
+                    cache: Default::default(),
+                });
+                l
+            });
+        use crate::processing::erased::ConfigParametersHandle;
+        use crate::processing::erased::ParametrizedCommitProc;
+        ParametrizedCommitProcessorHandle(self.erased_handle(), ConfigParametersHandle(l))
+    }
+}
+impl crate::processing::erased::CommitProc for GradleFileProc {
+    fn prepare_processing(
+        &self,
+        _repository: &git2::Repository,
+        _commit_builder: crate::preprocessed::CommitBuilder,
+        _param_handle: crate::processing::erased::ParametrizedCommitProcessorHandle,
+    ) -> Box<dyn crate::processing::erased::PreparedCommitProc> {
+        unimplemented!("required for processing at the root of a project")
+    }
+
+    fn get_commit(&self, _commit_oid: git2::Oid) -> Option<&crate::Commit> {
+        unimplemented!("required for processing at the root of a project")
+    }
+
+    fn commits(&self) -> Box<dyn Iterator<Item = (&git2::Oid, &crate::Commit)> + '_> {
+        unimplemented!("required for processing at the root of a project")
+    }
+
+    fn insert_commit(&mut self, _commit_oid: git2::Oid, _commit: crate::Commit) {
+        unimplemented!("required for processing at the root of a project")
+    }
+
+    fn remove_commit(&mut self, _commit_oid: git2::Oid) -> Option<crate::Commit> {
+        unimplemented!("required for processing at the root of a project")
+    }
+}
+
+impl crate::processing::erased::CommitProcExt for GradleFileProc {
+    type Holder = GradleFileProcessorHolder;
+}
+impl crate::processing::erased::ParametrizedCommitProc2 for GradleFileProcessorHolder {
+    type Proc = GradleFileProc;
+
+    fn with_parameters_mut(
+        &mut self,
+        parameters: crate::processing::erased::ConfigParametersHandle,
+    ) -> &mut Self::Proc {
+        assert_eq!(0, parameters.0);
+        self.0.as_mut().unwrap()
+    }
+
+    fn with_parameters(
+        &self,
+        parameters: crate::processing::erased::ConfigParametersHandle,
+    ) -> &Self::Proc {
+        assert_eq!(0, parameters.0);
+        self.0.as_ref().unwrap()
+    }
+}
+impl CacheHolding<crate::processing::caches::GradleFile> for GradleFileProc {
+    fn get_caches_mut(&mut self) -> &mut crate::processing::caches::GradleFile {
+        &mut self.cache
+    }
+
+    fn get_caches(&self) -> &crate::processing::caches::GradleFile {
+        &self.cache
+    }
+}
+impl CacheHolding<crate::processing::caches::GradleFile> for GradleFileProcessorHolder {
+    fn get_caches_mut(&mut self) -> &mut crate::processing::caches::GradleFile {
+        &mut self.0.as_mut().unwrap().cache
+    }
+
+    fn get_caches(&self) -> &crate::processing::caches::GradleFile {
+        &self.0.as_ref().unwrap().cache
+    }
+}
+
+// # Gradle
+#[derive(Default)]
+pub struct GradleProcessorHolder(Vec<GradleProc>);
+pub struct GradleProc {
+    parameter: Parameter,
+    cache: crate::processing::caches::Gradle,
+    commits: std::collections::HashMap<git2::Oid, crate::Commit>,
+}
+impl crate::processing::erased::Parametrized for GradleProcessorHolder {
+    type T = Parameter;
+    fn register_param(
+        &mut self,
+        t: Self::T,
+    ) -> crate::processing::erased::ParametrizedCommitProcessorHandle {
+        let l = self
+            .0
+            .iter()
+            .position(|x| &x.parameter == &t)
+            .unwrap_or_else(|| {
+                let l = self.0.len();
+                self.0.push(GradleProc {
+                    parameter: t,
+                    cache: Default::default(),
+                    commits: Default::default(),
+                });
+                l
+            });
+
+        use crate::processing::erased::ConfigParametersHandle;
+        use crate::processing::erased::ParametrizedCommitProc;
+        ParametrizedCommitProcessorHandle(self.erased_handle(), ConfigParametersHandle(l))
+    }
+}
+
+struct PreparedGradleCommitProc<'repo> {
+    repository: &'repo git2::Repository,
+    commit_builder: crate::preprocessed::CommitBuilder,
+    pub(crate) handle: ParametrizedCommitProcessorHandle,
+}
+
+impl<'repo> crate::processing::erased::PreparedCommitProc for PreparedGradleCommitProc<'repo> {
+    fn process(
+        self: Box<PreparedGradleCommitProc<'repo>>,
+        prepro: &mut RepositoryProcessor,
+    ) -> hyperast::store::defaults::NodeIdentifier {
+        let dir_path = PathBuf::from("");
+        let mut dir_path = dir_path.components().peekable();
+        let name = b"";
+        let root_full_node = GradleProcessor::<MavenModuleAcc>::new(
+            self.repository,
+            prepro,
+            &mut dir_path,
+            name,
+            self.commit_builder.tree_oid(),
+            self.handle,
+        )
+        .process();
+        let h = prepro
+            .processing_systems
+            .mut_or_default::<GradleProcessorHolder>();
+        let handle = self.handle;
+        let commit_oid = self.commit_builder.commit_oid();
+        let commit = self.commit_builder.finish(root_full_node.0);
+        h.with_parameters_mut(handle.1)
+            .commits
+            .insert(commit_oid, commit);
+        root_full_node.0
+    }
+}
+
+impl crate::processing::erased::CommitProc for GradleProc {
+    fn prepare_processing<'repo>(
+        &self,
+        repository: &'repo git2::Repository,
+        commit_builder: crate::preprocessed::CommitBuilder,
+        handle: crate::processing::ParametrizedCommitProcessorHandle,
+    ) -> Box<dyn crate::processing::erased::PreparedCommitProc + 'repo> {
+        Box::new(PreparedGradleCommitProc {
+            repository,
+            commit_builder,
+            handle,
+        })
+    }
+
+    fn get_commit(&self, commit_oid: git2::Oid) -> Option<&crate::Commit> {
+        self.commits.get(&commit_oid)
+    }
+
+    fn commits(&self) -> Box<dyn Iterator<Item = (&git2::Oid, &crate::Commit)> + '_> {
+        Box::new(self.commits.iter())
+    }
+
+    fn insert_commit(&mut self, commit_oid: git2::Oid, commit: crate::Commit) {
+        self.commits.insert(commit_oid, commit);
+    }
+
+    fn remove_commit(&mut self, commit_oid: git2::Oid) -> Option<crate::Commit> {
+        self.commits.remove(&commit_oid)
+    }
+
+    fn clear_caches(&mut self) {
+        self.cache = Default::default();
+    }
+
+    fn get_lang_handle(&self, lang: &str) -> Option<ParametrizedCommitProcessorHandle> {
+        if lang.eq_ignore_ascii_case("java") {
+            Some(ParametrizedCommitProcessorHandle(
+                CommitProcessorHandle(std::any::TypeId::of::<
+                    crate::java_processor::JavaProcessorHolder,
+                >()),
+                self.parameter.java_handle.0,
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+impl crate::processing::erased::CommitProcExt for GradleProc {
+    type Holder = GradleProcessorHolder;
+}
+impl crate::processing::erased::ParametrizedCommitProc2 for GradleProcessorHolder {
+    type Proc = GradleProc;
+
+    fn with_parameters_mut(
+        &mut self,
+        parameters: crate::processing::erased::ConfigParametersHandle,
+    ) -> &mut Self::Proc {
+        &mut self.0[parameters.0]
+    }
+
+    fn with_parameters(
+        &self,
+        parameters: crate::processing::erased::ConfigParametersHandle,
+    ) -> &Self::Proc {
+        &self.0[parameters.0]
+    }
+}
+impl CacheHolding<crate::processing::caches::Gradle> for GradleProc {
+    fn get_caches_mut(&mut self) -> &mut crate::processing::caches::Gradle {
+        &mut self.cache
+    }
+    fn get_caches(&self) -> &crate::processing::caches::Gradle {
+        &self.cache
+    }
+}
+
+impl CacheHolding<crate::processing::caches::Gradle> for GradleProcessorHolder {
+    fn get_caches_mut(&mut self) -> &mut crate::processing::caches::Gradle {
+        &mut self.0[0].cache
+    }
+    fn get_caches(&self) -> &crate::processing::caches::Gradle {
+        &self.0[0].cache
+    }
+}
@@ -1,6 +1,8 @@
 use std::time::Instant;
 
 use crate::java_processor::SimpleStores;
+use crate::processing::pre_parsing::Parsed;
+use crate::processing::{Bounds, Comment};
 use crate::{BasicDirAcc, FailedParsing, FileProcessingResult, SuccessProcessing};
 use crate::{
     preprocessed::IsSkippedAna, processing::ObjectName, Accumulator,
@@ -32,9 +34,40 @@ where
     More: tree_gen::Prepro<SimpleStores>
         + tree_gen::PreproTSG<SimpleStores, Acc = java_tree_gen::Acc>,
 {
-    let time = Instant::now();
-    let tree = java_tree_gen::tree_sitter_parse(text);
-    let parsing_time = time.elapsed();
+    handle_java_file_with(tree_gen, name, text, None, Default::default())
+}
+
+/// Same as [`handle_java_file`] but reuses the tree of `pre` if the blob was parsed ahead of time,
+/// see [`crate::processing::pre_parsing`].
+/// Otherwise the file is parsed within `bounds`, or replaced by a placeholder.
+pub(crate) fn handle_java_file_with<'stores, 'cache, 'b: 'stores, More>(
+    tree_gen: &mut java_tree_gen::JavaTreeGen<
+        'stores,
+        'cache,
+        TStore,
+        hyperast::store::SimpleStores<TStore>,
+        More,
+    >,
+    name: &ObjectName,
+    text: &'b [u8],
+    pre: Option<Parsed>,
+    bounds: Bounds,
+) -> FileProcessingResult<java_tree_gen::FNode>
+where
+    More: tree_gen::Prepro<SimpleStores>
+        + tree_gen::PreproTSG<SimpleStores, Acc = java_tree_gen::Acc>,
+{
+    let language = hyperast_gen_ts_java::language();
+    let (tree, parsing_time) = match pre {
+        Some(pre) => (pre.tree, pre.parsing_time),
+        None => {
+            let time = Instant::now();
+            let tree = bounds.parse(text, &language);
+            (tree, time.elapsed())
+        }
+    };
+    let (text, tree, skipped) =
+        bounds.or_placeholder(name, text, tree, &language, Comment::Slashes);
     let tree = match tree {
         Ok(tree) => tree,
         Err(tree) => {
@@ -51,6 +84,7 @@ where
             }
         }
     };
+    let errors = skipped.map_or_else(|| crate::parse_errors::collect(&tree), |x| vec![x]);
     let time = Instant::now();
     let node = tree_gen.generate_file(&name.as_bytes(), text, tree.walk());
     let processing_time = time.elapsed();
@@ -58,10 +92,11 @@ where
         parsing_time,
         processing_time,
         node,
+        errors,
     })
 }
 
-type PrecompQueries = u16;
+type PrecompQueries = hyperast::tree_gen::PrecompQueries;
 
 pub struct JavaAcc {
     /// Identifying elements and fundamental derived metrics used to accelerate deduplication.
@@ -293,7 +293,7 @@ fn make(acc: JavaAcc, stores: &mut SimpleStores) -> hyperast_gen_ts_java::legion
 
     let mut dyn_builder = hyperast::store::nodes::legion::dyn_builder::EntityBuilder::new();
 
-    add_md_precomp_queries(&mut dyn_builder, acc.precomp_queries);
+    add_md_precomp_queries(&mut dyn_builder, &acc.precomp_queries);
     let children_is_empty = primary.children.is_empty();
     if acc.skiped_ana {
         use hyperast::store::nodes::EntityBuilder;
@@ -526,6 +526,22 @@ impl crate::processing::erased::CommitProc for JavaProc {
         self.commits.get(&commit_oid)
     }
 
+    fn commits(&self) -> Box<dyn Iterator<Item = (&git2::Oid, &crate::Commit)> + '_> {
+        Box::new(self.commits.iter())
+    }
+
+    fn insert_commit(&mut self, commit_oid: git2::Oid, commit: crate::Commit) {
+        self.commits.insert(commit_oid, commit);
+    }
+
+    fn remove_commit(&mut self, commit_oid: git2::Oid) -> Option<crate::Commit> {
+        self.commits.remove(&commit_oid)
+    }
+
+    fn clear_caches(&mut self) {
+        self.cache = Default::default();
+    }
+
     fn get_precomp_query(&self) -> Option<hyperast_tsquery::ZeroSepArrayStr> {
         dbg!(&self.parameter.query);
         // if self.parameter.query.is_none() {
@@ -695,16 +711,21 @@ impl RepositoryProcessor {
         (name, full_node)
     }
 
-    fn handle_java_blob(
+    pub(crate) fn handle_java_blob(
         &mut self,
         oid: Oid,
         name: &ObjectName,
         repository: &Repository,
         parameters: crate::processing::erased::ParametrizedCommitProcessor2Handle<JavaProc>,
     ) -> Result<(java_tree_gen::Local, IsSkippedAna), crate::ParseErr> {
+        let pre = self
+            .pre_parsed
+            .take(oid, crate::processing::pre_parsing::Lang::Java);
         self.processing_systems
             .caching_blob_handler::<crate::processing::file_sys::Java>()
             .handle2(oid, repository, name, parameters, |c, n, t| {
+                let reporting = c.reporting().clone();
+                let bounds = crate::processing::Bounds::reporting(self.limits, &reporting);
                 let line_break = if t.contains(&b'\r') {
                     "\r\n".as_bytes().to_vec()
                 } else {
@@ -746,7 +767,7 @@ impl RepositoryProcessor {
                             stores, md_cache, more
                         )
                         .with_line_break(line_break);
-                        crate::java::handle_java_file(&mut java_tree_gen, n, t)
+                        crate::java::handle_java_file_with(&mut java_tree_gen, n, t, pre, bounds)
                     }
                 } else if let Some(precomp) = &java_proc.parameter.prepro {
                     let more = hyperast::scripting::Prepro::<_, _>::from_arc(precomp.clone());
@@ -754,18 +775,18 @@ impl RepositoryProcessor {
                     let mut java_tree_gen =
                         java_tree_gen::JavaTreeGen::with_preprocessing(stores, md_cache, more)
                             .with_line_break(line_break);
-                    crate::java::handle_java_file(&mut java_tree_gen, n, t)
+                    crate::java::handle_java_file_with(&mut java_tree_gen, n, t, pre, bounds)
                 } else if let Some(more) = &java_proc.query {
                     let more = &more.0;
                     let more: hyperast_tsquery::PreparedQuerying<_, _, _> = more.into();
                     let mut java_tree_gen =
                         java_tree_gen::JavaTreeGen::with_preprocessing(stores, md_cache, more)
                             .with_line_break(line_break);
-                    crate::java::handle_java_file::<_>(&mut java_tree_gen, n, t)
+                    crate::java::handle_java_file_with::<_>(&mut java_tree_gen, n, t, pre, bounds)
                 } else {
                     let mut java_tree_gen = java_tree_gen::JavaTreeGen::new(stores, md_cache)
                         .with_line_break(line_break);
-                    crate::java::handle_java_file(&mut java_tree_gen, n, t)
+                    crate::java::handle_java_file_with(&mut java_tree_gen, n, t, pre, bounds)
                 }
                 .map_err(|_| crate::ParseErr::IllFormed)?;
 
@@ -778,6 +799,8 @@ impl RepositoryProcessor {
                     java_proc.cache.md_cache.len(),
                     java_proc.cache.object_map.len()
                 );
+                self.parse_errors
+                    .insert(r.node.local.compressed_node, r.errors);
 
                 let r = r.node;
 
@@ -0,0 +1,276 @@
+//! Inverted index from labels to the files containing them.
+//!
+//! Answers "which files (and commits) contain label X" without walking the content of files,
+//! only directories are walked, see [`LabelIndex::in_tree`].
+//! As files are deduplicated, each distinct file is indexed once,
+//! while [`crate::preprocessed::RepositoryProcessor`] processes the commits using it.
+//!
+//! The index is optional, see [`crate::multi_preprocessed::PreProcessedRepositories::enable_label_index`].
+//! It is not persisted, it is rebuilt after loading a snapshot or collecting garbage,
+//! as labels are renumbered.
+use std::collections::{HashMap, HashSet};
+
+use hyperast::store::defaults::{LabelIdentifier, NodeIdentifier};
+use hyperast::types::{HyperAST, HyperType, LabelStore, Labeled, WithChildren};
+
+use crate::SimpleStores;
+
+/// see the [module documentation](self)
+#[derive(Default, Debug)]
+pub struct LabelIndex {
+    files: HashMap<LabelIdentifier, HashSet<NodeIdentifier>>,
+    /// files and directories already indexed, they are skipped in the following commits
+    indexed: HashSet<NodeIdentifier>,
+    /// kinds of the nodes of indexed files, along whether all of them are labeled
+    kinds: HashMap<&'static str, bool>,
+}
+
+impl LabelIndex {
+    /// Indexes the files under the directory `root` (e.g. the root of a commit)
+    /// that are not already indexed
+    pub fn index_tree(&mut self, stores: &SimpleStores, root: NodeIdentifier) {
+        let mut stack = vec![root];
+        while let Some(id) = stack.pop() {
+            if !self.indexed.insert(id) {
+                continue;
+            }
+            if !stores.resolve_type(&id).is_directory() {
+                self.index_file(stores, id);
+                continue;
+            }
+            let n = stores.node_store.resolve(id);
+            if let Some(cs) = n.children() {
+                stack.extend(cs.iter_children().copied());
+            }
+        }
+    }
+
+    fn index_file(&mut self, stores: &SimpleStores, file: NodeIdentifier) {
+        let mut labels = HashSet::new();
+        // subtrees are often repeated in a file
+        let mut visited = HashSet::new();
+        let mut stack = vec![file];
+        while let Some(id) = stack.pop() {
+            if !visited.insert(id) {
+                continue;
+            }
+            let n = stores.node_store.resolve(id);
+            let label = n.try_get_label();
+            if let Some(l) = label {
+                labels.insert(*l);
+            }
+            let kind = stores.resolve_type(&id).as_static_str();
+            *self.kinds.entry(kind).or_insert(true) &= label.is_some();
+            if let Some(cs) = n.children() {
+                stack.extend(cs.iter_children().copied());
+            }
+        }
+        for l in labels {
+            self.files.entry(l).or_default().insert(file);
+        }
+    }
+
+    /// The indexed files containing `label`, in no particular order
+    pub fn files(&self, label: &LabelIdentifier) -> impl Iterator<Item = NodeIdentifier> + '_ {
+        self.files.get(label).into_iter().flatten().copied()
+    }
+
+    pub fn file_contains(&self, file: &NodeIdentifier, label: &LabelIdentifier) -> bool {
+        self.files.get(label).map_or(false, |x| x.contains(file))
+    }
+
+    /// number of distinct labels
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// The files containing `label` under the directory `root` (e.g. the root of a commit),
+    /// along their path from `root`
+    pub fn in_tree(
+        &self,
+        stores: &SimpleStores,
+        root: NodeIdentifier,
+        label: &LabelIdentifier,
+    ) -> Vec<(String, NodeIdentifier)> {
+        let mut files = vec![];
+        let Some(containing) = self.files.get(label) else {
+            return files;
+        };
+        let mut stack = vec![(String::new(), root)];
+        while let Some((path, id)) = stack.pop() {
+            if containing.contains(&id) {
+                files.push((path, id));
+                continue;
+            }
+            if !stores.resolve_type(&id).is_directory() {
+                continue;
+            }
+            let n = stores.node_store.resolve(id);
+            let Some(cs) = n.children() else {
+                continue;
+            };
+            for c in cs.iter_children() {
+                let name = stores
+                    .node_store
+                    .resolve(*c)
+                    .try_get_label()
+                    .map_or("", |l| stores.label_store.resolve(l));
+                let path = if path.is_empty() {
+                    name.to_string()
+                } else {
+                    format!("{}/{}", path, name)
+                };
+                stack.push((path, *c));
+            }
+        }
+        files.sort();
+        files
+    }
+
+    /// Whether a file under `root` might contain all the `labels`,
+    /// files that are not indexed might contain anything
+    pub fn tree_contains(
+        &self,
+        stores: &SimpleStores,
+        root: NodeIdentifier,
+        labels: &[LabelIdentifier],
+    ) -> bool {
+        let Some((first, rest)) = labels.split_first() else {
+            return true;
+        };
+        let Some(containing) = self.files.get(first) else {
+            return !self.indexed.contains(&root);
+        };
+        let mut visited = HashSet::new();
+        let mut stack = vec![root];
+        while let Some(id) = stack.pop() {
+            if !visited.insert(id) {
+                continue;
+            }
+            if !self.indexed.contains(&id) {
+                return true;
+            }
+            if containing.contains(&id) {
+                if rest.iter().all(|l| self.file_contains(&id, l)) {
+                    return true;
+                }
+                continue;
+            }
+            if !stores.resolve_type(&id).is_directory() {
+                continue;
+            }
+            let n = stores.node_store.resolve(id);
+            if let Some(cs) = n.children() {
+                stack.extend(cs.iter_children().copied());
+            }
+        }
+        false
+    }
+
+    /// Whether a file under `root` might match a query, given the texts required by each of its patterns
+    /// along the kinds of the nodes compared to them, see [`hyperast_tsquery::Query::required_texts`].
+    ///
+    /// Only the texts compared to labeled leaves, e.g. identifiers, are looked for,
+    /// the text of other nodes (e.g. `modifiers`) is not a label.
+    pub fn may_match(
+        &self,
+        stores: &SimpleStores,
+        root: NodeIdentifier,
+        required: &[Vec<(&str, Vec<&str>)>],
+    ) -> bool {
+        required.iter().any(|texts| {
+            let labels: Option<Vec<_>> = (texts.iter())
+                .filter(|(_, kinds)| self.are_labeled(kinds))
+                .map(|(s, _)| stores.label_store.get(*s))
+                .collect();
+            // a text that is not even a label is nowhere
+            labels.map_or(false, |labels| self.tree_contains(stores, root, &labels))
+        })
+    }
+
+    /// Whether the nodes of these `kinds` are labeled in all indexed files,
+    /// false for kinds that were never indexed
+    fn are_labeled(&self, kinds: &[&str]) -> bool {
+        !kinds.is_empty() && kinds.iter().all(|k| self.kinds.get(*k) == Some(&true))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn files_containing_a_label() {
+        use hyperast_gen_ts_java::legion_with_refs as java_tree_gen;
+        let mut stores = SimpleStores::default();
+        let mut md_cache = Default::default();
+        let mut java_tree_gen = java_tree_gen::JavaTreeGen {
+            line_break: "\n".as_bytes().to_vec(),
+            stores: stores.mut_with_ts(),
+            md_cache: &mut md_cache,
+            more: (),
+        };
+        let mut file = |name: &[u8], text: &[u8]| {
+            crate::java::handle_java_file(&mut java_tree_gen, &name.into(), text)
+                .unwrap()
+                .local
+                .compressed_node
+        };
+        let a = file(b"A.java", b"class A { void foo() {} }");
+        let b = file(b"B.java", b"class B { int bar; }");
+        let stores = &stores;
+        let mut index = LabelIndex::default();
+        index.index_tree(stores, a);
+        index.index_tree(stores, b);
+        let foo = stores.label_store.get("foo").unwrap();
+        let bar = stores.label_store.get("bar").unwrap();
+        assert_eq!(index.files(&foo).collect::<Vec<_>>(), vec![a]);
+        assert!(index.file_contains(&b, &bar));
+        assert!(index.tree_contains(stores, a, &[foo]));
+        assert!(!index.tree_contains(stores, a, &[foo, bar]));
+        let id = |s| (s, vec!["identifier"]);
+        assert!(index.may_match(stores, b, &[vec![id("foo")], vec![id("bar")]]));
+        assert!(!index.may_match(stores, b, &[vec![id("foo")], vec![id("baz")]]));
+    }
+
+    #[test]
+    fn match_texts_of_unlabeled_nodes() {
+        use hyperast_gen_ts_java::legion_with_refs as java_tree_gen;
+        let mut stores = SimpleStores::default();
+        let mut md_cache = Default::default();
+        let mut java_tree_gen = java_tree_gen::JavaTreeGen {
+            line_break: "\n".as_bytes().to_vec(),
+            stores: stores.mut_with_ts(),
+            md_cache: &mut md_cache,
+            more: (),
+        };
+        let a = crate::java::handle_java_file(
+            &mut java_tree_gen,
+            &b"A.java".into(),
+            b"public class A { int foo; }",
+        )
+        .unwrap()
+        .local
+        .compressed_node;
+        let stores = &stores;
+        let mut index = LabelIndex::default();
+        index.index_tree(stores, a);
+        let query = |source: &str| {
+            hyperast_tsquery::Query::new(source, hyperast_gen_ts_java::language()).unwrap()
+        };
+        // the text of modifiers is not a label, it cannot rule out a file
+        let mods = query(r#"((class_declaration (modifiers) @mods) (#eq? @mods "public"))"#);
+        let required = mods.required_texts();
+        assert_eq!(required, vec![vec![("public", vec!["modifiers"])]]);
+        assert!(index.may_match(stores, a, &required));
+        // the text of an identifier is a label
+        let foo = query(r#"((identifier) @id (#eq? @id "foo"))"#);
+        assert!(index.may_match(stores, a, &foo.required_texts()));
+        let bar = query(r#"((identifier) @id (#eq? @id "bar"))"#);
+        assert!(!index.may_match(stores, a, &bar.required_texts()));
+    }
+}
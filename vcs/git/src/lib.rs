@@ -2,25 +2,45 @@
 #![feature(extract_if)]
 #[cfg(feature = "impact")]
 pub mod allrefs;
+#[cfg(feature = "any")]
+pub mod any;
 pub mod cpp;
+pub mod gc;
 pub mod git;
+pub mod gradle;
 pub mod java;
+pub mod label_index;
 pub mod make;
 pub mod maven;
+pub mod npm;
+pub mod ts;
 
+#[cfg(feature = "any")]
+pub mod any_processor;
 #[cfg(feature = "cpp")]
 pub mod cpp_processor;
+#[cfg(feature = "gradle")]
+pub mod gradle_processor;
 #[cfg(feature = "java")]
 pub mod java_processor;
 #[cfg(feature = "make")]
 pub mod make_processor;
 #[cfg(feature = "maven")]
 pub mod maven_processor;
+#[cfg(feature = "npm")]
+pub mod npm_processor;
 pub mod multi_preprocessed;
 pub mod no_space;
+#[cfg(feature = "any")]
+pub mod opaque;
+pub mod parse_errors;
 /// for now only tested on maven repositories with a pom in root.
 pub mod preprocessed;
 pub mod processing;
+pub mod snapshot;
+pub mod store_log;
+#[cfg(feature = "ts")]
+pub mod ts_processor;
 mod utils;
 
 #[cfg(test)]
@@ -54,6 +74,7 @@ pub struct Commit {
     memory_used: Bytes,
     pub ast_root: hyperast::store::nodes::DefaultNodeIdentifier,
     pub tree_oid: git2::Oid,
+    parse_failures: u32,
 }
 
 impl Commit {
@@ -63,6 +84,11 @@ impl Commit {
     pub fn memory_used(&self) -> Bytes {
         self.memory_used
     }
+    /// Files of the commit that could not be processed,
+    /// files already processed in previous commits are not counted again
+    pub fn parse_failures(&self) -> u32 {
+        self.parse_failures
+    }
 }
 
 trait Accumulator: hyperast::tree_gen::Accumulator<Node = (LabelIdentifier, Self::Unlabeled)> {
@@ -128,11 +154,48 @@ fn ts_lang_java() -> Option<tree_sitter::Language> {
 fn ts_lang_java() -> Option<tree_sitter::Language> {
     None
 }
+#[cfg(feature = "ts")]
+fn ts_lang_ts() -> Option<tree_sitter::Language> {
+    Some(hyperast_gen_ts_ts::language())
+}
+#[cfg(not(feature = "ts"))]
+fn ts_lang_ts() -> Option<tree_sitter::Language> {
+    None
+}
+#[cfg(feature = "c")]
+fn ts_lang_c() -> Option<tree_sitter::Language> {
+    Some(hyperast_gen_ts_c::language())
+}
+#[cfg(not(feature = "c"))]
+fn ts_lang_c() -> Option<tree_sitter::Language> {
+    None
+}
+#[cfg(feature = "maven")]
+fn ts_lang_xml() -> Option<tree_sitter::Language> {
+    Some(hyperast_gen_ts_xml::language())
+}
+#[cfg(not(feature = "maven"))]
+fn ts_lang_xml() -> Option<tree_sitter::Language> {
+    None
+}
+#[cfg(feature = "tsquery")]
+fn ts_lang_tsquery() -> Option<tree_sitter::Language> {
+    Some(hyperast_gen_ts_tsquery::language())
+}
+#[cfg(not(feature = "tsquery"))]
+fn ts_lang_tsquery() -> Option<tree_sitter::Language> {
+    None
+}
 
 pub fn resolve_language(language: &str) -> Option<tree_sitter::Language> {
     match language {
         "Java" | "java" => ts_lang_java(),
         "Cpp" | "cpp" => ts_lang_cpp(),
+        // javascript is parsed with the typescript grammar
+        "Ts" | "ts" | "TypeScript" | "typescript" | "JavaScript" | "javascript" => ts_lang_ts(),
+        "C" | "c" => ts_lang_c(),
+        "Xml" | "xml" => ts_lang_xml(),
+        "TsQuery" | "tsquery" | "query" => ts_lang_tsquery(),
         _ => None,
     }
 }
@@ -218,6 +281,19 @@ pub(crate) struct SuccessProcessing<N, D = Duration> {
     pub parsing_time: D,
     pub processing_time: D,
     pub node: N,
+    /// empty if the CST has no errors, see [`crate::parse_errors`]
+    pub errors: Vec<parse_errors::ParseError>,
+}
+
+impl<N, D> SuccessProcessing<N, D> {
+    pub(crate) fn map_node<M>(self, f: impl FnOnce(N) -> M) -> SuccessProcessing<M, D> {
+        SuccessProcessing {
+            parsing_time: self.parsing_time,
+            processing_time: self.processing_time,
+            node: f(self.node),
+            errors: self.errors,
+        }
+    }
 }
 
 pub(crate) type FileProcessingResult<N, D = Duration> = Result<SuccessProcessing<N, D>, FailedParsing<D>>;
@@ -486,6 +486,18 @@ impl crate::processing::erased::CommitProc for MakefileProc {
     fn get_commit(&self, commit_oid: git2::Oid) -> Option<&crate::Commit> {
         unimplemented!("required for processing at the root of a project")
     }
+
+    fn commits(&self) -> Box<dyn Iterator<Item = (&git2::Oid, &crate::Commit)> + '_> {
+        unimplemented!("required for processing at the root of a project")
+    }
+
+    fn insert_commit(&mut self, _commit_oid: git2::Oid, _commit: crate::Commit) {
+        unimplemented!("required for processing at the root of a project")
+    }
+
+    fn remove_commit(&mut self, _commit_oid: git2::Oid) -> Option<crate::Commit> {
+        unimplemented!("required for processing at the root of a project")
+    }
 }
 
 impl crate::processing::erased::CommitProcExt for MakefileProc {
@@ -616,6 +628,22 @@ impl crate::processing::erased::CommitProc for MakeProc {
         self.commits.get(&commit_oid)
     }
 
+    fn commits(&self) -> Box<dyn Iterator<Item = (&git2::Oid, &crate::Commit)> + '_> {
+        Box::new(self.commits.iter())
+    }
+
+    fn insert_commit(&mut self, commit_oid: git2::Oid, commit: crate::Commit) {
+        self.commits.insert(commit_oid, commit);
+    }
+
+    fn remove_commit(&mut self, commit_oid: git2::Oid) -> Option<crate::Commit> {
+        self.commits.remove(&commit_oid)
+    }
+
+    fn clear_caches(&mut self) {
+        self.cache = Default::default();
+    }
+
     fn get_lang_handle(&self, lang: &str) -> Option<ParametrizedCommitProcessorHandle> {
         dbg!(self.parameter.cpp_handle.0.0);
         if lang.eq_ignore_ascii_case("cpp") {
@@ -25,7 +25,7 @@ pub(crate) fn handle_pom_file<'a>(
     tree_gen: &mut XmlTreeGen<'a, TStore>,
     name: &ObjectName,
     text: &'a [u8],
-) -> Result<POM, ParseErr> {
+) -> Result<(POM, Vec<crate::parse_errors::ParseError>), ParseErr> {
     let tree = match XmlTreeGen::<TStore>::tree_sitter_parse(text) {
         Ok(tree) => tree,
         Err(tree) => {
@@ -38,6 +38,7 @@ pub(crate) fn handle_pom_file<'a>(
             }
         }
     };
+    let errors = crate::parse_errors::collect(&tree);
     let x = tree_gen
         .generate_file(name.as_bytes(), text, tree.walk())
         .local;
@@ -49,7 +50,7 @@ pub(crate) fn handle_pom_file<'a>(
         source_dirs: vec!["src/main/java".to_owned()],
         test_source_dirs: vec!["src/test/java".to_owned()],
     };
-    Ok(x)
+    Ok((x, errors))
 }
 
 #[derive(Debug, Clone)]
@@ -167,10 +168,9 @@ impl<'a> IterMavenModules2<'a> {
                                 "f name: {:?}",
                                 self.stores.label_store.resolve(n.get_label_unchecked())
                             );
-                            self.stores
-                                .label_store
-                                .resolve(n.get_label_unchecked())
-                                .eq("pom.xml")
+                            is_module_file(
+                                self.stores.label_store.resolve(n.get_label_unchecked()),
+                            )
                         } else {
                             false
                         }
@@ -193,6 +193,14 @@ impl<'a> IterMavenModules2<'a> {
     }
 }
 
+/// The files that make a directory a module, ie. a maven pom.xml or a gradle build or settings script
+fn is_module_file(name: &str) -> bool {
+    matches!(
+        name,
+        "pom.xml" | "build.gradle" | "build.gradle.kts" | "settings.gradle" | "settings.gradle.kts"
+    )
+}
+
 #[derive(Debug, Clone)]
 pub struct MD {
     pub(crate) metrics: DefaultMetrics,
@@ -489,10 +497,9 @@ impl<'a, T: TreePath<NodeIdentifier>> IterMavenModules<'a, T> {
                                 "f name: {:?}",
                                 self.stores.label_store.resolve(n.get_label_unchecked())
                             );
-                            self.stores
-                                .label_store
-                                .resolve(n.get_label_unchecked())
-                                .eq("pom.xml")
+                            is_module_file(
+                                self.stores.label_store.resolve(n.get_label_unchecked()),
+                            )
                         } else {
                             false
                         }
@@ -323,7 +323,7 @@ impl<'a, 'b, 'c, const RMS: bool, const FFWD: bool>
 }
 
 impl MavenModuleAcc {
-    fn init_scripting(
+    pub(crate) fn init_scripting(
         mut self,
         prep_scripting: Option<
             &hyperast::scripting::Prepro<
@@ -350,7 +350,7 @@ impl MavenModuleAcc {
 
 // TODO generalize and factor similar preps
 // and use the type in ParametrizedCommitProcessor2Handle to get the Holder
-fn prep_scripting(
+pub(crate) fn prep_scripting(
     prepro: &RepositoryProcessor,
     handle: crate::processing::erased::ConfigParametersHandle,
 ) -> Option<&std::sync::Arc<str>> {
@@ -460,14 +460,16 @@ impl RepositoryProcessor {
             .caching_blob_handler::<crate::processing::file_sys::Pom>()
             .handle(oid, repository, &name, parameters, |c, n, t| {
                 // let caches = c.mut_or_default::<PomProcessorHolder>().get_caches_mut();
-                crate::maven::handle_pom_file(
+                let (pom, errors) = crate::maven::handle_pom_file(
                     &mut XmlTreeGen {
                         line_break: "\n".as_bytes().to_vec(),
                         stores: self.main_stores.mut_with_ts(),
                     },
                     n,
                     t,
-                )
+                )?;
+                self.parse_errors.insert(pom.compressed_node, errors);
+                Ok(pom)
             })?;
         let name = self.intern_object_name(&name);
         assert!(!parent_acc.primary.children_names.contains(&name));
@@ -480,7 +482,9 @@ impl RepositoryProcessor {
         name: &ObjectName,
         text: &[u8],
     ) -> Result<crate::maven::POM, crate::ParseErr> {
-        crate::maven::handle_pom_file(&mut self.xml_generator(), name, text)
+        let (pom, errors) = crate::maven::handle_pom_file(&mut self.xml_generator(), name, text)?;
+        self.parse_errors.insert(pom.compressed_node, errors);
+        Ok(pom)
     }
 
     pub(crate) fn xml_generator(&mut self) -> XmlTreeGen<hyperast_gen_ts_xml::types::TStore> {
@@ -491,11 +495,11 @@ impl RepositoryProcessor {
     }
 }
 
-struct MavenModuleHelper {
+pub(crate) struct MavenModuleHelper {
     name: String,
-    submodules: (bool, Vec<PathBuf>),
-    source_directories: (bool, Vec<PathBuf>),
-    test_source_directories: (bool, Vec<PathBuf>),
+    pub(crate) submodules: (bool, Vec<PathBuf>),
+    pub(crate) source_directories: (bool, Vec<PathBuf>),
+    pub(crate) test_source_directories: (bool, Vec<PathBuf>),
 }
 
 impl From<(&mut MavenModuleAcc, &ObjectName)> for MavenModuleHelper {
@@ -661,6 +665,18 @@ impl crate::processing::erased::CommitProc for PomProc {
     fn get_commit(&self, _commit_oid: git2::Oid) -> Option<&crate::Commit> {
         unimplemented!("required for processing at the root of a project")
     }
+
+    fn commits(&self) -> Box<dyn Iterator<Item = (&git2::Oid, &crate::Commit)> + '_> {
+        unimplemented!("required for processing at the root of a project")
+    }
+
+    fn insert_commit(&mut self, _commit_oid: git2::Oid, _commit: crate::Commit) {
+        unimplemented!("required for processing at the root of a project")
+    }
+
+    fn remove_commit(&mut self, _commit_oid: git2::Oid) -> Option<crate::Commit> {
+        unimplemented!("required for processing at the root of a project")
+    }
 }
 
 impl crate::processing::erased::CommitProcExt for PomProc {
@@ -793,6 +809,22 @@ impl crate::processing::erased::CommitProc for MavenProc {
         self.commits.get(&commit_oid)
     }
 
+    fn commits(&self) -> Box<dyn Iterator<Item = (&git2::Oid, &crate::Commit)> + '_> {
+        Box::new(self.commits.iter())
+    }
+
+    fn insert_commit(&mut self, commit_oid: git2::Oid, commit: crate::Commit) {
+        self.commits.insert(commit_oid, commit);
+    }
+
+    fn remove_commit(&mut self, commit_oid: git2::Oid) -> Option<crate::Commit> {
+        self.commits.remove(&commit_oid)
+    }
+
+    fn clear_caches(&mut self) {
+        self.cache = Default::default();
+    }
+
     fn get_lang_handle(&self, lang: &str) -> Option<ParametrizedCommitProcessorHandle> {
         if lang.eq_ignore_ascii_case("java") {
             Some(ParametrizedCommitProcessorHandle(
@@ -10,7 +10,7 @@ use crate::{
     preprocessed::{CommitProcessor, RepositoryProcessor},
     processing::{
         erased::{CommitProcExt, ParametrizedCommitProcessorHandle},
-        ConfiguredRepo, ConfiguredRepo2, ConfiguredRepoHandle2, RepoConfig,
+        ConfigParams, ConfiguredRepo, ConfiguredRepo2, ConfiguredRepoHandle2, RepoConfig,
     },
     Commit, SimpleStores,
 };
@@ -22,12 +22,15 @@ pub struct PreProcessedRepositories {
     // pub commits: HashMap<RepoConfig, HashMap<git2::Oid, Commit>>,
     pub processor: RepositoryProcessor,
     // pub processing_ordered_commits: HashMap<String,Vec<git2::Oid>>,
-    configs: HashMap<Repo, ParametrizedCommitProcessorHandle>,
+    pub(crate) configs: HashMap<Repo, ParametrizedCommitProcessorHandle>,
+    /// the custom parameters of each registered repository
+    pub(crate) params: HashMap<Repo, ConfigParams>,
 }
 
 #[derive(Default)]
 pub struct CommitsPerSys {
     pub maven: HashMap<git2::Oid, Commit>,
+    pub gradle: HashMap<git2::Oid, Commit>,
     pub make: HashMap<git2::Oid, Commit>,
     pub npm: HashMap<git2::Oid, Commit>,
     pub any: HashMap<git2::Oid, Commit>,
@@ -45,6 +48,7 @@ impl CommitsPerSys {
     pub fn accessCommits<'a>(&'a self, sys: &RepoConfig) -> &'a HashMap<git2::Oid, Commit> {
         match sys {
             RepoConfig::JavaMaven => &self.maven,
+            RepoConfig::JavaGradle => &self.gradle,
             RepoConfig::CppMake => &self.make,
             RepoConfig::TsNpm => &self.npm,
             RepoConfig::Any => &self.any,
@@ -101,8 +105,73 @@ impl<'prepro, 'repo, Sys, CP: CommitProcessor<Sys>> CommitBuilder<'prepro, 'repo
 }
 
 impl PreProcessedRepositories {
+    /// Drop the caches of processors,
+    /// configs are then registered again with their custom parameters, along their processed commits.
     pub fn purge_caches(&mut self) {
-        self.processor.purge_caches()
+        self.purge_caches_retaining(|_, _| true)
+    }
+
+    /// Same as [`PreProcessedRepositories::purge_caches`],
+    /// but only the commits for which `retain` holds are registered again
+    pub(crate) fn purge_caches_retaining(&mut self, retain: impl Fn(&Repo, &git2::Oid) -> bool) {
+        let systems = &self.processor.processing_systems;
+        let params = &mut self.params;
+        let configured: Vec<_> = self
+            .configs
+            .drain()
+            .map(|(repo, handle)| {
+                let proc = systems.by_id(&handle.0).unwrap().get(handle.1);
+                let commits: Vec<_> = proc
+                    .commits()
+                    .filter(|(k, _)| retain(&repo, *k))
+                    .map(|(k, v)| (*k, v.clone()))
+                    .collect();
+                let params = params.remove(&repo).unwrap_or_default();
+                (repo, crate::snapshot::config_of(&handle), params, commits)
+            })
+            .collect();
+        self.processor.purge_caches();
+        for (repo, config, params, commits) in configured {
+            let Some(config) = config else {
+                log::warn!("could not re-register {}", repo.spec());
+                continue;
+            };
+            let handle = match self.register_config_with_params(repo.clone(), config, &params) {
+                Ok(handle) => handle.config,
+                Err(e) => {
+                    log::warn!("could not re-register {}: {}", repo.spec(), e);
+                    continue;
+                }
+            };
+            let proc = self
+                .processor
+                .processing_systems
+                .by_id_mut(&handle.0)
+                .unwrap()
+                .get_mut(handle.1);
+            for (oid, commit) in commits {
+                proc.insert_commit(oid, commit);
+            }
+        }
+    }
+
+    /// Drop the caches of blobs of the processors configured for `repo`,
+    /// e.g. those referring to placeholders of an interrupted commit.
+    /// Unlike [`PreProcessedRepositories::purge_caches`], configs and processed commits are kept
+    pub fn purge_blob_caches(&mut self, repo: &Repo) {
+        let Some(handle) = self.configs.get(repo) else {
+            return;
+        };
+        let systems = &mut self.processor.processing_systems;
+        let proc = systems.by_id(&handle.0).unwrap().get(handle.1);
+        let langs =
+            ["java", "cpp", "ts", "c", "xml", "tsquery"].map(|lang| proc.get_lang_handle(lang));
+        for h in std::iter::once(*handle).chain(langs.into_iter().flatten()) {
+            if let Some(proc) = systems.by_id_mut(&h.0) {
+                proc.get_mut(h.1).clear_caches();
+            }
+        }
+        self.processor.pre_parsed.reset();
     }
 
     pub fn get_commit(
@@ -137,6 +206,22 @@ impl PreProcessedRepositories {
                 let config = h.register_param(crate::maven_processor::Parameter { java_handle });
                 ConfiguredRepoHandle2 { spec: repo, config }
             }
+            #[cfg(feature = "gradle")]
+            RepoConfig::JavaGradle => {
+                let t = crate::java_processor::Parameter::faster();
+                let h_java = self
+                    .processor
+                    .processing_systems
+                    .mut_or_default::<crate::java_processor::JavaProcessorHolder>();
+                let java_handle =
+                    crate::processing::erased::CommitProcExt::register_param(h_java, t);
+                let h = self
+                    .processor
+                    .processing_systems
+                    .mut_or_default::<crate::gradle_processor::GradleProcessorHolder>();
+                let config = h.register_param(crate::maven_processor::Parameter { java_handle });
+                ConfiguredRepoHandle2 { spec: repo, config }
+            }
             RepoConfig::CppMake => {
                 let q: &[&str] = &["(translation_unit)"];
                 let t = crate::cpp_processor::Parameter { query: Some(q.into()) };
@@ -152,19 +237,46 @@ impl PreProcessedRepositories {
                 let config = h.register_param(crate::make_processor::Parameter { cpp_handle });
                 ConfiguredRepoHandle2 { spec: repo, config }
             }
+            #[cfg(feature = "npm")]
+            RepoConfig::TsNpm => {
+                let t = crate::ts_processor::Parameter::default();
+                let h_ts = self
+                    .processor
+                    .processing_systems
+                    .mut_or_default::<crate::ts_processor::TsProcessorHolder>();
+                let ts_handle = crate::processing::erased::CommitProcExt::register_param(h_ts, t);
+                let h = self
+                    .processor
+                    .processing_systems
+                    .mut_or_default::<crate::npm_processor::NpmProcessorHolder>();
+                let config = h.register_param(crate::npm_processor::Parameter { ts_handle });
+                ConfiguredRepoHandle2 { spec: repo, config }
+            }
+            #[cfg(feature = "any")]
+            RepoConfig::Any => {
+                let java = crate::java_processor::Parameter::faster();
+                let q: &[&str] = &["(translation_unit)"];
+                let cpp = crate::cpp_processor::Parameter {
+                    query: Some(q.into()),
+                };
+                let config = self.register_any_param(java, cpp);
+                ConfiguredRepoHandle2 { spec: repo, config }
+            }
             _ => todo!(),
         };
 
         self.configs.insert(r.spec.clone(), r.config);
+        self.params.insert(r.spec.clone(), ConfigParams::None);
         r
     }
 
+    /// Errors if the processors of `config` cannot run pre-processing scripts
     pub fn register_config_with_prepro(
         &mut self,
         repo: Repo,
         config: RepoConfig,
         prepro: std::sync::Arc<str>,
-    ) -> ConfiguredRepoHandle2 {
+    ) -> Result<ConfiguredRepoHandle2, String> {
         use crate::processing::erased::Parametrized;
         let r = match config {
             RepoConfig::JavaMaven => {
@@ -173,7 +285,7 @@ impl PreProcessedRepositories {
                     .processing_systems
                     .mut_or_default::<crate::java_processor::JavaProcessorHolder>();
                 let t = crate::java_processor::Parameter {
-                    prepro: Some(prepro),
+                    prepro: Some(prepro.clone()),
                     query: None,
                     tsg: None,
                 };
@@ -187,6 +299,27 @@ impl PreProcessedRepositories {
                     config: h.register_param(crate::maven_processor::Parameter { java_handle }),
                 }
             }
+            #[cfg(feature = "gradle")]
+            RepoConfig::JavaGradle => {
+                let h_java = self
+                    .processor
+                    .processing_systems
+                    .mut_or_default::<crate::java_processor::JavaProcessorHolder>();
+                let t = crate::java_processor::Parameter {
+                    prepro: Some(prepro.clone()),
+                    query: None,
+                    tsg: None,
+                };
+                let java_handle = CommitProcExt::register_param(h_java, t);
+                let h = self
+                    .processor
+                    .processing_systems
+                    .mut_or_default::<crate::gradle_processor::GradleProcessorHolder>();
+                ConfiguredRepoHandle2 {
+                    spec: repo,
+                    config: h.register_param(crate::maven_processor::Parameter { java_handle }),
+                }
+            }
             RepoConfig::CppMake => {
                 let t = crate::cpp_processor::Parameter { query: None };
                 let h_cpp = self
@@ -201,18 +334,36 @@ impl PreProcessedRepositories {
                 let config = h.register_param(crate::make_processor::Parameter { cpp_handle });
                 ConfiguredRepoHandle2 { spec: repo, config }
             }
+            #[cfg(feature = "npm")]
+            RepoConfig::TsNpm => {
+                return Err("pre-processing scripts are not supported on typescript".to_string())
+            }
+            // only java files are pre-processed
+            #[cfg(feature = "any")]
+            RepoConfig::Any => {
+                let java = crate::java_processor::Parameter {
+                    prepro: Some(prepro.clone()),
+                    query: None,
+                    tsg: None,
+                };
+                let cpp = crate::cpp_processor::Parameter { query: None };
+                let config = self.register_any_param(java, cpp);
+                ConfiguredRepoHandle2 { spec: repo, config }
+            }
             _ => todo!(),
         };
         self.configs.insert(r.spec.clone(), r.config);
-        r
+        self.params.insert(r.spec.clone(), ConfigParams::Prepro(prepro));
+        Ok(r)
     }
 
+    /// Errors if the processors of `config` cannot precompute queries
     pub fn register_config_with_prequeries(
         &mut self,
         repo: Repo,
         config: RepoConfig,
         query: &[&str],
-    ) -> ConfiguredRepoHandle2 {
+    ) -> Result<ConfiguredRepoHandle2, String> {
         use crate::processing::erased::Parametrized;
         let r = match config {
             RepoConfig::JavaMaven => {
@@ -235,6 +386,27 @@ impl PreProcessedRepositories {
                     config: h.register_param(crate::maven_processor::Parameter { java_handle }),
                 }
             }
+            #[cfg(feature = "gradle")]
+            RepoConfig::JavaGradle => {
+                let h_java = self
+                    .processor
+                    .processing_systems
+                    .mut_or_default::<crate::java_processor::JavaProcessorHolder>();
+                let t = crate::java_processor::Parameter {
+                    prepro: None,
+                    query: Some(query.into()),
+                    tsg: None,
+                };
+                let java_handle = CommitProcExt::register_param(h_java, t);
+                let h = self
+                    .processor
+                    .processing_systems
+                    .mut_or_default::<crate::gradle_processor::GradleProcessorHolder>();
+                ConfiguredRepoHandle2 {
+                    spec: repo,
+                    config: h.register_param(crate::maven_processor::Parameter { java_handle }),
+                }
+            }
             RepoConfig::CppMake => {
                 let t = crate::cpp_processor::Parameter { query: Some(query.into()) };
                 let h_cpp = self
@@ -249,18 +421,38 @@ impl PreProcessedRepositories {
                 let config = h.register_param(crate::make_processor::Parameter { cpp_handle });
                 ConfiguredRepoHandle2 { spec: repo, config }
             }
+            #[cfg(feature = "npm")]
+            RepoConfig::TsNpm => {
+                return Err("precomputed queries are not supported on typescript".to_string())
+            }
+            // queries are precomputed on java and cpp files
+            #[cfg(feature = "any")]
+            RepoConfig::Any => {
+                let java = crate::java_processor::Parameter {
+                    prepro: None,
+                    query: Some(query.into()),
+                    tsg: None,
+                };
+                let cpp = crate::cpp_processor::Parameter {
+                    query: Some(query.into()),
+                };
+                let config = self.register_any_param(java, cpp);
+                ConfiguredRepoHandle2 { spec: repo, config }
+            }
             _ => todo!(),
         };
         self.configs.insert(r.spec.clone(), r.config);
-        r
+        self.params.insert(r.spec.clone(), ConfigParams::Prequeries(query.iter().map(|x| x.to_string()).collect()));
+        Ok(r)
     }
 
+    /// Errors if the processors of `config` cannot run tree-sitter-graph specifications
     pub fn register_config_with_tsg(
         &mut self,
         repo: Repo,
         config: RepoConfig,
         tsg: std::sync::Arc<str>,
-    ) -> ConfiguredRepoHandle2 {
+    ) -> Result<ConfiguredRepoHandle2, String> {
         use crate::processing::erased::Parametrized;
         let r = match config {
             RepoConfig::JavaMaven => {
@@ -271,7 +463,7 @@ impl PreProcessedRepositories {
                 let t = crate::java_processor::Parameter {
                     prepro: None,
                     query: None,
-                    tsg: Some(tsg),
+                    tsg: Some(tsg.clone()),
                 };
                 let java_handle = CommitProcExt::register_param(h_java, t);
                 let h = self
@@ -283,13 +475,171 @@ impl PreProcessedRepositories {
                     config: h.register_param(crate::maven_processor::Parameter { java_handle }),
                 }
             }
+            #[cfg(feature = "gradle")]
+            RepoConfig::JavaGradle => {
+                let h_java = self
+                    .processor
+                    .processing_systems
+                    .mut_or_default::<crate::java_processor::JavaProcessorHolder>();
+                let t = crate::java_processor::Parameter {
+                    prepro: None,
+                    query: None,
+                    tsg: Some(tsg.clone()),
+                };
+                let java_handle = CommitProcExt::register_param(h_java, t);
+                let h = self
+                    .processor
+                    .processing_systems
+                    .mut_or_default::<crate::gradle_processor::GradleProcessorHolder>();
+                ConfiguredRepoHandle2 {
+                    spec: repo,
+                    config: h.register_param(crate::maven_processor::Parameter { java_handle }),
+                }
+            }
             RepoConfig::CppMake => {
-                unimplemented!()
+                return Err("tree-sitter-graph is not supported on cpp".to_string())
+            }
+            #[cfg(feature = "npm")]
+            RepoConfig::TsNpm => {
+                return Err("tree-sitter-graph is not supported on typescript".to_string())
+            }
+            // only java files are given to the specification
+            #[cfg(feature = "any")]
+            RepoConfig::Any => {
+                let java = crate::java_processor::Parameter {
+                    prepro: None,
+                    query: None,
+                    tsg: Some(tsg.clone()),
+                };
+                let cpp = crate::cpp_processor::Parameter { query: None };
+                let config = self.register_any_param(java, cpp);
+                ConfiguredRepoHandle2 { spec: repo, config }
             }
             _ => todo!(),
         };
         self.configs.insert(r.spec.clone(), r.config);
-        r
+        self.params.insert(r.spec.clone(), ConfigParams::Tsg(tsg));
+        Ok(r)
+    }
+
+    /// Register `repo` with the custom `params` of one of the other `register_config*` methods
+    pub fn register_config_with_params(
+        &mut self,
+        repo: Repo,
+        config: RepoConfig,
+        params: &ConfigParams,
+    ) -> Result<ConfiguredRepoHandle2, String> {
+        match params {
+            ConfigParams::None => Ok(self.register_config(repo, config)),
+            ConfigParams::Prepro(prepro) => {
+                self.register_config_with_prepro(repo, config, prepro.clone())
+            }
+            ConfigParams::Prequeries(query) => {
+                let query: Vec<&str> = query.iter().map(|x| x.as_str()).collect();
+                self.register_config_with_prequeries(repo, config, &query)
+            }
+            ConfigParams::Tsg(tsg) => self.register_config_with_tsg(repo, config, tsg.clone()),
+        }
+    }
+
+    /// The custom parameters `repo` was registered with
+    pub fn params(&self, repo: &Repo) -> Option<&ConfigParams> {
+        self.params.get(repo)
+    }
+
+    /// The languages of a [`RepoConfig::Any`] repository share the processors of their build systems,
+    /// registered with the given parameters, typescript files are processed without any.
+    #[cfg(feature = "any")]
+    fn register_any_param(
+        &mut self,
+        java: crate::java_processor::Parameter,
+        cpp: crate::cpp_processor::Parameter,
+    ) -> crate::processing::ParametrizedCommitProcessorHandle {
+        use crate::processing::erased::Parametrized;
+        let h_java = self
+            .processor
+            .processing_systems
+            .mut_or_default::<crate::java_processor::JavaProcessorHolder>();
+        let java_handle = CommitProcExt::register_param(h_java, java);
+        let h_cpp = self
+            .processor
+            .processing_systems
+            .mut_or_default::<crate::cpp_processor::CppProcessorHolder>();
+        let cpp_handle = CommitProcExt::register_param(h_cpp, cpp);
+        let t = crate::ts_processor::Parameter::default();
+        let h_ts = self
+            .processor
+            .processing_systems
+            .mut_or_default::<crate::ts_processor::TsProcessorHolder>();
+        let ts_handle = CommitProcExt::register_param(h_ts, t);
+        let h = self
+            .processor
+            .processing_systems
+            .mut_or_default::<crate::any_processor::AnyProcessorHolder>();
+        h.register_param(crate::any_processor::Parameter {
+            java_handle,
+            cpp_handle,
+            ts_handle,
+        })
+    }
+
+    /// The registered repositories along their config,
+    /// None if the config is not one of [`RepoConfig`]
+    pub fn configs(&self) -> impl Iterator<Item = (&Repo, Option<RepoConfig>)> + '_ {
+        self.configs
+            .iter()
+            .map(|(repo, handle)| (repo, crate::snapshot::config_of(handle)))
+    }
+
+    /// The commits processed with the config of `repo`.
+    ///
+    /// WARN repositories registered with the same config share their processor,
+    /// thus it also contains their commits.
+    pub fn processed_commits(&self, repo: &Repo) -> Option<Vec<(git2::Oid, &Commit)>> {
+        let handle = self.configs.get(repo)?;
+        let systems = &self.processor.processing_systems;
+        let proc = systems.by_id(&handle.0)?.get(handle.1);
+        Some(proc.commits().map(|(oid, commit)| (*oid, commit)).collect())
+    }
+
+    /// Forget the config of `repo`,
+    /// the commits already processed are kept in the stores
+    pub fn unregister(&mut self, repo: &Repo) -> bool {
+        self.params.remove(repo);
+        self.configs.remove(repo).is_some()
+    }
+
+    /// The roots of the commits processed with the configs of registered repositories
+    pub(crate) fn processed_roots(&self) -> Vec<NodeIdentifier> {
+        (self.configs.keys())
+            .filter_map(|repo| self.processed_commits(repo))
+            .flatten()
+            .map(|(_, commit)| commit.ast_root)
+            .collect()
+    }
+
+    /// Maintain a [`crate::label_index`] of processed commits, starting with the already processed ones
+    pub fn enable_label_index(&mut self) {
+        self.processor.label_index = Some(Default::default());
+        self.rebuild_label_index();
+    }
+
+    pub fn disable_label_index(&mut self) {
+        self.processor.label_index = None;
+    }
+
+    /// Indexes again the processed commits if the label index is enabled,
+    /// e.g. once labels were renumbered
+    pub(crate) fn rebuild_label_index(&mut self) {
+        if self.processor.label_index.is_none() {
+            return;
+        }
+        let roots = self.processed_roots();
+        let processor = &mut self.processor;
+        let index = processor.label_index.insert(Default::default());
+        for root in roots {
+            index.index_tree(&processor.main_stores, root);
+        }
     }
 
     pub fn get_config(&self, repo: Repo) -> Option<ConfiguredRepoHandle2> {
@@ -298,6 +648,20 @@ impl PreProcessedRepositories {
             .map(|&config| ConfiguredRepoHandle2 { config, spec: repo })
     }
 
+    /// Find a registered repository from its host (see [`Repo::host`]), user and name.
+    ///
+    /// Forges can also be designated by their short name, e.g. `github`.
+    pub fn find_repo(&self, host: &str, user: &str, name: &str) -> Option<Repo> {
+        let host = match host.parse::<crate::git::Forge>() {
+            Ok(forge) => forge.host().to_string(),
+            Err(_) => host.to_string(),
+        };
+        self.configs
+            .keys()
+            .find(|r| r.user() == user && r.name() == name && r.host() == host)
+            .cloned()
+    }
+
     pub fn get_precomp_query(
         &self,
         handle: ParametrizedCommitProcessorHandle,
@@ -0,0 +1,270 @@
+use enumset::EnumSet;
+use hyperast::store::defaults::{LabelIdentifier, NodeIdentifier};
+use hyperast_gen_ts_ts::legion as ts_tree_gen;
+use hyperast_gen_ts_xml::{legion::XmlTreeGen, types::TStore};
+
+use crate::{
+    processing::ObjectName, Accumulator, BasicDirAcc, DefaultMetrics, ParseErr,
+    PROPAGATE_ERROR_ON_BAD_CST_NODE,
+};
+
+/// Handles a package.json
+///
+/// Like for Makefiles the manifest is stored as an empty document,
+/// only the declared workspaces are extracted, to find the packages of a monorepo.
+pub(crate) fn handle_package_json_file<'a>(
+    tree_gen: &mut XmlTreeGen<'a, TStore>,
+    name: &ObjectName,
+    text: &'a [u8],
+) -> Result<PackageJson, ParseErr> {
+    let content = std::str::from_utf8(text)?;
+    const PLACEHOLDER: &[u8] = b"<package></package>";
+    let tree = match hyperast_gen_ts_xml::legion::tree_sitter_parse_xml(PLACEHOLDER) {
+        Ok(tree) => tree,
+        Err(tree) => {
+            log::warn!("bad CST: {:?}", name.try_str());
+            log::debug!("{}", tree.root_node().to_sexp());
+            if PROPAGATE_ERROR_ON_BAD_CST_NODE {
+                return Err(ParseErr::IllFormed);
+            } else {
+                tree
+            }
+        }
+    };
+    let x = tree_gen
+        .generate_file(name.as_bytes(), PLACEHOLDER, tree.walk())
+        .local;
+    let x = PackageJson {
+        compressed_node: x.compressed_node,
+        metrics: x.metrics,
+        workspaces: workspaces(content),
+    };
+    Ok(x)
+}
+
+#[derive(Debug, Clone)]
+pub struct PackageJson {
+    pub compressed_node: NodeIdentifier,
+    pub metrics: DefaultMetrics,
+    /// glob patterns of the directories containing the packages of the workspace
+    workspaces: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MD {
+    pub(crate) metrics: DefaultMetrics,
+    pub(crate) status: EnumSet<SemFlags>,
+}
+
+#[derive(enumset::EnumSetType, Debug)]
+pub enum SemFlags {
+    IsNpmPackage,
+    HoldNpmPackage,
+}
+
+pub struct NpmModuleAcc {
+    pub(crate) primary: BasicDirAcc<NodeIdentifier, LabelIdentifier, DefaultMetrics>,
+    /// workspace patterns relative to this directory
+    pub(crate) workspaces: Vec<String>,
+    pub(crate) status: EnumSet<SemFlags>,
+}
+
+impl From<String> for NpmModuleAcc {
+    fn from(name: String) -> Self {
+        Self::new(name)
+    }
+}
+
+impl NpmModuleAcc {
+    pub(crate) fn new(name: String) -> Self {
+        Self {
+            primary: BasicDirAcc::new(name),
+            workspaces: vec![],
+            status: Default::default(),
+        }
+    }
+    pub(crate) fn with_workspaces(name: String, workspaces: Vec<String>) -> Self {
+        Self {
+            primary: BasicDirAcc::new(name),
+            workspaces,
+            status: Default::default(),
+        }
+    }
+}
+
+impl NpmModuleAcc {
+    pub(crate) fn push_package_json(&mut self, name: LabelIdentifier, full_node: PackageJson) {
+        self.status |= SemFlags::IsNpmPackage;
+        assert!(!self.primary.children_names.contains(&name));
+        self.primary.children.push(full_node.compressed_node);
+        self.primary.children_names.push(name);
+        for w in full_node.workspaces {
+            if !self.workspaces.contains(&w) {
+                self.workspaces.push(w);
+            }
+        }
+        self.primary.metrics.acc(full_node.metrics);
+    }
+    pub fn push_submodule(&mut self, name: LabelIdentifier, full_node: (NodeIdentifier, MD)) {
+        if full_node.1.status.contains(SemFlags::HoldNpmPackage)
+            || full_node.1.status.contains(SemFlags::IsNpmPackage)
+        {
+            self.status |= SemFlags::HoldNpmPackage;
+        }
+        self.primary.children.push(full_node.0);
+        self.primary.children_names.push(name);
+        self.primary.metrics.acc(full_node.1.metrics);
+    }
+    pub(crate) fn push_source_file(
+        &mut self,
+        name: LabelIdentifier,
+        full_node: ts_tree_gen::Local,
+        _skiped_ana: bool,
+    ) {
+        self.primary.children.push(full_node.compressed_node);
+        self.primary.children_names.push(name);
+        self.primary.metrics.acc(full_node.metrics);
+    }
+    pub(crate) fn push_source_directory(
+        &mut self,
+        name: LabelIdentifier,
+        full_node: ts_tree_gen::Local,
+    ) {
+        self.primary.children.push(full_node.compressed_node);
+        self.primary.children_names.push(name);
+        self.primary.metrics.acc(full_node.metrics);
+    }
+}
+
+impl hyperast::tree_gen::Accumulator for NpmModuleAcc {
+    type Node = (LabelIdentifier, (NodeIdentifier, MD));
+    fn push(&mut self, (name, full_node): Self::Node) {
+        self.push_submodule(name, full_node)
+    }
+}
+
+impl Accumulator for NpmModuleAcc {
+    type Unlabeled = (NodeIdentifier, MD);
+}
+
+/// Workspaces declared in a package.json,
+/// either as an array of patterns or as the `packages` of an object (the yarn variant).
+///
+/// Negated patterns are not supported and ignored.
+pub fn workspaces(text: &str) -> Vec<String> {
+    let value: serde_json::Value = match serde_json::from_str(text) {
+        Ok(value) => value,
+        Err(err) => {
+            log::debug!("package.json is not valid json: {}", err);
+            return vec![];
+        }
+    };
+    let patterns = match value.get("workspaces") {
+        Some(serde_json::Value::Array(x)) => x,
+        Some(serde_json::Value::Object(x)) => match x.get("packages") {
+            Some(serde_json::Value::Array(x)) => x,
+            _ => return vec![],
+        },
+        _ => return vec![],
+    };
+    let mut r = vec![];
+    for p in patterns.iter().filter_map(|x| x.as_str()) {
+        let p = p.trim_start_matches("./").trim_end_matches('/');
+        if p.is_empty() || p.starts_with('!') || p.starts_with("..") || p.starts_with('/') {
+            log::warn!("npm workspace pattern {:?} is not handled", p);
+            continue;
+        }
+        if !r.iter().any(|x| x == p) {
+            r.push(p.to_string());
+        }
+    }
+    r
+}
+
+/// Matches the child directory `name` against workspace `patterns`.
+///
+/// Returns whether `name` is a workspace package,
+/// and the patterns, relative to `name`, that could match packages deeper in the tree.
+pub(crate) fn child_workspaces(patterns: &[String], name: &str) -> (bool, Vec<String>) {
+    fn aux(p: &str, name: &str, r: &mut (bool, Vec<String>)) {
+        let (head, rest) = p.split_once('/').unwrap_or((p, ""));
+        if head == "**" {
+            // matches zero directories
+            if !rest.is_empty() {
+                aux(rest, name, r);
+            } else {
+                r.0 = true;
+            }
+            // or one and more
+            if !r.1.iter().any(|x| x == p) {
+                r.1.push(p.to_string());
+            }
+        } else if glob_match(head.as_bytes(), name.as_bytes()) {
+            if rest.is_empty() {
+                r.0 = true;
+            } else if !r.1.iter().any(|x| x == rest) {
+                r.1.push(rest.to_string());
+            }
+        }
+    }
+    let mut r = (false, vec![]);
+    for p in patterns {
+        aux(p, name, &mut r);
+    }
+    r
+}
+
+/// glob matching of a single path component, supports `*` and `?`
+fn glob_match(pattern: &[u8], name: &[u8]) -> bool {
+    match (pattern.first(), name.first()) {
+        (None, None) => true,
+        (Some(b'*'), _) => {
+            glob_match(&pattern[1..], name) || (!name.is_empty() && glob_match(pattern, &name[1..]))
+        }
+        (Some(b'?'), Some(_)) => glob_match(&pattern[1..], &name[1..]),
+        (Some(p), Some(n)) if p == n => glob_match(&pattern[1..], &name[1..]),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn declared_workspaces() {
+        let text = r#"{
+  "name": "root",
+  "private": true,
+  "workspaces": ["packages/*", "./apps/web/", "!packages/ignored"]
+}"#;
+        assert_eq!(workspaces(text), vec!["packages/*", "apps/web"]);
+        let text = r#"{ "workspaces": { "packages": ["libs/**"], "nohoist": ["**/react"] } }"#;
+        assert_eq!(workspaces(text), vec!["libs/**"]);
+        assert!(workspaces(r#"{ "name": "single" }"#).is_empty());
+    }
+
+    #[test]
+    fn matching_workspaces() {
+        let patterns = vec![
+            "packages/*".to_string(),
+            "apps/web".to_string(),
+            "libs/**".to_string(),
+            "tool-*".to_string(),
+        ];
+        assert_eq!(
+            child_workspaces(&patterns, "packages"),
+            (false, vec!["*".to_string()])
+        );
+        assert_eq!(child_workspaces(&patterns, "tool-cli"), (true, vec![]));
+        assert_eq!(
+            child_workspaces(&patterns, "libs"),
+            (false, vec!["**".to_string()])
+        );
+        assert_eq!(
+            child_workspaces(&["**".to_string()], "nested"),
+            (true, vec!["**".to_string()])
+        );
+        assert_eq!(child_workspaces(&patterns, "docs"), (false, vec![]));
+    }
+}
@@ -0,0 +1,179 @@
+//! Language neutral types,
+//! given to the files that no generator handles (see [`crate::any_processor`]).
+//! Only their name is kept.
+
+use std::fmt::Display;
+
+use hyperast::types::{AnyType, HyperType, LangRef, LangWrapper, TypeU16};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u16)]
+pub enum Type {
+    /// a file without a matching generator
+    File,
+}
+
+const S_T_L: &'static [Type] = &[Type::File];
+
+impl Type {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            Type::File => "File",
+        }
+    }
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.to_str())
+    }
+}
+
+#[derive(Debug)]
+pub struct Lang;
+
+pub type TType = TypeU16<Lang>;
+
+impl hyperast::types::Lang<Type> for Lang {
+    fn make(t: u16) -> &'static Type {
+        Lang.make(t)
+    }
+    fn to_u16(t: Type) -> u16 {
+        Lang.to_u16(t)
+    }
+}
+
+impl LangRef<Type> for Lang {
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Lang>()
+    }
+
+    fn make(&self, t: u16) -> &'static Type {
+        &S_T_L[t as usize]
+    }
+
+    fn to_u16(&self, t: Type) -> u16 {
+        t as u16
+    }
+
+    fn ts_symbol(&self, _t: Type) -> u16 {
+        unimplemented!("no grammar for opaque files")
+    }
+}
+
+impl LangRef<AnyType> for Lang {
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Lang>()
+    }
+
+    fn make(&self, _t: u16) -> &'static AnyType {
+        todo!()
+    }
+
+    fn to_u16(&self, t: AnyType) -> u16 {
+        let t: &Type = t.as_any().downcast_ref().unwrap();
+        Lang.to_u16(*t)
+    }
+
+    fn ts_symbol(&self, _t: AnyType) -> u16 {
+        unimplemented!("no grammar for opaque files")
+    }
+}
+
+impl LangRef<TType> for Lang {
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Lang>()
+    }
+
+    fn make(&self, t: u16) -> &'static TType {
+        // TypeU16 only holds the u16, like in the generated languages
+        unsafe { std::mem::transmute(&S_T_L[t as usize]) }
+    }
+
+    fn to_u16(&self, t: TType) -> u16 {
+        t.e() as u16
+    }
+
+    fn ts_symbol(&self, _t: TType) -> u16 {
+        unimplemented!("no grammar for opaque files")
+    }
+}
+
+impl hyperast::types::LLang<TType> for Lang {
+    type I = u16;
+
+    type E = Type;
+
+    const TE: &[Self::E] = S_T_L;
+
+    fn as_lang_wrapper() -> LangWrapper<TType> {
+        From::<&'static (dyn LangRef<_>)>::from(&Lang)
+    }
+}
+
+impl HyperType for Type {
+    fn generic_eq(&self, other: &dyn HyperType) -> bool
+    where
+        Self: 'static + PartialEq + Sized,
+    {
+        other
+            .as_any()
+            .downcast_ref::<Self>()
+            .map_or(false, |a| self == a)
+    }
+
+    fn as_shared(&self) -> hyperast::types::Shared {
+        hyperast::types::Shared::Other
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_static(&self) -> &'static dyn HyperType {
+        <Lang as hyperast::types::Lang<Type>>::make(*self as u16)
+    }
+
+    fn as_static_str(&self) -> &'static str {
+        self.to_str()
+    }
+
+    fn is_file(&self) -> bool {
+        true
+    }
+
+    fn is_directory(&self) -> bool {
+        false
+    }
+
+    fn is_spaces(&self) -> bool {
+        false
+    }
+
+    fn is_syntax(&self) -> bool {
+        false
+    }
+
+    fn is_hidden(&self) -> bool {
+        false
+    }
+
+    fn is_named(&self) -> bool {
+        true
+    }
+
+    fn is_supertype(&self) -> bool {
+        false
+    }
+
+    fn get_lang(&self) -> LangWrapper<Self>
+    where
+        Self: Sized,
+    {
+        LangWrapper::from(&Lang as &(dyn LangRef<Self> + 'static))
+    }
+
+    fn lang_ref(&self) -> LangWrapper<AnyType> {
+        LangWrapper::from(&Lang as &(dyn LangRef<AnyType> + 'static))
+    }
+}
@@ -0,0 +1,241 @@
+//! Syntax errors found by tree-sitter in processed files.
+//!
+//! Unless [`crate::PROPAGATE_ERROR_ON_BAD_CST_NODE`] is set, files with ERROR or MISSING nodes
+//! are still processed, so their errors are recorded here.
+//! Errors are kept per file node, as files are deduplicated,
+//! a file with the same name and content has the same errors in every commit.
+//!
+//! Files that were not parsed at all are also recorded here,
+//! see [`crate::processing::FileLimits`].
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+
+use hyperast::store::defaults::NodeIdentifier;
+use hyperast::store::nodes::legion::persist::{
+    read_u32, read_u64, write_u32, write_u64, IdRemap, Persist,
+};
+use hyperast::types::{HyperAST, HyperType, LabelStore, Labeled, WithChildren};
+
+use crate::SimpleStores;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// text that could not be parsed
+    Error,
+    /// node inserted by tree-sitter to recover, it is empty
+    Missing,
+    /// the whole file was not parsed, see [`crate::processing::FileLimits`]
+    TooLarge,
+    TimedOut,
+    /// processing was cancelled while parsing the file, see [`crate::processing::Progress::cancel`]
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    /// byte offsets in the file
+    pub start: usize,
+    pub end: usize,
+    /// zero-based position of `start`
+    pub row: usize,
+    pub column: usize,
+}
+
+/// The ERROR and MISSING nodes of `tree`, outermost first.
+///
+/// Errors nested in an ERROR node are not reported separately.
+pub(crate) fn collect(tree: &tree_sitter::Tree) -> Vec<ParseError> {
+    let mut errors = vec![];
+    if !tree.root_node().has_error() {
+        return errors;
+    }
+    let mut cursor = tree.walk();
+    loop {
+        let node = cursor.node();
+        let kind = if node.is_error() {
+            Some(ParseErrorKind::Error)
+        } else if node.is_missing() {
+            Some(ParseErrorKind::Missing)
+        } else {
+            None
+        };
+        if let Some(kind) = kind {
+            let position = node.start_position();
+            errors.push(ParseError {
+                kind,
+                start: node.start_byte(),
+                end: node.end_byte(),
+                row: position.row,
+                column: position.column,
+            });
+        } else if node.has_error() && cursor.goto_first_child() {
+            continue;
+        }
+        while !cursor.goto_next_sibling() {
+            if !cursor.goto_parent() {
+                return errors;
+            }
+        }
+    }
+}
+
+/// The parse errors of processed files, only files with errors are present
+#[derive(Default, Debug)]
+pub struct ParseErrors(HashMap<NodeIdentifier, Box<[ParseError]>>);
+
+impl ParseErrors {
+    pub fn get(&self, file: &NodeIdentifier) -> Option<&[ParseError]> {
+        self.0.get(file).map(|x| x.as_ref())
+    }
+
+    pub(crate) fn insert(&mut self, file: NodeIdentifier, errors: Vec<ParseError>) {
+        if !errors.is_empty() {
+            self.0.insert(file, errors.into());
+        }
+    }
+
+    /// Keeps the errors of the files for which `f` holds, e.g. the ones remaining after a [`crate::gc`]
+    pub(crate) fn retain(&mut self, f: impl Fn(&NodeIdentifier) -> bool) {
+        self.0.retain(|file, _| f(file))
+    }
+
+    /// number of distinct files with errors
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The files with errors under the directory `root` (e.g. the root of a commit),
+    /// along their path from `root`
+    pub fn in_tree(
+        &self,
+        stores: &SimpleStores,
+        root: NodeIdentifier,
+    ) -> Vec<(String, NodeIdentifier)> {
+        let mut files = vec![];
+        if self.0.is_empty() {
+            return files;
+        }
+        let mut stack = vec![(String::new(), root)];
+        while let Some((path, id)) = stack.pop() {
+            if self.0.contains_key(&id) {
+                files.push((path, id));
+                continue;
+            }
+            // errors are only recorded on files, no need to look inside them
+            if !stores.resolve_type(&id).is_directory() {
+                continue;
+            }
+            let n = stores.node_store.resolve(id);
+            let Some(cs) = n.children() else {
+                continue;
+            };
+            for c in cs.iter_children() {
+                let name = stores
+                    .node_store
+                    .resolve(*c)
+                    .try_get_label()
+                    .map_or("", |l| stores.label_store.resolve(l));
+                let path = if path.is_empty() {
+                    name.to_string()
+                } else {
+                    format!("{}/{}", path, name)
+                };
+                stack.push((path, *c));
+            }
+        }
+        files.sort();
+        files
+    }
+
+    /// Persists the errors of `files`, the ones without errors are skipped
+    pub(crate) fn persist_some(
+        &self,
+        files: &[NodeIdentifier],
+        w: &mut dyn Write,
+    ) -> io::Result<()> {
+        let files: Vec<_> = files
+            .iter()
+            .filter_map(|x| Some((x, self.0.get(x)?)))
+            .collect();
+        write_u64(w, files.len() as u64)?;
+        for (file, errors) in files {
+            file.persist(w)?;
+            write_u32(w, errors.len() as u32)?;
+            for e in errors.iter() {
+                w.write_all(&[e.kind as u8])?;
+                write_u64(w, e.start as u64)?;
+                write_u64(w, e.end as u64)?;
+                write_u64(w, e.row as u64)?;
+                write_u64(w, e.column as u64)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn persist(&self, w: &mut dyn Write) -> io::Result<()> {
+        let files: Vec<_> = self.0.keys().copied().collect();
+        self.persist_some(&files, w)
+    }
+
+    /// Adds the persisted errors, see [`ParseErrors::persist_some`]
+    pub(crate) fn restore_into(&mut self, r: &mut dyn Read, remap: &IdRemap) -> io::Result<()> {
+        let n = read_u64(r)?;
+        for _ in 0..n {
+            let file = NodeIdentifier::restore(r, remap)?;
+            let count = read_u32(r)?;
+            let mut errors = Vec::with_capacity(count as usize);
+            for _ in 0..count {
+                let mut kind = [0; 1];
+                r.read_exact(&mut kind)?;
+                let kind = match kind[0] {
+                    0 => ParseErrorKind::Error,
+                    1 => ParseErrorKind::Missing,
+                    2 => ParseErrorKind::TooLarge,
+                    3 => ParseErrorKind::TimedOut,
+                    4 => ParseErrorKind::Cancelled,
+                    x => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("unknown parse error kind {}", x),
+                        ))
+                    }
+                };
+                errors.push(ParseError {
+                    kind,
+                    start: read_u64(r)? as usize,
+                    end: read_u64(r)? as usize,
+                    row: read_u64(r)? as usize,
+                    column: read_u64(r)? as usize,
+                });
+            }
+            self.insert(file, errors);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collect_outermost_errors() {
+        let text = b"class A { void f( { } }";
+        let tree = hyperast_gen_ts_java::legion_with_refs::tree_sitter_parse(text)
+            .expect_err("should contain errors");
+        let errors = collect(&tree);
+        assert!(!errors.is_empty());
+        for e in &errors {
+            assert!(e.start <= e.end && e.end <= text.len());
+            assert_eq!(e.row, 0);
+        }
+        let tree = hyperast_gen_ts_java::legion_with_refs::tree_sitter_parse(b"class A {}")
+            .expect("should be valid");
+        assert!(collect(&tree).is_empty());
+    }
+}
@@ -44,6 +44,20 @@ pub struct RepositoryProcessor {
     pub processing_systems: crate::processing::erased::ProcessorMap,
     pub parsing_time: Duration,
     pub processing_time: Duration,
+    /// see [`crate::store_log`]
+    pub(crate) store_log: Option<crate::store_log::StoreLog>,
+    /// Parse the blobs of upcoming commits in parallel in [`RepositoryProcessor::pre_pro`],
+    /// None to parse blobs sequentially, while processing them
+    pub parallel: Option<crate::processing::ParallelConfig>,
+    /// Files exceeding these limits are replaced by placeholders
+    pub limits: crate::processing::FileLimits,
+    pub(crate) pre_parsed: crate::processing::pre_parsing::PreParsed,
+    /// ERROR and MISSING nodes of the processed files, see [`crate::parse_errors`]
+    pub parse_errors: crate::parse_errors::ParseErrors,
+    /// None unless enabled, see [`crate::label_index`]
+    pub label_index: Option<crate::label_index::LabelIndex>,
+    /// nodes inserted while processing dropped commits, see [`RepositoryProcessor::drop_journal`]
+    pub(crate) unlogged_nodes: Vec<hyperast::store::defaults::NodeIdentifier>,
 }
 // NOTE what about making a constraints between sys processors
 // it should be a 1..n relation so it must be impl on the target
@@ -87,6 +101,38 @@ impl RepositoryProcessor {
 
     pub fn purge_caches(&mut self) {
         self.processing_systems.clear();
+        self.pre_parsed.reset();
+    }
+
+    /// Report the following processings to `progress`,
+    /// they stop before the next commit once it is cancelled.
+    /// The files being parsed are interrupted and replaced by placeholders, so the commit is dropped,
+    /// but caches of processors might still refer to these placeholders,
+    /// see [`crate::multi_preprocessed::PreProcessedRepositories::purge_blob_caches`].
+    ///
+    /// None to stop reporting.
+    pub fn set_progress(&mut self, progress: Option<std::sync::Arc<crate::processing::Progress>>) {
+        self.processing_systems.reporting_mut().progress = progress
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.processing_systems.reporting().is_cancelled()
+    }
+
+    /// Indexes the files of a processed commit, if the label index is enabled
+    pub(crate) fn index_commit(
+        &mut self,
+        handle: crate::processing::ParametrizedCommitProcessorHandle,
+        oid: Oid,
+    ) {
+        let Some(index) = &mut self.label_index else {
+            return;
+        };
+        let proc = self.processing_systems.by_id(&handle.0).unwrap();
+        let Some(commit) = proc.get(handle.1).get_commit(oid) else {
+            return;
+        };
+        index.index_tree(&self.main_stores, commit.ast_root);
     }
 }
 
@@ -225,20 +271,70 @@ impl RepositoryProcessor {
         size: usize,
     ) -> Vec<Oid> {
         let mut r = Vec::with_capacity(rw.size_hint().0);
+        if let Some(parallel) = self.parallel {
+            // parse the blobs of a window of commits in parallel, then build them in order
+            let mut rw = rw.take(size).peekable();
+            while rw.peek().is_some() {
+                let window: Vec<_> = rw.by_ref().take(parallel.window.max(1)).collect();
+                let progress = self.processing_systems.reporting().progress.clone();
+                let bounds = crate::processing::Bounds::new(self.limits, progress.as_deref());
+                self.pre_parsed
+                    .prefetch(&repository.repo, &window, parallel.threads, bounds);
+                for oid in window {
+                    if self.is_cancelled() {
+                        break;
+                    }
+                    if self.pre_pro_commit(repository, oid) {
+                        r.push(oid);
+                    }
+                }
+                self.pre_parsed.clear();
+                if self.is_cancelled() {
+                    break;
+                }
+            }
+            return r;
+        }
         for _ in 0..size {
+            if self.is_cancelled() {
+                break;
+            }
             let Some(oid) = rw.next() else { break };
-            let builder = crate::preprocessed::CommitBuilder::start(&repository.repo, oid);
-            let commit_processor = self
-                .processing_systems
+            if self.pre_pro_commit(repository, oid) {
+                r.push(oid);
+            }
+        }
+        r
+    }
+
+    /// Returns false if the commit was dropped, as it was cancelled while being processed
+    fn pre_pro_commit(&mut self, repository: &ConfiguredRepo2, oid: Oid) -> bool {
+        let builder = crate::preprocessed::CommitBuilder::start(&repository.repo, oid)
+            .counting_failures(&self.processing_systems.reporting().failures);
+        let commit_processor = self
+            .processing_systems
+            .by_id_mut(&repository.config.0)
+            .unwrap()
+            .get_mut(repository.config.1);
+        let _id = commit_processor
+            .prepare_processing(&repository.repo, builder, repository.config)
+            .process(self);
+        if self.is_cancelled() {
+            // some of its files might have been replaced by placeholders
+            self.processing_systems
                 .by_id_mut(&repository.config.0)
                 .unwrap()
-                .get_mut(repository.config.1);
-            let _id = commit_processor
-                .prepare_processing(&repository.repo, builder, repository.config)
-                .process(self);
-            r.push(oid);
+                .get_mut(repository.config.1)
+                .remove_commit(oid);
+            self.drop_journal();
+            return false;
         }
-        r
+        self.log_commit(&repository.spec, repository.config, oid);
+        self.index_commit(repository.config, oid);
+        if let Some(progress) = &self.processing_systems.reporting().progress {
+            progress.commit_done();
+        }
+        true
     }
 }
 
@@ -609,6 +705,8 @@ pub struct CommitBuilder {
     parents: Vec<git2::Oid>,
     memory_used: hyperast_gen_ts_java::utils::MemoryUsage,
     time: Instant,
+    /// the failure counter and its value at the start
+    failures: Option<(std::sync::Arc<std::sync::atomic::AtomicUsize>, usize)>,
 }
 
 impl CommitBuilder {
@@ -629,9 +727,20 @@ impl CommitBuilder {
             parents,
             time,
             memory_used,
+            failures: None,
         }
     }
 
+    /// Also count the blobs that failed to be processed, see [`Commit::parse_failures`]
+    pub(crate) fn counting_failures(
+        mut self,
+        failures: &std::sync::Arc<std::sync::atomic::AtomicUsize>,
+    ) -> Self {
+        let start = failures.load(std::sync::atomic::Ordering::Relaxed);
+        self.failures = Some((failures.clone(), start));
+        self
+    }
+
     pub(crate) fn tree_oid(&self) -> git2::Oid {
         self.tree_oid
     }
@@ -646,6 +755,9 @@ impl CommitBuilder {
         let memory_used = memory_used.into();
         let tree_oid = self.tree_oid;
         let parents = self.parents;
+        let parse_failures = self.failures.map_or(0, |(failures, start)| {
+            (failures.load(std::sync::atomic::Ordering::Relaxed) - start) as u32
+        });
 
         Commit {
             parents,
@@ -653,6 +765,7 @@ impl CommitBuilder {
             ast_root,
             processing_time,
             memory_used,
+            parse_failures,
         }
     }
 }
@@ -7,8 +7,20 @@ mod blob_caching;
 pub mod erased;
 pub use erased::ParametrizedCommitProcessorHandle;
 
+pub(crate) mod pre_parsing;
+pub use pre_parsing::ParallelConfig;
+
+mod limits;
+pub(crate) use limits::{Bounds, Comment};
+pub use limits::FileLimits;
+
+mod progress;
+pub(crate) use progress::Reporting;
+pub use progress::{Progress, ProgressSnapshot};
+
 pub enum BuildSystem {
     Maven,
+    Gradle,
     Make,
     Npm,
     None,
@@ -16,6 +28,7 @@ pub enum BuildSystem {
 
 pub enum ProcessingConfig<P> {
     JavaMaven { limit: usize, dir_path: P },
+    JavaGradle { limit: usize, dir_path: P },
     CppMake { limit: usize, dir_path: P },
     TsNpm { limit: usize, dir_path: P },
     Any { limit: usize, dir_path: P },
@@ -27,10 +40,25 @@ pub enum ProcessingConfig<P> {
 pub enum RepoConfig {
     CppMake,
     JavaMaven,
+    JavaGradle,
     TsNpm,
     Any,
 }
 
+/// Custom parameters of a registered repository, on top of its [`RepoConfig`],
+/// kept to register it again, e.g. when caches are purged or a snapshot is loaded
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum ConfigParams {
+    #[default]
+    None,
+    /// a lua script computing metrics while building subtrees
+    Prepro(std::sync::Arc<str>),
+    /// queries whose matches are precomputed while building subtrees
+    Prequeries(Vec<String>),
+    /// a tree-sitter-graph specification
+    Tsg(std::sync::Arc<str>),
+}
+
 impl std::str::FromStr for RepoConfig {
     type Err = String;
 
@@ -40,10 +68,13 @@ impl std::str::FromStr for RepoConfig {
             "cpp" => Self::CppMake,
             "Java" => Self::JavaMaven,
             "java" => Self::JavaMaven,
+            "Gradle" => Self::JavaGradle,
+            "gradle" => Self::JavaGradle,
             "typescript" => Self::TsNpm,
             "javascript" => Self::TsNpm,
             "Ts" => Self::TsNpm,
             "ts" => Self::TsNpm,
+            "Any" => Self::Any,
             "any" => Self::Any,
             x => return Err(format!("'{}' is not anvailable config", x)),
         })
@@ -61,8 +92,18 @@ impl From<&RepoConfig> for ProcessingConfig<&'static str> {
                 limit: 3,
                 dir_path: "",
             },
-            RepoConfig::TsNpm => todo!(),
-            RepoConfig::Any => todo!(),
+            RepoConfig::JavaGradle => Self::JavaGradle {
+                limit: 3,
+                dir_path: "",
+            },
+            RepoConfig::TsNpm => Self::TsNpm {
+                limit: 3,
+                dir_path: "",
+            },
+            RepoConfig::Any => Self::Any {
+                limit: 3,
+                dir_path: "",
+            },
         }
     }
 }
@@ -136,6 +177,14 @@ impl ConfiguredRepoHandle2 {
             config: self.config,
         }
     }
+    /// Like [`ConfiguredRepoHandle2::fetch`], but errors instead of panicking
+    pub fn try_fetch(self) -> Result<ConfiguredRepo2, String> {
+        Ok(ConfiguredRepo2 {
+            repo: self.spec.try_fetch()?,
+            spec: self.spec,
+            config: self.config,
+        })
+    }
 }
 
 pub struct ConfiguredRepo {
@@ -301,6 +350,26 @@ pub(crate) mod caches {
         }
     }
 
+    #[derive(Default)]
+    pub struct Ts {
+        pub(crate) md_cache: hyperast_gen_ts_ts::legion::MDCache,
+        pub object_map: NamedMap<(hyperast_gen_ts_ts::legion::Local, IsSkippedAna)>,
+    }
+
+    impl super::ObjectMapper for Ts {
+        type K = (git2::Oid, ObjectName);
+
+        type V = (hyperast_gen_ts_ts::legion::Local, IsSkippedAna);
+
+        fn get(&self, key: &Self::K) -> Option<&Self::V> {
+            self.object_map.get(key)
+        }
+
+        fn insert(&mut self, key: Self::K, value: Self::V) -> Option<Self::V> {
+            self.object_map.insert(key, value)
+        }
+    }
+
     #[derive(Default)]
     pub struct Maven {
         pub object_map: OidMap<(NodeIdentifier, crate::maven::MD)>,
@@ -325,6 +394,31 @@ pub(crate) mod caches {
         }
     }
 
+    #[derive(Default)]
+    pub struct Gradle {
+        pub object_map: OidMap<(NodeIdentifier, crate::maven::MD)>,
+    }
+
+    #[derive(Default)]
+    pub struct GradleFile {
+        pub object_map: NamedMap<crate::gradle::GradleFile>,
+    }
+
+    impl super::ObjectMapper for GradleFile {
+        // named as the same content can be a build or a settings script
+        type K = (git2::Oid, ObjectName);
+
+        type V = crate::gradle::GradleFile;
+
+        fn get(&self, key: &Self::K) -> Option<&Self::V> {
+            self.object_map.get(key)
+        }
+
+        fn insert(&mut self, key: Self::K, value: Self::V) -> Option<Self::V> {
+            self.object_map.insert(key, value)
+        }
+    }
+
     #[derive(Default)]
     pub struct Make {
         pub object_map: OidMap<(NodeIdentifier, crate::make::MD)>,
@@ -349,6 +443,98 @@ pub(crate) mod caches {
         }
     }
 
+    #[derive(Default)]
+    pub struct Npm {
+        pub object_map: OidMap<(NodeIdentifier, crate::npm::MD)>,
+    }
+
+    #[derive(Default)]
+    pub struct PackageJson {
+        pub object_map: OidMap<crate::npm::PackageJson>,
+    }
+
+    impl super::ObjectMapper for PackageJson {
+        type K = git2::Oid;
+
+        type V = crate::npm::PackageJson;
+
+        fn get(&self, key: &Self::K) -> Option<&Self::V> {
+            self.object_map.get(key)
+        }
+
+        fn insert(&mut self, key: Self::K, value: Self::V) -> Option<Self::V> {
+            self.object_map.insert(key, value)
+        }
+    }
+
+    #[derive(Default)]
+    pub struct Any {
+        pub object_map: NamedMap<(NodeIdentifier, crate::DefaultMetrics)>,
+    }
+
+    #[cfg(feature = "c")]
+    #[derive(Default)]
+    pub struct C {
+        pub(crate) md_cache: hyperast_gen_ts_c::legion::MDCache,
+        pub object_map: NamedMap<(NodeIdentifier, crate::DefaultMetrics)>,
+    }
+
+    #[cfg(feature = "c")]
+    impl super::ObjectMapper for C {
+        type K = (git2::Oid, ObjectName);
+
+        type V = (NodeIdentifier, crate::DefaultMetrics);
+
+        fn get(&self, key: &Self::K) -> Option<&Self::V> {
+            self.object_map.get(key)
+        }
+
+        fn insert(&mut self, key: Self::K, value: Self::V) -> Option<Self::V> {
+            self.object_map.insert(key, value)
+        }
+    }
+
+    #[derive(Default)]
+    pub struct Xml {
+        pub object_map: NamedMap<(NodeIdentifier, crate::DefaultMetrics)>,
+    }
+
+    impl super::ObjectMapper for Xml {
+        type K = (git2::Oid, ObjectName);
+
+        type V = (NodeIdentifier, crate::DefaultMetrics);
+
+        fn get(&self, key: &Self::K) -> Option<&Self::V> {
+            self.object_map.get(key)
+        }
+
+        fn insert(&mut self, key: Self::K, value: Self::V) -> Option<Self::V> {
+            self.object_map.insert(key, value)
+        }
+    }
+
+    #[cfg(feature = "tsquery")]
+    #[derive(Default)]
+    pub struct TsQuery {
+        pub(crate) md_cache: hyperast_gen_ts_tsquery::legion::MDCache,
+        pub object_map: NamedMap<(NodeIdentifier, crate::DefaultMetrics)>,
+    }
+
+    #[cfg(feature = "tsquery")]
+    impl super::ObjectMapper for TsQuery {
+        type K = (git2::Oid, ObjectName);
+
+        type V = (NodeIdentifier, crate::DefaultMetrics);
+
+        fn get(&self, key: &Self::K) -> Option<&Self::V> {
+            self.object_map.get(key)
+        }
+
+        fn insert(&mut self, key: Self::K, value: Self::V) -> Option<Self::V> {
+            self.object_map.insert(key, value)
+        }
+    }
+
     // // any
     // pub object_map_any: OidMap<(NodeIdentifier, DefaultMetrics)>,
     // // maven
@@ -390,6 +576,11 @@ pub mod file_sys {
     /// The default file system, directories and files
     pub struct Any;
 
+    #[cfg(feature = "any")]
+    impl CachesHolding for Any {
+        type Caches = super::caches::Any;
+    }
+
     /// The maven scheme https://maven.apache.org/guides/introduction/introduction-to-the-standard-directory-layout.html ,
     /// made of nested maven modules.
     /// Each maven module has a config file (often a pom.xml),
@@ -418,6 +609,34 @@ pub mod file_sys {
         }
     }
 
+    /// The gradle scheme https://docs.gradle.org/current/userguide/multi_project_builds.html ,
+    /// like the maven one, made of nested projects, each with their own src/main/java/ and src/test/java/ directories.
+    /// Subprojects are declared in a settings.gradle (or settings.gradle.kts),
+    /// and each project has a build script, build.gradle (or build.gradle.kts).
+    #[cfg(feature = "gradle")]
+    pub struct Gradle;
+
+    impl CachesHolding for Gradle {
+        type Caches = super::caches::Gradle;
+    }
+
+    #[cfg(feature = "gradle")]
+    pub struct GradleFile;
+
+    #[cfg(feature = "gradle")]
+    impl CachesHolding for GradleFile {
+        type Caches = super::caches::GradleFile;
+    }
+
+    impl super::InFiles for GradleFile {
+        fn matches(name: &ObjectName) -> bool {
+            name.0.eq(b"build.gradle")
+                || name.0.eq(b"build.gradle.kts")
+                || name.0.eq(b"settings.gradle")
+                || name.0.eq(b"settings.gradle.kts")
+        }
+    }
+
     /// The java scheme,
     /// made of packages and modules https://docs.oracle.com/javase/specs/jls/se11/html/jls-7.html
     #[cfg(feature = "maven")]
@@ -489,6 +708,90 @@ pub mod file_sys {
     /// or is a collection of packages that contains a packages/ directory where each package is located
     #[cfg(feature = "npm")]
     pub struct Npm;
+
+    impl CachesHolding for Npm {
+        type Caches = super::caches::Npm;
+    }
+
+    #[cfg(feature = "npm")]
+    pub struct PackageJson;
+
+    impl CachesHolding for PackageJson {
+        type Caches = super::caches::PackageJson;
+    }
+
+    impl super::InFiles for PackageJson {
+        fn matches(name: &ObjectName) -> bool {
+            name.0.eq(b"package.json")
+        }
+    }
+
+    /// Typescript and javascript sources, both handled by the typescript grammar
+    #[cfg(feature = "ts")]
+    pub struct Ts;
+
+    impl CachesHolding for Ts {
+        type Caches = super::caches::Ts;
+    }
+
+    impl super::InFiles for Ts {
+        fn matches(name: &ObjectName) -> bool {
+            name.0.ends_with(b".ts")
+                || name.0.ends_with(b".mts")
+                || name.0.ends_with(b".cts")
+                || name.0.ends_with(b".js")
+                || name.0.ends_with(b".mjs")
+                || name.0.ends_with(b".cjs")
+        }
+    }
+
+    /// C sources, headers are left to the cpp grammar as they are most often shared
+    #[cfg(feature = "c")]
+    pub struct C;
+
+    #[cfg(feature = "c")]
+    impl CachesHolding for C {
+        type Caches = super::caches::C;
+    }
+
+    #[cfg(feature = "c")]
+    impl super::InFiles for C {
+        fn matches(name: &ObjectName) -> bool {
+            name.0.ends_with(b".c")
+        }
+    }
+
+    /// Any xml document, outside of the build systems relying on them
+    #[cfg(feature = "maven")]
+    pub struct Xml;
+
+    #[cfg(feature = "maven")]
+    impl CachesHolding for Xml {
+        type Caches = super::caches::Xml;
+    }
+
+    #[cfg(feature = "maven")]
+    impl super::InFiles for Xml {
+        fn matches(name: &ObjectName) -> bool {
+            name.0.ends_with(b".xml")
+        }
+    }
+
+    /// Tree-sitter queries
+    #[cfg(feature = "tsquery")]
+    pub struct TsQuery;
+
+    #[cfg(feature = "tsquery")]
+    impl CachesHolding for TsQuery {
+        type Caches = super::caches::TsQuery;
+    }
+
+    #[cfg(feature = "tsquery")]
+    impl super::InFiles for TsQuery {
+        fn matches(name: &ObjectName) -> bool {
+            name.0.ends_with(b".scm")
+        }
+    }
 }
 
 impl crate::preprocessed::RepositoryProcessor {
@@ -509,13 +812,16 @@ macro_rules! make_multi {
 make_multi! {
     Java(Java, ),
     Pom,
+    GradleFile,
     Cpp,
     MakeFile,
     Ts,
     Js,
+    PackageJson,
     ;
     Maven [Java] Xml => crate::maven::Md,
+    Gradle [Java] GradleFile => crate::maven::Md,
     Make [Cpp] MakeFile => crate::make::Md,
-    Npm [Ts, Js] Xml => crate::make::Md,
+    Npm [Ts, Js] PackageJson => crate::npm::Md,
     None => crate::make::Md,
 }
@@ -63,7 +63,11 @@ impl<'cache, Sys> CachingBlobWrapper2<'cache, Sys> {
         std::str::from_utf8(blob.content())?;
         let text = blob.content();
         let full_node = wrapped(self.processors, &name, text);
-        if let Ok(x) = &full_node {
+        let reporting = self.processors.reporting();
+        reporting.blob_done(text.len(), full_node.is_ok());
+        // the placeholder of a timed out or cancelled parsing is not kept
+        let transient = reporting.take_transient();
+        if let (Ok(x), false) = (&full_node, transient) {
             self.processors
                 // .mut_or_default::<Sys::Holder>().get_caches_mut()
                 .mut_or_default::<T::Holder>()
@@ -135,7 +139,11 @@ impl<'cache, Sys> CachingBlobWrapper2<'cache, Sys> {
 
         let text = blob.content();
         let full_node = wrapped(self.processors, &name, text);
-        if let Ok(x) = &full_node {
+        let reporting = self.processors.reporting();
+        reporting.blob_done(text.len(), full_node.is_ok());
+        // the placeholder of a timed out or cancelled parsing is not kept
+        let transient = reporting.take_transient();
+        if let (Ok(x), false) = (&full_node, transient) {
             self.processors
                 .mut_or_default::<T::Holder>()
                 .with_parameters_mut(parameters.0)
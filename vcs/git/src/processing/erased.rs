@@ -62,6 +62,15 @@ pub trait CommitProc {
     ) -> Box<dyn PreparedCommitProc + 'repo>;
 
     fn get_commit(&self, commit_oid: git2::Oid) -> Option<&crate::Commit>;
+    /// All the commits handled by this processor, e.g. to persist them
+    fn commits(&self) -> Box<dyn Iterator<Item = (&git2::Oid, &crate::Commit)> + '_>;
+    /// Register an already processed commit, e.g. loaded from a snapshot
+    fn insert_commit(&mut self, commit_oid: git2::Oid, commit: crate::Commit);
+    /// Forget a processed commit, e.g. one that was interrupted
+    fn remove_commit(&mut self, commit_oid: git2::Oid) -> Option<crate::Commit>;
+    /// Drop the caches of blobs, e.g. those referring to placeholders of an interrupted commit,
+    /// the processed commits are kept
+    fn clear_caches(&mut self) {}
     fn get_precomp_query(&self) -> Option<hyperast_tsquery::ZeroSepArrayStr> {
         None
     }
@@ -148,6 +157,18 @@ fn t() {
         fn get_commit(&self, commit_oid: git2::Oid) -> Option<&crate::Commit> {
             unimplemented!("required for processing at the root of a project")
         }
+
+        fn commits(&self) -> Box<dyn Iterator<Item = (&git2::Oid, &crate::Commit)> + '_> {
+            unimplemented!("required for processing at the root of a project")
+        }
+
+        fn insert_commit(&mut self, commit_oid: git2::Oid, commit: crate::Commit) {
+            unimplemented!("required for processing at the root of a project")
+        }
+
+        fn remove_commit(&mut self, commit_oid: git2::Oid) -> Option<crate::Commit> {
+            unimplemented!("required for processing at the root of a project")
+        }
     }
     impl ParametrizedCommitProc for P0 {
         fn get_mut(&mut self, parameters: ConfigParametersHandle) -> &mut dyn CommitProc {
@@ -225,16 +246,26 @@ pub use spreaded::ErasableProcessor;
 mod spreaded {
     use super::*;
 
-    pub struct ProcessorMap<V>(std::collections::HashMap<std::any::TypeId, V>);
+    pub struct ProcessorMap<V>(
+        std::collections::HashMap<std::any::TypeId, V>,
+        crate::processing::Reporting,
+    );
     impl<V> Default for ProcessorMap<V> {
         fn default() -> Self {
-            Self(Default::default())
+            Self(Default::default(), Default::default())
         }
     }
     impl<V> ProcessorMap<V> {
         pub(crate) fn clear(&mut self) {
             self.0.clear()
         }
+        /// Where the processing of blobs is reported, it is not cleared with the processors
+        pub(crate) fn reporting(&self) -> &crate::processing::Reporting {
+            &self.1
+        }
+        pub(crate) fn reporting_mut(&mut self) -> &mut crate::processing::Reporting {
+            &mut self.1
+        }
     }
 
     unsafe impl<V> Send for ProcessorMap<V> {}
@@ -335,6 +366,18 @@ mod spreaded {
             fn get_commit(&self, commit_oid: git2::Oid) -> Option<&crate::Commit> {
                 todo!()
             }
+
+            fn commits(&self) -> Box<dyn Iterator<Item = (&git2::Oid, &crate::Commit)> + '_> {
+                todo!()
+            }
+
+            fn insert_commit(&mut self, commit_oid: git2::Oid, commit: crate::Commit) {
+                todo!()
+            }
+
+            fn remove_commit(&mut self, commit_oid: git2::Oid) -> Option<crate::Commit> {
+                todo!()
+            }
         }
         impl CommitProcExt for P {
             type Holder = P0;
@@ -0,0 +1,217 @@
+//! Bounds on the parsing of files, so that pathological ones (e.g. huge generated sources)
+//! do not stall the processing of commits.
+//!
+//! A file that is not parsed is replaced by a placeholder file made of a single comment,
+//! it is recorded as a [`ParseError`] spanning the whole file, whose kind tells why it was skipped.
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+
+use hyperast::tree_gen::utils_ts::{tree_sitter_parse, tree_sitter_parse_bounded, ParseBounds};
+
+use super::{ObjectName, Progress, Reporting};
+use crate::parse_errors::{ParseError, ParseErrorKind};
+
+/// Limits on each file parsed while processing commits,
+/// see [`crate::preprocessed::RepositoryProcessor::limits`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FileLimits {
+    /// in bytes, larger files are not parsed
+    pub max_size: Option<usize>,
+    /// parsing a file for longer is abandoned
+    pub timeout: Option<Duration>,
+}
+
+/// The limits of a processing along its cancellation flag
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Bounds<'a> {
+    limits: FileLimits,
+    cancel: Option<&'a AtomicUsize>,
+    /// raised when a file is replaced by the placeholder of a timed out or cancelled parsing
+    transient: Option<&'a AtomicBool>,
+}
+
+/// How a language writes comments, to make placeholders
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Comment {
+    /// `// ...`
+    Slashes,
+    /// `; ...`
+    Semicolon,
+    /// `<!-- ... -->`
+    Xml,
+}
+
+impl<'a> Bounds<'a> {
+    pub(crate) fn new(limits: FileLimits, progress: Option<&'a Progress>) -> Self {
+        Self {
+            limits,
+            cancel: progress.map(|x| x.cancellation_flag()),
+            transient: None,
+        }
+    }
+
+    /// Same as [`Bounds::new`], also flagging the files that should not be cached on `reporting`,
+    /// see [`Reporting::take_transient`]
+    pub(crate) fn reporting(limits: FileLimits, reporting: &'a Reporting) -> Self {
+        Self {
+            limits,
+            cancel: reporting.progress.as_ref().map(|x| x.cancellation_flag()),
+            transient: Some(&reporting.transient),
+        }
+    }
+
+    /// Parses `text` unless it is too large, Err with the reason if it was skipped
+    pub(crate) fn parse(
+        &self,
+        text: &[u8],
+        language: &tree_sitter::Language,
+    ) -> Result<Result<tree_sitter::Tree, tree_sitter::Tree>, ParseError> {
+        if self.limits.max_size.map_or(false, |max| text.len() > max) {
+            return Err(skipped(ParseErrorKind::TooLarge, text));
+        }
+        if self.is_cancelled() {
+            return Err(skipped(ParseErrorKind::Cancelled, text));
+        }
+        let bounds = ParseBounds {
+            timeout_micros: self.limits.timeout.map_or(0, |x| x.as_micros() as u64),
+            cancellation_flag: self.cancel,
+        };
+        tree_sitter_parse_bounded(text, language, bounds).ok_or_else(|| {
+            if self.is_cancelled() {
+                skipped(ParseErrorKind::Cancelled, text)
+            } else {
+                skipped(ParseErrorKind::TimedOut, text)
+            }
+        })
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel
+            .map_or(false, |x| x.load(Ordering::Relaxed) != 0)
+    }
+
+    /// The text and tree to generate a file from, the ones of a placeholder if it was skipped.
+    ///
+    /// The placeholder of a timed out or cancelled parsing is flagged,
+    /// the file could be parsed next time.
+    pub(crate) fn or_placeholder<'b>(
+        &self,
+        name: &ObjectName,
+        text: &'b [u8],
+        tree: Result<Result<tree_sitter::Tree, tree_sitter::Tree>, ParseError>,
+        language: &tree_sitter::Language,
+        comment: Comment,
+    ) -> (
+        &'b [u8],
+        Result<tree_sitter::Tree, tree_sitter::Tree>,
+        Option<ParseError>,
+    ) {
+        match tree {
+            Ok(tree) => (text, tree, None),
+            Err(skipped) => {
+                log::warn!("skipped {:?}: {:?}", name.try_str(), skipped.kind);
+                if matches!(
+                    skipped.kind,
+                    ParseErrorKind::TimedOut | ParseErrorKind::Cancelled
+                ) {
+                    if let Some(transient) = self.transient {
+                        transient.store(true, Ordering::Relaxed);
+                    }
+                }
+                let text = placeholder(skipped.kind, comment);
+                (text, tree_sitter_parse(text, language), Some(skipped))
+            }
+        }
+    }
+}
+
+fn skipped(kind: ParseErrorKind, text: &[u8]) -> ParseError {
+    ParseError {
+        kind,
+        start: 0,
+        end: text.len(),
+        row: 0,
+        column: 0,
+    }
+}
+
+/// The content of the file replacing a skipped one
+fn placeholder(kind: ParseErrorKind, comment: Comment) -> &'static [u8] {
+    macro_rules! placeholder {
+        ($reason:literal) => {
+            match comment {
+                Comment::Slashes => concat!("// skipped by hyperast: ", $reason, "\n"),
+                Comment::Semicolon => concat!("; skipped by hyperast: ", $reason, "\n"),
+                Comment::Xml => concat!("<!-- skipped by hyperast: ", $reason, " -->\n"),
+            }
+            .as_bytes()
+        };
+    }
+    match kind {
+        ParseErrorKind::TooLarge => placeholder!("too large"),
+        ParseErrorKind::TimedOut => placeholder!("parsing timed out"),
+        ParseErrorKind::Cancelled => placeholder!("processing cancelled"),
+        ParseErrorKind::Error | ParseErrorKind::Missing => {
+            unreachable!("files with syntax errors are not skipped")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skip_large_files_and_cancelled_parsing() {
+        let language = hyperast_gen_ts_java::language();
+        let text = b"class A { void f() {} }";
+        let limits = FileLimits {
+            max_size: Some(10),
+            timeout: None,
+        };
+        let skipped = Bounds::new(limits, None)
+            .parse(text, &language)
+            .unwrap_err();
+        assert_eq!(skipped.kind, ParseErrorKind::TooLarge);
+        assert_eq!(skipped.end, text.len());
+
+        let progress = Progress::default();
+        assert!(Bounds::new(FileLimits::default(), Some(&progress))
+            .parse(text, &language)
+            .unwrap()
+            .is_ok());
+        progress.cancel();
+        let skipped = Bounds::new(FileLimits::default(), Some(&progress))
+            .parse(text, &language)
+            .unwrap_err();
+        assert_eq!(skipped.kind, ParseErrorKind::Cancelled);
+
+        let name = ObjectName::from(&b"A.java"[..]);
+        let reporting = Reporting::default();
+        let bounds = Bounds::reporting(FileLimits::default(), &reporting);
+        let (text, tree, _) =
+            bounds.or_placeholder(&name, text, Err(skipped), &language, Comment::Slashes);
+        assert_eq!(text, b"// skipped by hyperast: processing cancelled\n");
+        assert!(tree.is_ok());
+        // could be parsed next time, so it is not cached
+        assert!(reporting.take_transient());
+        assert!(!reporting.take_transient());
+    }
+
+    #[test]
+    fn cache_placeholders_of_large_files() {
+        let language = hyperast_gen_ts_java::language();
+        let text = b"class A { void f() {} }";
+        let limits = FileLimits {
+            max_size: Some(10),
+            timeout: None,
+        };
+        let reporting = Reporting::default();
+        let bounds = Bounds::reporting(limits, &reporting);
+        let tree = bounds.parse(text, &language);
+        let name = ObjectName::from(&b"A.java"[..]);
+        let (_, _, skipped) = bounds.or_placeholder(&name, text, tree, &language, Comment::Slashes);
+        assert_eq!(skipped.unwrap().kind, ParseErrorKind::TooLarge);
+        assert!(!reporting.take_transient());
+    }
+}
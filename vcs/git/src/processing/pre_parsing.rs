@@ -0,0 +1,207 @@
+//! Parsing of blobs ahead of their processing.
+//!
+//! Parsing is independent of the stores so it can be done in parallel,
+//! on a window of upcoming commits.
+//! Building the subtrees (the hash-consing in the [`hyperast::store::nodes::legion::NodeStore`])
+//! stays sequential and in the same order,
+//! thus the resulting node identifiers are the same as without pre-parsing.
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant},
+};
+
+use git2::{ObjectType, Oid, Repository};
+
+use super::{Bounds, InFiles, ObjectName};
+use crate::parse_errors::ParseError;
+
+/// Enables the parallel pre-parsing of blobs in [`crate::preprocessed::RepositoryProcessor::pre_pro`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParallelConfig {
+    /// number of parsing threads
+    pub threads: usize,
+    /// number of upcoming commits whose new blobs are parsed together
+    pub window: usize,
+}
+
+impl Default for ParallelConfig {
+    fn default() -> Self {
+        Self {
+            threads: std::thread::available_parallelism().map_or(1, |x| x.get()),
+            window: 8,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Lang {
+    #[cfg(feature = "java")]
+    Java,
+    #[cfg(feature = "cpp")]
+    Cpp,
+    #[cfg(feature = "ts")]
+    Ts,
+}
+
+impl Lang {
+    fn of(name: &ObjectName) -> Option<Self> {
+        #[cfg(feature = "java")]
+        if super::file_sys::Java::matches(name) {
+            return Some(Lang::Java);
+        }
+        #[cfg(feature = "cpp")]
+        if super::file_sys::Cpp::matches(name) {
+            return Some(Lang::Cpp);
+        }
+        #[cfg(feature = "ts")]
+        if super::file_sys::Ts::matches(name) {
+            return Some(Lang::Ts);
+        }
+        None
+    }
+
+    fn language(&self) -> tree_sitter::Language {
+        match self {
+            #[cfg(feature = "java")]
+            Lang::Java => hyperast_gen_ts_java::language(),
+            #[cfg(feature = "cpp")]
+            Lang::Cpp => hyperast_gen_ts_cpp::language(),
+            #[cfg(feature = "ts")]
+            Lang::Ts => hyperast_gen_ts_ts::language(),
+        }
+    }
+
+    fn parse(&self, text: &[u8], bounds: Bounds) -> Parsed {
+        let time = Instant::now();
+        let tree = bounds.parse(text, &self.language());
+        let parsing_time = time.elapsed();
+        Parsed { tree, parsing_time }
+    }
+}
+
+/// A blob parsed ahead of time
+pub(crate) struct Parsed {
+    /// Err if the CST contains errors, like [`hyperast_gen_ts_java::legion_with_refs::tree_sitter_parse`],
+    /// or outer Err if the blob was skipped, see [`Bounds::parse`]
+    pub(crate) tree: Result<Result<tree_sitter::Tree, tree_sitter::Tree>, ParseError>,
+    pub(crate) parsing_time: Duration,
+}
+
+#[derive(Default)]
+pub(crate) struct PreParsed {
+    parsed: HashMap<(Oid, Lang), Parsed>,
+    /// git objects visited in the current window, unchanged directories and blobs are not walked again
+    seen: HashSet<Oid>,
+    /// git objects visited in the previous window,
+    /// older ones are forgotten so that memory stays bounded on long histories
+    previous: HashSet<Oid>,
+}
+
+impl PreParsed {
+    /// Takes the tree parsed ahead of time for the blob `oid`, if any
+    pub(crate) fn take(&mut self, oid: Oid, lang: Lang) -> Option<Parsed> {
+        self.parsed.remove(&(oid, lang))
+    }
+
+    /// Drops the trees not consumed, e.g. blobs that were filtered out by a build system
+    pub(crate) fn clear(&mut self) {
+        self.parsed.clear();
+    }
+
+    /// Also forgets the visited objects
+    pub(crate) fn reset(&mut self) {
+        self.parsed.clear();
+        self.seen.clear();
+        self.previous.clear();
+    }
+
+    /// true if `oid` was not visited in the current or previous window
+    fn visit(&mut self, oid: Oid) -> bool {
+        self.seen.insert(oid) && !self.previous.contains(&oid)
+    }
+
+    /// Parses in parallel the blobs of `commits` that were not seen before.
+    ///
+    /// Reading objects is done on the current thread as [`Repository`] is not [`Sync`].
+    pub(crate) fn prefetch(
+        &mut self,
+        repository: &Repository,
+        commits: &[Oid],
+        threads: usize,
+        bounds: Bounds,
+    ) {
+        self.previous = std::mem::take(&mut self.seen);
+        let mut todo: Vec<(Oid, Lang, Vec<u8>)> = vec![];
+        for oid in commits {
+            let Ok(commit) = repository.find_commit(*oid) else {
+                continue;
+            };
+            let Ok(tree) = commit.tree() else {
+                continue;
+            };
+            self.collect(repository, tree, &mut todo);
+        }
+        if todo.is_empty() {
+            return;
+        }
+        log::debug!("pre-parsing {} blobs", todo.len());
+        let threads = threads.clamp(1, todo.len());
+        let chunk_size = todo.len().div_ceil(threads);
+        let parsed: Vec<_> = std::thread::scope(|s| {
+            let handles: Vec<_> = todo
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    s.spawn(move || {
+                        chunk
+                            .iter()
+                            .map(|(oid, lang, text)| ((*oid, *lang), lang.parse(text, bounds)))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|h| h.join().unwrap())
+                .collect()
+        });
+        self.parsed.extend(parsed);
+    }
+
+    fn collect(
+        &mut self,
+        repository: &Repository,
+        tree: git2::Tree,
+        todo: &mut Vec<(Oid, Lang, Vec<u8>)>,
+    ) {
+        if !self.visit(tree.id()) {
+            return;
+        }
+        for entry in tree.iter() {
+            match entry.kind() {
+                Some(ObjectType::Tree) => {
+                    if let Ok(tree) = repository.find_tree(entry.id()) {
+                        self.collect(repository, tree, todo);
+                    }
+                }
+                Some(ObjectType::Blob) => {
+                    let name = ObjectName::from(entry.name_bytes());
+                    let Some(lang) = Lang::of(&name) else {
+                        continue;
+                    };
+                    if !self.visit(entry.id()) {
+                        continue;
+                    }
+                    let Ok(blob) = repository.find_blob(entry.id()) else {
+                        continue;
+                    };
+                    // non utf8 blobs are rejected before parsing anyway
+                    if std::str::from_utf8(blob.content()).is_err() {
+                        continue;
+                    }
+                    todo.push((entry.id(), lang, blob.content().to_vec()));
+                }
+                _ => (),
+            }
+        }
+    }
+}
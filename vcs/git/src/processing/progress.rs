@@ -0,0 +1,125 @@
+//! Progress of a processing, observable (and cancellable) from other threads.
+use std::sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Arc,
+};
+
+/// Counters updated while processing commits,
+/// see [`crate::preprocessed::RepositoryProcessor::set_progress`]
+#[derive(Debug, Default)]
+pub struct Progress {
+    commits: AtomicUsize,
+    files: AtomicUsize,
+    bytes: AtomicUsize,
+    errors: AtomicUsize,
+    /// non zero once cancelled, an usize to also interrupt parsers, see [`Progress::cancellation_flag`]
+    cancelled: AtomicUsize,
+}
+
+/// A copy of the counters of a [`Progress`] at some point
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProgressSnapshot {
+    /// commits done, including the ones that were already processed
+    pub commits: usize,
+    /// files parsed, blobs found in caches are not counted
+    pub files: usize,
+    /// size of the parsed files
+    pub bytes: usize,
+    /// files that could not be processed
+    pub errors: usize,
+}
+
+impl Progress {
+    pub fn commit_done(&self) {
+        self.commits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn blob_done(&self, len: usize, ok: bool) {
+        self.files.fetch_add(1, Ordering::Relaxed);
+        self.bytes.fetch_add(len, Ordering::Relaxed);
+        if !ok {
+            self.errors.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Processing stops before the next commit, the commits already processed are kept.
+    ///
+    /// The files being parsed are interrupted, so the commit being processed is dropped.
+    pub fn cancel(&self) {
+        self.cancelled.store(1, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed) != 0
+    }
+
+    /// The flag given to tree-sitter parsers, see [`tree_sitter::Parser::set_cancellation_flag`]
+    pub(crate) fn cancellation_flag(&self) -> &AtomicUsize {
+        &self.cancelled
+    }
+
+    pub fn snapshot(&self) -> ProgressSnapshot {
+        ProgressSnapshot {
+            commits: self.commits.load(Ordering::Relaxed),
+            files: self.files.load(Ordering::Relaxed),
+            bytes: self.bytes.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// What blob handlers report to, see [`crate::processing::erased::ProcessorMap::reporting`]
+#[derive(Default, Clone)]
+pub(crate) struct Reporting {
+    pub(crate) progress: Option<Arc<Progress>>,
+    /// blobs that could not be processed since the start, never reset
+    pub(crate) failures: Arc<AtomicUsize>,
+    /// raised by the last blob if it was replaced by the placeholder of a timed out or cancelled parsing,
+    /// see [`crate::processing::Bounds::reporting`]
+    pub(crate) transient: Arc<AtomicBool>,
+}
+
+impl Reporting {
+    pub(crate) fn blob_done(&self, len: usize, ok: bool) {
+        if !ok {
+            self.failures.fetch_add(1, Ordering::Relaxed);
+        }
+        if let Some(progress) = &self.progress {
+            progress.blob_done(len, ok);
+        }
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.progress.as_ref().map_or(false, |x| x.is_cancelled())
+    }
+
+    /// If the last blob should not be cached, lowering the flag for the next one
+    pub(crate) fn take_transient(&self) -> bool {
+        self.transient.swap(false, Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_blobs_and_errors() {
+        let progress = Progress::default();
+        progress.blob_done(10, true);
+        progress.blob_done(5, false);
+        progress.commit_done();
+        assert_eq!(
+            progress.snapshot(),
+            ProgressSnapshot {
+                commits: 1,
+                files: 2,
+                bytes: 15,
+                errors: 1,
+            }
+        );
+        assert!(!progress.is_cancelled());
+        progress.cancel();
+        assert!(progress.is_cancelled());
+    }
+}
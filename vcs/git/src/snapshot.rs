@@ -0,0 +1,290 @@
+//! Snapshots of [`PreProcessedRepositories`],
+//! i.e. the HyperAST stores followed by the config, custom parameters and commits
+//! of each configured repository.
+//!
+//! Caches of processors are not persisted,
+//! they get filled again as new commits are processed.
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use hyperast::store::nodes::legion::persist::{
+    read_string, read_u32, read_u64, write_str, write_u32, write_u64, IdRemap, Persist,
+};
+use hyperast::store::snapshot::Registry;
+
+use crate::processing::erased::ParametrizedCommitProc as _;
+use crate::{
+    git::Repo,
+    multi_preprocessed::PreProcessedRepositories,
+    processing::{ConfigParams, RepoConfig},
+    Commit, SimpleStores,
+};
+
+/// All the components that can be produced while processing repositories.
+pub fn registry() -> Registry {
+    let mut r = Registry::with_defaults();
+    #[cfg(feature = "java")]
+    r.register_type::<hyperast_gen_ts_java::types::Lang>("java_type");
+    #[cfg(feature = "cpp")]
+    r.register_type::<hyperast_gen_ts_cpp::types::Lang>("cpp_type");
+    #[cfg(any(feature = "maven", feature = "npm"))]
+    r.register_type::<hyperast_gen_ts_xml::types::Lang>("xml_type");
+    #[cfg(feature = "ts")]
+    r.register_type::<hyperast_gen_ts_ts::types::Lang>("ts_type");
+    #[cfg(feature = "c")]
+    r.register_type::<hyperast_gen_ts_c::types::Lang>("c_type");
+    #[cfg(feature = "tsquery")]
+    r.register_type::<hyperast_gen_ts_tsquery::types::Lang>("tsquery_type");
+    #[cfg(feature = "any")]
+    r.register_type::<crate::opaque::Lang>("opaque_type");
+    #[cfg(feature = "maven")]
+    r.register_with::<enumset::EnumSet<crate::maven::SemFlags>>(
+        "maven_status",
+        |x, w| write_u64(w, x.as_u64()),
+        |r, _| {
+            enumset::EnumSet::try_from_u64(read_u64(r)?)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "bad maven status"))
+        },
+    );
+    r
+}
+
+impl PreProcessedRepositories {
+    pub fn save_snapshot(&self, registry: &Registry, w: &mut dyn Write) -> io::Result<()> {
+        self.processor.main_stores.save_snapshot(registry, w)?;
+        let configs: Vec<_> = self
+            .configs
+            .iter()
+            .filter_map(|(repo, handle)| Some((repo, handle, config_of(handle)?)))
+            .collect();
+        write_u32(w, configs.len() as u32)?;
+        for (repo, handle, config) in configs {
+            write_str(w, &repo.spec())?;
+            write_str(w, config_to_str(config))?;
+            write_params(w, self.params.get(repo).unwrap_or(&ConfigParams::None))?;
+            let proc = self
+                .processor
+                .processing_systems
+                .by_id(&handle.0)
+                .unwrap()
+                .get(handle.1);
+            let mut commits: Vec<_> = proc.commits().collect();
+            commits.sort_by_key(|(oid, _)| **oid);
+            write_u64(w, commits.len() as u64)?;
+            for (oid, commit) in commits {
+                w.write_all(oid.as_bytes())?;
+                commit.persist(w)?;
+            }
+        }
+        self.processor.parse_errors.persist(w)
+    }
+
+    /// Replace the stores with the ones of the snapshot and register the persisted commits.
+    ///
+    /// Processors (and their caches) are reset,
+    /// configs are registered again with their custom parameters.
+    pub fn load_snapshot(&mut self, registry: &Registry, r: &mut dyn Read) -> io::Result<()> {
+        let (stores, remap) = SimpleStores::load_snapshot(registry, r)?;
+        self.processor.main_stores = stores;
+        // caches refer to the previous stores
+        self.processor.purge_caches();
+        let configured: Vec<_> = self.configs.drain().collect();
+        let mut params: HashMap<_, _> = self.params.drain().collect();
+        let n = read_u32(r)?;
+        let mut loaded = Vec::with_capacity(n as usize);
+        for _ in 0..n {
+            let repo: Repo = read_string(r)?.parse().map_err(invalid)?;
+            let config: RepoConfig = read_string(r)?.parse().map_err(invalid)?;
+            let config_params = read_params(r)?;
+            let count = read_u64(r)?;
+            let mut commits = Vec::with_capacity(count as usize);
+            for _ in 0..count {
+                let oid = read_oid(r)?;
+                commits.push((oid, Commit::restore(r, &remap)?));
+            }
+            loaded.push((repo, config, config_params, commits));
+        }
+        self.processor.parse_errors = Default::default();
+        self.processor.parse_errors.restore_into(r, &remap)?;
+        // processors were purged, so configs are registered again
+        for (repo, config, config_params, commits) in loaded {
+            params.remove(&repo);
+            let handle = self
+                .register_config_with_params(repo, config, &config_params)
+                .map_err(invalid)?
+                .config;
+            let proc = self
+                .processor
+                .processing_systems
+                .by_id_mut(&handle.0)
+                .unwrap()
+                .get_mut(handle.1);
+            for (oid, commit) in commits {
+                proc.insert_commit(oid, commit);
+            }
+        }
+        for (repo, handle) in configured {
+            if self.configs.contains_key(&repo) {
+                continue;
+            }
+            let Some(config) = config_of(&handle) else {
+                log::warn!("could not re-register {}", repo.spec());
+                continue;
+            };
+            let config_params = params.remove(&repo).unwrap_or_default();
+            if let Err(e) = self.register_config_with_params(repo.clone(), config, &config_params) {
+                log::warn!("could not re-register {}: {}", repo.spec(), e);
+            }
+        }
+        // labels of the snapshot are numbered differently
+        self.rebuild_label_index();
+        Ok(())
+    }
+
+    pub fn save_snapshot_to_file(&self, registry: &Registry, path: &Path) -> io::Result<()> {
+        // write to a temporary file first, to never leave a truncated snapshot behind
+        let tmp = path.with_extension("tmp");
+        let mut w = io::BufWriter::new(std::fs::File::create(&tmp)?);
+        self.save_snapshot(registry, &mut w)?;
+        w.flush()?;
+        drop(w);
+        std::fs::rename(tmp, path)
+    }
+
+    pub fn load_snapshot_from_file(&mut self, registry: &Registry, path: &Path) -> io::Result<()> {
+        let mut r = io::BufReader::new(std::fs::File::open(path)?);
+        self.load_snapshot(registry, &mut r)
+    }
+}
+
+pub(crate) fn config_of(handle: &crate::processing::ParametrizedCommitProcessorHandle) -> Option<RepoConfig> {
+    let id = handle.0 .0;
+    #[cfg(feature = "maven")]
+    if id == std::any::TypeId::of::<crate::maven_processor::MavenProcessorHolder>() {
+        return Some(RepoConfig::JavaMaven);
+    }
+    #[cfg(feature = "gradle")]
+    if id == std::any::TypeId::of::<crate::gradle_processor::GradleProcessorHolder>() {
+        return Some(RepoConfig::JavaGradle);
+    }
+    #[cfg(feature = "make")]
+    if id == std::any::TypeId::of::<crate::make_processor::MakeProcessorHolder>() {
+        return Some(RepoConfig::CppMake);
+    }
+    #[cfg(feature = "npm")]
+    if id == std::any::TypeId::of::<crate::npm_processor::NpmProcessorHolder>() {
+        return Some(RepoConfig::TsNpm);
+    }
+    #[cfg(feature = "any")]
+    if id == std::any::TypeId::of::<crate::any_processor::AnyProcessorHolder>() {
+        return Some(RepoConfig::Any);
+    }
+    None
+}
+
+pub(crate) fn config_to_str(config: RepoConfig) -> &'static str {
+    match config {
+        RepoConfig::CppMake => "cpp",
+        RepoConfig::JavaMaven => "java",
+        RepoConfig::JavaGradle => "gradle",
+        RepoConfig::TsNpm => "ts",
+        RepoConfig::Any => "any",
+    }
+}
+
+fn write_params(w: &mut dyn Write, params: &ConfigParams) -> io::Result<()> {
+    match params {
+        ConfigParams::None => write_u32(w, 0),
+        ConfigParams::Prepro(prepro) => {
+            write_u32(w, 1)?;
+            write_str(w, prepro)
+        }
+        ConfigParams::Prequeries(query) => {
+            write_u32(w, 2)?;
+            write_u32(w, query.len() as u32)?;
+            query.iter().try_for_each(|q| write_str(w, q))
+        }
+        ConfigParams::Tsg(tsg) => {
+            write_u32(w, 3)?;
+            write_str(w, tsg)
+        }
+    }
+}
+
+fn read_params(r: &mut dyn Read) -> io::Result<ConfigParams> {
+    Ok(match read_u32(r)? {
+        0 => ConfigParams::None,
+        1 => ConfigParams::Prepro(read_string(r)?.into()),
+        2 => {
+            let n = read_u32(r)?;
+            ConfigParams::Prequeries((0..n).map(|_| read_string(r)).collect::<io::Result<_>>()?)
+        }
+        3 => ConfigParams::Tsg(read_string(r)?.into()),
+        x => return Err(invalid(format!("unknown config parameters {}", x))),
+    })
+}
+
+fn invalid(e: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+pub(crate) fn read_oid(r: &mut dyn Read) -> io::Result<git2::Oid> {
+    let mut b = [0; 20];
+    r.read_exact(&mut b)?;
+    git2::Oid::from_bytes(&b).map_err(|e| invalid(e.to_string()))
+}
+
+impl Persist for Commit {
+    fn persist(&self, w: &mut dyn Write) -> io::Result<()> {
+        write_u32(w, self.parents.len() as u32)?;
+        for p in &self.parents {
+            w.write_all(p.as_bytes())?;
+        }
+        w.write_all(&self.processing_time.to_le_bytes())?;
+        write_u64(w, self.memory_used.bytes() as u64)?;
+        self.ast_root.persist(w)?;
+        w.write_all(self.tree_oid.as_bytes())?;
+        write_u32(w, self.parse_failures)
+    }
+
+    fn restore(r: &mut dyn Read, remap: &IdRemap) -> io::Result<Self> {
+        let n = read_u32(r)?;
+        let parents = (0..n).map(|_| read_oid(r)).collect::<io::Result<_>>()?;
+        let mut b = [0; 16];
+        r.read_exact(&mut b)?;
+        let processing_time = u128::from_le_bytes(b);
+        let memory_used = (read_u64(r)? as isize).into();
+        let ast_root = Persist::restore(r, remap)?;
+        let tree_oid = read_oid(r)?;
+        let parse_failures = read_u32(r)?;
+        Ok(Self {
+            parents,
+            processing_time,
+            memory_used,
+            ast_root,
+            tree_oid,
+            parse_failures,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn params_round_trip() {
+        let all = [
+            ConfigParams::None,
+            ConfigParams::Prepro("return 1".into()),
+            ConfigParams::Prequeries(vec!["(try_statement)".into(), "(if_statement)".into()]),
+            ConfigParams::Tsg("(identifier) {}".into()),
+        ];
+        for params in all {
+            let mut w = vec![];
+            write_params(&mut w, &params).unwrap();
+            assert_eq!(read_params(&mut &w[..]).unwrap(), params);
+        }
+    }
+}
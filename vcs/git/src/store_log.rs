@@ -0,0 +1,437 @@
+//! Append-only log of the stores, written while commits are processed.
+//!
+//! After each processed commit, the labels and nodes interned for it are appended,
+//! along the parse errors of its new files, followed by the commit itself.
+//! Records are framed with their length, so a log cut in the middle of a record
+//! (e.g. the process was killed) is replayed up to its last complete commit.
+//!
+//! Garbage collections (see [`crate::gc`]) are also appended, with the commits they retained,
+//! they are replayed at the same point to get the same stores.
+//!
+//! The log is relative to the stores it was opened on,
+//! usually empty ones or the ones of a snapshot (see [`crate::snapshot`]).
+use std::collections::{HashMap, HashSet};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use hyperast::store::defaults::NodeIdentifier;
+use hyperast::store::nodes::legion::persist::{
+    read_string, read_u32, read_u64, write_str, write_u32, write_u64, IdRemap, Persist,
+};
+use hyperast::store::snapshot::Registry;
+use hyperast::types::{Childrn, LabelStore as _, WithChildren};
+
+use crate::processing::erased::ParametrizedCommitProc as _;
+use crate::{
+    git::Repo,
+    multi_preprocessed::PreProcessedRepositories,
+    processing::{ParametrizedCommitProcessorHandle, RepoConfig},
+    snapshot::{config_of, config_to_str, read_oid},
+    Commit, SimpleStores,
+};
+
+const MAGIC: &[u8; 8] = b"HYPERLOG";
+const VERSION: u32 = 4;
+
+const LABELS: u8 = b'L';
+const NODES: u8 = b'N';
+const PARSE_ERRORS: u8 = b'E';
+const COMMIT: u8 = b'C';
+const GC: u8 = b'G';
+
+pub struct StoreLog {
+    file: File,
+    registry: Registry,
+    logged_labels: usize,
+    /// offset just after the last record written
+    end: u64,
+    buf: Vec<u8>,
+    /// framed records, written to the file all at once
+    records: Vec<u8>,
+}
+
+impl StoreLog {
+    /// Append what was interned for `oid` then the commit itself.
+    ///
+    /// On error nothing is appended.
+    pub(crate) fn append_commit(
+        &mut self,
+        stores: &SimpleStores,
+        nodes: &[hyperast::store::defaults::NodeIdentifier],
+        parse_errors: &crate::parse_errors::ParseErrors,
+        repo: &Repo,
+        config: RepoConfig,
+        oid: git2::Oid,
+        commit: &Commit,
+    ) -> io::Result<()> {
+        let staged = self.stage_commit(stores, nodes, parse_errors, repo, config, oid, commit);
+        self.write_staged(staged)?;
+        self.logged_labels = stores.label_store.len();
+        Ok(())
+    }
+
+    fn stage_commit(
+        &mut self,
+        stores: &SimpleStores,
+        nodes: &[hyperast::store::defaults::NodeIdentifier],
+        parse_errors: &crate::parse_errors::ParseErrors,
+        repo: &Repo,
+        config: RepoConfig,
+        oid: git2::Oid,
+        commit: &Commit,
+    ) -> io::Result<()> {
+        let labels = &stores.label_store;
+        if labels.len() > self.logged_labels {
+            write_u64(&mut self.buf, self.logged_labels as u64)?;
+            write_u64(&mut self.buf, (labels.len() - self.logged_labels) as u64)?;
+            for l in labels.iter_from(self.logged_labels) {
+                write_str(&mut self.buf, l)?;
+            }
+            self.frame(LABELS)?;
+        }
+        if !nodes.is_empty() {
+            stores
+                .node_store
+                .persist_nodes(&self.registry, nodes, &mut self.buf)?;
+            self.frame(NODES)?;
+        }
+        if nodes.iter().any(|x| parse_errors.get(x).is_some()) {
+            parse_errors.persist_some(nodes, &mut self.buf)?;
+            self.frame(PARSE_ERRORS)?;
+        }
+        write_str(&mut self.buf, &repo.spec())?;
+        write_str(&mut self.buf, config_to_str(config))?;
+        self.buf.write_all(oid.as_bytes())?;
+        commit.persist(&mut self.buf)?;
+        self.frame(COMMIT)
+    }
+
+    /// Append a garbage collection, before it is done, see [`PreProcessedRepositories::collect_garbage`]
+    pub(crate) fn append_gc(
+        &mut self,
+        retained: &HashMap<Repo, HashSet<git2::Oid>>,
+    ) -> io::Result<()> {
+        let staged = self.stage_gc(retained);
+        self.write_staged(staged)
+    }
+
+    fn stage_gc(&mut self, retained: &HashMap<Repo, HashSet<git2::Oid>>) -> io::Result<()> {
+        write_u32(&mut self.buf, retained.len() as u32)?;
+        for (repo, commits) in retained {
+            write_str(&mut self.buf, &repo.spec())?;
+            write_u64(&mut self.buf, commits.len() as u64)?;
+            for oid in commits {
+                self.buf.write_all(oid.as_bytes())?;
+            }
+        }
+        self.frame(GC)
+    }
+
+    /// The labels were renumbered by a garbage collection,
+    /// the ones that follow are logged from there
+    pub(crate) fn labels_compacted(&mut self, labels: &hyperast::store::labels::LabelStore) {
+        self.logged_labels = labels.len();
+    }
+
+    fn frame(&mut self, tag: u8) -> io::Result<()> {
+        self.records.push(tag);
+        write_u64(&mut self.records, self.buf.len() as u64)?;
+        self.records.append(&mut self.buf);
+        Ok(())
+    }
+
+    /// Write the staged records, or none of them if staging or writing failed,
+    /// so that the next records do not follow a partial one.
+    fn write_staged(&mut self, staged: io::Result<()>) -> io::Result<()> {
+        let len = self.records.len() as u64;
+        let written = staged.and_then(|()| {
+            self.file.write_all(&self.records)?;
+            self.file.sync_data()
+        });
+        self.buf.clear();
+        self.records.clear();
+        if let Err(e) = written {
+            self.file.set_len(self.end)?;
+            self.file.seek(SeekFrom::Start(self.end))?;
+            return Err(e);
+        }
+        self.end += len;
+        Ok(())
+    }
+}
+
+impl PreProcessedRepositories {
+    /// Replay the log at `path` if it exists, then append to it as commits are processed.
+    ///
+    /// Everything after the last complete commit of the log is discarded.
+    pub fn open_store_log(&mut self, registry: Registry, path: &Path) -> io::Result<()> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        let len = file.metadata()?.len();
+        if len == 0 {
+            file.write_all(MAGIC)?;
+            write_u32(&mut file, VERSION)?;
+            registry.write_table(&mut file)?;
+            write_u64(
+                &mut file,
+                self.processor.main_stores.label_store.len() as u64,
+            )?;
+        } else {
+            let end = self.replay(&registry, BufReader::new(&mut file))?;
+            if end < len {
+                log::warn!("discarding {} bytes of incomplete records", len - end);
+                file.set_len(end)?;
+            }
+        }
+        let end = file.seek(SeekFrom::End(0))?;
+        self.processor.main_stores.node_store.start_journal();
+        self.processor.store_log = Some(StoreLog {
+            file,
+            registry,
+            logged_labels: self.processor.main_stores.label_store.len(),
+            end,
+            buf: vec![],
+            records: vec![],
+        });
+        Ok(())
+    }
+
+    /// Returns the offset just after the last complete commit.
+    fn replay(&mut self, registry: &Registry, r: impl Read) -> io::Result<u64> {
+        let mut r = Counting(r, 0);
+        let mut magic = [0; 8];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("not a HyperAST store log".into()));
+        }
+        if read_u32(&mut r)? != VERSION {
+            return Err(invalid("unsupported store log version".into()));
+        }
+        let table = registry.read_table(&mut r)?;
+        let base_labels = read_u64(&mut r)? as usize;
+        if base_labels != self.processor.main_stores.label_store.len() {
+            return Err(invalid(format!(
+                "the log was started on stores with {} labels, current ones have {}",
+                base_labels,
+                self.processor.main_stores.label_store.len()
+            )));
+        }
+        let mut remap = IdRemap::default();
+        let mut end = r.1;
+        // records of the commit being replayed, only applied once the commit is complete
+        let mut pending: Vec<(u8, Vec<u8>)> = vec![];
+        let mut count = 0;
+        loop {
+            let mut tag = [0; 1];
+            match r.read_exact(&mut tag) {
+                Ok(()) => (),
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+            let Ok(len) = read_u64(&mut r) else { break };
+            let mut payload = vec![0; len as usize];
+            if r.read_exact(&mut payload).is_err() {
+                break;
+            }
+            if tag[0] == GC {
+                if !pending.is_empty() {
+                    return Err(invalid(
+                        "garbage collection in the middle of a commit".into(),
+                    ));
+                }
+                self.replay_gc(&mut &payload[..])?;
+                end = r.1;
+                continue;
+            }
+            if tag[0] != COMMIT {
+                pending.push((tag[0], payload));
+                continue;
+            }
+            for (tag, payload) in pending.drain(..) {
+                let payload = &mut &payload[..];
+                match tag {
+                    LABELS => self.replay_labels(payload)?,
+                    NODES => self
+                        .processor
+                        .main_stores
+                        .node_store
+                        .restore_nodes(&table, &mut remap, payload)?,
+                    PARSE_ERRORS => self.processor.parse_errors.restore_into(payload, &remap)?,
+                    x => return Err(invalid(format!("unknown record {}", x))),
+                }
+            }
+            self.replay_commit(&mut &payload[..], &remap)?;
+            count += 1;
+            end = r.1;
+        }
+        log::info!("replayed {} commits from the store log", count);
+        Ok(end)
+    }
+
+    fn replay_labels(&mut self, r: &mut dyn Read) -> io::Result<()> {
+        let labels = &mut self.processor.main_stores.label_store;
+        let start = read_u64(r)? as usize;
+        let count = read_u64(r)? as usize;
+        if start != labels.len() {
+            return Err(invalid("labels of the log are out of sync".into()));
+        }
+        for i in start..start + count {
+            let id = labels.get_or_insert(read_string(r)?.as_str());
+            if Some(id) != hyperast::store::labels::label_id_from_usize(i) {
+                return Err(invalid("duplicated label in the log".into()));
+            }
+        }
+        Ok(())
+    }
+
+    fn replay_gc(&mut self, r: &mut dyn Read) -> io::Result<()> {
+        let n = read_u32(r)?;
+        let mut retained = HashMap::with_capacity(n as usize);
+        for _ in 0..n {
+            let repo: Repo = read_string(r)?.parse().map_err(invalid)?;
+            let count = read_u64(r)?;
+            let commits = (0..count).map(|_| read_oid(r)).collect::<io::Result<_>>()?;
+            retained.insert(repo, commits);
+        }
+        self.collect_unretained(&retained);
+        Ok(())
+    }
+
+    fn replay_commit(&mut self, r: &mut dyn Read, remap: &IdRemap) -> io::Result<()> {
+        let repo: Repo = read_string(r)?.parse().map_err(invalid)?;
+        let config: RepoConfig = read_string(r)?.parse().map_err(invalid)?;
+        let oid = read_oid(r)?;
+        let commit = Commit::restore(r, remap)?;
+        let handle = match self.configs.get(&repo) {
+            Some(handle) => *handle,
+            None => self.register_config(repo, config).config,
+        };
+        self.processor
+            .processing_systems
+            .by_id_mut(&handle.0)
+            .unwrap()
+            .get_mut(handle.1)
+            .insert_commit(oid, commit);
+        self.processor.index_commit(handle, oid);
+        Ok(())
+    }
+}
+
+impl crate::preprocessed::RepositoryProcessor {
+    /// Append the commit that was just processed to the store log, if any.
+    pub(crate) fn log_commit(
+        &mut self,
+        repo: &Repo,
+        handle: ParametrizedCommitProcessorHandle,
+        oid: git2::Oid,
+    ) {
+        let Some(store_log) = &mut self.store_log else {
+            return;
+        };
+        let journal = self.main_stores.node_store.take_journal();
+        let Some(config) = config_of(&handle) else {
+            log::warn!("commits of {} cannot be logged", repo.spec());
+            self.unlogged_nodes.extend(journal);
+            return;
+        };
+        let proc = self.processing_systems.by_id(&handle.0).unwrap();
+        let commit = proc.get(handle.1).get_commit(oid).unwrap();
+        let used = unlogged_used(
+            &self.main_stores,
+            &self.unlogged_nodes,
+            commit.ast_root,
+            &journal,
+        );
+        // in insertion order, so children still come first
+        let nodes: Vec<_> = (self.unlogged_nodes.iter())
+            .filter(|x| used.contains(*x))
+            .chain(&journal)
+            .copied()
+            .collect();
+        match store_log.append_commit(
+            &self.main_stores,
+            &nodes,
+            &self.parse_errors,
+            repo,
+            config,
+            oid,
+            commit,
+        ) {
+            Ok(()) => self.unlogged_nodes.retain(|x| !used.contains(x)),
+            Err(e) => {
+                log::error!(
+                    "could not log commit {}, its nodes are kept aside: {}",
+                    oid,
+                    e
+                );
+                self.unlogged_nodes.extend(journal);
+            }
+        }
+    }
+}
+
+impl crate::preprocessed::RepositoryProcessor {
+    /// Drain the journal of a commit that was dropped, so that its placeholders are not logged.
+    ///
+    /// Its other nodes can be shared by the next commits,
+    /// they are kept aside to be logged along the first commit using them.
+    pub(crate) fn drop_journal(&mut self) {
+        if self.store_log.is_none() {
+            return;
+        }
+        let nodes = self.main_stores.node_store.take_journal();
+        self.unlogged_nodes.extend(nodes);
+    }
+}
+
+/// The `unlogged` nodes used by `root`, given the `journal` of its commit
+fn unlogged_used(
+    stores: &SimpleStores,
+    unlogged: &[NodeIdentifier],
+    root: NodeIdentifier,
+    journal: &[NodeIdentifier],
+) -> HashSet<NodeIdentifier> {
+    let mut used = HashSet::new();
+    if unlogged.is_empty() {
+        return used;
+    }
+    let candidates: HashSet<_> = unlogged.iter().copied().collect();
+    // nodes inserted before the dropped commits were logged along their descendants,
+    // so the unlogged nodes used by the commit are only reachable through new or unlogged nodes
+    let mut stack = journal.to_vec();
+    if candidates.contains(&root) && used.insert(root) {
+        stack.push(root);
+    }
+    while let Some(id) = stack.pop() {
+        let n = stores.node_store.resolve(id);
+        let Some(cs) = n.children() else {
+            continue;
+        };
+        for c in cs.iter_children() {
+            if candidates.contains(&c) && used.insert(c) {
+                stack.push(c);
+            }
+        }
+    }
+    used
+}
+
+/// Keeps track of the read offset.
+struct Counting<R>(R, u64);
+
+impl<R: Read> Read for Counting<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.0.read(buf)?;
+        self.1 += n as u64;
+        Ok(n)
+    }
+}
+
+fn invalid(e: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
@@ -268,3 +268,178 @@ public class ClientClass extends ChildClass {
 	private class InnerClass3a {}
 }
 "#;
+
+#[test]
+fn repo_spec_roundtrip() {
+    use crate::git::{Forge, Repo};
+    let forge: Repo = "github.com/INRIA/spoon".parse().unwrap();
+    assert_eq!(forge, Forge::Github.repo("INRIA", "spoon"));
+    let url: Repo = "https://git.example.org:8443/team/project.git".parse().unwrap();
+    assert_eq!((url.user(), url.name()), ("team", "project"));
+    assert_eq!(url.host(), "git.example.org_8443");
+    assert_eq!(url.forge(), None);
+    let local: Repo = "file:///srv/git/team/project.git".parse().unwrap();
+    assert_eq!((local.host().as_str(), local.user(), local.name()), ("local", "team", "project"));
+    assert_eq!(local, Repo::local("/srv/git/team/project.git").unwrap());
+    for repo in [forge, url, local] {
+        assert_eq!(repo, repo.spec().parse().unwrap());
+    }
+}
+
+#[test]
+fn repo_path_traversal() {
+    use crate::git::{try_nofetch_repository, Forge, Repo};
+    assert!("github.com/../spoon".parse::<Repo>().is_err());
+    assert!(Forge::Github.try_repo("INRIA", "..").is_err());
+    assert!(Forge::Github.try_repo("", "spoon").is_err());
+    assert!(Repo::from_url("https://git.example.org/../project").is_err());
+    let root = std::env::temp_dir().join(format!("hyperast_traversal_{}", std::process::id()));
+    let err = try_nofetch_repository("https://git.example.org/team/../../../etc", &root);
+    assert!(err.is_err());
+    assert!(!root.join("etc").exists());
+    // opening a missing clone, e.g. while collecting garbage, creates no directory
+    let err = try_nofetch_repository("https://git.example.org/team/project", &root);
+    assert!(err.is_err());
+    assert!(!root.join("team").exists());
+    // a missing clone is an error, not a panic
+    assert!(Repo::local(root.join("team/project")).unwrap().try_nofetch().is_err());
+    let _ = std::fs::remove_dir_all(root);
+}
+
+#[test]
+fn local_repo_outside_allowed_roots() {
+    use crate::git::{allow_local_root, Repo};
+    let root = std::env::temp_dir().join(format!("hyperast_local_{}", std::process::id()));
+    let path = root.join("team/project");
+    let _ = std::fs::remove_dir_all(&root);
+    commit_files(&path, &[("README", "readme")]);
+    let repo: Repo = path.to_string_lossy().parse().unwrap();
+    assert!(repo.try_nofetch().is_err());
+    allow_local_root(root.join("team"));
+    assert!(repo.try_nofetch().is_ok());
+    // not escaping the allowed root through ..
+    let other = root.join("other/project");
+    commit_files(&other, &[("README", "readme")]);
+    let escaping = Repo::local(root.join("team/../other/project")).unwrap();
+    assert!(escaping.try_nofetch().is_err());
+    let _ = std::fs::remove_dir_all(root);
+}
+
+/// Commits `files` on top of HEAD, in a repository initialized at `path` if needed.
+fn commit_files(path: &std::path::Path, files: &[(&str, &str)]) -> git2::Oid {
+    let repository = git2::Repository::open(path)
+        .or_else(|_| git2::Repository::init(path))
+        .unwrap();
+    for (name, text) in files {
+        let file = path.join(name);
+        std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+        std::fs::write(file, text).unwrap();
+    }
+    let mut index = repository.index().unwrap();
+    index
+        .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
+        .unwrap();
+    index.write().unwrap();
+    let tree = repository.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature = git2::Signature::now("hyperast", "hyperast@example.org").unwrap();
+    let parent = repository.head().ok().and_then(|h| h.peel_to_commit().ok());
+    let parents: Vec<_> = parent.iter().collect();
+    repository
+        .commit(Some("HEAD"), &signature, &signature, "test", &tree, &parents)
+        .unwrap()
+}
+
+#[test]
+fn parallel_pre_parsing_same_roots() {
+    use crate::git::Repo;
+    use crate::multi_preprocessed::PreProcessedRepositories;
+    use crate::processing::{ConfiguredRepoTrait, ParallelConfig, RepoConfig};
+    use hyperast::{hashed::SyntaxNodeHashsKinds, types::WithHashs, types::WithStats};
+    let root = std::env::temp_dir().join(format!("hyperast_parallel_{}", std::process::id()));
+    let path = root.join("team/project");
+    let _ = std::fs::remove_dir_all(&root);
+    let pom = "<project><modelVersion>4.0.0</modelVersion></project>";
+    commit_files(
+        &path,
+        &[
+            ("pom.xml", pom),
+            ("src/main/java/A.java", "class A { void f() {} }"),
+            ("src/main/java/B.java", "class B { int b; }"),
+        ],
+    );
+    commit_files(
+        &path,
+        &[
+            ("src/main/java/A.java", "class A { void f() { g(); } }"),
+            ("src/main/java/p/C.java", "package p; class C {}"),
+        ],
+    );
+    commit_files(&path, &[("src/main/java/B.java", "class B { int b = 1; }")]);
+    crate::git::allow_local_root(&root);
+
+    let process = |parallel: Option<ParallelConfig>| {
+        let mut repos = PreProcessedRepositories::default();
+        repos.processor.parallel = parallel;
+        let repo = repos
+            .register_config(Repo::local(&path).unwrap(), RepoConfig::JavaMaven)
+            .nofetch();
+        let oids = repos.pre_process_with_limit(&repo, "", "", 10).unwrap();
+        assert_eq!(oids.len(), 3);
+        // identifiers are not comparable between stores, but hashes are
+        oids.iter()
+            .map(|oid| {
+                let root = repos.get_commit(repo.config(), oid).unwrap().ast_root;
+                let node = repos.processor.main_stores.node_store.resolve(root);
+                let hash = WithHashs::hash(&node, SyntaxNodeHashsKinds::Syntax);
+                (*oid, hash, node.size())
+            })
+            .collect::<Vec<_>>()
+    };
+    let sequential = process(None);
+    let parallel = process(Some(ParallelConfig {
+        threads: 2,
+        window: 2,
+    }));
+    let _ = std::fs::remove_dir_all(&root);
+    assert_eq!(sequential, parallel);
+}
+
+#[test]
+fn snapshot_of_ts_commit() {
+    use crate::git::Repo;
+    use crate::multi_preprocessed::PreProcessedRepositories;
+    use crate::processing::{ConfiguredRepoTrait, RepoConfig};
+    use hyperast::{hashed::SyntaxNodeHashsKinds, types::WithHashs};
+    let root = std::env::temp_dir().join(format!("hyperast_snapshot_ts_{}", std::process::id()));
+    let path = root.join("team/project");
+    let _ = std::fs::remove_dir_all(&root);
+    let oid = commit_files(
+        &path,
+        &[
+            ("package.json", r#"{ "name": "project" }"#),
+            ("src/index.ts", "export function f(x: number) { return x + 1; }"),
+        ],
+    );
+    crate::git::allow_local_root(&root);
+    let repo = Repo::local(&path).unwrap();
+    let hash = |repos: &PreProcessedRepositories| {
+        let handle = repos.get_config(repo.clone()).unwrap().nofetch();
+        let root = repos.get_commit(handle.config(), &oid).unwrap().ast_root;
+        let node = repos.processor.main_stores.node_store.resolve(root);
+        WithHashs::hash(&node, SyntaxNodeHashsKinds::Syntax)
+    };
+
+    let mut repos = PreProcessedRepositories::default();
+    let handle = repos
+        .register_config(repo.clone(), RepoConfig::TsNpm)
+        .nofetch();
+    repos.pre_process_with_limit(&handle, "", "", 1).unwrap();
+    let registry = crate::snapshot::registry();
+    let mut snapshot = vec![];
+    repos.save_snapshot(&registry, &mut snapshot).unwrap();
+
+    let mut loaded = PreProcessedRepositories::default();
+    loaded.load_snapshot(&registry, &mut &snapshot[..]).unwrap();
+    let _ = std::fs::remove_dir_all(&root);
+    assert_eq!(hash(&repos), hash(&loaded));
+}
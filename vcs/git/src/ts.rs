@@ -0,0 +1,117 @@
+use std::time::Instant;
+
+use crate::{
+    preprocessed::IsSkippedAna,
+    processing::pre_parsing::Parsed,
+    processing::{Bounds, Comment, ObjectName},
+    Accumulator, BasicDirAcc, FailedParsing, FileProcessingResult, SuccessProcessing,
+    PROPAGATE_ERROR_ON_BAD_CST_NODE,
+};
+
+use hyperast::{
+    hashed::SyntaxNodeHashs,
+    store::defaults::{LabelIdentifier, NodeIdentifier},
+    tree_gen::SubTreeMetrics,
+};
+
+use hyperast_gen_ts_ts::{legion as ts_tree_gen, types::TStore};
+
+pub(crate) fn handle_ts_file<'stores, 'cache, 'b: 'stores>(
+    tree_gen: &mut ts_tree_gen::TsTreeGen<'stores, 'cache, TStore>,
+    name: &ObjectName,
+    text: &'b [u8],
+) -> FileProcessingResult<ts_tree_gen::FNode> {
+    handle_ts_file_with(tree_gen, name, text, None, Default::default())
+}
+
+/// Same as [`handle_ts_file`] but reuses the tree of `pre` if the blob was parsed ahead of time,
+/// see [`crate::processing::pre_parsing`].
+/// Otherwise the file is parsed within `bounds`, or replaced by a placeholder.
+pub(crate) fn handle_ts_file_with<'stores, 'cache, 'b: 'stores>(
+    tree_gen: &mut ts_tree_gen::TsTreeGen<'stores, 'cache, TStore>,
+    name: &ObjectName,
+    text: &'b [u8],
+    pre: Option<Parsed>,
+    bounds: Bounds,
+) -> FileProcessingResult<ts_tree_gen::FNode> {
+    let language = hyperast_gen_ts_ts::language();
+    let (tree, parsing_time) = match pre {
+        Some(pre) => (pre.tree, pre.parsing_time),
+        None => {
+            let time = Instant::now();
+            let tree = bounds.parse(text, &language);
+            (tree, time.elapsed())
+        }
+    };
+    let (text, tree, skipped) =
+        bounds.or_placeholder(name, text, tree, &language, Comment::Slashes);
+    let tree = match tree {
+        Ok(tree) => tree,
+        Err(tree) => {
+            log::warn!("bad CST: {:?}", name.try_str());
+            log::debug!("{}", tree.root_node().to_sexp());
+            if PROPAGATE_ERROR_ON_BAD_CST_NODE {
+                return Err(FailedParsing {
+                    parsing_time,
+                    tree,
+                    error: "CST contains parsing errors",
+                });
+            } else {
+                tree
+            }
+        }
+    };
+    let errors = skipped.map_or_else(|| crate::parse_errors::collect(&tree), |x| vec![x]);
+    let time = Instant::now();
+    let node = tree_gen.generate_file(name.as_bytes(), text, tree.walk());
+    let processing_time = time.elapsed();
+    Ok(SuccessProcessing {
+        parsing_time,
+        processing_time,
+        node,
+        errors,
+    })
+}
+
+pub struct TsAcc {
+    pub(crate) primary:
+        BasicDirAcc<NodeIdentifier, LabelIdentifier, SubTreeMetrics<SyntaxNodeHashs<u32>>>,
+}
+
+impl TsAcc {
+    pub(crate) fn new(name: String) -> Self {
+        Self {
+            primary: BasicDirAcc::new(name),
+        }
+    }
+}
+
+impl From<String> for TsAcc {
+    fn from(name: String) -> Self {
+        Self::new(name)
+    }
+}
+
+impl TsAcc {
+    pub(crate) fn push(
+        &mut self,
+        name: LabelIdentifier,
+        full_node: ts_tree_gen::Local,
+        _skiped_ana: bool,
+    ) {
+        self.primary
+            .push(name, full_node.compressed_node, full_node.metrics);
+    }
+}
+
+impl hyperast::tree_gen::Accumulator for TsAcc {
+    type Node = (LabelIdentifier, (ts_tree_gen::Local, IsSkippedAna));
+    fn push(&mut self, (name, (full_node, _skiped_ana)): Self::Node) {
+        self.primary
+            .push(name, full_node.compressed_node, full_node.metrics);
+    }
+}
+
+impl Accumulator for TsAcc {
+    type Unlabeled = (ts_tree_gen::Local, IsSkippedAna);
+}
@@ -0,0 +1,392 @@
+use crate::{
+    git::BasicGitObject,
+    npm::NpmModuleAcc,
+    preprocessed::{IsSkippedAna, RepositoryProcessor},
+    processing::{CacheHolding, InFiles, ObjectName},
+    ts::TsAcc,
+    Processor, StackEle,
+};
+use git2::{Oid, Repository};
+use hyperast::{
+    store::nodes::legion::eq_node,
+    types::{ETypeStore as _, LabelStore},
+};
+use hyperast_gen_ts_ts::{legion as ts_gen, types::Type};
+use std::{iter::Peekable, path::Components};
+
+/// dependencies are not part of the project
+pub(crate) const NODE_MODULES: &[u8] = b"node_modules";
+
+pub(crate) fn prepare_dir_exploration(tree: git2::Tree) -> Vec<BasicGitObject> {
+    tree.iter()
+        .rev()
+        .map(TryInto::try_into)
+        .filter_map(|x| x.ok())
+        .collect()
+}
+
+pub type SimpleStores = hyperast::store::SimpleStores<hyperast_gen_ts_ts::types::TStore>;
+
+pub struct TsProcessor<'repo, 'prepro, 'd, 'c, Acc> {
+    repository: &'repo Repository,
+    prepro: &'prepro mut RepositoryProcessor,
+    stack: Vec<StackEle<Acc>>,
+    pub dir_path: &'d mut Peekable<Components<'c>>,
+    parameters: &'d crate::processing::erased::ParametrizedCommitProcessor2Handle<TsProc>,
+}
+
+impl<'repo, 'b, 'd, 'c, Acc: From<String>> TsProcessor<'repo, 'b, 'd, 'c, Acc> {
+    pub(crate) fn new(
+        repository: &'repo Repository,
+        prepro: &'b mut RepositoryProcessor,
+        dir_path: &'d mut Peekable<Components<'c>>,
+        name: &ObjectName,
+        oid: git2::Oid,
+        parameters: &'d crate::processing::erased::ParametrizedCommitProcessor2Handle<TsProc>,
+    ) -> Self {
+        let tree = repository.find_tree(oid).unwrap();
+        let prepared = prepare_dir_exploration(tree);
+        let name = name.try_into().unwrap();
+        let stack = vec![StackEle::new(oid, prepared, Acc::from(name))];
+        Self {
+            stack,
+            repository,
+            prepro,
+            dir_path,
+            parameters,
+        }
+    }
+}
+
+impl<'repo, 'b, 'd, 'c> Processor<TsAcc> for TsProcessor<'repo, 'b, 'd, 'c, TsAcc> {
+    fn pre(&mut self, current_object: BasicGitObject) {
+        match current_object {
+            BasicGitObject::Tree(_, name) if name.as_bytes() == NODE_MODULES => {
+                log::debug!("skip {:?}", name.try_str());
+            }
+            BasicGitObject::Tree(oid, name) => {
+                self.handle_tree_cached(oid, name);
+            }
+            BasicGitObject::Blob(oid, name) => {
+                if crate::processing::file_sys::Ts::matches(&name) {
+                    if let Err(err) = self.prepro.help_handle_ts_file(
+                        oid,
+                        &mut self.stack.last_mut().unwrap().acc,
+                        &name,
+                        self.repository,
+                        *self.parameters,
+                    ) {
+                        log::debug!("{:?}", err);
+                    }
+                } else {
+                    log::debug!("not ts source file {:?}", name.try_str());
+                }
+            }
+        }
+    }
+    fn post(&mut self, oid: Oid, acc: TsAcc) -> Option<(ts_gen::Local, IsSkippedAna)> {
+        let skiped_ana = true;
+        let name = acc.primary.name.clone();
+        let key = (oid, name.as_bytes().into());
+        let full_node = make(acc, self.prepro.main_stores_mut().mut_with_ts());
+        self.prepro
+            .processing_systems
+            .mut_or_default::<TsProcessorHolder>()
+            .get_caches_mut()
+            .object_map
+            .insert(key, (full_node.clone(), skiped_ana));
+        let name = self.prepro.main_stores.label_store.get_or_insert(name);
+        if self.stack.is_empty() {
+            Some((full_node, skiped_ana))
+        } else {
+            let w = &mut self.stack.last_mut().unwrap().acc;
+            assert!(
+                !w.primary.children_names.contains(&name),
+                "{:?} {:?}",
+                w.primary.children_names,
+                name
+            );
+            w.push(name, full_node.clone(), skiped_ana);
+            None
+        }
+    }
+
+    fn stack(&mut self) -> &mut Vec<StackEle<TsAcc>> {
+        &mut self.stack
+    }
+}
+
+impl<'repo, 'prepro, 'd, 'c> TsProcessor<'repo, 'prepro, 'd, 'c, TsAcc> {
+    fn handle_tree_cached(&mut self, oid: Oid, name: ObjectName) {
+        if let Some(already) = self
+            .prepro
+            .processing_systems
+            .mut_or_default::<TsProcessorHolder>()
+            .get_caches_mut()
+            .object_map
+            .get(&(oid, name.clone()))
+        {
+            // reinit already computed node for post order
+            let full_node = already.clone();
+            let w = &mut self.stack.last_mut().unwrap().acc;
+            let name = self.prepro.intern_object_name(&name);
+            assert!(!w.primary.children_names.contains(&name));
+            hyperast::tree_gen::Accumulator::push(w, (name, full_node));
+        } else {
+            log::debug!("tree {:?}", name.try_str());
+            let tree = self.repository.find_tree(oid).unwrap();
+            let prepared: Vec<BasicGitObject> = prepare_dir_exploration(tree);
+            self.stack.push(StackEle::new(
+                oid,
+                prepared,
+                TsAcc::new(name.try_into().unwrap()),
+            ));
+        }
+    }
+}
+
+/// Nothing to configure for now, the typescript generator does not precompute queries
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Parameter {}
+
+#[derive(Default)]
+pub(crate) struct TsProcessorHolder(Option<TsProc>);
+pub(crate) struct TsProc {
+    parameter: Parameter,
+    cache: crate::processing::caches::Ts,
+    commits: std::collections::HashMap<git2::Oid, crate::Commit>,
+}
+impl crate::processing::erased::Parametrized for TsProcessorHolder {
+    type T = Parameter;
+    fn register_param(
+        &mut self,
+        t: Self::T,
+    ) -> crate::processing::erased::ParametrizedCommitProcessorHandle {
+        let l = self
+            .0
+            .iter()
+            .position(|x| &x.parameter == &t)
+            .unwrap_or_else(|| {
+                let l = 0;
+                self.0 = Some(TsProc {
+                    parameter: t,
+                    cache: Default::default(),
+                    commits: Default::default(),
+                });
+                l
+            });
+        use crate::processing::erased::ConfigParametersHandle;
+        use crate::processing::erased::ParametrizedCommitProc;
+        use crate::processing::erased::ParametrizedCommitProcessorHandle;
+        ParametrizedCommitProcessorHandle(self.erased_handle(), ConfigParametersHandle(l))
+    }
+}
+
+impl crate::processing::erased::CommitProc for TsProc {
+    fn prepare_processing(
+        &self,
+        _repository: &git2::Repository,
+        _builder: crate::preprocessed::CommitBuilder,
+        _handle: crate::processing::ParametrizedCommitProcessorHandle,
+    ) -> Box<dyn crate::processing::erased::PreparedCommitProc> {
+        unimplemented!("required for processing ts at the root of a project")
+    }
+
+    fn get_commit(&self, commit_oid: git2::Oid) -> Option<&crate::Commit> {
+        self.commits.get(&commit_oid)
+    }
+
+    fn commits(&self) -> Box<dyn Iterator<Item = (&git2::Oid, &crate::Commit)> + '_> {
+        Box::new(self.commits.iter())
+    }
+
+    fn insert_commit(&mut self, commit_oid: git2::Oid, commit: crate::Commit) {
+        self.commits.insert(commit_oid, commit);
+    }
+
+    fn remove_commit(&mut self, commit_oid: git2::Oid) -> Option<crate::Commit> {
+        self.commits.remove(&commit_oid)
+    }
+
+    fn clear_caches(&mut self) {
+        self.cache = Default::default();
+    }
+}
+
+impl crate::processing::erased::CommitProcExt for TsProc {
+    type Holder = TsProcessorHolder;
+}
+impl crate::processing::erased::ParametrizedCommitProc2 for TsProcessorHolder {
+    type Proc = TsProc;
+
+    fn with_parameters_mut(
+        &mut self,
+        parameters: crate::processing::erased::ConfigParametersHandle,
+    ) -> &mut Self::Proc {
+        assert_eq!(0, parameters.0);
+        self.0.as_mut().unwrap()
+    }
+
+    fn with_parameters(
+        &self,
+        parameters: crate::processing::erased::ConfigParametersHandle,
+    ) -> &Self::Proc {
+        assert_eq!(0, parameters.0);
+        self.0.as_ref().unwrap()
+    }
+}
+impl CacheHolding<crate::processing::caches::Ts> for TsProc {
+    fn get_caches_mut(&mut self) -> &mut crate::processing::caches::Ts {
+        &mut self.cache
+    }
+    fn get_caches(&self) -> &crate::processing::caches::Ts {
+        &self.cache
+    }
+}
+
+impl CacheHolding<crate::processing::caches::Ts> for TsProcessorHolder {
+    fn get_caches_mut(&mut self) -> &mut crate::processing::caches::Ts {
+        &mut self.0.as_mut().unwrap().cache
+    }
+    fn get_caches(&self) -> &crate::processing::caches::Ts {
+        &self.0.as_ref().unwrap().cache
+    }
+}
+
+impl RepositoryProcessor {
+    pub(crate) fn handle_ts_blob(
+        &mut self,
+        oid: Oid,
+        name: &ObjectName,
+        repository: &Repository,
+        parameters: crate::processing::erased::ParametrizedCommitProcessor2Handle<TsProc>,
+    ) -> Result<(ts_gen::Local, IsSkippedAna), crate::ParseErr> {
+        let pre = self
+            .pre_parsed
+            .take(oid, crate::processing::pre_parsing::Lang::Ts);
+        self.processing_systems
+            .caching_blob_handler::<crate::processing::file_sys::Ts>()
+            .handle2(oid, repository, &name, parameters, |c, n, t| {
+                let reporting = c.reporting().clone();
+                let bounds = crate::processing::Bounds::reporting(self.limits, &reporting);
+                let line_break = if t.contains(&b'\r') { "\r\n" } else { "\n" }
+                    .as_bytes()
+                    .to_vec();
+                let holder = c.mut_or_default::<TsProcessorHolder>();
+                let ts_proc = holder.0.as_mut().unwrap();
+                let md_cache = &mut ts_proc.cache.md_cache;
+                let stores = self
+                    .main_stores
+                    .mut_with_ts::<hyperast_gen_ts_ts::types::TStore>();
+                let mut ts_tree_gen = ts_gen::TsTreeGen::new(stores, md_cache);
+                ts_tree_gen.line_break = line_break;
+                crate::ts::handle_ts_file_with(&mut ts_tree_gen, n, t, pre, bounds)
+                    .map(|x| {
+                        let local = x.node.local.clone();
+                        self.parse_errors.insert(local.compressed_node, x.errors);
+                        self.parsing_time += x.parsing_time;
+                        self.processing_time += x.processing_time;
+                        (local, false)
+                    })
+                    .map_err(|_| crate::ParseErr::IllFormed)
+            })
+    }
+
+    pub(crate) fn help_handle_ts_file(
+        &mut self,
+        oid: Oid,
+        parent: &mut TsAcc,
+        name: &ObjectName,
+        repository: &Repository,
+        parameters: crate::processing::erased::ParametrizedCommitProcessor2Handle<TsProc>,
+    ) -> Result<(), crate::ParseErr> {
+        let (full_node, skiped_ana) = self.handle_ts_blob(oid, name, repository, parameters)?;
+        let name = self.intern_object_name(name);
+        assert!(!parent.primary.children_names.contains(&name));
+        parent.push(name, full_node, skiped_ana);
+        Ok(())
+    }
+
+    pub(crate) fn help_handle_ts_file2(
+        &mut self,
+        oid: Oid,
+        parent: &mut NpmModuleAcc,
+        name: &ObjectName,
+        repository: &Repository,
+        parameters: crate::processing::erased::ParametrizedCommitProcessor2Handle<TsProc>,
+    ) -> Result<(), crate::ParseErr> {
+        let (full_node, skiped_ana) = self.handle_ts_blob(oid, name, repository, parameters)?;
+        let name = self.intern_object_name(name);
+        assert!(!parent.primary.children_names.contains(&name));
+        parent.push_source_file(name, full_node, skiped_ana);
+        Ok(())
+    }
+
+    pub(crate) fn handle_ts_directory<'b, 'd: 'b>(
+        &mut self,
+        repository: &Repository,
+        dir_path: &'b mut Peekable<Components<'d>>,
+        name: &ObjectName,
+        oid: git2::Oid,
+        handle: crate::processing::erased::ParametrizedCommitProcessor2Handle<TsProc>,
+    ) -> (ts_gen::Local, IsSkippedAna) {
+        TsProcessor::<TsAcc>::new(repository, self, dir_path, name, oid, &handle).process()
+    }
+
+    pub(crate) fn help_handle_ts_folder<'a, 'b, 'c, 'd: 'c>(
+        &'a mut self,
+        repository: &'b Repository,
+        dir_path: &'c mut Peekable<Components<'d>>,
+        oid: Oid,
+        name: &ObjectName,
+        handle: crate::processing::erased::ParametrizedCommitProcessor2Handle<TsProc>,
+    ) -> <TsAcc as hyperast::tree_gen::Accumulator>::Node {
+        let full_node = self.handle_ts_directory(repository, dir_path, name, oid, handle);
+        let name = self.intern_object_name(name);
+        (name, full_node)
+    }
+}
+
+fn make(acc: TsAcc, stores: &mut SimpleStores) -> ts_gen::Local {
+    use hyperast::hashed::{IndexingHashBuilder, MetaDataHashsBuilder};
+    let node_store = &mut stores.node_store;
+    let label_store = &mut stores.label_store;
+    let kind = Type::Directory;
+    let interned_kind = hyperast_gen_ts_ts::types::TStore::intern(kind);
+    let label_id = label_store.get_or_insert(acc.primary.name.clone());
+
+    let primary = acc
+        .primary
+        .map_metrics(|m| m.finalize(&interned_kind, &label_id, 0));
+    let hashable = primary.metrics.hashs.most_discriminating();
+    let eq = eq_node(&interned_kind, Some(&label_id), &primary.children);
+    let insertion = node_store.prepare_insertion(&hashable, eq);
+
+    if let Some(id) = insertion.occupied_id() {
+        let metrics = primary.metrics.map_hashs(|h| h.build());
+        return ts_gen::Local {
+            compressed_node: id,
+            metrics,
+        };
+    }
+
+    let mut dyn_builder = hyperast::store::nodes::legion::dyn_builder::EntityBuilder::new();
+
+    let children_is_empty = primary.children.is_empty();
+
+    let metrics = primary.persist(&mut dyn_builder, interned_kind, label_id);
+    let metrics = metrics.map_hashs(|h| h.build());
+    let hashs = metrics.add_md_metrics(&mut dyn_builder, children_is_empty);
+    hashs.persist(&mut dyn_builder);
+
+    let vacant = insertion.vacant();
+    let node_id = hyperast::store::nodes::legion::NodeStore::insert_built_after_prepare(
+        vacant,
+        dyn_builder.build(),
+    );
+
+    ts_gen::Local {
+        compressed_node: node_id,
+        metrics,
+    }
+}
@@ -11,6 +11,12 @@ impl hyperast::store::TyDown<hyperast_gen_ts_cpp::types::TStore> for TStore {}
 impl hyperast::store::TyDown<hyperast_gen_ts_java::types::TStore> for TStore {}
 #[cfg(feature = "maven")]
 impl hyperast::store::TyDown<hyperast_gen_ts_xml::types::TStore> for TStore {}
+#[cfg(feature = "ts")]
+impl hyperast::store::TyDown<hyperast_gen_ts_ts::types::TStore> for TStore {}
+#[cfg(feature = "c")]
+impl hyperast::store::TyDown<hyperast_gen_ts_c::types::TStore> for TStore {}
+#[cfg(feature = "tsquery")]
+impl hyperast::store::TyDown<hyperast_gen_ts_tsquery::types::TStore> for TStore {}
 
 impl Default for TStore {
     fn default() -> Self {
@@ -62,6 +68,25 @@ impl<'a> hyperast::types::RoleStore for TStore {
                 );
                 hyperast_gen_ts_xml::types::TStore::resolve_field(t.get_lang(), field_id)
             }
+            #[cfg(feature = "ts")]
+            "hyperast_gen_ts_ts::types::Lang" => {
+                let t =
+                    hyperast_gen_ts_ts::types::TType::new(hyperast_gen_ts_ts::types::Type::Spaces);
+                hyperast_gen_ts_ts::types::TStore::resolve_field(t.get_lang(), field_id)
+            }
+            #[cfg(feature = "c")]
+            "hyperast_gen_ts_c::types::Lang" => {
+                let t =
+                    hyperast_gen_ts_c::types::TType::new(hyperast_gen_ts_c::types::Type::Spaces);
+                hyperast_gen_ts_c::types::TStore::resolve_field(t.get_lang(), field_id)
+            }
+            #[cfg(feature = "tsquery")]
+            "hyperast_gen_ts_tsquery::types::Lang" => {
+                let t = hyperast_gen_ts_tsquery::types::TType::new(
+                    hyperast_gen_ts_tsquery::types::Type::Spaces,
+                );
+                hyperast_gen_ts_tsquery::types::TStore::resolve_field(t.get_lang(), field_id)
+            }
             x => panic!("{}", x),
         }
     }
@@ -91,6 +116,25 @@ impl<'a> hyperast::types::RoleStore for TStore {
                 );
                 hyperast_gen_ts_xml::types::TStore::intern_role(t.get_lang(), role)
             }
+            #[cfg(feature = "ts")]
+            "hyperast_gen_ts_ts::types::Lang" => {
+                let t =
+                    hyperast_gen_ts_ts::types::TType::new(hyperast_gen_ts_ts::types::Type::Spaces);
+                hyperast_gen_ts_ts::types::TStore::intern_role(t.get_lang(), role)
+            }
+            #[cfg(feature = "c")]
+            "hyperast_gen_ts_c::types::Lang" => {
+                let t =
+                    hyperast_gen_ts_c::types::TType::new(hyperast_gen_ts_c::types::Type::Spaces);
+                hyperast_gen_ts_c::types::TStore::intern_role(t.get_lang(), role)
+            }
+            #[cfg(feature = "tsquery")]
+            "hyperast_gen_ts_tsquery::types::Lang" => {
+                let t = hyperast_gen_ts_tsquery::types::TType::new(
+                    hyperast_gen_ts_tsquery::types::Type::Spaces,
+                );
+                hyperast_gen_ts_tsquery::types::TStore::intern_role(t.get_lang(), role)
+            }
             x => panic!("{}", x),
         }
     }
@@ -103,32 +147,64 @@ impl TypeStore for TStore {
         erazed: &impl hyperast::types::ErasedHolder,
         tid: std::any::TypeId,
     ) -> Self::Ty {
-        unsafe {
+        #[cfg(feature = "java")]
+        if let Some(t) = unsafe {
             erazed.unerase_ref_unchecked::<hyperast_gen_ts_java::types::TType>(
                 std::any::TypeId::of::<hyperast_gen_ts_java::types::TType>(),
             )
+        } {
+            return t.as_static().into();
         }
-        .map(|t| t.as_static().into())
-        .or_else(|| {
-            unsafe {
-                erazed.unerase_ref_unchecked::<hyperast_gen_ts_cpp::types::TType>(
-                    std::any::TypeId::of::<hyperast_gen_ts_cpp::types::TType>(),
-                )
-            }
-            .map(|t| t.as_static().into())
-        })
-        .or_else(|| {
-            unsafe {
-                erazed.unerase_ref_unchecked::<hyperast_gen_ts_xml::types::TType>(
-                    std::any::TypeId::of::<hyperast_gen_ts_xml::types::TType>(),
-                )
-            }
-            .map(|t| t.as_static().into())
-        })
-        .unwrap_or_else(|| {
-            dbg!(tid);
-            dbg!(std::any::type_name::<Self::Ty>());
-            unreachable!()
-        })
+        #[cfg(feature = "cpp")]
+        if let Some(t) = unsafe {
+            erazed.unerase_ref_unchecked::<hyperast_gen_ts_cpp::types::TType>(
+                std::any::TypeId::of::<hyperast_gen_ts_cpp::types::TType>(),
+            )
+        } {
+            return t.as_static().into();
+        }
+        #[cfg(feature = "maven")]
+        if let Some(t) = unsafe {
+            erazed.unerase_ref_unchecked::<hyperast_gen_ts_xml::types::TType>(
+                std::any::TypeId::of::<hyperast_gen_ts_xml::types::TType>(),
+            )
+        } {
+            return t.as_static().into();
+        }
+        #[cfg(feature = "ts")]
+        if let Some(t) = unsafe {
+            erazed.unerase_ref_unchecked::<hyperast_gen_ts_ts::types::TType>(
+                std::any::TypeId::of::<hyperast_gen_ts_ts::types::TType>(),
+            )
+        } {
+            return t.as_static().into();
+        }
+        #[cfg(feature = "c")]
+        if let Some(t) = unsafe {
+            erazed.unerase_ref_unchecked::<hyperast_gen_ts_c::types::TType>(std::any::TypeId::of::<
+                hyperast_gen_ts_c::types::TType,
+            >())
+        } {
+            return t.as_static().into();
+        }
+        #[cfg(feature = "tsquery")]
+        if let Some(t) = unsafe {
+            erazed.unerase_ref_unchecked::<hyperast_gen_ts_tsquery::types::TType>(
+                std::any::TypeId::of::<hyperast_gen_ts_tsquery::types::TType>(),
+            )
+        } {
+            return t.as_static().into();
+        }
+        #[cfg(feature = "any")]
+        if let Some(t) = unsafe {
+            erazed.unerase_ref_unchecked::<crate::opaque::TType>(std::any::TypeId::of::<
+                crate::opaque::TType,
+            >())
+        } {
+            return t.as_static().into();
+        }
+        dbg!(tid);
+        dbg!(std::any::type_name::<Self::Ty>());
+        unreachable!()
     }
 }